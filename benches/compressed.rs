@@ -0,0 +1,76 @@
+//! Compare the throughput and stored size of `CompressedDB` against a
+//! plain `KeyValueStore`. Run with `cargo bench --features snappy`.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use bm::{RefCountBackend, KeyValueStore, CompressedDB, InheritedDigestConstruct, Vector, Owned, Tree,
+		 KeyValueDB};
+use generic_array::GenericArray;
+use sha2::{Digest, Sha256};
+
+type Construct = InheritedDigestConstruct<Sha256>;
+type Value = GenericArray<u8, typenum::U32>;
+type Map = HashMap<Vec<u8>, Vec<u8>>;
+
+const LEN: usize = 1 << 14;
+const ROUNDS: u32 = 4;
+
+/// Leaves holding a small integer, as most of their bytes are zero.
+fn small(i: usize) -> Value {
+	let mut ret = Value::default();
+	ret[..8].copy_from_slice(&(i as u64).to_le_bytes());
+	ret
+}
+
+/// Leaves holding a hash, which do not compress.
+fn hashed(i: usize) -> Value {
+	Sha256::digest(&(i as u64).to_le_bytes())
+}
+
+fn stored_bytes(db: &Map) -> usize {
+	db.iter().map(|(key, value)| key.len() + value.len()).sum()
+}
+
+fn run<D: KeyValueDB + Default>(values: &[Value], db_of: fn(&D) -> &Map) -> (Duration, Duration, usize) where
+	D::Error: std::fmt::Debug,
+{
+	let mut write = Duration::default();
+	let mut read = Duration::default();
+	let mut bytes = 0;
+	for _ in 0..ROUNDS {
+		let mut db = RefCountBackend::<Construct, KeyValueStore<D>>::default();
+
+		let start = Instant::now();
+		let vector = Vector::<Owned, Construct>::create_from(&mut db, values, Some(LEN as u64 * 2)).unwrap();
+		write += start.elapsed();
+
+		let start = Instant::now();
+		for (i, value) in values.iter().enumerate() {
+			assert_eq!(&vector.get(&mut db, i as u64).unwrap(), value);
+		}
+		read += start.elapsed();
+
+		bytes = stored_bytes(db_of(db.store().db()));
+		vector.drop(&mut db).unwrap();
+	}
+	(write / ROUNDS, read / ROUNDS, bytes)
+}
+
+fn report(name: &str, (write, read, bytes): (Duration, Duration, usize)) {
+	println!(
+		"{:<20} write {:>8.2} Mops/s, read {:>8.2} Mops/s, {:>9} bytes",
+		name,
+		LEN as f64 / write.as_secs_f64() / 1e6,
+		LEN as f64 / read.as_secs_f64() / 1e6,
+		bytes,
+	);
+}
+
+fn main() {
+	for (workload, leaf) in [("small", small as fn(usize) -> Value), ("hashed", hashed)] {
+		let values = (0..LEN).map(leaf).collect::<Vec<_>>();
+		report(&format!("{} plain", workload), run::<Map>(&values, |db| db));
+		report(&format!("{} compressed", workload), run::<CompressedDB<Map>>(&values, |db| db.db()));
+	}
+}
@@ -50,7 +50,7 @@ pub fn into_tree_derive(input: TokenStream) -> TokenStream {
 			}).collect::<Vec<_>>();
 
 		let inner = quote! {
-			let mut vector = Vec::new();
+			let mut vector = bm_le::utils::Vec::new();
 			#(#fields)*
 			bm_le::utils::vector_tree(&vector, db, None)
 		};
@@ -65,6 +65,10 @@ pub fn into_tree_derive(input: TokenStream) -> TokenStream {
 			(where_fields, inner)
 		},
 		Data::Enum(ref data) => {
+			if data.variants.len() > 128 {
+				panic!("Too many variants for an ssz union")
+			}
+
 			let mut where_fields = Vec::new();
 
 			let variants = data.variants
@@ -142,6 +146,7 @@ pub fn from_tree_derive(input: TokenStream) -> TokenStream {
 			.map(|(i, f)| {
 				let name = &f.0;
 				let ty = &f.1.ty;
+				let i = i as u64;
 
 				(quote_spanned! { f.1.span() => #name },
 				 if has_attribute("bm", &f.1.attrs, "compact") {
@@ -170,7 +175,7 @@ pub fn from_tree_derive(input: TokenStream) -> TokenStream {
 		Data::Struct(ref data) => {
 			let (where_fields, fields) = build_fields(&data.fields);
 
-			let fields_count = fields.iter().count();
+			let fields_count = fields.iter().count() as u64;
 			let fields = fields.into_iter().map(|f| {
 				let name = f.0;
 				let value = f.1;
@@ -197,6 +202,10 @@ pub fn from_tree_derive(input: TokenStream) -> TokenStream {
 			(where_fields, inner)
 		},
 		Data::Enum(ref data) => {
+			if data.variants.len() > 128 {
+				panic!("Too many variants for an ssz union")
+			}
+
 			let mut where_fields = Vec::new();
 
 			let variants = data.variants
@@ -209,7 +218,7 @@ pub fn from_tree_derive(input: TokenStream) -> TokenStream {
 					let ident = &variant.ident;
 
 					where_fields.append(&mut variant_where_fields);
-					let fields_count = variant_fields.iter().count();
+					let fields_count = variant_fields.iter().count() as u64;
 
 					match variant.fields {
 						Fields::Named(_) => {
@@ -263,7 +272,7 @@ pub fn from_tree_derive(input: TokenStream) -> TokenStream {
 							quote! {
 								#i => {
 									if vector_root != &Default::default() {
-										return Err(bm_le::Error::CorruptedDatabase)
+										return Err(bm_le::Error::corrupted())
 									}
 
 									Ok(#name::#ident)
@@ -277,7 +286,7 @@ pub fn from_tree_derive(input: TokenStream) -> TokenStream {
 				bm_le::utils::decode_with_type(root, db, |vector_root, db, ty| {
 					match ty {
 						#(#variants)*
-						_ => return Err(bm_le::Error::CorruptedDatabase)
+						_ => return Err(bm_le::Error::corrupted())
 					}
 				})
 			})
@@ -305,6 +314,540 @@ pub fn from_tree_derive(input: TokenStream) -> TokenStream {
 	proc_macro::TokenStream::from(expanded)
 }
 
+fn attribute_value(prefix: &str, attrs: &[syn::Attribute], name: &str) -> Option<String> {
+	for attr in attrs {
+		let meta = match attr.parse_meta() {
+			Ok(meta) => meta,
+			Err(_) => continue,
+		};
+
+		if let syn::Meta::List(list) = meta {
+			if list.ident == prefix {
+				for nested in &list.nested {
+					if let syn::NestedMeta::Meta(syn::Meta::NameValue(value)) = nested {
+						if value.ident == name {
+							if let syn::Lit::Str(ref lit) = value.lit {
+								return Some(lit.value())
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+
+	None
+}
+
+fn config_max_len(attrs: &[syn::Attribute]) -> Option<(String, bool)> {
+	attribute_value("bm", attrs, "config_max_len").map(|value| (value, false))
+		.or_else(|| attribute_value("bm", attrs, "config_max_len_type").map(|value| (value, true)))
+}
+
+#[proc_macro_derive(FromTreeWithConfig, attributes(bm))]
+pub fn from_tree_with_config_derive(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let name = input.ident;
+
+	let config_trait = attribute_value("bm", &input.attrs, "config_trait").map(|value| {
+		syn::parse_str::<syn::Path>(&value).expect("config_trait must be a path")
+	});
+
+	let mut generics = input.generics.clone();
+	generics.params.push(match config_trait {
+		Some(ref config_trait) => syn::parse_quote!(BMConfig: #config_trait),
+		None => syn::parse_quote!(BMConfig),
+	});
+	let (impl_generics, _, _) = generics.split_for_impl();
+	let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let fields = match input.data {
+		Data::Struct(ref data) => normalized_fields(&data.fields),
+		_ => panic!("Unsupported"),
+	};
+	let fields_count = fields.len() as u64;
+
+	let where_fields = fields.iter().filter_map(|f| {
+		let ty = &f.1.ty;
+		let compact = has_attribute("bm", &f.1.attrs, "compact");
+
+		Some(if let Some((max_len, _)) = config_max_len(&f.1.attrs) {
+			if max_len.contains(',') {
+				return None
+			} else if compact {
+				quote_spanned! { f.1.span() => #ty: bm_le::FromCompactListTree }
+			} else {
+				quote_spanned! { f.1.span() => #ty: bm_le::FromCompositeListTree }
+			}
+		} else if has_attribute("bm", &f.1.attrs, "config") {
+			quote_spanned! { f.1.span() => #ty: bm_le::FromTreeWithConfig<BMConfig> }
+		} else if compact {
+			quote_spanned! { f.1.span() => bm_le::Compact<#ty>: bm_le::FromTree }
+		} else {
+			quote_spanned! { f.1.span() => #ty: bm_le::FromTree }
+		})
+	}).collect::<Vec<_>>();
+
+	let fields = fields.iter().enumerate().map(|(i, f)| {
+		let name = &f.0;
+		let ty = &f.1.ty;
+		let i = i as u64;
+		let compact = has_attribute("bm", &f.1.attrs, "compact");
+
+		let value = if let Some((max_len, from_type)) = config_max_len(&f.1.attrs) {
+			let mut max_lens = max_len.split(',').map(|max_len| if from_type {
+				let marker = syn::parse_str::<syn::Path>(max_len.trim())
+					.expect("config_max_len_type must be a path");
+				quote_spanned! {
+					f.1.span() => <#marker as bm_le::MaxLenFromConfig<BMConfig>>::max_len_from_config(config)
+				}
+			} else {
+				let method = Ident::new(max_len.trim(), f.1.span());
+				quote_spanned! { f.1.span() => config.#method() as u64 }
+			}).collect::<Vec<_>>();
+			let nested = max_lens.len() > 1;
+			let max_len = max_lens.remove(0);
+			let inner_max_len = max_lens.pop().unwrap_or_else(|| max_len.clone());
+
+			let inner = if compact {
+				quote_spanned! {
+					f.1.span() => |root: &<DB::Construct as bm_le::Construct>::Value, db: &mut DB| {
+						bm_le::FromCompactListTree::from_compact_list_tree(
+							root, db, Some(#inner_max_len),
+						)
+					}
+				}
+			} else {
+				quote_spanned! {
+					f.1.span() => |root: &<DB::Construct as bm_le::Construct>::Value, db: &mut DB| {
+						bm_le::FromCompositeListTree::from_composite_list_tree(
+							root, db, Some(#inner_max_len),
+						)
+					}
+				}
+			};
+			let inner = max_lens.into_iter().rev().fold(inner, |inner, max_len| {
+				quote_spanned! {
+					f.1.span() => |root: &<DB::Construct as bm_le::Construct>::Value, db: &mut DB| {
+						bm_le::utils::from_nested_list_tree(
+							root, db, Some(#max_len), #inner,
+						)
+					}
+				}
+			});
+
+			if nested {
+				quote_spanned! {
+					f.1.span() =>
+						bm_le::utils::from_nested_list_tree(
+							&vector.get(db, #i)?,
+							db,
+							Some(#max_len),
+							#inner,
+						)?
+				}
+			} else if compact {
+				quote_spanned! {
+					f.1.span() =>
+						<#ty as bm_le::FromCompactListTree>::from_compact_list_tree(
+							&vector.get(db, #i)?,
+							db,
+							Some(#max_len),
+						)?
+				}
+			} else {
+				quote_spanned! {
+					f.1.span() =>
+						<#ty as bm_le::FromCompositeListTree>::from_composite_list_tree(
+							&vector.get(db, #i)?,
+							db,
+							Some(#max_len),
+						)?
+				}
+			}
+		} else if has_attribute("bm", &f.1.attrs, "config") {
+			quote_spanned! {
+				f.1.span() =>
+					<#ty as bm_le::FromTreeWithConfig<BMConfig>>::from_tree_with_config(
+						&vector.get(db, #i)?,
+						db,
+						config,
+					)?
+			}
+		} else if compact {
+			quote_spanned! {
+				f.1.span() =>
+					<bm_le::Compact<#ty> as bm_le::FromTree>::from_tree(
+						&vector.get(db, #i)?,
+						db,
+					)?.0
+			}
+		} else {
+			quote_spanned! {
+				f.1.span() =>
+					bm_le::FromTree::from_tree(
+						&vector.get(db, #i)?,
+						db,
+					)?
+			}
+		};
+
+		quote! {
+			#name: #value,
+		}
+	}).collect::<Vec<_>>();
+
+	let expanded = quote! {
+		impl #impl_generics bm_le::FromTreeWithConfig<BMConfig> for #name #ty_generics where
+			#where_clause
+			#(#where_fields),*
+		{
+			fn from_tree_with_config<DB: bm_le::ReadBackend>(
+				root: &<DB::Construct as bm_le::Construct>::Value,
+				db: &mut DB,
+				config: &BMConfig,
+			) -> Result<Self, bm_le::Error<DB::Error>> where
+				DB::Construct: bm_le::CompatibleConstruct
+			{
+				use bm_le::Leak;
+
+				let vector = bm_le::DanglingVector::<DB::Construct>::from_leaked(
+					(root.clone(), #fields_count, None)
+				);
+
+				Ok(Self {
+					#(#fields)*
+				})
+			}
+		}
+	};
+
+	proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(SigningRoot, attributes(bm))]
+pub fn signing_root_derive(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let name = &input.ident;
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let fields = match input.data {
+		Data::Struct(ref data) => normalized_fields(&data.fields),
+		_ => panic!("Unsupported"),
+	};
+	let fields = fields.iter()
+		.filter(|f| !has_attribute("bm", &f.1.attrs, "skip_signing"))
+		.collect::<Vec<_>>();
+
+	let where_fields = fields.iter().map(|f| {
+		let ty = &f.1.ty;
+
+		if has_attribute("bm", &f.1.attrs, "compact") {
+			quote_spanned! {
+				f.1.span() => for<'a> bm_le::CompactRef<'a, #ty>: bm_le::IntoTree
+			}
+		} else {
+			quote_spanned! {
+				f.1.span() => #ty: bm_le::IntoTree
+			}
+		}
+	}).collect::<Vec<_>>();
+
+	let fields = fields.iter().map(|f| {
+		let ident = &f.0;
+
+		if has_attribute("bm", &f.1.attrs, "compact") {
+			quote_spanned! { f.1.span() => {
+				vector.push(bm_le::IntoTree::into_tree(&bm_le::CompactRef(&self.#ident), db)?);
+			} }
+		} else {
+			quote_spanned! { f.1.span() => {
+				vector.push(bm_le::IntoTree::into_tree(&self.#ident, db)?);
+			} }
+		}
+	}).collect::<Vec<_>>();
+
+	let expanded = quote! {
+		impl #impl_generics bm_le::SigningRoot for #name #ty_generics where
+			#where_clause
+			#(#where_fields),*
+		{
+			fn signing_root<DB: bm_le::WriteBackend>(
+				&self,
+				db: &mut DB
+			) -> Result<<DB::Construct as bm_le::Construct>::Value, bm_le::Error<DB::Error>> where
+				DB::Construct: bm_le::CompatibleConstruct
+			{
+				let mut vector = bm_le::utils::Vec::new();
+				#(#fields)*
+				bm_le::utils::vector_tree(&vector, db, None)
+			}
+		}
+	};
+
+	proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(Encode, attributes(bm))]
+pub fn encode_derive(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let name = &input.ident;
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let build_fields = |fs, prefix| {
+		let where_fields = normalized_fields(fs)
+			.iter()
+			.map(|f| {
+				let ty = &f.1.ty;
+
+				quote_spanned! {
+					f.1.span() => #ty: bm_le::Encode
+				}
+			}).collect::<Vec<_>>();
+
+		let fixed_lens = normalized_fields(fs)
+			.iter()
+			.map(|f| {
+				let ty = &f.1.ty;
+
+				quote_spanned! {
+					f.1.span() => <#ty as bm_le::Encode>::fixed_len()?
+				}
+			}).collect::<Vec<_>>();
+
+		let fields = normalized_fields(fs)
+			.iter()
+			.map(|f| {
+				let ident = &f.0;
+
+				quote_spanned! { f.1.span() => {
+					encoder.append(#prefix #ident);
+				} }
+			}).collect::<Vec<_>>();
+
+		let fixed_len = quote! {
+			Some(0 #(+ #fixed_lens)*)
+		};
+
+		let inner = quote! {
+			let mut encoder = bm_le::CompositeEncoder::new();
+			#(#fields)*
+			encoder.finish(out);
+		};
+
+		(where_fields, fixed_len, inner)
+	};
+
+	let (where_fields, fixed_len, inner) = match input.data {
+		Data::Struct(ref data) => build_fields(&data.fields, quote! { &self. }),
+		Data::Enum(ref data) => {
+			if data.variants.len() > 128 {
+				panic!("Too many variants for an ssz union")
+			}
+
+			let mut where_fields = Vec::new();
+
+			let variants = data.variants
+				.iter()
+				.enumerate()
+				.map(|(i, variant)| {
+					let (mut variant_where_fields, _, variant_inner) = build_fields(
+						&variant.fields,
+						if is_fields_variant_unnamed(variant) { quote! { variant. } } else { quote! {} }
+					);
+					where_fields.append(&mut variant_where_fields);
+
+					let selector = i as u8;
+					let variant_inner = match normalized_fields(&variant.fields).first() {
+						Some(f) if variant.fields.iter().count() == 1 => {
+							let ident = &f.0;
+							if is_fields_variant_unnamed(variant) {
+								quote! { bm_le::Encode::encode_to(variant.#ident, out); }
+							} else {
+								quote! { bm_le::Encode::encode_to(#ident, out); }
+							}
+						},
+						_ => variant_inner,
+					};
+
+					normalized_variant_match_cause(&input.ident, variant, quote! {
+						out.push(#selector);
+						#variant_inner
+					})
+				}).collect::<Vec<_>>();
+
+			(where_fields, quote! { None }, quote! {
+				match self {
+					#(#variants)*
+				}
+			})
+		},
+		Data::Union(_) => panic!("Unsupported"),
+	};
+
+	let expanded = quote! {
+		impl #impl_generics bm_le::Encode for #name #ty_generics where
+			#where_clause
+			#(#where_fields),*
+		{
+			fn fixed_len() -> Option<usize> {
+				#fixed_len
+			}
+
+			fn encode_to(&self, out: &mut bm_le::utils::Vec<u8>) {
+				#inner
+			}
+		}
+	};
+
+	proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(Decode, attributes(bm))]
+pub fn decode_derive(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let name = &input.ident;
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let build_fields = |fs| {
+		let where_fields = normalized_fields(fs)
+			.iter()
+			.map(|f| {
+				let ty = &f.1.ty;
+
+				quote_spanned! {
+					f.1.span() => #ty: bm_le::Decode
+				}
+			}).collect::<Vec<_>>();
+
+		let fixed_lens = normalized_fields(fs)
+			.iter()
+			.map(|f| {
+				let ty = &f.1.ty;
+
+				quote_spanned! {
+					f.1.span() => <#ty as bm_le::Decode>::fixed_len()
+				}
+			}).collect::<Vec<_>>();
+
+		let fields = normalized_fields(fs)
+			.iter()
+			.enumerate()
+			.map(|(i, f)| {
+				let ident = &f.0;
+
+				quote_spanned! { f.1.span() =>
+					#ident: bm_le::Decode::decode(parts[#i])?,
+				}
+			}).collect::<Vec<_>>();
+
+		(where_fields, fixed_lens, fields)
+	};
+
+	let (where_fields, fixed_len, inner) = match input.data {
+		Data::Struct(ref data) => {
+			let (where_fields, fixed_lens, fields) = build_fields(&data.fields);
+
+			let fixed_len = fixed_lens.iter().map(|l| quote! { + #l? });
+
+			(where_fields, quote! {
+				Some(0 #(#fixed_len)*)
+			}, quote! {
+				let parts = bm_le::decode_composite(bytes, &[#(#fixed_lens),*])?;
+
+				Ok(Self {
+					#(#fields)*
+				})
+			})
+		},
+		Data::Enum(ref data) => {
+			if data.variants.len() > 128 {
+				panic!("Too many variants for an ssz union")
+			}
+
+			let mut where_fields = Vec::new();
+
+			let variants = data.variants
+				.iter()
+				.enumerate()
+				.map(|(i, variant)| {
+					let (mut variant_where_fields, fixed_lens, fields) = build_fields(
+						&variant.fields,
+					);
+					let ident = &variant.ident;
+					let selector = i as u8;
+
+					where_fields.append(&mut variant_where_fields);
+
+					match variant.fields {
+						Fields::Unit => quote! {
+							#selector => {
+								if !value.is_empty() {
+									return Err(bm_le::DecodeError::InvalidLength)
+								}
+
+								Ok(#name::#ident)
+							},
+						},
+						_ if fields.len() == 1 => {
+							let field = &normalized_fields(&variant.fields)[0].0;
+
+							quote! {
+								#selector => {
+									let parts = [value];
+
+									Ok(#name::#ident {
+										#field: bm_le::Decode::decode(parts[0])?,
+									})
+								},
+							}
+						},
+						_ => quote! {
+							#selector => {
+								let parts = bm_le::decode_composite(value, &[#(#fixed_lens),*])?;
+
+								Ok(#name::#ident {
+									#(#fields)*
+								})
+							},
+						},
+					}
+				}).collect::<Vec<_>>();
+
+			(where_fields, quote! { None }, quote! {
+				let (selector, value) = bytes.split_first()
+					.ok_or(bm_le::DecodeError::InvalidLength)?;
+
+				match *selector {
+					#(#variants)*
+					_ => Err(bm_le::DecodeError::InvalidValue),
+				}
+			})
+		},
+		Data::Union(_) => panic!("Not supported"),
+	};
+
+	let expanded = quote! {
+		impl #impl_generics bm_le::Decode for #name #ty_generics where
+			#where_clause
+			#(#where_fields),*
+		{
+			fn fixed_len() -> Option<usize> {
+				#fixed_len
+			}
+
+			fn decode(bytes: &[u8]) -> Result<Self, bm_le::DecodeError> {
+				#inner
+			}
+		}
+	};
+
+	proc_macro::TokenStream::from(expanded)
+}
+
 #[proc_macro_derive(Partialable, attributes(bm))]
 pub fn partialable_derive(input: TokenStream) -> TokenStream {
 	let input = parse_macro_input!(input as DeriveInput);
@@ -379,3 +922,231 @@ pub fn partialable_derive(input: TokenStream) -> TokenStream {
 
 	proc_macro::TokenStream::from(expanded)
 }
+
+#[proc_macro_derive(Lensable, attributes(bm))]
+pub fn lensable_derive(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let vis = input.vis;
+	let name = input.ident;
+	let lens_name = Ident::new(&format!("{}Lens", name), name.span());
+
+	let expanded = match input.data {
+		Data::Struct(ref data) => {
+			let fields = match data.fields {
+				Fields::Named(_) => normalized_fields(&data.fields),
+				_ => panic!("Lensable only supports structs with named fields"),
+			};
+			let total = fields.len();
+
+			let methods = fields.iter().enumerate().map(|(i, f)| {
+				let ident = &f.0;
+				let ty = &f.1.ty;
+
+				if has_attribute("bm", &f.1.attrs, "compact") {
+					quote_spanned! { f.1.span() =>
+						/// Lens to the field.
+						pub fn #ident(&self) -> bm_le::Lens<bm_le::Compact<#ty>> {
+							self.0.field(#i, #total)
+						}
+					}
+				} else {
+					quote_spanned! { f.1.span() =>
+						/// Lens to the field.
+						pub fn #ident(&self) -> <#ty as bm_le::Lensable>::Lens {
+							<#ty as bm_le::Lensable>::lens(self.0.field::<#ty>(#i, #total).index())
+						}
+					}
+				}
+			});
+
+			quote! {
+				/// Lens type.
+				#vis struct #lens_name(pub bm_le::Lens<#name>);
+
+				impl #lens_name {
+					#(#methods)*
+				}
+
+				impl core::ops::Deref for #lens_name {
+					type Target = bm_le::Lens<#name>;
+
+					fn deref(&self) -> &bm_le::Lens<#name> {
+						&self.0
+					}
+				}
+
+				impl bm_le::Lensable for #name {
+					type Lens = #lens_name;
+
+					fn lens(index: bm_le::Index) -> #lens_name {
+						#lens_name(bm_le::Lens::new(index))
+					}
+				}
+
+				impl #name {
+					/// Lens to the fields from the root.
+					pub fn fields() -> #lens_name {
+						<Self as bm_le::Lensable>::lens(bm_le::Index::root())
+					}
+				}
+			}
+		},
+		_ => panic!("Unsupported data type"),
+	};
+
+	proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(FromWitness, attributes(bm))]
+pub fn from_witness_derive(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let vis = input.vis;
+	let name = input.ident;
+	let witness_name = Ident::new(&format!("{}Witness", name), name.span());
+
+	let expanded = match input.data {
+		Data::Struct(ref data) => {
+			let fields = match data.fields {
+				Fields::Named(_) => normalized_fields(&data.fields),
+				_ => panic!("FromWitness only supports structs with named fields"),
+			};
+			let total = fields.len();
+
+			let struct_inner = fields.iter().map(|f| {
+				let ident = &f.0;
+				let ty = &f.1.ty;
+
+				if has_attribute("bm", &f.1.attrs, "witness") {
+					quote_spanned! { f.1.span() =>
+						pub #ident: Result<<#ty as bm_le::FromWitness>::Witness, bm_le::MissingField>,
+					}
+				} else {
+					quote_spanned! { f.1.span() =>
+						pub #ident: Result<#ty, bm_le::MissingField>,
+					}
+				}
+			});
+
+			let decode_inner = fields.iter().enumerate().map(|(i, f)| {
+				let ident = &f.0;
+				let ty = &f.1.ty;
+				let field_name = ident.to_string();
+
+				let decode = if has_attribute("bm", &f.1.attrs, "witness") {
+					quote_spanned! { f.1.span() =>
+						<#ty as bm_le::FromWitness>::from_witness(root, db)
+					}
+				} else if has_attribute("bm", &f.1.attrs, "compact") {
+					quote_spanned! { f.1.span() =>
+						<bm_le::Compact<#ty> as bm_le::FromTree>::from_tree(root, db)
+							.map(|value| value.0)
+					}
+				} else {
+					quote_spanned! { f.1.span() =>
+						<#ty as bm_le::FromTree>::from_tree(root, db)
+					}
+				};
+
+				quote! {
+					#ident: bm_le::witness::field_from_witness(
+						root, db, #field_name, #i, #total,
+						|root, db| #decode
+					)?,
+				}
+			});
+
+			quote! {
+				/// Witness type.
+				#vis struct #witness_name {
+					#(#struct_inner)*
+				}
+
+				impl bm_le::FromWitness for #name {
+					type Witness = #witness_name;
+
+					fn from_witness<DB: bm_le::ReadBackend>(
+						root: &<DB::Construct as bm_le::Construct>::Value,
+						db: &mut DB,
+					) -> Result<#witness_name, bm_le::Error<DB::Error>> where
+						DB::Construct: bm_le::CompatibleConstruct
+					{
+						Ok(#witness_name {
+							#(#decode_inner)*
+						})
+					}
+				}
+			}
+		},
+		_ => panic!("Unsupported data type"),
+	};
+
+	proc_macro::TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(Json, attributes(bm))]
+pub fn json_derive(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let name = &input.ident;
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let fields = match input.data {
+		Data::Struct(ref data) => match data.fields {
+			Fields::Named(_) => normalized_fields(&data.fields),
+			_ => panic!("Json only supports structs with named fields"),
+		},
+		_ => panic!("Unsupported data type"),
+	};
+
+	let where_fields = fields.iter().map(|f| {
+		let ty = &f.1.ty;
+
+		quote_spanned! {
+			f.1.span() => #ty: bm_le::Json
+		}
+	}).collect::<Vec<_>>();
+
+	let keys = fields.iter().map(|f| {
+		let key = f.1.ident.as_ref().expect("Fields are named; qed").to_string();
+		key.trim_start_matches("r#").to_string()
+	}).collect::<Vec<_>>();
+
+	let to_fields = fields.iter().zip(keys.iter()).map(|(f, key)| {
+		let ident = &f.0;
+
+		quote_spanned! { f.1.span() =>
+			object.insert(#key.into(), bm_le::Json::to_json(&self.#ident));
+		}
+	}).collect::<Vec<_>>();
+
+	let from_fields = fields.iter().zip(keys.iter()).map(|(f, key)| {
+		let ident = &f.0;
+
+		quote_spanned! { f.1.span() =>
+			#ident: bm_le::json::json_field(object, #key)?,
+		}
+	}).collect::<Vec<_>>();
+
+	let expanded = quote! {
+		impl #impl_generics bm_le::Json for #name #ty_generics where
+			#where_clause
+			#(#where_fields),*
+		{
+			fn to_json(&self) -> bm_le::JsonValue {
+				let mut object = bm_le::JsonMap::new();
+				#(#to_fields)*
+				bm_le::JsonValue::Object(object)
+			}
+
+			fn from_json(value: &bm_le::JsonValue) -> Result<Self, bm_le::JsonError> {
+				let object = value.as_object().ok_or(bm_le::JsonError::InvalidType)?;
+
+				Ok(Self {
+					#(#from_fields)*
+				})
+			}
+		}
+	};
+
+	proc_macro::TokenStream::from(expanded)
+}
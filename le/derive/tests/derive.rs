@@ -1,7 +1,7 @@
 use sha2::{Digest, Sha256};
 use primitive_types::H256;
 use bm::InMemoryBackend;
-use bm_le::{IntoTree, FromTree, MaxVec, DigestConstruct, tree_root};
+use bm_le::{IntoTree, FromTree, SigningRoot, MaxVec, DigestConstruct, Union, Hashed, tree_root, signing_root};
 use generic_array::GenericArray;
 
 fn chunk(data: &[u8]) -> H256 {
@@ -37,6 +37,42 @@ struct ConfigContainer {
 	f: MaxVec<u64, typenum::U5>,
 }
 
+#[derive(IntoTree, SigningRoot)]
+struct SignedContainer {
+	a: u64,
+	#[bm(compact)]
+	b: MaxVec<u8, typenum::U32>,
+	#[bm(skip_signing)]
+	signature: u128,
+}
+
+#[derive(IntoTree)]
+struct UnsignedContainer {
+	a: u64,
+	#[bm(compact)]
+	b: MaxVec<u8, typenum::U32>,
+}
+
+#[derive(IntoTree, FromTree, Debug, Eq, PartialEq)]
+struct HashedContainer {
+	a: u64,
+	b: Hashed<MaxVec<u64, typenum::U5>>,
+	c: Hashed<(u64, u32)>,
+}
+
+#[derive(IntoTree)]
+struct UnhashedContainer {
+	a: u64,
+	b: MaxVec<u64, typenum::U5>,
+	c: (u64, u32),
+}
+
+#[derive(IntoTree, FromTree, Debug, Eq, PartialEq)]
+struct OptionContainer {
+	a: u64,
+	b: Option<u64>,
+}
+
 #[derive(IntoTree, FromTree, Debug, Eq, PartialEq)]
 pub enum EnumTest {
 	A(u128),
@@ -86,4 +122,58 @@ fn test_enum() {
 	assert_eq!(d1, e1);
 	assert_eq!(d2, e2);
 	assert_eq!(d3, e3);
+
+	assert_eq!(a1, Union::new(0, 123u128).unwrap().into_tree(&mut db).unwrap());
+	assert_eq!(Union::<u128>::from_tree(&a1, &mut db).unwrap().into_value(), 123);
+}
+
+#[test]
+fn test_option() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	assert_eq!(tree_root::<Sha256, _>(&None::<u64>), h(&[0; 32], &[0; 32]));
+	assert_eq!(
+		Some(5u64).into_tree(&mut db).unwrap(),
+		Union::new(1, 5u64).unwrap().into_tree(&mut db).unwrap()
+	);
+
+	let none = OptionContainer { a: 1, b: None };
+	let some = OptionContainer { a: 1, b: Some(2) };
+	let none_root = none.into_tree(&mut db).unwrap();
+	let some_root = some.into_tree(&mut db).unwrap();
+	assert_eq!(
+		H256::from(none_root.clone()),
+		h(&chunk(&[1]).0, &h(&[0; 32], &[0; 32]).0)
+	);
+	assert_eq!(OptionContainer::from_tree(&none_root, &mut db).unwrap(), none);
+	assert_eq!(OptionContainer::from_tree(&some_root, &mut db).unwrap(), some);
+}
+
+#[test]
+fn test_signing_root() {
+	let signed = SignedContainer { a: 1, b: MaxVec::from(vec![2, 3]), signature: 4 };
+	let unsigned = UnsignedContainer { a: 1, b: MaxVec::from(vec![2, 3]) };
+
+	assert_eq!(signing_root::<Sha256, _>(&signed), tree_root::<Sha256, _>(&unsigned));
+	assert_ne!(signing_root::<Sha256, _>(&signed), tree_root::<Sha256, _>(&signed));
+}
+
+#[test]
+fn test_hashed() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let mut container = HashedContainer {
+		a: 1,
+		b: Hashed::new(MaxVec::from(vec![2, 3])),
+		c: Hashed::new((4, 5)),
+	};
+
+	let root = container.into_tree(&mut db).unwrap();
+	assert_eq!(root, UnhashedContainer { a: 1, b: MaxVec::from(vec![2, 3]), c: (4, 5) }.into_tree(&mut db).unwrap());
+	assert!(!container.b.is_dirty() && !container.c.is_dirty());
+
+	container.a = 2;
+	container.b.push(6);
+	assert!(container.b.is_dirty() && !container.c.is_dirty());
+	let root = container.into_tree(&mut db).unwrap();
+	assert_eq!(root, UnhashedContainer { a: 2, b: MaxVec::from(vec![2, 3, 6]), c: (4, 5) }.into_tree(&mut db).unwrap());
+	assert_eq!(HashedContainer::from_tree(&root, &mut db).unwrap(), container);
 }
@@ -0,0 +1,69 @@
+use sha2::Sha256;
+use bm::InMemoryBackend;
+use bm_le::{IntoTree, FromTree, Encode, Decode, DecodeError, MaxVec, DigestConstruct};
+use typenum::U8;
+
+#[derive(IntoTree, FromTree, Encode, Decode, Debug, Eq, PartialEq)]
+struct Fixed {
+	a: u16,
+	b: u8,
+}
+
+#[derive(IntoTree, FromTree, Encode, Decode, Debug, Eq, PartialEq)]
+struct Variable {
+	a: u16,
+	#[bm(compact)]
+	b: MaxVec<u8, U8>,
+	c: Fixed,
+}
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq)]
+enum EnumTest {
+	A(MaxVec<u8, U8>),
+	B {
+		c: u16,
+		d: u8,
+	},
+	E,
+}
+
+#[test]
+fn test_container() {
+	let fixed = Fixed { a: 1, b: 2 };
+	assert_eq!(<Fixed as Encode>::fixed_len(), Some(3));
+	assert_eq!(fixed.encode(), vec![1, 0, 2]);
+
+	let variable = Variable { a: 1, b: MaxVec::from(vec![3, 4]), c: Fixed { a: 5, b: 6 } };
+	let bytes = variable.encode();
+	assert_eq!(<Variable as Encode>::fixed_len(), None);
+	assert_eq!(bytes, vec![1, 0, 9, 0, 0, 0, 5, 0, 6, 3, 4]);
+	assert_eq!(Variable::decode(&bytes), Ok(variable));
+	assert_eq!(Variable::decode(&bytes[..8]), Err(DecodeError::InvalidLength));
+}
+
+#[test]
+fn test_encode_and_tree() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let variable = Variable { a: 1, b: MaxVec::from(vec![3, 4]), c: Fixed { a: 5, b: 6 } };
+
+	let decoded = Variable::decode(&variable.encode()).unwrap();
+	let root = decoded.into_tree(&mut db).unwrap();
+	assert_eq!(root, variable.into_tree(&mut db).unwrap());
+	assert_eq!(Variable::from_tree(&root, &mut db).unwrap(), variable);
+}
+
+#[test]
+fn test_enum() {
+	let a = EnumTest::A(MaxVec::from(vec![1, 2]));
+	let b = EnumTest::B { c: 3, d: 4 };
+	let e = EnumTest::E;
+
+	assert_eq!(a.encode(), vec![0, 1, 2]);
+	assert_eq!(b.encode(), vec![1, 3, 0, 4]);
+	assert_eq!(e.encode(), vec![2]);
+	assert_eq!(EnumTest::decode(&a.encode()), Ok(a));
+	assert_eq!(EnumTest::decode(&b.encode()), Ok(b));
+	assert_eq!(EnumTest::decode(&e.encode()), Ok(e));
+	assert_eq!(EnumTest::decode(&[3]), Err(DecodeError::InvalidValue));
+	assert_eq!(EnumTest::decode(&[2, 0]), Err(DecodeError::InvalidLength));
+}
@@ -0,0 +1,48 @@
+use sha2::Sha256;
+use bm::InMemoryBackend;
+use bm_le::{IntoTree, FromTree, Json, JsonError, MaxVec, BitList, DigestConstruct,
+			to_json_string, from_json_str};
+use primitive_types::H256;
+use typenum::{U8, U16};
+
+#[derive(IntoTree, FromTree, Json, Debug, Eq, PartialEq)]
+struct Checkpoint {
+	epoch: u64,
+	root: H256,
+}
+
+#[derive(IntoTree, FromTree, Json, Debug, Eq, PartialEq)]
+struct Attestation {
+	aggregation_bits: BitList<U16>,
+	#[bm(compact)]
+	graffiti: MaxVec<u8, U8>,
+	#[bm(compact)]
+	balances: MaxVec<u64, U8>,
+	target: Checkpoint,
+}
+
+#[test]
+fn test_json() {
+	let attestation = Attestation {
+		aggregation_bits: BitList::from_bits(vec![true, true, false]).unwrap(),
+		graffiti: MaxVec::from(vec![0xca, 0xfe]),
+		balances: MaxVec::from(vec![32_000_000_000, 1]),
+		target: Checkpoint { epoch: 5, root: H256::repeat_byte(0x11) },
+	};
+
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let root = attestation.into_tree(&mut db).unwrap();
+	let decoded = Attestation::from_tree(&root, &mut db).unwrap();
+
+	let json = to_json_string(&decoded);
+	assert_eq!(json, format!(
+		"{{\"aggregation_bits\":\"0x0b\",\"balances\":[\"32000000000\",\"1\"],\"graffiti\":\"0xcafe\",\
+		 \"target\":{{\"epoch\":\"5\",\"root\":\"0x{}\"}}}}",
+		"11".repeat(32),
+	));
+	assert_eq!(from_json_str::<Attestation>(&json), Ok(attestation));
+
+	let mut value = decoded.to_json();
+	value.as_object_mut().unwrap().remove("target");
+	assert_eq!(Attestation::from_json(&value), Err(JsonError::MissingField("target")));
+}
@@ -0,0 +1,53 @@
+use bm_le::{FromTree, IntoTree, Lensable, DigestConstruct, MaxVec, DanglingRaw, Index};
+use sha2::Sha256;
+use bm::{InMemoryBackend, Tree};
+use typenum::{U4, U16};
+
+#[derive(FromTree, IntoTree, Lensable, Clone, Debug, Eq, PartialEq)]
+struct Validator {
+	pubkey: u64,
+	balance: u64,
+	slashed: bool,
+}
+
+#[derive(FromTree, IntoTree, Lensable, Clone, Debug, Eq, PartialEq)]
+struct State {
+	slot: u64,
+	validators: MaxVec<Validator, U16>,
+	#[bm(compact)]
+	balances: MaxVec<u64, U4>,
+}
+
+#[test]
+fn lens_test() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let mut state = State {
+		slot: 1,
+		validators: MaxVec::from((0..6).map(|i| Validator {
+			pubkey: i,
+			balance: 32 + i,
+			slashed: false,
+		}).collect::<Vec<_>>()),
+		balances: MaxVec::from(vec![1, 2, 3]),
+	};
+	let mut raw = DanglingRaw::<DigestConstruct<Sha256>>::new(state.into_tree(&mut db).unwrap());
+
+	let balance = State::fields().validators().at(5).balance();
+	assert_eq!(balance.index(), Index::from_one(0b10_1001_0101).unwrap());
+	assert_eq!(balance.get(&raw, &mut db).unwrap(), 37);
+	assert_eq!(State::fields().validators().at(5).pubkey().get(&raw, &mut db).unwrap(), 5);
+	assert!(!State::fields().validators().at(5).slashed().get(&raw, &mut db).unwrap());
+	assert_eq!(State::fields().validators().at(2).get(&raw, &mut db).unwrap(), state.validators[2]);
+	assert_eq!(State::fields().balances().get(&raw, &mut db).unwrap().0, state.balances);
+
+	balance.set(&mut raw, &mut db, &100).unwrap();
+	State::fields().slot().set(&mut raw, &mut db, &2).unwrap();
+	State::fields().validators().at(1).update(&mut raw, &mut db, |v| v.slashed = true).unwrap();
+	State::fields().balances().at(2).update(&mut raw, &mut db, |b| *b += 10).unwrap();
+	state.validators[5].balance = 100;
+	state.slot = 2;
+	state.validators[1].slashed = true;
+	state.balances[2] += 10;
+	assert_eq!(raw.root(), state.into_tree(&mut db).unwrap());
+	assert_eq!(State::fields().get(&raw, &mut db).unwrap(), state);
+}
@@ -0,0 +1,147 @@
+use sha2::Sha256;
+use bm::InMemoryBackend;
+use bm_le::{IntoTree, FromTreeWithConfig, MaxLenFromConfig, Compact, MaxVec, DigestConstruct, Error};
+use typenum::{U4, U8};
+
+pub trait Config {
+	fn max_values(&self) -> usize;
+	fn max_bytes(&self) -> usize;
+}
+
+pub struct TestConfig;
+
+impl Config for TestConfig {
+	fn max_values(&self) -> usize { 4 }
+	fn max_bytes(&self) -> usize { 8 }
+}
+
+pub struct MaxValues;
+
+impl<C: Config> MaxLenFromConfig<C> for MaxValues {
+	fn max_len_from_config(config: &C) -> u64 { config.max_values() as u64 }
+}
+
+pub struct SmallConfig;
+
+impl Config for SmallConfig {
+	fn max_values(&self) -> usize { 2 }
+	fn max_bytes(&self) -> usize { 8 }
+}
+
+#[derive(IntoTree)]
+struct Inner {
+	a: u64,
+	b: MaxVec<u64, U4>,
+}
+
+#[derive(FromTreeWithConfig, Debug, Eq, PartialEq)]
+#[bm(config_trait = "Config")]
+struct InnerWithConfig {
+	a: u64,
+	#[bm(config_max_len = "max_values")]
+	b: Vec<u64>,
+}
+
+#[derive(IntoTree)]
+struct Outer {
+	a: Inner,
+	#[bm(compact)]
+	b: MaxVec<u8, U8>,
+	c: u32,
+	#[bm(compact)]
+	d: MaxVec<u8, U8>,
+}
+
+#[derive(FromTreeWithConfig, Debug, Eq, PartialEq)]
+#[bm(config_trait = "Config")]
+struct OuterWithConfig {
+	#[bm(config)]
+	a: InnerWithConfig,
+	#[bm(compact, config_max_len = "max_bytes")]
+	b: Vec<u8>,
+	c: u32,
+	#[bm(config_max_len = "max_bytes")]
+	d: String,
+}
+
+#[derive(FromTreeWithConfig, Debug, Eq, PartialEq)]
+#[bm(config_trait = "Config")]
+struct InnerWithType {
+	a: u64,
+	#[bm(config_max_len_type = "MaxValues")]
+	b: Vec<u64>,
+}
+
+#[derive(FromTreeWithConfig, Debug, Eq, PartialEq)]
+struct InnerWithTypenum {
+	a: u64,
+	#[bm(config_max_len_type = "U4")]
+	b: Vec<u64>,
+}
+
+#[derive(IntoTree)]
+struct Nested {
+	a: MaxVec<MaxVec<u64, U4>, U8>,
+	b: MaxVec<Compact<MaxVec<u8, U8>>, U4>,
+}
+
+#[derive(FromTreeWithConfig, Debug, Eq, PartialEq)]
+#[bm(config_trait = "Config")]
+struct NestedWithConfig {
+	#[bm(config_max_len = "max_bytes, max_values")]
+	a: Vec<Vec<u64>>,
+	#[bm(compact, config_max_len = "max_values, max_bytes")]
+	b: Vec<Vec<u8>>,
+}
+
+#[test]
+fn test_with_config() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let outer = Outer {
+		a: Inner { a: 1, b: MaxVec::from(vec![2, 3, 4]) },
+		b: MaxVec::from(vec![5, 6, 7, 8, 9]),
+		c: 10,
+		d: MaxVec::from(b"merkle".to_vec()),
+	};
+	let root = outer.into_tree(&mut db).unwrap();
+
+	let decoded = OuterWithConfig::from_tree_with_config(&root, &mut db, &TestConfig).unwrap();
+	assert_eq!(decoded, OuterWithConfig {
+		a: InnerWithConfig { a: 1, b: vec![2, 3, 4] },
+		b: vec![5, 6, 7, 8, 9],
+		c: 10,
+		d: "merkle".to_string(),
+	});
+}
+
+#[test]
+fn test_nested_with_config() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let nested = Nested {
+		a: MaxVec::from(vec![MaxVec::from(vec![1, 2]), MaxVec::from(vec![]), MaxVec::from(vec![3])]),
+		b: MaxVec::from(vec![Compact(MaxVec::from(vec![4, 5, 6])), Compact(MaxVec::from(vec![7]))]),
+	};
+	let root = nested.into_tree(&mut db).unwrap();
+
+	let decoded = NestedWithConfig::from_tree_with_config(&root, &mut db, &TestConfig).unwrap();
+	assert_eq!(decoded, NestedWithConfig {
+		a: vec![vec![1, 2], vec![], vec![3]],
+		b: vec![vec![4, 5, 6], vec![7]],
+	});
+}
+
+#[test]
+fn test_max_len_from_config() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let inner = Inner { a: 1, b: MaxVec::from(vec![2, 3, 4]) };
+	let root = inner.into_tree(&mut db).unwrap();
+
+	assert_eq!(InnerWithType::from_tree_with_config(&root, &mut db, &TestConfig).unwrap(),
+			   InnerWithType { a: 1, b: vec![2, 3, 4] });
+	assert_eq!(InnerWithTypenum::from_tree_with_config(&root, &mut db, &()).unwrap(),
+			   InnerWithTypenum { a: 1, b: vec![2, 3, 4] });
+	assert_eq!(InnerWithType::from_tree_with_config(&root, &mut db, &SmallConfig),
+			   Err(Error::MaxLenExceeded { len: 3, max_len: 2 }));
+	assert_eq!(InnerWithConfig::from_tree_with_config(&root, &mut db, &SmallConfig),
+			   Err(Error::MaxLenExceeded { len: 3, max_len: 2 }));
+}
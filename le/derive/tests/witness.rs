@@ -0,0 +1,60 @@
+use bm_le::{FromTree, IntoTree, FromWitness, DigestConstruct, MaxVec, DanglingRaw,
+			MissingField, Index, Lens};
+use bm_le::gindex::{PathElement, generalized_index};
+use sha2::Sha256;
+use bm::{InMemoryBackend, ProvingBackend};
+use primitive_types::H256;
+use typenum::U16;
+
+#[derive(FromTree, IntoTree, FromWitness, Clone, Debug, Eq, PartialEq)]
+struct Checkpoint {
+	epoch: u64,
+	root: H256,
+}
+
+#[derive(FromTree, IntoTree, FromWitness, Clone, Debug, Eq, PartialEq)]
+struct State {
+	slot: u64,
+	#[bm(compact)]
+	balances: MaxVec<u64, U16>,
+	#[bm(witness)]
+	finalized: Checkpoint,
+	justified: Checkpoint,
+}
+
+#[test]
+fn witness_test() {
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let state = State {
+		slot: 1,
+		balances: MaxVec::from(vec![2, 3, 4]),
+		finalized: Checkpoint { epoch: 5, root: H256::repeat_byte(6) },
+		justified: Checkpoint { epoch: 7, root: H256::repeat_byte(8) },
+	};
+	let root = state.into_tree(&mut db).unwrap();
+
+	let proofs = {
+		let raw = DanglingRaw::<DigestConstruct<Sha256>>::new(root.clone());
+		let mut proving = ProvingBackend::new(&mut db);
+		Lens::<u64>::new(generalized_index(&[
+			PathElement::Field { index: 0, total: 4 },
+		])).get(&raw, &mut proving).unwrap();
+		Lens::<H256>::new(generalized_index(&[
+			PathElement::Field { index: 2, total: 4 },
+			PathElement::Field { index: 1, total: 2 },
+		])).get(&raw, &mut proving).unwrap();
+		proving.into_state().proofs
+	};
+	let mut witness_db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	witness_db.populate(proofs);
+
+	let witness = State::from_witness(&root, &mut witness_db).unwrap();
+	assert_eq!(witness.slot, Ok(1));
+	assert_eq!(witness.balances, Err(MissingField { name: "balances", index: Index::from_one(5).unwrap() }));
+	assert_eq!(witness.justified, Err(MissingField { name: "justified", index: Index::from_one(7).unwrap() }));
+
+	let finalized = witness.finalized.unwrap();
+	assert_eq!(finalized.epoch, Ok(5));
+	assert_eq!(finalized.root, Ok(H256::repeat_byte(6)));
+	assert!(State::from_tree(&root, &mut witness_db).is_err());
+}
@@ -1,9 +1,14 @@
 use bm::{ReadBackend, WriteBackend, Construct, Error, Index, DanglingRaw, Leak};
-use primitive_types::{H256, U256};
+use primitive_types::U256;
+use generic_array::{GenericArray, ArrayLength};
+use typenum::Unsigned;
 use alloc::boxed::Box;
+#[cfg(feature = "non-spec")]
+use core::convert::TryFrom;
 
-use crate::{IntoTree, FromTree, Value, CompatibleConstruct};
+use crate::{IntoTree, FromTree, End, ChunkSize, LayoutOf, CompatibleConstruct};
 use crate::utils::{mix_in_type, decode_with_type};
+use crate::mode::{write_uint, read_uint};
 
 impl IntoTree for bool {
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
@@ -30,11 +35,10 @@ macro_rules! impl_builtin_uint {
 			fn into_tree<DB: WriteBackend>(&self, _db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 				DB::Construct: CompatibleConstruct,
 			{
-				let mut ret = [0u8; 32];
-				let bytes = self.to_le_bytes();
-				ret[..bytes.len()].copy_from_slice(&bytes);
+				let mut ret = <DB::Construct as Construct>::Value::default();
+				write_uint::<LayoutOf<DB::Construct>>(&self.to_le_bytes(), ret.as_mut());
 
-				Ok(Value(H256::from(ret)))
+				Ok(ret)
 			}
 		}
 
@@ -45,11 +49,10 @@ macro_rules! impl_builtin_uint {
 				let raw = DanglingRaw::from_leaked(root.clone());
 
 				match raw.get(db, Index::root())? {
-					None => Err(Error::CorruptedDatabase),
+					None => Err(Error::corrupted()),
 					Some(value) => {
 						let mut bytes = Self::default().to_le_bytes();
-						let bytes_len = bytes.len();
-						bytes.copy_from_slice(&value.0[..bytes_len]);
+						read_uint::<LayoutOf<DB::Construct>>(value.0.as_ref(), &mut bytes);
 
 						Ok(Self::from_le_bytes(bytes))
 					},
@@ -60,15 +63,43 @@ macro_rules! impl_builtin_uint {
 }
 
 impl_builtin_uint!(u8, u16, u32, u64, u128);
+#[cfg(feature = "non-spec")]
+impl_builtin_uint!(i8, i16, i32, i64, i128);
+
+#[cfg(feature = "non-spec")]
+macro_rules! impl_builtin_size {
+	( $( $t:ty => $fixed:ty ),* ) => { $(
+		impl IntoTree for $t {
+			fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				(*self as $fixed).into_tree(db)
+			}
+		}
+
+		impl FromTree for $t {
+			fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				<$t>::try_from(<$fixed>::from_tree(root, db)?).map_err(|_| Error::corrupted())
+			}
+		}
+	)* }
+}
+
+#[cfg(feature = "non-spec")]
+impl_builtin_size!(usize => u64, isize => i64);
 
 impl IntoTree for U256 {
 	fn into_tree<DB: WriteBackend>(&self, _db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let mut ret = [0u8; 32];
-		self.to_little_endian(&mut ret);
+		let mut le = [0u8; 32];
+		self.to_little_endian(&mut le);
+		let mut ret = <DB::Construct as Construct>::Value::default();
+		write_uint::<LayoutOf<DB::Construct>>(&le, ret.as_mut());
 
-		Ok(Value(H256::from(ret)))
+		Ok(ret)
 	}
 }
 
@@ -79,38 +110,52 @@ impl FromTree for U256 {
 		let raw = DanglingRaw::from_leaked(root.clone());
 
 		match raw.get(db, Index::root())? {
-			None => Err(Error::CorruptedDatabase),
+			None => Err(Error::corrupted()),
 			Some(value) => {
-				Ok(U256::from_little_endian(&value.as_ref()))
+				let mut le = [0u8; 32];
+				read_uint::<LayoutOf<DB::Construct>>(value.as_ref(), &mut le);
+				Ok(U256::from_little_endian(&le))
 			},
 		}
 	}
 }
 
-impl IntoTree for Value {
+/// Values of another size than the nodes of the construct fail with
+/// `InvalidParameter`.
+impl<N: ArrayLength<u8>, L> IntoTree for End<N, L> {
 	fn into_tree<DB: WriteBackend>(&self, _db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		Ok(self.clone())
+		if N::to_usize() != <ChunkSize<DB::Construct> as Unsigned>::to_usize() {
+			return Err(Error::InvalidParameter)
+		}
+
+		Ok(End::new(GenericArray::clone_from_slice(&self.0)))
 	}
 }
 
-impl FromTree for Value {
+/// Values of another size than the nodes of the construct fail with
+/// `InvalidParameter`.
+impl<N: ArrayLength<u8>, L> FromTree for End<N, L> {
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, _db: &mut DB) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		Ok(root.clone())
+		if N::to_usize() != <ChunkSize<DB::Construct> as Unsigned>::to_usize() {
+			return Err(Error::InvalidParameter)
+		}
+
+		Ok(End::new(GenericArray::clone_from_slice(&root.0)))
 	}
 }
 
-impl IntoTree for bm::CompactValue<Value> {
+impl<N: ArrayLength<u8>, L> IntoTree for bm::CompactValue<End<N, L>> {
 	fn into_tree<DB: WriteBackend>(
 		&self, db: &mut DB
 	) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
 		match self {
-			bm::CompactValue::Single(value) => Ok(value.clone()),
+			bm::CompactValue::Single(value) => value.into_tree(db),
 			bm::CompactValue::Combined(boxed) => {
 				let left = boxed.as_ref().0.into_tree(db)?;
 				let right = boxed.as_ref().1.into_tree(db)?;
@@ -135,7 +180,7 @@ impl<T> FromTree for Option<T> where
 					Ok(None)
 				},
 				1 => Ok(Some(T::from_tree(inner, db)?)),
-				_ => Err(Error::CorruptedDatabase),
+				_ => Err(Error::corrupted()),
 			}
 		})
 	}
@@ -0,0 +1,228 @@
+//! Debugging companion computing roots, proofs and structure of ssz
+//! merkle trees over byte files.
+
+use std::{env, fs, process};
+use std::str::FromStr;
+use primitive_types::H256;
+use sha2::Sha256;
+use bm_le::{DigestConstruct, InMemoryBackend, ReadBackend, Value, Index, ElementalFixedVecRef,
+			ElementalVariableVecRef, IntoCompactVectorTree, IntoCompactListTree};
+use bm_le::utils::required_depth;
+use bm_le::vectors::generate;
+use bm::{CompactValue, IndexSelection, VisitControl, visit};
+
+type Construct = DigestConstruct<Sha256>;
+type Backend = InMemoryBackend<Construct>;
+
+const USAGE: &str = "\
+Usage:
+  merklist root [--list <max_len>] <file>
+  merklist prove [--list <max_len>] <file> <chunk>
+  merklist verify <root> <leaf> <index> [<branch>...]
+  merklist dump [--list <max_len>] [--depth <depth>] <file>
+  merklist vectors <seed> <count>
+
+Files are merkleized as ssz byte vectors, or as byte lists of maximum
+length <max_len> with --list. Proofs are printed as the generalized
+index of the chunk followed by the branch from the leaf up. Test
+vectors are printed as JSON lines of type, serialized value and root.";
+
+fn hex(bytes: &[u8]) -> String {
+	let mut out = String::from("0x");
+	for byte in bytes {
+		out.push_str(&format!("{:02x}", byte));
+	}
+	out
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+	let s = s.strip_prefix("0x").unwrap_or(s);
+	if s.len() != 64 {
+		return Err(format!("expected 32-byte hex value, got {}", s))
+	}
+
+	let mut bytes = [0u8; 32];
+	for (i, byte) in bytes.iter_mut().enumerate() {
+		*byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16)
+			.map_err(|_| format!("invalid hex value {}", s))?;
+	}
+	Ok(Value::from(H256::from(bytes)))
+}
+
+fn parse_index(s: &str) -> Result<Index, String> {
+	match u128::from_str(s) {
+		Ok(value) => Index::from_u128(value),
+		Err(_) => Index::from_str(s).ok(),
+	}.ok_or_else(|| format!("invalid generalized index {}", s))
+}
+
+fn parse_number(s: Option<String>, name: &str) -> Result<u64, String> {
+	let s = s.ok_or_else(|| format!("missing {}", name))?;
+	u64::from_str(&s).map_err(|_| format!("invalid {} {}", name, s))
+}
+
+/// Merkleized byte file, with the depth of its chunks from the root.
+struct Merkleized {
+	db: Backend,
+	root: Value,
+	chunks: u64,
+	depth: usize,
+}
+
+impl Merkleized {
+	fn new(data: &[u8], max_len: Option<u64>) -> Result<Self, String> {
+		let mut db = Backend::default();
+		let chunks = (data.len() as u64).div_ceil(32);
+		let (root, depth) = match max_len {
+			Some(max_len) => {
+				if data.len() as u64 > max_len {
+					return Err(format!("file of {} bytes exceeds maximum length {}", data.len(), max_len))
+				}
+				let root = ElementalVariableVecRef(data).into_compact_list_tree(&mut db, Some(max_len))
+					.map_err(|e| format!("{:?}", e))?;
+				(root, required_depth(max_len.div_ceil(32)) + 1)
+			},
+			None => {
+				let root = ElementalFixedVecRef(data).into_compact_vector_tree(&mut db, None)
+					.map_err(|e| format!("{:?}", e))?;
+				(root, required_depth(chunks))
+			},
+		};
+
+		Ok(Self { db, root, chunks, depth })
+	}
+
+	/// Generalized index, leaf and branch from the leaf up of a chunk.
+	fn prove(&mut self, chunk: u64) -> Result<(Index, Value, Vec<Value>), String> {
+		if chunk >= self.chunks {
+			return Err(format!("chunk {} out of {} chunks", chunk, self.chunks))
+		}
+		let index = Index::from_u128((1u128 << self.depth) + chunk as u128)
+			.ok_or_else(|| "chunk index too deep".to_string())?;
+
+		let mut current = self.root.clone();
+		let mut branch = Vec::new();
+		for selection in index.path() {
+			let (left, right) = self.db.get(&current)
+				.map_err(|e| format!("{:?}", e))?
+				.ok_or_else(|| "missing intermediate node".to_string())?;
+			match selection {
+				IndexSelection::Left => { branch.push(right); current = left; },
+				IndexSelection::Right => { branch.push(left); current = right; },
+			}
+		}
+		branch.reverse();
+
+		Ok((index, current, branch))
+	}
+}
+
+fn verify(root: &Value, leaf: Value, index: Index, branch: Vec<Value>) -> bool {
+	match CompactValue::from_plain(leaf, branch, index) {
+		Some(compact) => compact.root::<Construct>() == *root,
+		None => false,
+	}
+}
+
+fn run(mut args: Vec<String>) -> Result<(), String> {
+	if args.is_empty() {
+		return Err(USAGE.to_string())
+	}
+	let command = args.remove(0);
+
+	let mut max_len = None;
+	let mut max_depth = None;
+	let mut positional = Vec::new();
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--list" => max_len = Some(parse_number(args.next(), "maximum length")?),
+			"--depth" => max_depth = Some(parse_number(args.next(), "depth")? as usize),
+			_ => positional.push(arg),
+		}
+	}
+	let mut positional = positional.into_iter();
+	let mut read = || -> Result<Vec<u8>, String> {
+		let path = positional.next().ok_or_else(|| "missing file".to_string())?;
+		fs::read(&path).map_err(|e| format!("{}: {}", path, e))
+	};
+
+	match command.as_str() {
+		"root" => {
+			let merkleized = Merkleized::new(&read()?, max_len)?;
+			println!("{}", hex(merkleized.root.as_ref()));
+		},
+		"prove" => {
+			let mut merkleized = Merkleized::new(&read()?, max_len)?;
+			let chunk = parse_number(positional.next(), "chunk")?;
+			let (index, leaf, branch) = merkleized.prove(chunk)?;
+			println!("root {}", hex(merkleized.root.as_ref()));
+			println!("leaf {}", hex(leaf.as_ref()));
+			println!("index {}", index.to_u128().map(|i| i.to_string()).unwrap_or_else(|| index.to_string()));
+			for node in branch {
+				println!("{}", hex(node.as_ref()));
+			}
+		},
+		"verify" => {
+			let root = parse_value(&positional.next().ok_or_else(|| "missing root".to_string())?)?;
+			let leaf = parse_value(&positional.next().ok_or_else(|| "missing leaf".to_string())?)?;
+			let index = parse_index(&positional.next().ok_or_else(|| "missing index".to_string())?)?;
+			let branch = positional.map(|s| parse_value(&s)).collect::<Result<Vec<_>, _>>()?;
+			if verify(&root, leaf, index, branch) {
+				println!("valid");
+			} else {
+				return Err("invalid proof".to_string())
+			}
+		},
+		"dump" => {
+			let mut merkleized = Merkleized::new(&read()?, max_len)?;
+			let max_depth = max_depth.unwrap_or(usize::MAX);
+			visit(&mut merkleized.db, &merkleized.root, |node| {
+				println!("{}{} {}{}", "  ".repeat(node.depth), node.index, hex(node.value.as_ref()),
+						 if node.children.is_none() { " (leaf)" } else { "" });
+				if node.depth >= max_depth { VisitControl::SkipChildren } else { VisitControl::Continue }
+			}).map_err(|e| format!("{:?}", e))?;
+		},
+		"vectors" => {
+			let seed = parse_number(positional.next(), "seed")?;
+			let count = parse_number(positional.next(), "count")?;
+			for vector in generate::<Sha256>(seed, count as usize) {
+				println!("{}", vector);
+			}
+		},
+		_ => return Err(USAGE.to_string()),
+	}
+
+	Ok(())
+}
+
+fn main() {
+	if let Err(message) = run(env::args().skip(1).collect()) {
+		eprintln!("{}", message);
+		process::exit(1);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_prove_and_verify() {
+		let data = (0..100u8).collect::<Vec<_>>();
+		for max_len in [None, Some(256)] {
+			let mut merkleized = Merkleized::new(&data, max_len).unwrap();
+			for chunk in 0..4 {
+				let (index, leaf, branch) = merkleized.prove(chunk).unwrap();
+				let leaf_value = parse_value(&hex(leaf.as_ref())).unwrap();
+				assert_eq!(leaf_value, leaf);
+				assert!(verify(&merkleized.root, leaf, index, branch.clone()));
+				assert!(!verify(&merkleized.root, Value::default(), index, branch));
+			}
+			assert!(merkleized.prove(4).is_err());
+		}
+
+		assert_eq!(parse_index("5").unwrap(), parse_index("0b101").unwrap());
+		assert!(Merkleized::new(&data, Some(64)).is_err());
+	}
+}
@@ -0,0 +1,432 @@
+use bm::{ReadBackend, WriteBackend, Construct, Error};
+use typenum::Unsigned;
+use core::marker::PhantomData;
+use core::iter::FromIterator;
+use core::ops::{Index, IndexMut};
+use core::slice::SliceIndex;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::{ElementalVariableVecRef, ElementalVariableVec, IntoCompactListTree,
+			ElementalFixedVecRef, ElementalFixedVec, IntoCompactVectorTree,
+			IntoTree, FromTree, CompatibleConstruct, DecodeMode};
+
+/// Bitlist with maximum length. In `ssz`'s definition, this is a
+/// "bitlist", whose serialization ends with a length-marker bit.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BitList<ML>(Vec<bool>, PhantomData<ML>);
+
+impl<ML: Unsigned> BitList<ML> {
+	/// Create a bitlist of `len` unset bits. Return `None` if `len` is
+	/// larger than the maximum length.
+	pub fn with_len(len: usize) -> Option<Self> {
+		if len > ML::to_usize() {
+			return None
+		}
+
+		Some(Self(vec![false; len], PhantomData))
+	}
+
+	/// Create a bitlist from bits. Return `None` if there are more bits
+	/// than the maximum length.
+	pub fn from_bits(bits: Vec<bool>) -> Option<Self> {
+		if bits.len() > ML::to_usize() {
+			return None
+		}
+
+		Some(Self(bits, PhantomData))
+	}
+
+	/// Decode a bitlist from bytes with the trailing length-marker bit.
+	/// Return `None` if the marker bit is missing or the length is
+	/// larger than the maximum length.
+	pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+		let last = *bytes.last()?;
+		if last == 0 {
+			return None
+		}
+
+		let marker = 7 - last.leading_zeros() as usize;
+		let len = (bytes.len() - 1) * 8 + marker;
+		if len > ML::to_usize() {
+			return None
+		}
+
+		Some(Self((0..len).map(|i| bytes[i / 8] & (1 << (i % 8)) != 0).collect(), PhantomData))
+	}
+
+	/// Encode the bitlist into bytes with the trailing length-marker bit.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = vec![0u8; self.0.len() / 8 + 1];
+		for (i, bit) in self.0.iter().enumerate() {
+			bytes[i / 8] |= (*bit as u8) << (i % 8);
+		}
+		bytes[self.0.len() / 8] |= 1 << (self.0.len() % 8);
+		bytes
+	}
+
+	/// Length of the bitlist.
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	/// Whether the bitlist is empty.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Get the bit at index.
+	pub fn get(&self, index: usize) -> Option<bool> {
+		self.0.get(index).cloned()
+	}
+
+	/// Set the bit at index. Return `None` if the index is out of
+	/// range.
+	pub fn set(&mut self, index: usize, bit: bool) -> Option<()> {
+		*self.0.get_mut(index)? = bit;
+		Some(())
+	}
+
+	/// Push a new bit. Return `None` if the bitlist is full.
+	pub fn push(&mut self, bit: bool) -> Option<()> {
+		if self.0.len() >= ML::to_usize() {
+			return None
+		}
+
+		self.0.push(bit);
+		Some(())
+	}
+
+	/// All bits of the bitlist.
+	pub fn bits(&self) -> &[bool] {
+		&self.0
+	}
+}
+
+impl<ML: Unsigned> FromIterator<bool> for BitList<ML> {
+	/// Collect bits into a bitlist. Panics if there are more bits than
+	/// the maximum length.
+	fn from_iter<I: IntoIterator<Item=bool>>(iter: I) -> Self {
+		let mut bits = Self::default();
+		bits.extend(iter);
+		bits
+	}
+}
+
+impl<ML: Unsigned> Extend<bool> for BitList<ML> {
+	/// Push all bits. Panics if the bitlist overflows its maximum
+	/// length.
+	fn extend<I: IntoIterator<Item=bool>>(&mut self, iter: I) {
+		for bit in iter {
+			self.push(bit).expect("bitlist exceeds its maximum length");
+		}
+	}
+}
+
+impl<ML> IntoIterator for BitList<ML> {
+	type Item = bool;
+	type IntoIter = alloc::vec::IntoIter<bool>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a, ML> IntoIterator for &'a BitList<ML> {
+	type Item = &'a bool;
+	type IntoIter = core::slice::Iter<'a, bool>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
+
+impl<'a, ML> IntoIterator for &'a mut BitList<ML> {
+	type Item = &'a mut bool;
+	type IntoIter = core::slice::IterMut<'a, bool>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter_mut()
+	}
+}
+
+impl<ML, I: SliceIndex<[bool]>> Index<I> for BitList<ML> {
+	type Output = I::Output;
+
+	fn index(&self, index: I) -> &I::Output {
+		&self.0[index]
+	}
+}
+
+impl<ML, I: SliceIndex<[bool]>> IndexMut<I> for BitList<ML> {
+	fn index_mut(&mut self, index: I) -> &mut I::Output {
+		&mut self.0[index]
+	}
+}
+
+impl<ML> AsRef<[bool]> for BitList<ML> {
+	fn as_ref(&self) -> &[bool] {
+		&self.0
+	}
+}
+
+impl<ML> Default for BitList<ML> {
+	fn default() -> Self {
+		Self(Vec::new(), PhantomData)
+	}
+}
+
+impl<ML: Unsigned> IntoTree for BitList<ML> {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalVariableVecRef(&self.0).into_compact_list_tree(db, Some(ML::to_u64()))
+	}
+}
+
+impl<ML: Unsigned> FromTree for BitList<ML> {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		Self::from_tree_with_mode(root, db, DecodeMode::Strict)
+	}
+}
+
+impl<ML: Unsigned> BitList<ML> {
+	/// Decode like `from_tree` in decode `mode`, so that
+	/// `DecodeMode::Lenient` accepts set padding bits.
+	pub fn from_tree_with_mode<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		mode: DecodeMode,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let value = ElementalVariableVec::<bool>::from_compact_list_tree_with_mode(
+			root, db, Some(ML::to_u64()), mode
+		)?;
+		Ok(Self(value.0, PhantomData))
+	}
+}
+
+/// Bitvector with fixed length. In `ssz`'s definition, this is a
+/// "bitvector".
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BitVector<L>(Vec<bool>, PhantomData<L>);
+
+impl<L: Unsigned> BitVector<L> {
+	/// Create a bitvector of unset bits.
+	pub fn new() -> Self {
+		Self(vec![false; L::to_usize()], PhantomData)
+	}
+
+	/// Create a bitvector from bits. Return `None` if the number of
+	/// bits is not the fixed length.
+	pub fn from_bits(bits: Vec<bool>) -> Option<Self> {
+		if bits.len() != L::to_usize() {
+			return None
+		}
+
+		Some(Self(bits, PhantomData))
+	}
+
+	/// Decode a bitvector from bytes. Return `None` if the number of
+	/// bytes does not match the fixed length, or padding bits are set.
+	pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+		let len = L::to_usize();
+		if bytes.len() != len.div_ceil(8) {
+			return None
+		}
+		if (len..(bytes.len() * 8)).any(|i| bytes[i / 8] & (1 << (i % 8)) != 0) {
+			return None
+		}
+
+		Some(Self((0..len).map(|i| bytes[i / 8] & (1 << (i % 8)) != 0).collect(), PhantomData))
+	}
+
+	/// Encode the bitvector into bytes.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = vec![0u8; self.0.len().div_ceil(8)];
+		for (i, bit) in self.0.iter().enumerate() {
+			bytes[i / 8] |= (*bit as u8) << (i % 8);
+		}
+		bytes
+	}
+
+	/// Length of the bitvector.
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	/// Whether the bitvector is empty.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Get the bit at index.
+	pub fn get(&self, index: usize) -> Option<bool> {
+		self.0.get(index).cloned()
+	}
+
+	/// Set the bit at index. Return `None` if the index is out of
+	/// range.
+	pub fn set(&mut self, index: usize, bit: bool) -> Option<()> {
+		*self.0.get_mut(index)? = bit;
+		Some(())
+	}
+
+	/// All bits of the bitvector.
+	pub fn bits(&self) -> &[bool] {
+		&self.0
+	}
+}
+
+impl<L: Unsigned> Default for BitVector<L> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<L: Unsigned> IntoTree for BitVector<L> {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalFixedVecRef(&self.0).into_compact_vector_tree(db, None)
+	}
+}
+
+impl<L: Unsigned> FromTree for BitVector<L> {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		Self::from_tree_with_mode(root, db, DecodeMode::Strict)
+	}
+}
+
+impl<L: Unsigned> BitVector<L> {
+	/// Decode like `from_tree` in decode `mode`, so that
+	/// `DecodeMode::Lenient` accepts set padding bits.
+	pub fn from_tree_with_mode<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		mode: DecodeMode,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let value = ElementalFixedVec::<bool>::from_compact_vector_tree_with_mode(
+			root, db, L::to_u64(), None, mode
+		)?;
+		Ok(Self(value.0, PhantomData))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Compact, MaxVec, DigestConstruct};
+
+	use bm::InMemoryBackend;
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+	use typenum::{U4, U16};
+
+	#[test]
+	fn test_bitlist_collection_traits() {
+		let mut bits = [true, false].iter().cloned().collect::<BitList<U4>>();
+		bits.extend(Some(true));
+		bits[1] = true;
+		assert_eq!(bits.as_ref(), &[true, true, true]);
+		assert_eq!((&bits).into_iter().filter(|bit| **bit).count(), 3);
+		assert_eq!(bits.into_iter().collect::<Vec<_>>(), vec![true, true, true]);
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_bitlist_collect_overflow() {
+		let _ = vec![true; 5].into_iter().collect::<BitList<U4>>();
+	}
+
+	#[test]
+	fn test_bitlist_bytes() {
+		let bits = BitList::<U16>::from_bytes(&[0b1010_0001, 0b0000_0101]).unwrap();
+		assert_eq!(bits.len(), 10);
+		assert_eq!(bits.get(0), Some(true));
+		assert_eq!(bits.get(5), Some(true));
+		assert_eq!(bits.get(8), Some(true));
+		assert_eq!(bits.get(9), Some(false));
+		assert_eq!(bits.get(10), None);
+		assert_eq!(bits.to_bytes(), vec![0b1010_0001, 0b0000_0101]);
+
+		assert_eq!(BitList::<U16>::from_bytes(&[0b0000_0001]).unwrap().len(), 0);
+		assert_eq!(BitList::<U16>::default().to_bytes(), vec![0b0000_0001]);
+		assert_eq!(BitList::<U16>::from_bytes(&[0b1010_0001, 0]), None);
+		assert_eq!(BitList::<U16>::from_bytes(&[]), None);
+		assert_eq!(BitList::<U4>::from_bytes(&[0b0010_0001]), None);
+	}
+
+	#[test]
+	fn test_bitlist_tree() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let mut bits = BitList::<U16>::with_len(9).unwrap();
+		bits.set(3, true).unwrap();
+		bits.push(true).unwrap();
+
+		let root = bits.into_tree(&mut db).unwrap();
+		let expected = Compact(MaxVec::<bool, U16>::from(bits.bits().to_vec())).into_tree(&mut db).unwrap();
+		assert_eq!(root, expected);
+		assert_eq!(BitList::<U16>::from_tree(&root, &mut db).unwrap(), bits);
+		assert_eq!(BitList::<U4>::from_tree(&root, &mut db), Err(Error::MaxLenExceeded { len: 10, max_len: 4 }));
+	}
+
+	#[test]
+	fn test_bitvector() {
+		use typenum::U10;
+
+		let mut bits = BitVector::<U10>::new();
+		bits.set(1, true).unwrap();
+		bits.set(9, true).unwrap();
+		assert_eq!(bits.set(10, true), None);
+		assert_eq!(bits.to_bytes(), vec![0b0000_0010, 0b0000_0010]);
+		assert_eq!(BitVector::<U10>::from_bytes(&bits.to_bytes()), Some(bits.clone()));
+		assert_eq!(BitVector::<U10>::from_bytes(&[0b0000_0010, 0b0000_0110]), None);
+		assert_eq!(BitVector::<U10>::from_bytes(&[0b0000_0010]), None);
+
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let root = bits.into_tree(&mut db).unwrap();
+		let mut array = GenericArray::<bool, U10>::default();
+		array[1] = true;
+		array[9] = true;
+		assert_eq!(root, Compact(array).into_tree(&mut db).unwrap());
+		assert_eq!(BitVector::<U10>::from_tree(&root, &mut db).unwrap(), bits);
+
+		let stray = Compact(GenericArray::<bool, U16>::from_exact_iter(
+			(0..16).map(|i| i == 1 || i == 12)
+		).unwrap()).into_tree(&mut db).unwrap();
+		assert_eq!(BitVector::<U10>::from_tree(&stray, &mut db), Err(Error::NonCanonical));
+		assert_eq!(BitVector::<U10>::from_tree_with_mode(&stray, &mut db, DecodeMode::Strict), Err(Error::NonCanonical));
+		assert_eq!(BitVector::<U10>::from_tree_with_mode(&stray, &mut db, DecodeMode::Lenient).unwrap().to_bytes(),
+				   vec![0b0000_0010, 0]);
+	}
+
+	#[test]
+	fn test_bitlist_decode_mode() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let stray = Compact(GenericArray::<bool, U16>::from_exact_iter(
+			(0..16).map(|i| i == 1 || i == 12)
+		).unwrap()).into_tree(&mut db).unwrap();
+
+		let root = crate::utils::mix_in_length(&stray, &mut db, 10).unwrap();
+		assert_eq!(BitList::<U16>::from_tree(&root, &mut db), Err(Error::NonCanonical));
+		assert_eq!(BitList::<U16>::from_tree_with_mode(&root, &mut db, DecodeMode::Lenient).unwrap().to_bytes(),
+				   vec![0b0000_0010, 0b0000_0100]);
+
+		let empty = crate::utils::mix_in_length(&stray, &mut db, 0).unwrap();
+		assert_eq!(BitList::<U16>::from_tree(&empty, &mut db), Err(Error::NonCanonical));
+		assert_eq!(BitList::<U16>::from_tree_with_mode(&empty, &mut db, DecodeMode::Lenient).unwrap(),
+				   BitList::default());
+
+		let canonical = BitList::<U16>::from_bits(vec![false, true]).unwrap();
+		let root = canonical.into_tree(&mut db).unwrap();
+		assert_eq!(BitList::<U16>::from_tree_with_mode(&root, &mut db, DecodeMode::Strict).unwrap(), canonical);
+		assert_eq!(BitList::<U16>::from_tree_with_mode(&root, &mut db, DecodeMode::Lenient).unwrap(), canonical);
+	}
+}
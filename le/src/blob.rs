@@ -0,0 +1,105 @@
+use bm::{ReadBackend, WriteBackend, Construct, Error, DanglingPackedVector, Leak};
+use bm::utils::vector_tree;
+use generic_array::GenericArray;
+use alloc::vec::Vec;
+
+use crate::{IntoTree, FromTree, ChunkSize, CompatibleConstruct};
+use crate::utils::{mix_in_length, decode_with_length};
+
+/// Leaf payload of arbitrary length, hashed down to a single value.
+/// The payload is split into chunks whose tree, with the byte length
+/// mixed in, is stored in the backend keyed by its root, so that the
+/// payload can be recovered from the root alone.
+///
+/// Chunks are always packed little-endian bytes, regardless of the
+/// global chunk layout.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Blob(pub Vec<u8>);
+
+#[cfg(feature = "parity-codec")]
+impl parity_codec::Encode for Blob {
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		self.0.encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl parity_codec::Decode for Blob {
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		Some(Self(Vec::decode(input)?))
+	}
+}
+
+impl From<Vec<u8>> for Blob {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+}
+
+impl AsRef<[u8]> for Blob {
+	fn as_ref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+struct BlobChunks<'a>(&'a [u8]);
+
+impl<'a> IntoTree for BlobChunks<'a> {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let chunks = self.0.chunks(<ChunkSize<DB::Construct> as typenum::Unsigned>::to_usize()).map(|bytes| {
+			let mut chunk = <DB::Construct as Construct>::Value::default();
+			chunk.0.as_mut()[..bytes.len()].copy_from_slice(bytes);
+			chunk
+		}).collect::<Vec<_>>();
+
+		vector_tree(&chunks, db, None)
+	}
+}
+
+impl IntoTree for Blob {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		mix_in_length(&BlobChunks(&self.0), db, self.0.len() as u64)
+	}
+}
+
+impl FromTree for Blob {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let (chunks_root, len) = decode_with_length::<<DB::Construct as Construct>::Value, _>(root, db)?;
+		let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, typenum::U1>, ChunkSize<DB::Construct>, typenum::U1>::from_leaked(
+			(chunks_root, len, None)
+		);
+
+		Ok(Self(packed.to_bytes(db)?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{DigestConstruct, ElementalVariableVecRef, IntoCompactListTree};
+
+	use bm::InMemoryBackend;
+	use sha2::Sha256;
+
+	#[test]
+	fn test_blob() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		for len in [0, 1, 32, 33, 100] {
+			let blob = Blob((0..len as u8).collect());
+			let root = blob.into_tree(&mut db).unwrap();
+			assert_eq!(root, ElementalVariableVecRef(&blob.0[..]).into_compact_list_tree(&mut db, None).unwrap());
+			assert_eq!(Blob::from_tree(&root, &mut db).unwrap(), blob);
+		}
+
+		let outer = (Blob(vec![7; 80]), 3u64);
+		let root = outer.into_tree(&mut db).unwrap();
+		assert_eq!(<(Blob, u64)>::from_tree(&root, &mut db).unwrap(), outer);
+	}
+}
@@ -0,0 +1,154 @@
+use alloc::vec::Vec;
+use alloc::string::String;
+use alloc::boxed::Box;
+use generic_array::{GenericArray, ArrayLength};
+use primitive_types::{H160, H256, H512, U256};
+use typenum::{Unsigned, NonZero};
+use vecarray::VecArray;
+
+use crate::{MaxVec, BitList, BitVector, Blob, Union, Compact, H384, H768};
+
+const CHUNK_LEN: usize = 32;
+
+/// Kind of an ssz type, `BasicKind` or `CompositeKind`.
+pub trait Kind {
+	/// Whether values are packed into chunks.
+	const IS_BASIC: bool;
+}
+
+/// Kind of basic types, packed into chunks in compact vectors and lists.
+pub struct BasicKind;
+
+/// Kind of composite types, each merkleized as its own subtree.
+pub struct CompositeKind;
+
+impl Kind for BasicKind {
+	const IS_BASIC: bool = true;
+}
+
+impl Kind for CompositeKind {
+	const IS_BASIC: bool = false;
+}
+
+/// Size class of an ssz type, `FixedSize` or `VariableSize`.
+pub trait SizeKind {
+	/// Whether the serialized size is fixed.
+	const IS_FIXED: bool;
+}
+
+/// Size class of types whose serialized size is fixed.
+pub struct FixedSize;
+
+/// Size class of types whose serialized size varies with the value.
+pub struct VariableSize;
+
+impl SizeKind for FixedSize {
+	const IS_FIXED: bool = true;
+}
+
+impl SizeKind for VariableSize {
+	const IS_FIXED: bool = false;
+}
+
+/// Type-level ssz classification of a type.
+pub trait Classified {
+	/// Whether the type is basic or composite.
+	type Kind: Kind;
+	/// Whether the type is fixed or variable size.
+	type Size: SizeKind;
+}
+
+/// Basic ssz type. Only basic types can be packed, so compact vectors
+/// and lists require their items to implement it.
+pub trait Basic: Classified<Kind=BasicKind, Size=FixedSize> {
+	/// Byte length of a value, which divides the chunk size.
+	type Len: Unsigned + NonZero;
+
+	/// Number of values packed into a chunk.
+	const PER_CHUNK: usize = CHUNK_LEN / Self::Len::USIZE;
+}
+
+/// Composite ssz type.
+pub trait Composite: Classified<Kind=CompositeKind> { }
+
+impl<T: Classified<Kind=CompositeKind>> Composite for T { }
+
+/// Whether values of `len` bytes fill a chunk without straddling it.
+const fn packs_evenly(len: usize) -> bool {
+	len <= CHUNK_LEN && CHUNK_LEN.is_multiple_of(len)
+}
+
+macro_rules! impl_basic {
+	( $( $t:ty => $len:ty ),* ) => { $(
+		impl Classified for $t {
+			type Kind = BasicKind;
+			type Size = FixedSize;
+		}
+
+		impl Basic for $t {
+			type Len = $len;
+		}
+
+		const _: () = assert!(packs_evenly(<$len as Unsigned>::USIZE),
+							  "basic values must pack evenly into chunks");
+	)* }
+}
+
+impl_basic!(bool => typenum::U1, u8 => typenum::U1, u16 => typenum::U2, u32 => typenum::U4,
+			u64 => typenum::U8, u128 => typenum::U16, U256 => typenum::U32);
+#[cfg(feature = "non-spec")]
+impl_basic!(i8 => typenum::U1, i16 => typenum::U2, i32 => typenum::U4, i64 => typenum::U8,
+			i128 => typenum::U16, usize => typenum::U8, isize => typenum::U8);
+
+macro_rules! impl_composite {
+	( $( [ $( $generics:tt )* ] $t:ty => $size:ty ),* ) => { $(
+		impl<$( $generics )*> Classified for $t {
+			type Kind = CompositeKind;
+			type Size = $size;
+		}
+	)* }
+}
+
+impl_composite!(
+	[] H160 => FixedSize, [] H256 => FixedSize, [] H384 => FixedSize,
+	[] H512 => FixedSize, [] H768 => FixedSize,
+	[L] BitVector<L> => FixedSize,
+	[T: Classified, L: ArrayLength<T>] GenericArray<T, L> => T::Size,
+	[T: Classified, L: Unsigned] VecArray<T, L> => T::Size,
+	[T: Classified, const N: usize] [T; N] => T::Size,
+	[T] Vec<T> => VariableSize, [T, ML] MaxVec<T, ML> => VariableSize,
+	[ML] BitList<ML> => VariableSize, [] String => VariableSize, [] Blob => VariableSize,
+	[T] Option<T> => VariableSize, [T] Union<T> => VariableSize,
+	[T: Composite] Compact<T> => T::Size
+);
+
+impl<T: Classified> Classified for Box<T> {
+	type Kind = T::Kind;
+	type Size = T::Size;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn is_basic<T: Classified>() -> bool {
+		T::Kind::IS_BASIC
+	}
+
+	fn is_fixed<T: Classified>() -> bool {
+		T::Size::IS_FIXED
+	}
+
+	#[test]
+	fn test_classification() {
+		assert!(is_basic::<u16>() && is_fixed::<u16>());
+		assert_eq!(<u16 as Basic>::PER_CHUNK, 16);
+		assert_eq!(<U256 as Basic>::PER_CHUNK, 1);
+		assert!(!is_basic::<H256>() && is_fixed::<H256>());
+		assert!(is_fixed::<[u64; 4]>() && is_fixed::<GenericArray<H256, typenum::U2>>());
+		assert!(!is_fixed::<[Vec<u8>; 2]>() && !is_fixed::<Option<u8>>());
+		assert!(!is_basic::<Compact<MaxVec<u8, typenum::U4>>>());
+		assert!(!is_fixed::<Compact<MaxVec<u8, typenum::U4>>>());
+		assert!(is_basic::<Box<u8>>());
+	}
+}
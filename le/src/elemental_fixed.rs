@@ -1,10 +1,21 @@
-use bm::{ReadBackend, WriteBackend, Construct, Error, DanglingPackedVector, DanglingVector, Leak, Sequence};
-use bm::utils::{vector_tree, host_max_len};
-use primitive_types::{H256, U256};
+use bm::{ReadBackend, WriteBackend, Construct, Error, DanglingPackedVector, DanglingVector, Leak};
+use bm::utils::{vector_tree, host_len, host_max_len, required_depth};
+use bm::{NoopBackend, NoopBackendError};
+#[cfg(feature = "parallel")]
+use bm::{InMemoryBackend, InMemoryBackendError};
+#[cfg(feature = "parallel")]
+use bm::utils::vector_tree_parallel;
+use primitive_types::U256;
 use generic_array::GenericArray;
 use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::ops::{Index, IndexMut};
+use core::slice::SliceIndex;
+use core::convert::TryFrom;
+use core::marker::PhantomData;
 
-use crate::{IntoTree, FromTree, Value, CompatibleConstruct};
+use crate::{IntoTree, FromTree, ChunkSize, CompatibleConstruct, Basic, DecodeMode, ChunkLayout, LayoutOf};
+use crate::mode::{write_uint, read_uint, check_limits, Layout};
 
 /// Traits for vector converting into a composite tree structure.
 pub trait IntoCompositeVectorTree {
@@ -37,7 +48,7 @@ pub trait FromCompositeVectorTree: Sized {
 	fn from_composite_vector_tree<DB: ReadBackend>(
 		root: &<DB::Construct as Construct>::Value,
 		db: &mut DB,
-		len: usize,
+		len: u64,
 		max_len: Option<u64>,
 	) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct;
@@ -50,7 +61,7 @@ pub trait FromCompactVectorTree: Sized {
 	fn from_compact_vector_tree<DB: ReadBackend>(
 		root: &<DB::Construct as Construct>::Value,
 		db: &mut DB,
-		len: usize,
+		len: u64,
 		max_len: Option<u64>,
 	) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct;
@@ -60,11 +71,86 @@ pub trait FromCompactVectorTree: Sized {
 /// Elemental `Vec` reference. In ssz's definition, this is a basic "vector".
 pub struct ElementalFixedVecRef<'a, T>(pub &'a [T]);
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 /// Elemental `Vec` value. In ssz's definition, this is a basic "vector".
 pub struct ElementalFixedVec<T>(pub Vec<T>);
 
+impl<T> AsRef<[T]> for ElementalFixedVec<T> {
+	fn as_ref(&self) -> &[T] {
+		&self.0
+	}
+}
+
+impl<T> FromIterator<T> for ElementalFixedVec<T> {
+	fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+		Self(iter.into_iter().collect())
+	}
+}
+
+impl<T> Extend<T> for ElementalFixedVec<T> {
+	fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		self.0.extend(iter)
+	}
+}
+
+impl<T> IntoIterator for ElementalFixedVec<T> {
+	type Item = T;
+	type IntoIter = alloc::vec::IntoIter<T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a, T> IntoIterator for &'a ElementalFixedVec<T> {
+	type Item = &'a T;
+	type IntoIter = core::slice::Iter<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
+
+impl<'a, T> IntoIterator for &'a mut ElementalFixedVec<T> {
+	type Item = &'a mut T;
+	type IntoIter = core::slice::IterMut<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter_mut()
+	}
+}
+
+impl<T, I: SliceIndex<[T]>> Index<I> for ElementalFixedVec<T> {
+	type Output = I::Output;
+
+	fn index(&self, index: I) -> &I::Output {
+		&self.0[index]
+	}
+}
+
+impl<T, I: SliceIndex<[T]>> IndexMut<I> for ElementalFixedVec<T> {
+	fn index_mut(&mut self, index: I) -> &mut I::Output {
+		&mut self.0[index]
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Encode> parity_codec::Encode for ElementalFixedVec<T> {
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		self.0.encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Decode> parity_codec::Decode for ElementalFixedVec<T> {
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		Some(Self(parity_codec::Decode::decode(input)?))
+	}
+}
+
 macro_rules! impl_builtin_fixed_uint_vector {
-	( $t:ty, $lt:ty ) => {
+	( $t:ty ) => {
 		impl<'a> IntoCompactVectorTree for ElementalFixedVecRef<'a, $t> {
 			fn into_compact_vector_tree<DB: WriteBackend>(
 				&self,
@@ -73,26 +159,30 @@ macro_rules! impl_builtin_fixed_uint_vector {
 			) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 				DB::Construct: CompatibleConstruct,
 			{
-				let mut chunks: Vec<Vec<u8>> = Vec::new();
+				if LayoutOf::<DB::Construct>::CHUNK_LAYOUT == ChunkLayout::Padded {
+					let chunks = self.0.iter().map(|value| {
+						let mut chunk = <DB::Construct as Construct>::Value::default();
+						write_uint::<LayoutOf<DB::Construct>>(&value.to_le_bytes(), chunk.0.as_mut());
+						chunk
+					}).collect::<Vec<_>>();
+
+					return vector_tree(&chunks, db, max_len)
+				}
+
+				let size = <<$t as Basic>::Len as typenum::Unsigned>::to_usize();
+				let mut chunks: Vec<<DB::Construct as Construct>::Value> = Vec::with_capacity(host_len::<ChunkSize<DB::Construct>, <$t as Basic>::Len>(self.0.len() as u64) as usize);
 
-				for value in self.0 {
-					if chunks.last().map(|v| v.len() == 32).unwrap_or(true) {
-						chunks.push(Vec::new());
+				for (i, value) in self.0.iter().enumerate() {
+					let offset = (i * size) % <ChunkSize<DB::Construct> as typenum::Unsigned>::to_usize();
+					if offset == 0 {
+						chunks.push(<DB::Construct as Construct>::Value::default());
 					}
 
 					let current = chunks.last_mut().expect("chunks must have at least one item; qed");
-					current.append(&mut value.to_le_bytes().into_iter().cloned().collect::<Vec<u8>>());
+					write_uint::<LayoutOf<DB::Construct>>(&value.to_le_bytes(), &mut current.0.as_mut()[offset..(offset + size)]);
 				}
 
-				if let Some(last) = chunks.last_mut() {
-					while last.len() < 32 {
-						last.push(0u8);
-					}
-				}
-
-				vector_tree(&chunks.into_iter().map(|c| {
-					Value(H256::from_slice(&c))
-				}).collect::<Vec<_>>(), db, max_len.map(|max| host_max_len::<typenum::U32, $lt>(max)))
+				vector_tree(&chunks, db, max_len.map(|max| host_max_len::<ChunkSize<DB::Construct>, <$t as Basic>::Len>(max)))
 			}
 		}
 
@@ -100,34 +190,264 @@ macro_rules! impl_builtin_fixed_uint_vector {
 			fn from_compact_vector_tree<DB: ReadBackend>(
 				root: &<DB::Construct as Construct>::Value,
 				db: &mut DB,
-				len: usize,
+				len: u64,
 				max_len: Option<u64>
 			) -> Result<Self, Error<DB::Error>> where
 				DB::Construct: CompatibleConstruct,
 			{
-				let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, $lt>, typenum::U32, $lt>::from_leaked(
+				check_limits(&db.decode_limits(), len, max_len)?;
+				let mut ret = Vec::new();
+				if LayoutOf::<DB::Construct>::CHUNK_LAYOUT == ChunkLayout::Padded {
+					check_empty::<DB::Construct, _>(root, len, max_len, DecodeMode::Strict)?;
+					let vector = DanglingVector::<DB::Construct>::from_leaked(
+						(root.clone(), len, max_len)
+					);
+					for chunk in vector.iter(db) {
+						let mut bytes = <$t>::default().to_le_bytes();
+						read_uint::<LayoutOf<DB::Construct>>(chunk?.as_ref(), &mut bytes);
+						ret.push(<$t>::from_le_bytes(bytes));
+					}
+
+					return Ok(Self(ret))
+				}
+
+				check_empty::<DB::Construct, _>(root, len, max_len.map(host_max_len::<ChunkSize<DB::Construct>, <$t as Basic>::Len>), DecodeMode::Strict)?;
+				let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, <$t as Basic>::Len>, ChunkSize<DB::Construct>, <$t as Basic>::Len>::from_leaked(
 					(root.clone(), len, max_len)
 				);
 
-				let mut ret = Vec::new();
-				for i in 0..len {
-					let value = packed.get(db, i)?;
-					let mut bytes = <$t>::default().to_le_bytes();
-					bytes.copy_from_slice(value.as_slice());
-					ret.push(<$t>::from_le_bytes(bytes));
+				for chunk in packed.chunks(db) {
+					let chunk = chunk?;
+					for value in chunk.as_ref().chunks(<<$t as Basic>::Len as typenum::Unsigned>::to_usize()) {
+						if ret.len() as u64 == len {
+							break
+						}
+
+						let mut bytes = <$t>::default().to_le_bytes();
+						read_uint::<LayoutOf<DB::Construct>>(value, &mut bytes);
+						ret.push(<$t>::from_le_bytes(bytes));
+					}
 				}
 
 				Ok(Self(ret))
 			}
 		}
+
+		impl ElementalFixedVec<$t> {
+			/// Decode like `from_compact_vector_tree` into `out`, whose
+			/// length is the length of the vector, without allocating.
+			pub fn from_compact_vector_tree_into<DB: ReadBackend>(
+				root: &<DB::Construct as Construct>::Value,
+				db: &mut DB,
+				out: &mut [$t],
+				max_len: Option<u64>
+			) -> Result<(), Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				let mut filled = 0;
+				if LayoutOf::<DB::Construct>::CHUNK_LAYOUT == ChunkLayout::Padded {
+					let vector = DanglingVector::<DB::Construct>::from_leaked(
+						(root.clone(), out.len() as u64, max_len)
+					);
+					for (slot, chunk) in out.iter_mut().zip(vector.iter(db)) {
+						let mut bytes = <$t>::default().to_le_bytes();
+						read_uint::<LayoutOf<DB::Construct>>(chunk?.as_ref(), &mut bytes);
+						*slot = <$t>::from_le_bytes(bytes);
+						filled += 1;
+					}
+				} else {
+					let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, <$t as Basic>::Len>, ChunkSize<DB::Construct>, <$t as Basic>::Len>::from_leaked(
+						(root.clone(), out.len() as u64, max_len)
+					);
+					for chunk in packed.chunks(db) {
+						let chunk = chunk?;
+						for value in chunk.as_ref().chunks(<<$t as Basic>::Len as typenum::Unsigned>::to_usize()) {
+							if filled == out.len() {
+								break
+							}
+
+							let mut bytes = <$t>::default().to_le_bytes();
+							read_uint::<LayoutOf<DB::Construct>>(value, &mut bytes);
+							out[filled] = <$t>::from_le_bytes(bytes);
+							filled += 1;
+						}
+					}
+				}
+
+				if filled != out.len() {
+					return Err(Error::corrupted())
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+impl_builtin_fixed_uint_vector!(u16);
+impl_builtin_fixed_uint_vector!(u32);
+impl_builtin_fixed_uint_vector!(u64);
+impl_builtin_fixed_uint_vector!(u128);
+// Byte vectors are the most common, and a byte is the same in either
+// endianness, so they are chunked directly instead of value by value.
+impl<'a> IntoCompactVectorTree for ElementalFixedVecRef<'a, u8> {
+	fn into_compact_vector_tree<DB: WriteBackend>(
+		&self,
+		db: &mut DB,
+		max_len: Option<u64>
+	) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		if LayoutOf::<DB::Construct>::CHUNK_LAYOUT == ChunkLayout::Padded {
+			let chunks = self.0.iter().map(|value| {
+				let mut chunk = <DB::Construct as Construct>::Value::default();
+				write_uint::<LayoutOf<DB::Construct>>(&[*value], chunk.0.as_mut());
+				chunk
+			}).collect::<Vec<_>>();
+
+			return vector_tree(&chunks, db, max_len)
+		}
+
+		let mut chunks: Vec<<DB::Construct as Construct>::Value> = Vec::with_capacity(
+			host_len::<ChunkSize<DB::Construct>, typenum::U1>(self.0.len() as u64) as usize
+		);
+		let mut exact = self.0.chunks_exact(<ChunkSize<DB::Construct> as typenum::Unsigned>::to_usize());
+		for bytes in &mut exact {
+			let mut chunk = <DB::Construct as Construct>::Value::default();
+			chunk.0.as_mut().copy_from_slice(bytes);
+			chunks.push(chunk);
+		}
+		let remainder = exact.remainder();
+		if !remainder.is_empty() {
+			let mut chunk = <DB::Construct as Construct>::Value::default();
+			chunk.0.as_mut()[..remainder.len()].copy_from_slice(remainder);
+			chunks.push(chunk);
+		}
+
+		vector_tree(&chunks, db, max_len.map(host_max_len::<ChunkSize<DB::Construct>, typenum::U1>))
+	}
+}
+
+impl FromCompactVectorTree for ElementalFixedVec<u8> {
+	fn from_compact_vector_tree<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		len: u64,
+		max_len: Option<u64>
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		check_limits(&db.decode_limits(), len, max_len)?;
+		if LayoutOf::<DB::Construct>::CHUNK_LAYOUT == ChunkLayout::Padded {
+			check_empty::<DB::Construct, _>(root, len, max_len, DecodeMode::Strict)?;
+			let vector = DanglingVector::<DB::Construct>::from_leaked(
+				(root.clone(), len, max_len)
+			);
+			let mut ret = Vec::new();
+			for chunk in vector.iter(db) {
+				let mut byte = [0u8];
+				read_uint::<LayoutOf<DB::Construct>>(chunk?.as_ref(), &mut byte);
+				ret.push(byte[0]);
+			}
+
+			return Ok(Self(ret))
+		}
+
+		check_empty::<DB::Construct, _>(root, len, max_len.map(host_max_len::<ChunkSize<DB::Construct>, typenum::U1>), DecodeMode::Strict)?;
+		let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, typenum::U1>, ChunkSize<DB::Construct>, typenum::U1>::from_leaked(
+			(root.clone(), len, max_len)
+		);
+
+		Ok(Self(packed.to_bytes(db)?))
+	}
+}
+
+impl ElementalFixedVec<u8> {
+	/// Decode like `from_compact_vector_tree` into `out`, whose length
+	/// is the length of the vector, without allocating.
+	pub fn from_compact_vector_tree_into<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		out: &mut [u8],
+		max_len: Option<u64>
+	) -> Result<(), Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut filled = 0;
+		if LayoutOf::<DB::Construct>::CHUNK_LAYOUT == ChunkLayout::Padded {
+			let vector = DanglingVector::<DB::Construct>::from_leaked(
+				(root.clone(), out.len() as u64, max_len)
+			);
+			for (slot, chunk) in out.iter_mut().zip(vector.iter(db)) {
+				read_uint::<LayoutOf<DB::Construct>>(chunk?.as_ref(), core::slice::from_mut(slot));
+				filled += 1;
+			}
+		} else {
+			let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, typenum::U1>, ChunkSize<DB::Construct>, typenum::U1>::from_leaked(
+				(root.clone(), out.len() as u64, max_len)
+			);
+			for chunk in packed.chunks(db) {
+				let chunk = chunk?;
+				let count = core::cmp::min(chunk.as_ref().len(), out.len() - filled);
+				out[filled..(filled + count)].copy_from_slice(&chunk.as_ref()[..count]);
+				filled += count;
+			}
+		}
+
+		if filled != out.len() {
+			return Err(Error::corrupted())
+		}
+		Ok(())
+	}
+}
+#[cfg(feature = "non-spec")]
+impl_builtin_fixed_uint_vector!(i8);
+#[cfg(feature = "non-spec")]
+impl_builtin_fixed_uint_vector!(i16);
+#[cfg(feature = "non-spec")]
+impl_builtin_fixed_uint_vector!(i32);
+#[cfg(feature = "non-spec")]
+impl_builtin_fixed_uint_vector!(i64);
+#[cfg(feature = "non-spec")]
+impl_builtin_fixed_uint_vector!(i128);
+
+#[cfg(feature = "non-spec")]
+macro_rules! impl_builtin_fixed_size_vector {
+	( $t:ty, $fixed:ty ) => {
+		impl<'a> IntoCompactVectorTree for ElementalFixedVecRef<'a, $t> {
+			fn into_compact_vector_tree<DB: WriteBackend>(
+				&self,
+				db: &mut DB,
+				max_len: Option<u64>
+			) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				let values = self.0.iter().map(|value| *value as $fixed).collect::<Vec<_>>();
+				ElementalFixedVecRef(&values).into_compact_vector_tree(db, max_len)
+			}
+		}
+
+		impl FromCompactVectorTree for ElementalFixedVec<$t> {
+			fn from_compact_vector_tree<DB: ReadBackend>(
+				root: &<DB::Construct as Construct>::Value,
+				db: &mut DB,
+				len: u64,
+				max_len: Option<u64>
+			) -> Result<Self, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				let values = ElementalFixedVec::<$fixed>::from_compact_vector_tree(root, db, len, max_len)?;
+				Ok(Self(values.0.into_iter()
+						.map(|value| core::convert::TryFrom::try_from(value).map_err(|_| Error::corrupted()))
+						.collect::<Result<Vec<$t>, _>>()?))
+			}
+		}
 	}
 }
 
-impl_builtin_fixed_uint_vector!(u8, typenum::U1);
-impl_builtin_fixed_uint_vector!(u16, typenum::U2);
-impl_builtin_fixed_uint_vector!(u32, typenum::U4);
-impl_builtin_fixed_uint_vector!(u64, typenum::U8);
-impl_builtin_fixed_uint_vector!(u128, typenum::U16);
+#[cfg(feature = "non-spec")]
+impl_builtin_fixed_size_vector!(usize, u64);
+#[cfg(feature = "non-spec")]
+impl_builtin_fixed_size_vector!(isize, i64);
 
 impl<'a> IntoCompactVectorTree for ElementalFixedVecRef<'a, U256> {
 	fn into_compact_vector_tree<DB: WriteBackend>(
@@ -138,8 +458,10 @@ impl<'a> IntoCompactVectorTree for ElementalFixedVecRef<'a, U256> {
 		DB::Construct: CompatibleConstruct,
 	{
 		vector_tree(&self.0.iter().map(|uint| {
-			let mut ret = Value::default();
-			uint.to_little_endian(&mut ret.0.as_mut());
+			let mut le = [0u8; 32];
+			uint.to_little_endian(&mut le);
+			let mut ret = <DB::Construct as Construct>::Value::default();
+			write_uint::<LayoutOf<DB::Construct>>(&le, ret.0.as_mut());
 			ret
 		}).collect::<Vec<_>>(), db, max_len)
 	}
@@ -149,19 +471,22 @@ impl FromCompactVectorTree for ElementalFixedVec<U256> {
 	fn from_compact_vector_tree<DB: ReadBackend>(
 		root: &<DB::Construct as Construct>::Value,
 		db: &mut DB,
-		len: usize,
+		len: u64,
 		max_len: Option<u64>
 	) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
+		check_limits(&db.decode_limits(), len, max_len)?;
+		check_empty::<DB::Construct, _>(root, len, max_len, DecodeMode::Strict)?;
 		let vector = DanglingVector::<DB::Construct>::from_leaked(
 			(root.clone(), len, max_len)
 		);
 
 		let mut ret = Vec::new();
-		for i in 0..len {
-			let value = vector.get(db, i)?;
-			ret.push(U256::from(value.as_ref()));
+		for value in vector.iter(db) {
+			let mut le = [0u8; 32];
+			read_uint::<LayoutOf<DB::Construct>>(value?.as_ref(), &mut le);
+			ret.push(U256::from_little_endian(&le));
 		}
 
 		Ok(Self(ret))
@@ -193,29 +518,53 @@ impl FromCompactVectorTree for ElementalFixedVec<bool> {
 	fn from_compact_vector_tree<DB: ReadBackend>(
 		root: &<DB::Construct as Construct>::Value,
 		db: &mut DB,
-		len: usize,
+		len: u64,
 		max_len: Option<u64>
 	) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, typenum::U1>, typenum::U32, typenum::U1>::from_leaked(
-			(root.clone(), (len + 7) / 8, max_len.map(|l| (l + 7) / 8))
+		Self::from_compact_vector_tree_with_mode(root, db, len, max_len, DecodeMode::Strict)
+	}
+}
+
+impl ElementalFixedVec<bool> {
+	/// Decode like `from_compact_vector_tree` in decode `mode`, which
+	/// is always `DecodeMode::Strict` for `FromTree`.
+	pub fn from_compact_vector_tree_with_mode<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		len: u64,
+		max_len: Option<u64>,
+		mode: DecodeMode,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		check_limits(&db.decode_limits(), len, max_len)?;
+		let max_bytes = max_len.map(|l| (l + 7) / 8);
+		check_empty::<DB::Construct, _>(root, len, max_bytes.map(host_max_len::<ChunkSize<DB::Construct>, typenum::U1>), mode)?;
+		let packed = DanglingPackedVector::<DB::Construct, GenericArray<u8, typenum::U1>, ChunkSize<DB::Construct>, typenum::U1>::from_leaked(
+			(root.clone(), (len + 7) / 8, max_bytes)
 		);
 
-		let mut bytes = Vec::new();
-		for i in 0..packed.len() {
-			bytes.push(packed.get(db, i)?[0]);
-		}
+		let bytes = packed.to_bytes(db)?;
+		let len = usize::try_from(len).map_err(|_| Error::InvalidParameter)?;
 		let mut ret = Vec::new();
 		for i in 0..len {
 			ret.push(bytes[i / 8] & (1 << (i % 8)) != 0);
 		}
-		// TODO: check to make sure rest of the bits are unset.
+		if mode == DecodeMode::Strict {
+			for i in len..(bytes.len() * 8) {
+				if bytes[i / 8] & (1 << (i % 8)) != 0 {
+					return Err(Error::NonCanonical)
+				}
+			}
+		}
 
 		Ok(Self(ret))
 	}
 }
 
+#[cfg(not(feature = "parallel"))]
 impl<'a, T> IntoCompositeVectorTree for ElementalFixedVecRef<'a, T> where
 	T: IntoTree,
 {
@@ -226,29 +575,76 @@ impl<'a, T> IntoCompositeVectorTree for ElementalFixedVecRef<'a, T> where
 	) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
+		#[cfg(feature = "tracing")]
+		let _span = tracing::trace_span!(target: "bm", "into_tree", ty = core::any::type_name::<T>(), len = self.0.len()).entered();
 		vector_tree(&self.0.iter().map(|value| {
 			value.into_tree(db)
 		}).collect::<Result<Vec<_>, _>>()?, db, max_len)
 	}
 }
 
+#[cfg(feature = "parallel")]
+impl<'a, T> IntoCompositeVectorTree for ElementalFixedVecRef<'a, T> where
+	T: IntoTree + Sync,
+{
+	fn into_composite_vector_tree<DB: WriteBackend>(
+		&self,
+		db: &mut DB,
+		max_len: Option<u64>
+	) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		use rayon::prelude::*;
+
+		#[cfg(feature = "tracing")]
+		let _span = tracing::trace_span!(target: "bm", "into_tree", ty = core::any::type_name::<T>(), len = self.0.len()).entered();
+		// Each element is merkleized into its own in-memory overlay, which
+		// is then merged into the target database sequentially.
+		let encoded = self.0.par_iter().map(|value| {
+			let mut overlay = InMemoryBackend::<DB::Construct>::default();
+			let root = value.into_tree(&mut overlay)?;
+			Ok((root, overlay))
+		}).collect::<Result<Vec<_>, Error<InMemoryBackendError>>>().map_err(|err| match err {
+			Error::CorruptedDatabase(context) => Error::CorruptedDatabase(context),
+			Error::Backend(_) => Error::corrupted(),
+			Error::AccessOverflowed(context) => Error::AccessOverflowed(context),
+			Error::InvalidParameter => Error::InvalidParameter,
+			Error::NonCanonical => Error::NonCanonical,
+			Error::MaxLenExceeded { len, max_len } => Error::MaxLenExceeded { len, max_len },
+		})?;
+
+		let mut roots = Vec::with_capacity(encoded.len());
+		for (root, overlay) in encoded {
+			for (key, (value, _)) in overlay.as_ref() {
+				if let Some(value) = value {
+					db.insert(key.clone(), value.clone())?;
+				}
+			}
+			roots.push(root);
+		}
+
+		vector_tree_parallel(&roots, db, max_len)
+	}
+}
+
 fn from_composite_vector_tree<T, F, DB: ReadBackend>(
 	root: &<DB::Construct as Construct>::Value,
 	db: &mut DB,
-	len: usize,
+	len: u64,
 	max_len: Option<u64>,
 	f: F
 ) -> Result<ElementalFixedVec<T>, Error<DB::Error>> where
 	DB::Construct: CompatibleConstruct,
 	F: Fn(&<DB::Construct as Construct>::Value, &mut DB) -> Result<T, Error<DB::Error>>
 {
+	check_limits(&db.decode_limits(), len, max_len)?;
+	check_empty::<DB::Construct, _>(root, len, max_len, DecodeMode::Strict)?;
 	let vector = DanglingVector::<DB::Construct>::from_leaked(
 		(root.clone(), len, max_len)
 	);
 	let mut ret = Vec::new();
 
-	for i in 0..len {
-		let value = vector.get(db, i)?;
+	for value in vector.get_many(db, &(0..len).collect::<Vec<_>>())? {
 		ret.push(f(&value, db)?);
 	}
 
@@ -259,7 +655,7 @@ impl<T: FromTree> FromCompositeVectorTree for ElementalFixedVec<T> {
 	fn from_composite_vector_tree<DB: ReadBackend>(
 		root: &<DB::Construct as Construct>::Value,
 		db: &mut DB,
-		len: usize,
+		len: u64,
 		max_len: Option<u64>
 	) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
@@ -268,6 +664,183 @@ impl<T: FromTree> FromCompositeVectorTree for ElementalFixedVec<T> {
 	}
 }
 
+/// Check in strict mode that the tree of an empty vector is the empty
+/// tree of `max_chunks` chunks, as written by encoders. Decoding other
+/// trees would accept roots that no value merkleizes to.
+fn check_empty<C: CompatibleConstruct, E>(
+	root: &C::Value,
+	len: u64,
+	max_chunks: Option<u64>,
+	mode: DecodeMode,
+) -> Result<(), Error<E>> {
+	if len != 0 || mode == DecodeMode::Lenient {
+		return Ok(())
+	}
+
+	let empty = C::empty_at(&mut NoopBackend::<C>::default(), required_depth(max_chunks.unwrap_or(0)))
+		.expect("Noop backend never fails in set; qed");
+	if &empty != root {
+		return Err(Error::NonCanonical)
+	}
+
+	Ok(())
+}
+
+/// Check that the claimed length fits in the maximum length.
+fn check_vector_len<E>(len: u64, max_len: Option<u64>) -> Result<(), Error<E>> {
+	if let Some(max_len) = max_len {
+		if len > max_len {
+			return Err(Error::MaxLenExceeded { len, max_len })
+		}
+	}
+
+	Ok(())
+}
+
+/// Check that the root calculated by `f` from the decoded value matches
+/// the tree.
+fn check_vector_root<C: CompatibleConstruct, E, F>(
+	root: &C::Value,
+	f: F,
+) -> Result<(), Error<E>> where
+	F: FnOnce(&mut NoopBackend<C>) -> Result<C::Value, Error<NoopBackendError>>,
+{
+	let expected = f(&mut NoopBackend::default()).map_err(|err| match err {
+		Error::CorruptedDatabase(context) => Error::CorruptedDatabase(context),
+		Error::Backend(_) => Error::corrupted(),
+		Error::AccessOverflowed(context) => Error::AccessOverflowed(context),
+		Error::InvalidParameter => Error::InvalidParameter,
+		Error::NonCanonical => Error::NonCanonical,
+		Error::MaxLenExceeded { len, max_len } => Error::MaxLenExceeded { len, max_len },
+	})?;
+	if &expected != root {
+		return Err(Error::NonCanonical)
+	}
+
+	Ok(())
+}
+
+/// Iterator decoding the items of a composite vector tree one by one.
+pub struct CompositeVectorTreeIter<'a, T, DB: ReadBackend> {
+	vector: DanglingVector<DB::Construct>,
+	index: u64,
+	db: &'a mut DB,
+	_marker: PhantomData<T>,
+}
+
+impl<'a, T: FromTree, DB: ReadBackend> Iterator for CompositeVectorTreeIter<'a, T, DB> where
+	DB::Construct: CompatibleConstruct,
+{
+	type Item = Result<T, Error<DB::Error>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index >= self.vector.len() {
+			return None
+		}
+
+		let index = self.index;
+		self.index += 1;
+		Some(self.vector.get(self.db, index).and_then(|value| T::from_tree(&value, self.db)))
+	}
+}
+
+impl<T: FromTree> ElementalFixedVec<T> {
+	/// Decode like `from_composite_vector_tree` into `out`, whose
+	/// length is the length of the vector, without allocating.
+	pub fn from_composite_vector_tree_into<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		out: &mut [T],
+		max_len: Option<u64>,
+	) -> Result<(), Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let vector = DanglingVector::<DB::Construct>::from_leaked(
+			(root.clone(), out.len() as u64, max_len)
+		);
+		let values = vector.get_many(db, &(0..out.len() as u64).collect::<Vec<_>>())?;
+		for (slot, value) in out.iter_mut().zip(values) {
+			*slot = T::from_tree(&value, db)?;
+		}
+
+		Ok(())
+	}
+
+	/// Decode the items of a composite tree lazily, for streaming large
+	/// vectors without collecting them.
+	pub fn composite_vector_tree_iter<'a, DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &'a mut DB,
+		len: u64,
+		max_len: Option<u64>,
+	) -> CompositeVectorTreeIter<'a, T, DB> where
+		DB::Construct: CompatibleConstruct,
+	{
+		CompositeVectorTreeIter {
+			vector: DanglingVector::<DB::Construct>::from_leaked((root.clone(), len, max_len)),
+			index: 0,
+			db,
+			_marker: PhantomData,
+		}
+	}
+
+	/// Convert from a composite tree like `from_composite_vector_tree`,
+	/// but reject lengths over the maximum length with
+	/// `Error::MaxLenExceeded`, and trees whose depth does not match
+	/// the maximum length, or whose leaves after the length are not
+	/// empty, with `Error::NonCanonical`.
+	pub fn from_composite_vector_tree_checked<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		len: u64,
+		max_len: Option<u64>,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		check_vector_len(len, max_len)?;
+
+		let vector = DanglingVector::<DB::Construct>::from_leaked(
+			(root.clone(), len, max_len)
+		);
+		let leaves = vector.get_many(db, &(0..len).collect::<Vec<_>>())?;
+		check_vector_root::<DB::Construct, _, _>(root, |noop| vector_tree(&leaves, noop, max_len))?;
+
+		Ok(Self(leaves.iter().map(|leaf| T::from_tree(leaf, db)).collect::<Result<_, _>>()?))
+	}
+}
+
+impl<T> ElementalFixedVec<T> where
+	Self: FromCompactVectorTree,
+	for<'a> ElementalFixedVecRef<'a, T>: IntoCompactVectorTree,
+{
+	/// Convert from a compact tree like `from_compact_vector_tree`, but
+	/// reject lengths over the maximum length with
+	/// `Error::MaxLenExceeded`, and trees that do not encode the
+	/// decoded values canonically, including trees of the wrong depth
+	/// or with non-zero bytes after the length, with
+	/// `Error::NonCanonical`.
+	pub fn from_compact_vector_tree_checked<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		len: u64,
+		max_len: Option<u64>,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		check_vector_len(len, max_len)?;
+
+		let value = Self::from_compact_vector_tree(root, db, len, max_len)?;
+		if value.0.len() as u64 != len {
+			return Err(Error::corrupted())
+		}
+		check_vector_root::<DB::Construct, _, _>(root, |noop| {
+			ElementalFixedVecRef(&value.0).into_compact_vector_tree(noop, max_len)
+		})?;
+
+		Ok(value)
+	}
+}
+
 impl<T> IntoCompactVectorTree for ElementalFixedVec<T> where
 	for<'a> ElementalFixedVecRef<'a, T>: IntoCompactVectorTree,
 {
@@ -295,3 +868,131 @@ impl<T> IntoCompositeVectorTree for ElementalFixedVec<T> where
 		ElementalFixedVecRef(&self.0).into_composite_vector_tree(db, max_len)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::DigestConstruct;
+
+	use bm::InMemoryBackend;
+	use sha2::Sha256;
+
+	#[test]
+	fn test_collection_traits() {
+		let mut vec = (0..4u16).collect::<ElementalFixedVec<_>>();
+		vec.extend(vec![4, 5]);
+		vec[0] = 9;
+		assert_eq!(vec.as_ref(), &[9, 1, 2, 3, 4, 5]);
+		assert_eq!(&vec[1..3], &[1, 2]);
+		for value in &mut vec {
+			*value += 1;
+		}
+		assert_eq!((&vec).into_iter().sum::<u16>(), 30);
+		assert_eq!(vec.into_iter().collect::<Vec<_>>(), vec![10, 2, 3, 4, 5, 6]);
+	}
+
+	#[test]
+	fn test_decode_into() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+
+		let data = (0..100u16).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&data).into_compact_vector_tree(&mut db, None).unwrap();
+		let mut out = [0u16; 100];
+		ElementalFixedVec::<u16>::from_compact_vector_tree_into(&encoded, &mut db, &mut out, None).unwrap();
+		assert_eq!(&out[..], &data[..]);
+
+		let bytes = (0..70u8).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&bytes).into_compact_vector_tree(&mut db, None).unwrap();
+		let mut out = [0u8; 70];
+		ElementalFixedVec::<u8>::from_compact_vector_tree_into(&encoded, &mut db, &mut out, None).unwrap();
+		assert_eq!(&out[..], &bytes[..]);
+
+		let pairs = (0..10u64).map(|i| (i, i * 2)).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&pairs).into_composite_vector_tree(&mut db, None).unwrap();
+		let mut out = [(0u64, 0u64); 10];
+		ElementalFixedVec::<(u64, u64)>::from_composite_vector_tree_into(&encoded, &mut db, &mut out, None).unwrap();
+		assert_eq!(&out[..], &pairs[..]);
+		let streamed = ElementalFixedVec::<(u64, u64)>::composite_vector_tree_iter(&encoded, &mut db, 10, None)
+			.collect::<Result<Vec<_>, _>>().unwrap();
+		assert_eq!(streamed, pairs);
+	}
+
+	#[test]
+	fn test_compact_bytes() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		for len in [0u8, 1, 31, 32, 33, 64, 100] {
+			let data = (0..len).collect::<Vec<_>>();
+			let encoded = ElementalFixedVecRef(&data).into_compact_vector_tree(&mut db, Some(128)).unwrap();
+			let expected = bm::OwnedPackedVector::<DigestConstruct<Sha256>, GenericArray<u8, typenum::U1>, ChunkSize<DigestConstruct<Sha256>>, typenum::U1>::from_bytes(
+				&mut db, &data, Some(128)
+			).unwrap();
+			assert_eq!(encoded, bm::Tree::root(&expected));
+			let decoded = ElementalFixedVec::<u8>::from_compact_vector_tree(&encoded, &mut db, data.len() as u64, Some(128)).unwrap();
+			assert_eq!(decoded.0, data);
+		}
+	}
+
+	#[test]
+	fn test_compact_roundtrip() {
+		let data = (0..1000u64).map(|i| i * 3).collect::<Vec<_>>();
+
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let encoded = ElementalFixedVecRef(&data).into_compact_vector_tree(&mut db, Some(1024)).unwrap();
+		let decoded = ElementalFixedVec::<u64>::from_compact_vector_tree(&encoded, &mut db, data.len() as u64, Some(1024)).unwrap();
+		assert_eq!(decoded.0, data);
+
+		let bools = (0..13).map(|i| i % 3 == 0).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&bools).into_compact_vector_tree(&mut db, None).unwrap();
+		let decoded = ElementalFixedVec::<bool>::from_compact_vector_tree(&encoded, &mut db, bools.len() as u64, None).unwrap();
+		assert_eq!(decoded.0, bools);
+	}
+
+	#[cfg(feature = "non-spec")]
+	#[test]
+	fn test_non_spec_roundtrip() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+
+		let data = (-50..50i16).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&data).into_compact_vector_tree(&mut db, None).unwrap();
+		let decoded = ElementalFixedVec::<i16>::from_compact_vector_tree(&encoded, &mut db, data.len() as u64, None).unwrap();
+		assert_eq!(decoded.0, data);
+		assert_eq!((-1i32).into_tree(&mut db).unwrap(), 0xffffffffu32.into_tree(&mut db).unwrap());
+
+		let sizes = (0..20usize).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&sizes).into_compact_vector_tree(&mut db, None).unwrap();
+		let expected = ElementalFixedVecRef(&(0..20u64).collect::<Vec<_>>()).into_compact_vector_tree(&mut db, None).unwrap();
+		assert_eq!(encoded, expected);
+		let decoded = ElementalFixedVec::<usize>::from_compact_vector_tree(&encoded, &mut db, sizes.len() as u64, None).unwrap();
+		assert_eq!(decoded.0, sizes);
+		assert_eq!(isize::from_tree(&(-3isize).into_tree(&mut db).unwrap(), &mut db).unwrap(), -3);
+	}
+	#[test]
+	fn test_composite_roundtrip() {
+		let data = (0..100u64).map(|i| (i, i * 2)).collect::<Vec<_>>();
+
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let encoded = ElementalFixedVecRef(&data).into_composite_vector_tree(&mut db, Some(128)).unwrap();
+		let decoded = ElementalFixedVec::<(u64, u64)>::from_composite_vector_tree(&encoded, &mut db, data.len() as u64, Some(128)).unwrap();
+		assert_eq!(decoded.0, data);
+	}
+
+	#[test]
+	fn test_checked() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+
+		let data = (1..11u64).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&data).into_compact_vector_tree(&mut db, Some(16)).unwrap();
+		let decoded = ElementalFixedVec::<u64>::from_compact_vector_tree_checked(&encoded, &mut db, 10, Some(16)).unwrap();
+		assert_eq!(decoded.0, data);
+		assert_eq!(ElementalFixedVec::<u64>::from_compact_vector_tree_checked(&encoded, &mut db, 9, Some(16)), Err(Error::NonCanonical));
+		assert!(ElementalFixedVec::<u64>::from_compact_vector_tree_checked(&encoded, &mut db, 10, Some(64)).is_err());
+		assert_eq!(ElementalFixedVec::<u64>::from_compact_vector_tree_checked(&encoded, &mut db, 20, Some(16)), Err(Error::MaxLenExceeded { len: 20, max_len: 16 }));
+
+		let data = (1..6u64).map(|i| (i, i * 2)).collect::<Vec<_>>();
+		let encoded = ElementalFixedVecRef(&data).into_composite_vector_tree(&mut db, Some(8)).unwrap();
+		let decoded = ElementalFixedVec::<(u64, u64)>::from_composite_vector_tree_checked(&encoded, &mut db, 5, Some(8)).unwrap();
+		assert_eq!(decoded.0, data);
+		assert_eq!(ElementalFixedVec::<(u64, u64)>::from_composite_vector_tree_checked(&encoded, &mut db, 4, Some(8)), Err(Error::NonCanonical));
+		assert_eq!(ElementalFixedVec::<(u64, u64)>::from_composite_vector_tree_checked(&encoded, &mut db, 5, Some(4)), Err(Error::MaxLenExceeded { len: 5, max_len: 4 }));
+	}
+}
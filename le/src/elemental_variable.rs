@@ -1,10 +1,13 @@
 use bm::{Error, ReadBackend, WriteBackend, Construct};
 use primitive_types::U256;
 use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::ops::{Index, IndexMut};
+use core::slice::SliceIndex;
 
 use crate::{ElementalFixedVec, FromCompactVectorTree, FromCompositeVectorTree,
 			ElementalFixedVecRef, IntoCompactVectorTree,
-			IntoCompositeVectorTree, CompatibleConstruct};
+			IntoCompositeVectorTree, CompatibleConstruct, DecodeMode};
 use crate::utils::{mix_in_length, decode_with_length};
 
 /// Traits for list converting into a tree structure.
@@ -59,9 +62,84 @@ pub trait FromCompactListTree: Sized {
 /// Variable `Vec` reference. In `ssz`'s definition, this is a "list".
 pub struct ElementalVariableVecRef<'a, T>(pub &'a [T]);
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 /// Variable `Vec` value. In `ssz`'s definition, this is a "list".
 pub struct ElementalVariableVec<T>(pub Vec<T>);
 
+impl<T> AsRef<[T]> for ElementalVariableVec<T> {
+	fn as_ref(&self) -> &[T] {
+		&self.0
+	}
+}
+
+impl<T> FromIterator<T> for ElementalVariableVec<T> {
+	fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+		Self(iter.into_iter().collect())
+	}
+}
+
+impl<T> Extend<T> for ElementalVariableVec<T> {
+	fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		self.0.extend(iter)
+	}
+}
+
+impl<T> IntoIterator for ElementalVariableVec<T> {
+	type Item = T;
+	type IntoIter = alloc::vec::IntoIter<T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a, T> IntoIterator for &'a ElementalVariableVec<T> {
+	type Item = &'a T;
+	type IntoIter = core::slice::Iter<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
+
+impl<'a, T> IntoIterator for &'a mut ElementalVariableVec<T> {
+	type Item = &'a mut T;
+	type IntoIter = core::slice::IterMut<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter_mut()
+	}
+}
+
+impl<T, I: SliceIndex<[T]>> Index<I> for ElementalVariableVec<T> {
+	type Output = I::Output;
+
+	fn index(&self, index: I) -> &I::Output {
+		&self.0[index]
+	}
+}
+
+impl<T, I: SliceIndex<[T]>> IndexMut<I> for ElementalVariableVec<T> {
+	fn index_mut(&mut self, index: I) -> &mut I::Output {
+		&mut self.0[index]
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Encode> parity_codec::Encode for ElementalVariableVec<T> {
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		self.0.encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Decode> parity_codec::Decode for ElementalVariableVec<T> {
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		Some(Self(parity_codec::Decode::decode(input)?))
+	}
+}
+
 macro_rules! impl_packed {
 	( $t:ty ) => {
 		impl<'a> IntoCompactListTree for ElementalVariableVecRef<'a, $t> {
@@ -72,7 +150,7 @@ macro_rules! impl_packed {
 			) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 				DB::Construct: CompatibleConstruct,
 			{
-				let len = self.0.len();
+				let len = self.0.len() as u64;
 
 				mix_in_length(&ElementalFixedVecRef(&self.0).into_compact_vector_tree(db, max_len)?,
 							  db, len)
@@ -88,6 +166,20 @@ impl_packed!(u32);
 impl_packed!(u64);
 impl_packed!(u128);
 impl_packed!(U256);
+#[cfg(feature = "non-spec")]
+impl_packed!(i8);
+#[cfg(feature = "non-spec")]
+impl_packed!(i16);
+#[cfg(feature = "non-spec")]
+impl_packed!(i32);
+#[cfg(feature = "non-spec")]
+impl_packed!(i64);
+#[cfg(feature = "non-spec")]
+impl_packed!(i128);
+#[cfg(feature = "non-spec")]
+impl_packed!(usize);
+#[cfg(feature = "non-spec")]
+impl_packed!(isize);
 
 impl<'a, T> IntoCompositeListTree for ElementalVariableVecRef<'a, T> where
 	for<'b> ElementalFixedVecRef<'b, T>: IntoCompositeVectorTree,
@@ -99,7 +191,7 @@ impl<'a, T> IntoCompositeListTree for ElementalVariableVecRef<'a, T> where
 	) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let len = self.0.len();
+		let len = self.0.len() as u64;
 
 		mix_in_length(&ElementalFixedVecRef(&self.0).into_composite_vector_tree(db, max_len)?,
 					  db, len)
@@ -113,9 +205,14 @@ fn from_list_tree<T, F, DB: ReadBackend>(
 	f: F
 ) -> Result<ElementalVariableVec<T>, Error<DB::Error>> where
 	DB::Construct: CompatibleConstruct,
-	F: FnOnce(&<DB::Construct as Construct>::Value, &mut DB, usize, Option<u64>) -> Result<ElementalFixedVec<T>, Error<DB::Error>>
+	F: FnOnce(&<DB::Construct as Construct>::Value, &mut DB, u64, Option<u64>) -> Result<ElementalFixedVec<T>, Error<DB::Error>>
 {
 	let (vector_root, len) = decode_with_length::<<DB::Construct as Construct>::Value, _>(root, db)?;
+	if let Some(max_len) = max_len {
+		if len > max_len {
+			return Err(Error::MaxLenExceeded { len, max_len })
+		}
+	}
 
 	let vector = f(
 		&vector_root, db, len, max_len
@@ -142,6 +239,25 @@ impl<T> FromCompactListTree for ElementalVariableVec<T> where
 	}
 }
 
+impl ElementalVariableVec<bool> {
+	/// Decode like `from_compact_list_tree` in decode `mode`, which is
+	/// always `DecodeMode::Strict` for `FromTree`.
+	pub fn from_compact_list_tree_with_mode<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		max_len: Option<u64>,
+		mode: DecodeMode,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		from_list_tree(root, db, max_len, |vector_root, db, len, max_len| {
+			ElementalFixedVec::<bool>::from_compact_vector_tree_with_mode(
+				vector_root, db, len, max_len, mode
+			)
+		})
+	}
+}
+
 impl<T> FromCompositeListTree for ElementalVariableVec<T> where
 	ElementalFixedVec<T>: FromCompositeVectorTree,
 {
@@ -191,11 +307,22 @@ impl<T> IntoCompositeListTree for ElementalVariableVec<T> where
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::{IntoTree, FromTree, DigestConstruct};
+	use crate::{IntoTree, FromTree, DigestConstruct, Value};
 
 	use bm::InMemoryBackend;
 	use sha2::Sha256;
 
+	#[test]
+	fn test_collection_traits() {
+		let mut list = (0..3u32).collect::<crate::MaxVec<_, typenum::U8>>();
+		list.extend(Some(7));
+		list[1] = 5;
+		assert_eq!(list.as_ref(), &[0, 5, 2, 7]);
+		assert_eq!(list.iter().rev().cloned().collect::<ElementalVariableVec<_>>().0, vec![7, 2, 5, 0]);
+		assert_eq!((&list).into_iter().max(), Some(&7));
+		assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 5, 2, 7]);
+	}
+
 	#[test]
 	fn test_plain() {
 		let data = {
@@ -211,4 +338,62 @@ mod tests {
 		let decoded = Vec::<u16>::from_tree(&encoded, &mut db).unwrap();
 		assert_eq!(data, decoded);
 	}
+
+	#[test]
+	fn test_max_len() {
+		use crate::MaxVec;
+		use typenum::{U2, U4};
+
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let data = MaxVec::<u64, U4>::from(vec![1, 2, 3]);
+		let encoded = data.into_tree(&mut db).unwrap();
+		assert_eq!(MaxVec::<u64, U4>::from_tree(&encoded, &mut db).unwrap(), data);
+		assert_eq!(MaxVec::<u64, U2>::from_tree(&encoded, &mut db), Err(Error::MaxLenExceeded { len: 3, max_len: 2 }));
+
+		let data = MaxVec::<(u64, u64), U2>::from(vec![(1, 2), (3, 4), (5, 6)]);
+		assert_eq!(data.into_tree(&mut db), Err(Error::InvalidParameter));
+	}
+
+	#[test]
+	fn test_empty() {
+		use sha2::Digest;
+
+		let zero = |depth: usize| (0..depth).fold(Value::default(), |node, _| {
+			let mut pair = node.as_ref().to_vec();
+			pair.extend_from_slice(node.as_ref());
+			Value::from(primitive_types::H256::from_slice(&Sha256::digest(&pair)))
+		});
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let empty_list = |db: &mut InMemoryBackend<DigestConstruct<Sha256>>, depth| {
+			mix_in_length(&zero(depth), db, 0).unwrap()
+		};
+
+		let root = ElementalVariableVecRef::<u8>(&[]).into_compact_list_tree(&mut db, Some(1024)).unwrap();
+		assert_eq!(root, empty_list(&mut db, 5));
+		assert_eq!(ElementalVariableVec::<u8>::from_compact_list_tree(&root, &mut db, Some(1024)).unwrap().0,
+				   Vec::<u8>::new());
+		let root = ElementalVariableVecRef::<u64>(&[]).into_compact_list_tree(&mut db, Some(5)).unwrap();
+		assert_eq!(root, empty_list(&mut db, 1));
+		let root = ElementalVariableVecRef::<bool>(&[]).into_compact_list_tree(&mut db, Some(512)).unwrap();
+		assert_eq!(root, empty_list(&mut db, 1));
+		assert_eq!(ElementalVariableVec::<bool>::from_compact_list_tree(&root, &mut db, Some(512)).unwrap().0,
+				   Vec::<bool>::new());
+		let root = ElementalVariableVecRef::<U256>(&[]).into_compact_list_tree(&mut db, Some(4)).unwrap();
+		assert_eq!(root, empty_list(&mut db, 2));
+		let root = ElementalVariableVecRef::<(u64, u64)>(&[]).into_composite_list_tree(&mut db, Some(4)).unwrap();
+		assert_eq!(root, empty_list(&mut db, 2));
+		assert_eq!(ElementalVariableVec::<(u64, u64)>::from_composite_list_tree(&root, &mut db, Some(4)).unwrap().0,
+				   Vec::<(u64, u64)>::new());
+		let root = ElementalFixedVecRef::<u16>(&[]).into_compact_vector_tree(&mut db, None).unwrap();
+		assert_eq!(root, zero(0));
+		assert_eq!(ElementalFixedVec::<u16>::from_compact_vector_tree(&root, &mut db, 0, None).unwrap().0,
+				   Vec::<u16>::new());
+
+		let stray = ElementalFixedVecRef(&[1u64]).into_compact_vector_tree(&mut db, Some(5)).unwrap();
+		let stray = mix_in_length(&stray, &mut db, 0).unwrap();
+		assert_eq!(ElementalVariableVec::<u64>::from_compact_list_tree(&stray, &mut db, Some(5)),
+				   Err(Error::NonCanonical));
+		assert_eq!(ElementalVariableVec::<(u64, u64)>::from_composite_list_tree(&stray, &mut db, Some(2)),
+				   Err(Error::NonCanonical));
+	}
 }
@@ -1,6 +1,6 @@
 use bm::{ReadBackend, WriteBackend, Construct, Error, DanglingVector, Leak};
 use bm::utils::vector_tree;
-use primitive_types::{H256, H512};
+use primitive_types::{H160, H256, H512};
 use generic_array::{GenericArray, ArrayLength};
 use vecarray::VecArray;
 use typenum::Unsigned;
@@ -8,9 +8,10 @@ use core::convert::TryFrom;
 use alloc::vec::Vec;
 use crate::{ElementalFixedVecRef, ElementalFixedVec, IntoCompositeVectorTree,
 			IntoCompactVectorTree, IntoTree, FromTree, FromCompositeVectorTree,
-			FromCompactVectorTree, Compact, CompactRef, CompatibleConstruct};
+			FromCompactVectorTree, Compact, CompactRef, CompatibleConstruct, Basic};
 
 impl<'a, T, L: ArrayLength<T>> IntoTree for CompactRef<'a, GenericArray<T, L>> where
+	T: Basic,
 	for<'b> ElementalFixedVecRef<'b, T>: IntoCompactVectorTree,
 {
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
@@ -21,6 +22,7 @@ impl<'a, T, L: ArrayLength<T>> IntoTree for CompactRef<'a, GenericArray<T, L>> w
 }
 
 impl<T, L: ArrayLength<T>> IntoTree for Compact<GenericArray<T, L>> where
+	T: Basic,
 	for<'a> ElementalFixedVecRef<'a, T>: IntoCompactVectorTree,
 {
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
@@ -31,13 +33,13 @@ impl<T, L: ArrayLength<T>> IntoTree for Compact<GenericArray<T, L>> where
 }
 
 impl<T, L: ArrayLength<T>> FromTree for Compact<GenericArray<T, L>> where
-	T: Default,
+	T: Basic + Default,
 	ElementalFixedVec<T>: FromCompactVectorTree,
 {
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let value = ElementalFixedVec::<T>::from_compact_vector_tree(root, db, L::to_usize(), None)?;
+		let value = ElementalFixedVec::<T>::from_compact_vector_tree(root, db, L::to_u64(), None)?;
 		let mut ret = GenericArray::default();
 		for (i, v) in value.0.into_iter().enumerate() {
 			ret[i] = v;
@@ -47,6 +49,7 @@ impl<T, L: ArrayLength<T>> FromTree for Compact<GenericArray<T, L>> where
 }
 
 impl<'a, T, L: Unsigned> IntoTree for CompactRef<'a, VecArray<T, L>> where
+	T: Basic,
 	for<'b> ElementalFixedVecRef<'b, T>: IntoCompactVectorTree,
 {
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
@@ -57,6 +60,7 @@ impl<'a, T, L: Unsigned> IntoTree for CompactRef<'a, VecArray<T, L>> where
 }
 
 impl<T, L: Unsigned> IntoTree for Compact<VecArray<T, L>> where
+	T: Basic,
 	for<'a> ElementalFixedVecRef<'a, T>: IntoCompactVectorTree,
 {
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
@@ -67,85 +71,230 @@ impl<T, L: Unsigned> IntoTree for Compact<VecArray<T, L>> where
 }
 
 impl<T, L: Unsigned> FromTree for Compact<VecArray<T, L>> where
-	T: Default,
+	T: Basic + Default,
 	ElementalFixedVec<T>: FromCompactVectorTree,
 {
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let value = ElementalFixedVec::<T>::from_compact_vector_tree(root, db, L::to_usize(), None)?;
-		Ok(Self(VecArray::try_from(value.0).map_err(|_| Error::CorruptedDatabase)?))
+		let value = ElementalFixedVec::<T>::from_compact_vector_tree(root, db, L::to_u64(), None)?;
+		Ok(Self(VecArray::try_from(value.0).map_err(|_| Error::corrupted())?))
 	}
 }
 
-impl IntoTree for H256 {
+macro_rules! construct_hash {
+	( $( #[$attr:meta] $name:ident => $len:expr ),* ) => { $(
+		#[$attr]
+		#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+		pub struct $name(pub [u8; $len]);
+
+		impl $name {
+			/// Create from a slice. Panics if the slice length mismatches.
+			pub fn from_slice(slice: &[u8]) -> Self {
+				let mut ret = Self::default();
+				ret.0.copy_from_slice(slice);
+				ret
+			}
+
+			/// Zero value.
+			pub fn zero() -> Self {
+				Self::default()
+			}
+		}
+
+		impl Default for $name {
+			fn default() -> Self {
+				Self([0u8; $len])
+			}
+		}
+
+		impl AsRef<[u8]> for $name {
+			fn as_ref(&self) -> &[u8] {
+				&self.0[..]
+			}
+		}
+
+		impl AsMut<[u8]> for $name {
+			fn as_mut(&mut self) -> &mut [u8] {
+				&mut self.0[..]
+			}
+		}
+
+		impl From<[u8; $len]> for $name {
+			fn from(value: [u8; $len]) -> Self {
+				Self(value)
+			}
+		}
+	)* }
+}
+
+construct_hash!(
+	/// 384-bit hash type, as of a BLS public key.
+	H384 => 48,
+	/// 768-bit hash type, as of a BLS signature.
+	H768 => 96
+);
+
+macro_rules! impl_fixed_hash {
+	( $( $t:ty => $len:expr ),* ) => { $(
+		impl IntoTree for $t {
+			fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				ElementalFixedVecRef(&self.0.as_ref()).into_compact_vector_tree(db, None)
+			}
+		}
+
+		impl FromTree for $t {
+			fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				let value = ElementalFixedVec::<u8>::from_compact_vector_tree(root, db, $len, None)?;
+				Ok(Self::from_slice(value.0.as_ref()))
+			}
+		}
+	)* }
+}
+
+impl_fixed_hash!(H160 => 20, H256 => 32, H384 => 48, H512 => 64, H768 => 96);
+
+impl<T, const N: usize> IntoTree for [T; N] where
+	for<'a> ElementalFixedVecRef<'a, T>: IntoCompositeVectorTree,
+{
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		ElementalFixedVecRef(&self.0.as_ref()).into_compact_vector_tree(db, None)
+		ElementalFixedVecRef(&self[..]).into_composite_vector_tree(db, None)
 	}
 }
 
-impl FromTree for H256 {
+impl<T, const N: usize> FromTree for [T; N] where
+	for<'a> ElementalFixedVec<T>: FromCompositeVectorTree,
+{
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let value = ElementalFixedVec::<u8>::from_compact_vector_tree(root, db, 32, None)?;
-		Ok(Self::from_slice(value.0.as_ref()))
+		let value = ElementalFixedVec::<T>::from_composite_vector_tree(root, db, N as u64, None)?;
+		<[T; N]>::try_from(value.0).map_err(|_| Error::corrupted())
+	}
+}
+
+impl<'a, T, const N: usize> IntoTree for CompactRef<'a, [T; N]> where
+	T: Basic,
+	for<'b> ElementalFixedVecRef<'b, T>: IntoCompactVectorTree,
+{
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalFixedVecRef(&self.0[..]).into_compact_vector_tree(db, None)
 	}
 }
 
-impl IntoTree for H512 {
+impl<T, const N: usize> IntoTree for Compact<[T; N]> where
+	T: Basic,
+	for<'a> ElementalFixedVecRef<'a, T>: IntoCompactVectorTree,
+{
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		ElementalFixedVecRef(&self.0.as_ref()).into_compact_vector_tree(db, None)
+		ElementalFixedVecRef(&self.0[..]).into_compact_vector_tree(db, None)
 	}
 }
 
-impl FromTree for H512 {
+impl<T, const N: usize> FromTree for Compact<[T; N]> where
+	T: Basic,
+	ElementalFixedVec<T>: FromCompactVectorTree,
+{
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let value = ElementalFixedVec::<u8>::from_compact_vector_tree(root, db, 32, None)?;
-		Ok(Self::from_slice(value.0.as_ref()))
+		let value = ElementalFixedVec::<T>::from_compact_vector_tree(root, db, N as u64, None)?;
+		Ok(Self(<[T; N]>::try_from(value.0).map_err(|_| Error::corrupted())?))
 	}
 }
 
-macro_rules! impl_fixed_array {
-	( $( $n:expr ),* ) => { $(
-		impl<T> IntoTree for [T; $n] where
+/// Implement the vector tree traits for a fixed-size array type, whose
+/// length given when decoding must match its own.
+macro_rules! impl_fixed_vector_tree {
+	( [ $( $generics:tt )* ], $ty:ty, $len:expr, $from_vec:expr ) => {
+		impl<$( $generics )*> IntoCompositeVectorTree for $ty where
 			for<'a> ElementalFixedVecRef<'a, T>: IntoCompositeVectorTree,
 		{
-			fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+			fn into_composite_vector_tree<DB: WriteBackend>(
+				&self,
+				db: &mut DB,
+				max_len: Option<u64>
+			) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 				DB::Construct: CompatibleConstruct,
 			{
-				ElementalFixedVecRef(&self[..]).into_composite_vector_tree(db, None)
+				ElementalFixedVecRef(&self[..]).into_composite_vector_tree(db, max_len)
 			}
 		}
 
-		impl<T> FromTree for [T; $n] where
-			T: Default + Copy,
-			for<'a> ElementalFixedVec<T>: FromCompositeVectorTree,
+		impl<$( $generics )*> IntoCompactVectorTree for $ty where
+			for<'a> ElementalFixedVecRef<'a, T>: IntoCompactVectorTree,
 		{
-			fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+			fn into_compact_vector_tree<DB: WriteBackend>(
+				&self,
+				db: &mut DB,
+				max_len: Option<u64>
+			) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 				DB::Construct: CompatibleConstruct,
 			{
-				let value = ElementalFixedVec::<T>::from_composite_vector_tree(root, db, $n, None)?;
-				let mut ret = [T::default(); $n];
-				for (i, v) in value.0.into_iter().enumerate() {
-					ret[i] = v;
+				ElementalFixedVecRef(&self[..]).into_compact_vector_tree(db, max_len)
+			}
+		}
+
+		impl<$( $generics )*> FromCompositeVectorTree for $ty where
+			ElementalFixedVec<T>: FromCompositeVectorTree,
+		{
+			fn from_composite_vector_tree<DB: ReadBackend>(
+				root: &<DB::Construct as Construct>::Value,
+				db: &mut DB,
+				len: u64,
+				max_len: Option<u64>,
+			) -> Result<Self, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				if len != $len {
+					return Err(Error::InvalidParameter)
 				}
-				Ok(ret)
+
+				let value = ElementalFixedVec::<T>::from_composite_vector_tree(root, db, len, max_len)?;
+				$from_vec(value.0).ok_or_else(Error::corrupted)
 			}
 		}
-	)* }
+
+		impl<$( $generics )*> FromCompactVectorTree for $ty where
+			ElementalFixedVec<T>: FromCompactVectorTree,
+		{
+			fn from_compact_vector_tree<DB: ReadBackend>(
+				root: &<DB::Construct as Construct>::Value,
+				db: &mut DB,
+				len: u64,
+				max_len: Option<u64>,
+			) -> Result<Self, Error<DB::Error>> where
+				DB::Construct: CompatibleConstruct,
+			{
+				if len != $len {
+					return Err(Error::InvalidParameter)
+				}
+
+				let value = ElementalFixedVec::<T>::from_compact_vector_tree(root, db, len, max_len)?;
+				$from_vec(value.0).ok_or_else(Error::corrupted)
+			}
+		}
+	}
 }
 
-impl_fixed_array!(1, 2, 3, 4, 5, 6, 7, 8,
-				  9, 10, 11, 12, 13, 14, 15, 16,
-				  17, 18, 19, 20, 21, 22, 23, 24,
-				  25, 26, 27, 28, 29, 30, 31, 32);
+impl_fixed_vector_tree!(
+	[T, const N: usize], [T; N], N as u64,
+	|value: Vec<T>| <[T; N]>::try_from(value).ok()
+);
+impl_fixed_vector_tree!(
+	[T, L: ArrayLength<T>], GenericArray<T, L>, L::to_u64(),
+	|value: Vec<T>| GenericArray::from_exact_iter(value)
+);
 
 impl<T, L: ArrayLength<T>> IntoTree for GenericArray<T, L> where
 	for<'a> ElementalFixedVecRef<'a, T>: IntoCompositeVectorTree,
@@ -163,7 +312,7 @@ impl<T, L: ArrayLength<T>> FromTree for GenericArray<T, L> where
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let value = ElementalFixedVec::<T>::from_composite_vector_tree(root, db, L::to_usize(), None)?;
+		let value = ElementalFixedVec::<T>::from_composite_vector_tree(root, db, L::to_u64(), None)?;
 		Ok(GenericArray::from_exact_iter(value.0)
 		   .expect("Fixed vec must build vector with L::as_usize; qed"))
 	}
@@ -185,8 +334,8 @@ impl<T, L: Unsigned> FromTree for VecArray<T, L> where
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct,
 	{
-		let value = ElementalFixedVec::<T>::from_composite_vector_tree(root, db, L::to_usize(), None)?;
-		Ok(VecArray::try_from(value.0).map_err(|_| Error::CorruptedDatabase)?)
+		let value = ElementalFixedVec::<T>::from_composite_vector_tree(root, db, L::to_u64(), None)?;
+		Ok(VecArray::try_from(value.0).map_err(|_| Error::corrupted())?)
 	}
 }
 
@@ -197,7 +346,7 @@ impl FromTree for () {
 		if root == &Default::default() {
 			Ok(())
 		} else {
-			Err(Error::CorruptedDatabase)
+			Err(Error::corrupted())
 		}
 	}
 }
@@ -226,15 +375,15 @@ macro_rules! impl_tuple {
 						i += 1;
 					}
 					value
-				}),+))
+				},)+))
 			}
 		}
 
-		impl<$($t: IntoTree),+> IntoTree for ($($t),+) {
+		impl<$($t: IntoTree),+> IntoTree for ($($t,)+) {
 			fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 				DB::Construct: CompatibleConstruct,
 			{
-				let ($($i),+) = self;
+				let ($($i,)+) = self;
 				let mut vector = Vec::new();
 				$({
 					vector.push($i.into_tree(db)?);
@@ -245,6 +394,7 @@ macro_rules! impl_tuple {
 	}
 }
 
+impl_tuple!(1, a => A);
 impl_tuple!(2, a => A, b => B);
 impl_tuple!(3, a => A, b => B, c => C);
 impl_tuple!(4, a => A, b => B, c => C, d => D);
@@ -253,3 +403,6 @@ impl_tuple!(6, a => A, b => B, c => C, d => D, e => E, f => F);
 impl_tuple!(7, a => A, b => B, c => C, d => D, e => E, f => F, g => G);
 impl_tuple!(8, a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H);
 impl_tuple!(9, a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I);
+impl_tuple!(10, a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I, j => J);
+impl_tuple!(11, a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I, j => J, k => K);
+impl_tuple!(12, a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I, j => J, k => K, l => L);
@@ -0,0 +1,206 @@
+//! Generalized index computation, compatible with the ssz spec's
+//! `get_generalized_index`.
+
+use bm::{Index, IndexSelection};
+use bm::utils::required_depth;
+
+/// Number of bytes in a chunk.
+pub const CHUNK_LEN: usize = 32;
+
+/// Element of a path from a root to a value.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PathElement {
+	/// Field at position `index` of a container with `total` fields.
+	Field {
+		/// Position of the field.
+		index: usize,
+		/// Number of fields in the container.
+		total: usize,
+	},
+	/// Item of a vector with `len` items. `packed` is the byte length
+	/// of a basic item packed into chunks, or `None` for a composite
+	/// item.
+	VectorItem {
+		/// Position of the item.
+		index: usize,
+		/// Length of the vector.
+		len: usize,
+		/// Byte length of packed basic items.
+		packed: Option<usize>,
+	},
+	/// Item of a list with maximum length `max_len`. `packed` is the
+	/// byte length of a basic item packed into chunks, or `None` for a
+	/// composite item.
+	ListItem {
+		/// Position of the item.
+		index: usize,
+		/// Maximum length of the list.
+		max_len: usize,
+		/// Byte length of packed basic items.
+		packed: Option<usize>,
+	},
+	/// Length mixed into a list, the spec's `__len__`.
+	ListLength,
+	/// Value of a union.
+	UnionValue,
+	/// Selector mixed into a union.
+	UnionSelector,
+}
+
+/// Number of chunks used by `len` items. `packed` is the byte length of
+/// packed basic items, or `None` for composite items.
+pub fn chunk_count(len: usize, packed: Option<usize>) -> usize {
+	match packed {
+		Some(item_len) => (len * item_len).div_ceil(CHUNK_LEN),
+		None => len,
+	}
+}
+
+/// Position of the chunk containing the item at `index`.
+pub fn chunk_index(index: usize, packed: Option<usize>) -> usize {
+	match packed {
+		Some(item_len) => index * item_len / CHUNK_LEN,
+		None => index,
+	}
+}
+
+/// Byte offset of the item at `index` inside its chunk.
+pub fn chunk_offset(index: usize, packed: Option<usize>) -> usize {
+	match packed {
+		Some(item_len) => index * item_len % CHUNK_LEN,
+		None => 0,
+	}
+}
+
+/// Position of the item at `index`, as the spec's `get_item_position`:
+/// the chunk containing it, and its start and end byte offsets in the
+/// chunk. Composite items take a whole chunk.
+pub fn get_item_position(index: usize, packed: Option<usize>) -> (usize, usize, usize) {
+	let start = chunk_offset(index, packed);
+	(chunk_index(index, packed), start, start + packed.unwrap_or(CHUNK_LEN))
+}
+
+impl PathElement {
+	/// Generalized index of this element relative to its parent.
+	pub fn index(&self) -> Index {
+		match *self {
+			PathElement::Field { index, total } =>
+				Index::from_depth(index as u64, required_depth(total as u64)),
+			PathElement::VectorItem { index, len, packed } => Index::from_depth(
+				chunk_index(index, packed) as u64,
+				required_depth(chunk_count(len, packed) as u64)
+			),
+			PathElement::ListItem { index, max_len, packed } => Index::root().left().sub(
+				Index::from_depth(
+					chunk_index(index, packed) as u64,
+					required_depth(chunk_count(max_len, packed) as u64)
+				)
+			),
+			PathElement::ListLength | PathElement::UnionSelector => Index::root().right(),
+			PathElement::UnionValue => Index::root().left(),
+		}
+	}
+}
+
+/// Concatenate generalized indices, each relative to the previous one.
+pub fn concat_generalized_indices(indices: &[Index]) -> Index {
+	indices.iter().fold(Index::root(), |acc, index| acc.sub(*index))
+}
+
+/// Number of branches from the root to `index`, the spec's
+/// `get_generalized_index_length`.
+pub fn get_generalized_index_length(index: Index) -> usize {
+	index.depth()
+}
+
+/// Bit at `position` of `index`, counting from the least significant
+/// bit, as the spec's `get_generalized_index_bit`. Below the length of
+/// the index, it selects the right child at depth `length - position`.
+pub fn get_generalized_index_bit(index: Index, position: usize) -> bool {
+	let length = index.depth();
+	if position >= length {
+		return position == length
+	}
+	index.path().nth(length - position - 1) == Some(IndexSelection::Right)
+}
+
+/// Sibling of `index`, the spec's `generalized_index_sibling`, or
+/// `None` for the root, which has no sibling.
+pub fn generalized_index_sibling(index: Index) -> Option<Index> {
+	index.sibling()
+}
+
+/// Left or right child of `index`, the spec's `generalized_index_child`.
+pub fn generalized_index_child(index: Index, right_side: bool) -> Index {
+	if right_side { index.right() } else { index.left() }
+}
+
+/// Parent of `index`, the spec's `generalized_index_parent`, or `None`
+/// for the root.
+pub fn generalized_index_parent(index: Index) -> Option<Index> {
+	index.parent()
+}
+
+/// Generalized index of the value at the given path from the root.
+pub fn generalized_index(path: &[PathElement]) -> Index {
+	path.iter().fold(Index::root(), |acc, element| acc.sub(element.index()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn gindex(value: usize) -> Index {
+		Index::from_one(value).unwrap()
+	}
+
+	#[test]
+	fn test_generalized_index() {
+		// Container of 3 fields, whose second field is a list of at most
+		// 16 containers of 3 fields.
+		assert_eq!(generalized_index(&[
+			PathElement::Field { index: 1, total: 3 },
+			PathElement::ListItem { index: 5, max_len: 16, packed: None },
+			PathElement::Field { index: 1, total: 3 },
+		]), gindex(0b10_1001_0101));
+		assert_eq!(generalized_index(&[
+			PathElement::Field { index: 1, total: 3 },
+			PathElement::ListLength,
+		]), gindex(0b1011));
+
+		// List of at most 100 `u64`, packed 4 in a chunk into 25 chunks.
+		assert_eq!(chunk_count(100, Some(8)), 25);
+		assert_eq!(chunk_offset(9, Some(8)), 8);
+		assert_eq!(generalized_index(&[
+			PathElement::ListItem { index: 9, max_len: 100, packed: Some(8) },
+		]), gindex(2 * 32 + 2));
+		assert_eq!(generalized_index(&[
+			PathElement::VectorItem { index: 40, len: 64, packed: Some(1) },
+		]), gindex(2 + 1));
+
+		assert_eq!(concat_generalized_indices(&[gindex(5), gindex(2), gindex(3)]), gindex(0b10101));
+		assert_eq!(generalized_index(&[]), Index::root());
+	}
+
+	#[test]
+	fn test_spec_helpers() {
+		for value in [1usize, 2, 3, 5, 12, 0b1011_0110, 1 << 40] {
+			let index = gindex(value);
+			let length = (usize::BITS - 1 - value.leading_zeros()) as usize;
+			assert_eq!(get_generalized_index_length(index), length);
+			for position in 0..(length + 3) {
+				assert_eq!(get_generalized_index_bit(index, position), value & (1 << position) != 0);
+			}
+
+			assert_eq!(generalized_index_sibling(index), Index::from_one(value ^ 1).filter(|_| value > 1));
+			assert_eq!(generalized_index_child(index, false), gindex(value * 2));
+			assert_eq!(generalized_index_child(index, true), gindex(value * 2 + 1));
+			assert_eq!(generalized_index_parent(index), Index::from_one(value / 2));
+		}
+
+		assert_eq!(concat_generalized_indices(&[gindex(3), gindex(1), gindex(4)]), gindex(0b1100));
+		assert_eq!(get_item_position(9, Some(8)), (2, 8, 16));
+		assert_eq!(get_item_position(31, Some(1)), (0, 31, 32));
+		assert_eq!(get_item_position(7, None), (7, 0, 32));
+	}
+}
@@ -0,0 +1,181 @@
+use bm::{ReadBackend, WriteBackend, Construct, Error};
+use core::cell::RefCell;
+use core::ops::{Deref, DerefMut};
+use alloc::vec::Vec;
+use generic_array::GenericArray;
+use crate::{IntoTree, FromTree, End, CompatibleConstruct};
+
+/// Value with its tree root memoized. The root is kept until the value
+/// is mutated through `DerefMut`, so a container re-merkleized each slot
+/// reuses the roots of its unchanged fields.
+///
+/// The memoized root is keyed by the construct, and the tree nodes are
+/// assumed to be already in the database where it is used again. The
+/// bytes of the root are kept, as its size depends on the construct.
+pub struct Hashed<T> {
+	value: T,
+	root: RefCell<Option<(&'static str, Vec<u8>)>>,
+}
+
+impl<T> Hashed<T> {
+	/// Create a new value without memoized root.
+	pub fn new(value: T) -> Self {
+		Self { value, root: RefCell::new(None) }
+	}
+
+	/// Convert into the inner value.
+	pub fn into_inner(self) -> T {
+		self.value
+	}
+
+	/// Whether the root needs to be calculated again.
+	pub fn is_dirty(&self) -> bool {
+		self.root.borrow().is_none()
+	}
+}
+
+/// Serialize the inner value only. The memoized root is not kept.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Hashed<T> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+		S: serde::Serializer,
+	{
+		self.value.serialize(serializer)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Hashed<T> {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+		D: serde::Deserializer<'de>,
+	{
+		Ok(Self::new(T::deserialize(deserializer)?))
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Encode> parity_codec::Encode for Hashed<T> {
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		self.value.encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Decode> parity_codec::Decode for Hashed<T> {
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		Some(Self::new(T::decode(input)?))
+	}
+}
+
+impl<T> Deref for Hashed<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.value
+	}
+}
+
+impl<T> DerefMut for Hashed<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		*self.root.get_mut() = None;
+		&mut self.value
+	}
+}
+
+impl<T> From<T> for Hashed<T> {
+	fn from(value: T) -> Self {
+		Self::new(value)
+	}
+}
+
+impl<T: Default> Default for Hashed<T> {
+	fn default() -> Self {
+		Self::new(T::default())
+	}
+}
+
+impl<T: Clone> Clone for Hashed<T> {
+	fn clone(&self) -> Self {
+		Self { value: self.value.clone(), root: self.root.clone() }
+	}
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Hashed<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		self.value.fmt(f)
+	}
+}
+
+impl<T: PartialEq> PartialEq for Hashed<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.value == other.value
+	}
+}
+
+impl<T: Eq> Eq for Hashed<T> { }
+
+impl<T: IntoTree> IntoTree for Hashed<T> {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let construct = core::any::type_name::<DB::Construct>();
+		if let Some((cached_construct, root)) = self.root.borrow().as_ref() {
+			if *cached_construct == construct {
+				return Ok(End::new(GenericArray::clone_from_slice(root)))
+			}
+		}
+
+		let root = self.value.into_tree(db)?;
+		*self.root.borrow_mut() = Some((construct, root.0.to_vec()));
+		Ok(root)
+	}
+}
+
+impl<T: FromTree> FromTree for Hashed<T> {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let value = T::from_tree(root, db)?;
+		Ok(Self {
+			value,
+			root: RefCell::new(Some((core::any::type_name::<DB::Construct>(), root.0.to_vec()))),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{DigestConstruct, MaxVec};
+
+	use bm::InMemoryBackend;
+	use bm::InstrumentedBackend;
+	use sha2::Sha256;
+	use typenum::U64;
+
+	#[test]
+	fn test_hashed() {
+		let mut db = InstrumentedBackend::new(InMemoryBackend::<DigestConstruct<Sha256>>::default());
+		let mut value = Hashed::new(MaxVec::<u64, U64>::from((0..40).collect::<Vec<_>>()));
+		let expected = value.value.into_tree(&mut db).unwrap();
+
+		db.reset();
+		assert!(value.is_dirty());
+		assert_eq!(value.into_tree(&mut db).unwrap(), expected);
+		assert!(db.stats().hashes > 0);
+
+		db.reset();
+		assert!(!value.is_dirty());
+		assert_eq!(value.into_tree(&mut db).unwrap(), expected);
+		assert_eq!(db.stats().hashes, 0);
+
+		value.push(40);
+		assert!(value.is_dirty());
+		assert_ne!(value.into_tree(&mut db).unwrap(), expected);
+
+		let root = value.into_tree(&mut db).unwrap();
+		let decoded = Hashed::<MaxVec<u64, U64>>::from_tree(&root, &mut db).unwrap();
+		assert!(!decoded.is_dirty());
+		assert_eq!(decoded, value);
+	}
+}
@@ -0,0 +1,380 @@
+//! Canonical JSON of ssz values, as used by the beacon node REST APIs.
+//!
+//! Unsigned integers are rendered as decimal strings, and parsed from
+//! either decimal or `0x`-prefixed big-endian hex strings. Byte vectors
+//! and lists, fixed hashes and bitfields are rendered as `0x`-prefixed
+//! hex of their ssz serialization. Other lists and vectors are arrays,
+//! and containers deriving `Json` are objects keyed by field name, with
+//! keys in sorted order.
+
+use core::fmt::Write;
+use core::convert::TryFrom;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use generic_array::{GenericArray, ArrayLength};
+use primitive_types::{H160, H256, H512, U256};
+use typenum::Unsigned;
+use vecarray::VecArray;
+
+pub use serde_json::{Value as JsonValue, Map as JsonMap};
+
+use crate::{MaxVec, BitList, BitVector, Union, Blob, H384, H768};
+
+/// Error when parsing a value from JSON.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JsonError {
+	/// The JSON is not valid.
+	InvalidJson,
+	/// The JSON value is not of the expected type, such as an array
+	/// where a string is expected.
+	InvalidType,
+	/// The JSON value is of the expected type, but not a valid value,
+	/// such as an overflowing integer or invalid hex.
+	InvalidValue,
+	/// The length of a sequence does not match the type.
+	InvalidLength,
+	/// A field of a container is missing.
+	MissingField(&'static str),
+}
+
+/// Traits for type rendering to and parsing from canonical JSON.
+pub trait Json: Sized {
+	/// Render this value as JSON.
+	fn to_json(&self) -> JsonValue;
+	/// Parse a value from JSON.
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError>;
+
+	/// Render a sequence of this type. Sequences are arrays, except for
+	/// bytes, which are hex strings.
+	fn sequence_to_json(values: &[Self]) -> JsonValue {
+		JsonValue::Array(values.iter().map(Json::to_json).collect())
+	}
+
+	/// Parse a sequence of this type.
+	fn sequence_from_json(value: &JsonValue) -> Result<Vec<Self>, JsonError> {
+		value.as_array().ok_or(JsonError::InvalidType)?.iter().map(Json::from_json).collect()
+	}
+}
+
+/// Render a value as a canonical JSON string.
+pub fn to_json_string<T: Json>(value: &T) -> String {
+	value.to_json().to_string()
+}
+
+/// Parse a value from a JSON string.
+pub fn from_json_str<T: Json>(s: &str) -> Result<T, JsonError> {
+	T::from_json(&serde_json::from_str(s).map_err(|_| JsonError::InvalidJson)?)
+}
+
+/// Parse the field `name` of a container object, used by the `Json`
+/// derive.
+pub fn json_field<T: Json>(object: &JsonMap<String, JsonValue>, name: &'static str) -> Result<T, JsonError> {
+	T::from_json(object.get(name).ok_or(JsonError::MissingField(name))?)
+}
+
+fn hex_to_json(bytes: &[u8]) -> JsonValue {
+	let mut ret = String::with_capacity(2 + bytes.len() * 2);
+	ret.push_str("0x");
+	for byte in bytes {
+		write!(ret, "{:02x}", byte).expect("Writing to string never fails; qed");
+	}
+	JsonValue::String(ret)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+	if !hex.len().is_multiple_of(2) {
+		return None
+	}
+
+	(0..hex.len()).step_by(2)
+		.map(|i| hex.get(i..(i + 2)).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+		.collect()
+}
+
+fn hex_from_json(value: &JsonValue) -> Result<Vec<u8>, JsonError> {
+	let hex = value.as_str().ok_or(JsonError::InvalidType)?;
+	hex.strip_prefix("0x").and_then(decode_hex).ok_or(JsonError::InvalidValue)
+}
+
+fn fixed_hex_from_json(value: &JsonValue, len: usize) -> Result<Vec<u8>, JsonError> {
+	let bytes = hex_from_json(value)?;
+	if bytes.len() != len {
+		return Err(JsonError::InvalidLength)
+	}
+	Ok(bytes)
+}
+
+/// Parse an unsigned integer of at most 256 bits, from a decimal or
+/// `0x`-prefixed hex string.
+fn uint_from_json(value: &JsonValue) -> Result<U256, JsonError> {
+	let s = value.as_str().ok_or(JsonError::InvalidType)?;
+	let parsed = match s.strip_prefix("0x") {
+		Some(hex) if !hex.is_empty() && hex.len() <= 64 => {
+			let padded = if hex.len().is_multiple_of(2) { String::from(hex) } else { format!("0{}", hex) };
+			decode_hex(&padded).map(|bytes| U256::from_big_endian(&bytes))
+		},
+		Some(_) => None,
+		None if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => U256::from_dec_str(s).ok(),
+		None => None,
+	};
+	parsed.ok_or(JsonError::InvalidValue)
+}
+
+impl Json for bool {
+	fn to_json(&self) -> JsonValue {
+		JsonValue::Bool(*self)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		value.as_bool().ok_or(JsonError::InvalidType)
+	}
+}
+
+macro_rules! impl_builtin_uint {
+	( $( $t:ty ),* ) => { $(
+		impl Json for $t {
+			fn to_json(&self) -> JsonValue {
+				JsonValue::String(self.to_string())
+			}
+
+			fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+				let value = uint_from_json(value)?;
+				if value > U256::from(<$t>::MAX) {
+					return Err(JsonError::InvalidValue)
+				}
+				Ok(value.as_u128() as $t)
+			}
+		}
+	)* }
+}
+
+impl_builtin_uint!(u16, u32, u64, u128);
+
+impl Json for u8 {
+	fn to_json(&self) -> JsonValue {
+		JsonValue::String(self.to_string())
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		let value = uint_from_json(value)?;
+		if value > U256::from(u8::MAX) {
+			return Err(JsonError::InvalidValue)
+		}
+		Ok(value.as_u32() as u8)
+	}
+
+	fn sequence_to_json(values: &[Self]) -> JsonValue {
+		hex_to_json(values)
+	}
+
+	fn sequence_from_json(value: &JsonValue) -> Result<Vec<Self>, JsonError> {
+		hex_from_json(value)
+	}
+}
+
+impl Json for U256 {
+	fn to_json(&self) -> JsonValue {
+		JsonValue::String(self.to_string())
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		uint_from_json(value)
+	}
+}
+
+macro_rules! impl_fixed_hash {
+	( $( $t:ty => $len:expr ),* ) => { $(
+		impl Json for $t {
+			fn to_json(&self) -> JsonValue {
+				hex_to_json(self.as_ref())
+			}
+
+			fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+				Ok(<$t>::from_slice(&fixed_hex_from_json(value, $len)?))
+			}
+		}
+	)* }
+}
+
+impl_fixed_hash!(H160 => 20, H256 => 32, H384 => 48, H512 => 64, H768 => 96);
+
+impl<T: Json, L: ArrayLength<T>> Json for GenericArray<T, L> {
+	fn to_json(&self) -> JsonValue {
+		T::sequence_to_json(self)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		GenericArray::from_exact_iter(T::sequence_from_json(value)?).ok_or(JsonError::InvalidLength)
+	}
+}
+
+impl<T: Json, L: Unsigned> Json for VecArray<T, L> {
+	fn to_json(&self) -> JsonValue {
+		T::sequence_to_json(self)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		VecArray::try_from(T::sequence_from_json(value)?).map_err(|_| JsonError::InvalidLength)
+	}
+}
+
+impl<T: Json> Json for Vec<T> {
+	fn to_json(&self) -> JsonValue {
+		T::sequence_to_json(self)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		T::sequence_from_json(value)
+	}
+}
+
+impl<T: Json, ML: Unsigned> Json for MaxVec<T, ML> {
+	fn to_json(&self) -> JsonValue {
+		T::sequence_to_json(self)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		let values = T::sequence_from_json(value)?;
+		if values.len() > ML::to_usize() {
+			return Err(JsonError::InvalidLength)
+		}
+		Ok(MaxVec::from(values))
+	}
+}
+
+impl Json for Blob {
+	fn to_json(&self) -> JsonValue {
+		hex_to_json(&self.0)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		Ok(Blob(hex_from_json(value)?))
+	}
+}
+
+impl<ML: Unsigned> Json for BitList<ML> {
+	fn to_json(&self) -> JsonValue {
+		hex_to_json(&self.to_bytes())
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		Self::from_bytes(&hex_from_json(value)?).ok_or(JsonError::InvalidValue)
+	}
+}
+
+impl<L: Unsigned> Json for BitVector<L> {
+	fn to_json(&self) -> JsonValue {
+		hex_to_json(&self.to_bytes())
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		Self::from_bytes(&fixed_hex_from_json(value, L::to_usize().div_ceil(8))?)
+			.ok_or(JsonError::InvalidValue)
+	}
+}
+
+/// Unions are objects of their selector and value, as `{"selector":
+/// "1", "data": ...}`.
+impl<T: Json> Json for Union<T> {
+	fn to_json(&self) -> JsonValue {
+		let mut object = JsonMap::new();
+		object.insert("selector".into(), self.selector().to_json());
+		object.insert("data".into(), self.value().to_json());
+		JsonValue::Object(object)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		let object = value.as_object().ok_or(JsonError::InvalidType)?;
+		Union::new(json_field(object, "selector")?, json_field(object, "data")?)
+			.ok_or(JsonError::InvalidValue)
+	}
+}
+
+/// Optional values are `null` when absent.
+impl<T: Json> Json for Option<T> {
+	fn to_json(&self) -> JsonValue {
+		self.as_ref().map(Json::to_json).unwrap_or(JsonValue::Null)
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		match value {
+			JsonValue::Null => Ok(None),
+			value => Ok(Some(T::from_json(value)?)),
+		}
+	}
+}
+
+impl<T: Json> Json for Box<T> {
+	fn to_json(&self) -> JsonValue {
+		self.as_ref().to_json()
+	}
+
+	fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+		Ok(Box::new(T::from_json(value)?))
+	}
+}
+
+macro_rules! impl_tuple {
+	($len:expr, $($i:tt => $t:ident),+) => {
+		/// Tuples are arrays of their fields.
+		impl<$($t: Json),+> Json for ($($t,)+) {
+			fn to_json(&self) -> JsonValue {
+				JsonValue::Array(alloc::vec![$(self.$i.to_json()),+])
+			}
+
+			fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+				let values = value.as_array().ok_or(JsonError::InvalidType)?;
+				if values.len() != $len {
+					return Err(JsonError::InvalidLength)
+				}
+				Ok(($($t::from_json(&values[$i])?,)+))
+			}
+		}
+	}
+}
+
+impl_tuple!(1, 0 => A);
+impl_tuple!(2, 0 => A, 1 => B);
+impl_tuple!(3, 0 => A, 1 => B, 2 => C);
+impl_tuple!(4, 0 => A, 1 => B, 2 => C, 3 => D);
+impl_tuple!(5, 0 => A, 1 => B, 2 => C, 3 => D, 4 => E);
+impl_tuple!(6, 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F);
+impl_tuple!(7, 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G);
+impl_tuple!(8, 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use typenum::{U4, U10, U32};
+
+	fn roundtrip<T: Json + PartialEq + core::fmt::Debug>(value: T, json: &str) {
+		assert_eq!(to_json_string(&value), json);
+		assert_eq!(from_json_str::<T>(json), Ok(value));
+	}
+
+	#[test]
+	fn test_json() {
+		roundtrip(true, "true");
+		roundtrip(42u8, "\"42\"");
+		roundtrip(u64::MAX, "\"18446744073709551615\"");
+		roundtrip(U256::MAX, &format!("\"{}\"", U256::MAX));
+		roundtrip(H256::repeat_byte(0xab), &format!("\"0x{}\"", "ab".repeat(32)));
+		roundtrip(MaxVec::<u8, U32>::from(vec![1, 2, 0xff]), "\"0x0102ff\"");
+		roundtrip(MaxVec::<u16, U4>::from(vec![1, 2]), "[\"1\",\"2\"]");
+		roundtrip(BitList::<U10>::from_bits(vec![true, false, true]).unwrap(), "\"0x0d\"");
+		roundtrip(BitVector::<U10>::from_bits(vec![true; 10]).unwrap(), "\"0xff03\"");
+		roundtrip((7u64, Some(H160::zero()), None::<u32>), &format!("[\"7\",\"0x{}\",null]", "00".repeat(20)));
+		roundtrip(Union::new(1, 3u32).unwrap(), "{\"data\":\"3\",\"selector\":\"1\"}");
+
+		assert_eq!(from_json_str::<u64>("\"0x10\""), Ok(16));
+		assert_eq!(from_json_str::<u8>("\"256\""), Err(JsonError::InvalidValue));
+		assert_eq!(from_json_str::<u8>("\"-1\""), Err(JsonError::InvalidValue));
+		assert_eq!(from_json_str::<u64>("16"), Err(JsonError::InvalidType));
+		assert_eq!(from_json_str::<MaxVec<u16, U4>>("[\"1\",\"2\",\"3\",\"4\",\"5\"]"),
+				   Err(JsonError::InvalidLength));
+		assert_eq!(from_json_str::<H160>("\"0x00\""), Err(JsonError::InvalidLength));
+		assert_eq!(from_json_str::<BitVector<U4>>("\"0x1f\""), Err(JsonError::InvalidValue));
+		assert_eq!(from_json_str::<bool>("tru"), Err(JsonError::InvalidJson));
+	}
+}
@@ -0,0 +1,350 @@
+use bm::{Index, ReadBackend, WriteBackend, Construct, Error, Raw, RootStatus};
+use core::marker::PhantomData;
+use alloc::vec::Vec;
+use alloc::string::String;
+use generic_array::{GenericArray, ArrayLength};
+use typenum::Unsigned;
+use primitive_types::{U256, H160, H256, H512};
+use crate::gindex::{PathElement, chunk_offset};
+use crate::mode::{write_uint, read_uint, Layout};
+use crate::{FromTree, IntoTree, CompatibleConstruct, LayoutOf, Basic, MaxVec, H384, H768,
+			BitList, BitVector, Union, Hashed, Compact};
+
+/// Typed path from a root to a value inside its tree.
+pub struct Lens<T> {
+	index: Index,
+	_marker: PhantomData<T>,
+}
+
+impl<T> Clone for Lens<T> {
+	fn clone(&self) -> Self {
+		Self::new(self.index)
+	}
+}
+
+impl<T> core::fmt::Debug for Lens<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_tuple("Lens").field(&self.index).finish()
+	}
+}
+
+impl<T> Lens<T> {
+	/// Create a new lens at the given generalized index.
+	pub fn new(index: Index) -> Self {
+		Self { index, _marker: PhantomData }
+	}
+
+	/// Generalized index of the value.
+	pub fn index(&self) -> Index {
+		self.index
+	}
+
+	/// Lens to a field of a container with `total` fields.
+	pub fn field<U>(&self, field: usize, total: usize) -> Lens<U> {
+		Lens::new(self.index.sub(PathElement::Field { index: field, total }.index()))
+	}
+
+	/// Get the value, only decoding the subtree at the index.
+	pub fn get<R: RootStatus, DB: ReadBackend>(
+		&self,
+		raw: &Raw<R, DB::Construct>,
+		db: &mut DB
+	) -> Result<T, Error<DB::Error>> where
+		T: FromTree,
+		DB::Construct: CompatibleConstruct,
+	{
+		let root = raw.get_existing(db, self.index)?;
+		T::from_tree(&root, db)
+	}
+
+	/// Set the value, only rehashing the path from the index to the
+	/// root. A list element must be within the current length of the
+	/// list.
+	pub fn set<R: RootStatus, DB: WriteBackend>(
+		&self,
+		raw: &mut Raw<R, DB::Construct>,
+		db: &mut DB,
+		value: &T
+	) -> Result<(), Error<DB::Error>> where
+		T: IntoTree,
+		DB::Construct: CompatibleConstruct,
+	{
+		let root = value.into_tree(db)?;
+		raw.set(db, self.index, root)
+	}
+
+	/// Update the value in place, only rehashing the path from the
+	/// index to the root.
+	pub fn update<R: RootStatus, DB: WriteBackend, F: FnOnce(&mut T)>(
+		&self,
+		raw: &mut Raw<R, DB::Construct>,
+		db: &mut DB,
+		f: F
+	) -> Result<(), Error<DB::Error>> where
+		T: FromTree + IntoTree,
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut value = self.get(raw, db)?;
+		f(&mut value);
+		self.set(raw, db, &value)
+	}
+}
+
+impl<T: Lensable, ML: Unsigned> Lens<MaxVec<T, ML>> {
+	/// Lens to an element of the list.
+	pub fn at(&self, index: usize) -> T::Lens {
+		T::lens(self.index.sub(PathElement::ListItem {
+			index, max_len: ML::to_usize(), packed: None,
+		}.index()))
+	}
+}
+
+impl<T: Lensable, L: ArrayLength<T>> Lens<GenericArray<T, L>> {
+	/// Lens to an element of the vector.
+	pub fn at(&self, index: usize) -> T::Lens {
+		T::lens(self.index.sub(PathElement::VectorItem {
+			index, len: L::to_usize(), packed: None,
+		}.index()))
+	}
+}
+
+impl<T: Packed, ML: Unsigned> Lens<Compact<MaxVec<T, ML>>> {
+	/// Lens to an element packed in the list.
+	pub fn at(&self, index: usize) -> PackedLens<T> {
+		PackedLens::new(self.index, PathElement::ListItem {
+			index, max_len: ML::to_usize(), packed: Some(T::LEN),
+		})
+	}
+}
+
+impl<T: Packed, L: ArrayLength<T>> Lens<Compact<GenericArray<T, L>>> {
+	/// Lens to an element packed in the vector.
+	pub fn at(&self, index: usize) -> PackedLens<T> {
+		PackedLens::new(self.index, PathElement::VectorItem {
+			index, len: L::to_usize(), packed: Some(T::LEN),
+		})
+	}
+}
+
+/// Basic value packed into the chunks of a compact vector or list.
+pub trait Packed: Basic + Sized {
+	/// Byte length of the value.
+	const LEN: usize = <Self::Len as Unsigned>::USIZE;
+
+	/// Read the value from its bytes, committed with layout `L`.
+	fn read<L: Layout>(bytes: &[u8]) -> Self;
+
+	/// Write the value into its bytes, committed with layout `L`.
+	fn write<L: Layout>(&self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_packed_uint {
+	( $( $t:ty ),* ) => { $(
+		impl Packed for $t {
+			fn read<L: Layout>(bytes: &[u8]) -> Self {
+				let mut raw = <$t>::default().to_le_bytes();
+				read_uint::<L>(bytes, &mut raw);
+				<$t>::from_le_bytes(raw)
+			}
+
+			fn write<L: Layout>(&self, bytes: &mut [u8]) {
+				write_uint::<L>(&self.to_le_bytes(), bytes);
+			}
+		}
+	)* }
+}
+
+impl_packed_uint!(u8, u16, u32, u64, u128);
+#[cfg(feature = "non-spec")]
+impl_packed_uint!(i8, i16, i32, i64, i128);
+
+impl Packed for U256 {
+	fn read<L: Layout>(bytes: &[u8]) -> Self {
+		let mut le = [0u8; 32];
+		read_uint::<L>(bytes, &mut le);
+		U256::from_little_endian(&le)
+	}
+
+	fn write<L: Layout>(&self, bytes: &mut [u8]) {
+		let mut le = [0u8; 32];
+		self.to_little_endian(&mut le);
+		write_uint::<L>(&le, bytes)
+	}
+}
+
+/// Typed path to a basic value packed into a chunk, together with
+/// other values.
+pub struct PackedLens<T> {
+	index: Index,
+	offset: usize,
+	_marker: PhantomData<T>,
+}
+
+impl<T: Packed> PackedLens<T> {
+	fn new(parent: Index, element: PathElement) -> Self {
+		let offset = match element {
+			PathElement::ListItem { index, packed, .. } |
+			PathElement::VectorItem { index, packed, .. } => chunk_offset(index, packed),
+			_ => 0,
+		};
+
+		Self { index: parent.sub(element.index()), offset, _marker: PhantomData }
+	}
+
+	/// Generalized index of the chunk holding the value.
+	pub fn index(&self) -> Index {
+		self.index
+	}
+
+	/// Byte offset of the value in its chunk.
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	/// Get the value from its chunk.
+	pub fn get<R: RootStatus, DB: ReadBackend>(
+		&self,
+		raw: &Raw<R, DB::Construct>,
+		db: &mut DB
+	) -> Result<T, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let chunk = raw.get_existing(db, self.index)?;
+		Ok(T::read::<LayoutOf<DB::Construct>>(&chunk.as_ref()[self.offset..(self.offset + T::LEN)]))
+	}
+
+	/// Set the value in its chunk, only rehashing the path from the
+	/// chunk to the root. A list element must be within the current
+	/// length of the list.
+	pub fn set<R: RootStatus, DB: WriteBackend>(
+		&self,
+		raw: &mut Raw<R, DB::Construct>,
+		db: &mut DB,
+		value: &T
+	) -> Result<(), Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut chunk: <DB::Construct as Construct>::Value =
+			raw.get_existing(db, self.index)?;
+		value.write::<LayoutOf<DB::Construct>>(&mut chunk.as_mut()[self.offset..(self.offset + T::LEN)]);
+		raw.set(db, self.index, chunk)
+	}
+
+	/// Update the value in place.
+	pub fn update<R: RootStatus, DB: WriteBackend, F: FnOnce(&mut T)>(
+		&self,
+		raw: &mut Raw<R, DB::Construct>,
+		db: &mut DB,
+		f: F
+	) -> Result<(), Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut value = self.get(raw, db)?;
+		f(&mut value);
+		self.set(raw, db, &value)
+	}
+}
+
+/// Value whose tree can be navigated with a lens.
+pub trait Lensable: Sized {
+	/// Lens type of the value.
+	type Lens;
+
+	/// Create a lens of the value at the given generalized index.
+	fn lens(index: Index) -> Self::Lens;
+}
+
+macro_rules! impl_lensable {
+	( $( $t:ty ),* ) => { $(
+		impl Lensable for $t {
+			type Lens = Lens<$t>;
+
+			fn lens(index: Index) -> Self::Lens {
+				Lens::new(index)
+			}
+		}
+	)* }
+}
+
+impl_lensable!(bool, u8, u16, u32, u64, u128, U256, H160, H256, H384, H512, H768, String);
+
+macro_rules! impl_generic_lensable {
+	( $( $t:ty => ( $( $param:tt )* ) ),* ) => { $(
+		impl<$( $param )*> Lensable for $t {
+			type Lens = Lens<$t>;
+
+			fn lens(index: Index) -> Self::Lens {
+				Lens::new(index)
+			}
+		}
+	)* }
+}
+
+impl_generic_lensable!(
+	MaxVec<T, ML> => (T, ML),
+	GenericArray<T, L> => (T, L: ArrayLength<T>),
+	Vec<T> => (T),
+	BitList<L> => (L),
+	BitVector<L> => (L),
+	Option<T> => (T),
+	Union<T> => (T)
+);
+
+/// Memoized value has the same tree as the inner value.
+impl<T: Lensable> Lensable for Hashed<T> {
+	type Lens = T::Lens;
+
+	fn lens(index: Index) -> Self::Lens {
+		T::lens(index)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::DigestConstruct;
+
+	use bm::{InMemoryBackend, InstrumentedBackend, DanglingRaw, Tree};
+	use sha2::Sha256;
+	use typenum::{U4, U8, U64};
+
+	#[test]
+	fn test_list_lens() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let list = MaxVec::<GenericArray<u64, U4>, U8>::from(vec![
+			GenericArray::from([1, 2, 3, 4]),
+			GenericArray::from([5, 6, 7, 8]),
+		]);
+		let mut raw = DanglingRaw::<DigestConstruct<Sha256>>::new(list.into_tree(&mut db).unwrap());
+
+		let lens = <MaxVec<GenericArray<u64, U4>, U8>>::lens(Index::root());
+		assert_eq!(lens.at(1).index(), Index::from_one(2 * 8 + 1).unwrap());
+		assert_eq!(lens.at(1).at(2).get(&raw, &mut db).unwrap(), 7);
+
+		lens.at(1).at(2).set(&mut raw, &mut db, &70).unwrap();
+		let mut expected = list.clone();
+		expected[1][2] = 70;
+		assert_eq!(raw.root(), expected.into_tree(&mut db).unwrap());
+		assert_eq!(lens.get(&raw, &mut db).unwrap(), expected);
+	}
+
+	#[test]
+	fn test_packed_lens() {
+		let mut db = InstrumentedBackend::new(InMemoryBackend::<DigestConstruct<Sha256>>::default());
+		let mut list = MaxVec::<u64, U64>::from((0..40).collect::<Vec<_>>());
+		let mut raw = DanglingRaw::<DigestConstruct<Sha256>>::new(Compact(list.clone()).into_tree(&mut db).unwrap());
+
+		let lens = Lens::<Compact<MaxVec<u64, U64>>>::new(Index::root());
+		assert_eq!(lens.at(9).index(), Index::from_one(2 * 16 + 2).unwrap());
+		assert_eq!(lens.at(9).offset(), 8);
+		assert_eq!(lens.at(9).get(&raw, &mut db).unwrap(), 9);
+
+		db.reset();
+		lens.at(9).update(&mut raw, &mut db, |value| *value += 100).unwrap();
+		assert_eq!(db.stats().hashes, 5);
+
+		list[9] += 100;
+		assert_eq!(raw.root(), Compact(list.clone()).into_tree(&mut db).unwrap());
+		assert_eq!(lens.get(&raw, &mut db).unwrap().0, list);
+	}
+}
@@ -8,15 +8,16 @@ extern crate alloc;
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
-use typenum::U32;
-use generic_array::GenericArray;
+use generic_array::{GenericArray, ArrayLength};
+use core::marker::PhantomData;
 use primitive_types::H256;
 use digest::Digest;
+use bm::utils::HashOnlyBackend;
 
 pub use bm::{Backend, ReadBackend, WriteBackend, InheritedDigestConstruct,
 			 UnitDigestConstruct, Construct, InheritedEmpty, Error, Vector,
 			 DanglingVector, List, Leak, NoopBackend, InMemoryBackend, Raw,
-			 RootStatus, OwnedRaw, DanglingRaw};
+			 RootStatus, OwnedRaw, DanglingRaw, Index, DecodeStats, DecodeLimits};
 
 mod basic;
 mod elemental_fixed;
@@ -24,77 +25,267 @@ mod elemental_variable;
 mod fixed;
 mod variable;
 mod partial;
+mod bits;
+mod union;
+mod map;
+mod hashed;
+mod blob;
+mod lens;
+mod class;
+mod serialize;
+mod mode;
+#[cfg(feature = "snappy")]
+mod snappy;
 pub mod utils;
+pub mod gindex;
+pub mod witness;
+pub mod vectors;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "spec-test")]
+pub mod spec_test;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ssz-types")]
+pub mod ssz_types_interop;
 
 pub use basic::Ignored;
 pub use elemental_fixed::{ElementalFixedVec, ElementalFixedVecRef,
 						  IntoCompactVectorTree, FromCompactVectorTree,
-						  IntoCompositeVectorTree, FromCompositeVectorTree};
+						  IntoCompositeVectorTree, FromCompositeVectorTree,
+						  CompositeVectorTreeIter};
 pub use elemental_variable::{ElementalVariableVec, ElementalVariableVecRef,
 							 IntoCompactListTree, FromCompactListTree,
 							 IntoCompositeListTree, FromCompositeListTree};
 pub use variable::MaxVec;
+pub use fixed::{H384, H768};
+pub use bits::{BitList, BitVector};
+pub use union::{Union, MAX_UNION_SELECTOR};
+pub use hashed::Hashed;
+pub use blob::Blob;
+pub use mode::{DecodeMode, Endianness, ChunkLayout, Layout, Ssz,
+			   BigEndian, Padded, BigEndianPadded};
+pub use lens::{Lens, Lensable, PackedLens, Packed};
+pub use class::{Classified, Kind, BasicKind, CompositeKind, SizeKind, FixedSize, VariableSize, Basic, Composite};
+pub use witness::{FromWitness, MissingField};
+pub use serialize::{Encode, Decode, DecodeError, CompositeEncoder, decode_composite, OFFSET_LEN};
+#[cfg(feature = "snappy")]
+pub use snappy::{encode_snappy, decode_snappy};
+#[cfg(feature = "json")]
+pub use json::{Json, JsonError, JsonValue, JsonMap, to_json_string, from_json_str};
+pub use vecarray::VecArray;
 pub use partial::{PartialIndex, PartialValue, PartialVec, PartialItem, Partialable};
 #[cfg(feature = "derive")]
-pub use bm_le_derive::{FromTree, IntoTree, Partialable};
+pub use bm_le_derive::{FromTree, IntoTree, Partialable, FromTreeWithConfig, SigningRoot,
+					   Encode, Decode, Lensable, FromWitness};
+#[cfg(all(feature = "derive", feature = "json"))]
+pub use bm_le_derive::Json;
 
-/// Digest construct for bm-le.
-pub type DigestConstruct<D> = bm::InheritedDigestConstruct<D, Value>;
+/// Digest construct for bm-le, whose values are of the digest output
+/// size, committing integers with layout `L`.
+pub type DigestConstruct<D, L = Ssz> = bm::InheritedDigestConstruct<D, End<<D as Digest>::OutputSize, L>>;
+
+/// End value of a ssz binary merkle tree whose nodes are `N` bytes, and
+/// whose integers are committed with layout `L`.
+pub struct End<N: ArrayLength<u8> = typenum::U32, L = Ssz>(pub GenericArray<u8, N>, pub PhantomData<fn() -> L>);
+
+impl<N: ArrayLength<u8>, L> End<N, L> {
+	/// Create a value from its bytes.
+	pub fn new(bytes: GenericArray<u8, N>) -> Self {
+		Self(bytes, PhantomData)
+	}
+}
 
 /// End value for 256-bit ssz binary merkle tree.
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[cfg_attr(feature = "parity-codec", derive(parity_codec::Encode, parity_codec::Decode))]
-pub struct Value(pub H256);
+pub type Value = End<typenum::U32>;
+
+// Implemented by hand, as derives would also require them of `N`.
+impl<N: ArrayLength<u8>, L> core::fmt::Debug for End<N, L> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_tuple("End").field(&self.0).finish()
+	}
+}
+
+impl<N: ArrayLength<u8>, L> Clone for End<N, L> {
+	fn clone(&self) -> Self {
+		Self::new(self.0.clone())
+	}
+}
+
+impl<N: ArrayLength<u8>, L> PartialEq for End<N, L> {
+	fn eq(&self, other: &Self) -> bool {
+		self.0 == other.0
+	}
+}
+
+impl<N: ArrayLength<u8>, L> Eq for End<N, L> { }
 
-impl Default for Value {
+impl<N: ArrayLength<u8>, L> PartialOrd for End<N, L> {
+	fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<N: ArrayLength<u8>, L> Ord for End<N, L> {
+	fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+		self.0.cmp(&other.0)
+	}
+}
+
+impl<N: ArrayLength<u8>, L> core::hash::Hash for End<N, L> {
+	fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+		self.0.hash(state)
+	}
+}
+
+impl<N: ArrayLength<u8>, L> Default for End<N, L> {
 	fn default() -> Self {
-		Self(H256::default())
+		Self::new(GenericArray::default())
 	}
 }
 
-impl AsRef<[u8]> for Value {
+impl<N: ArrayLength<u8>, L> AsRef<[u8]> for End<N, L> {
 	fn as_ref(&self) -> &[u8] {
 		self.0.as_ref()
 	}
 }
 
-impl AsMut<[u8]> for Value {
+impl<N: ArrayLength<u8>, L> AsMut<[u8]> for End<N, L> {
 	fn as_mut(&mut self) -> &mut [u8] {
 		self.0.as_mut()
 	}
 }
 
-impl From<usize> for Value {
-	fn from(value: usize) -> Self {
-		let mut ret = [0u8; 32];
-		let bytes = (value as u64).to_le_bytes();
-		(&mut ret[0..8]).copy_from_slice(&bytes);
-		Value(H256::from(ret))
+impl<N: ArrayLength<u8>, L> From<u64> for End<N, L> {
+	fn from(value: u64) -> Self {
+		let mut ret = Self::default();
+		ret.0[0..8].copy_from_slice(&value.to_le_bytes());
+		ret
 	}
 }
 
-impl Into<usize> for Value {
-	fn into(self) -> usize {
+impl<N: ArrayLength<u8>, L> Into<u64> for End<N, L> {
+	fn into(self) -> u64 {
 		let mut raw = [0u8; 8];
-		(&mut raw).copy_from_slice(&self.0[0..8]);
-		u64::from_le_bytes(raw) as usize
+		raw.copy_from_slice(&self.0[0..8]);
+		u64::from_le_bytes(raw)
 	}
 }
 
-impl From<GenericArray<u8, typenum::U32>> for Value {
-	fn from(array: GenericArray<u8, typenum::U32>) -> Self {
-		Self(H256::from_slice(array.as_slice()))
+impl<L> From<H256> for End<typenum::U32, L> {
+	fn from(hash: H256) -> Self {
+		Self::new(GenericArray::clone_from_slice(hash.as_bytes()))
+	}
+}
+
+impl<L> From<End<typenum::U32, L>> for H256 {
+	fn from(value: End<typenum::U32, L>) -> Self {
+		H256::from_slice(&value.0)
+	}
+}
+
+impl<L> core::convert::TryFrom<&[u8]> for End<typenum::U32, L> {
+	type Error = core::array::TryFromSliceError;
+
+	fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+		Ok(Self::new(<[u8; 32]>::try_from(bytes)?.into()))
+	}
+}
+
+/// Full `0x`-prefixed hex of the value.
+impl<N: ArrayLength<u8>, L> core::fmt::Display for End<N, L> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "0x")?;
+		for byte in self.0.iter() {
+			write!(f, "{:02x}", byte)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<N: ArrayLength<u8>, L> serde::Serialize for End<N, L> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+		S: serde::Serializer,
+	{
+		serializer.collect_str(self)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, N: ArrayLength<u8>, L> serde::Deserialize<'de> for End<N, L> {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+		D: serde::Deserializer<'de>,
+	{
+		let hex = alloc::string::String::deserialize(deserializer)?;
+		let digits = hex.strip_prefix("0x").unwrap_or(&hex).as_bytes();
+		if digits.len() != N::to_usize() * 2 {
+			return Err(<D::Error as serde::de::Error>::custom("invalid length"))
+		}
+
+		let mut ret = Self::default();
+		for (byte, pair) in ret.0.iter_mut().zip(digits.chunks(2)) {
+			let pair = core::str::from_utf8(pair)
+				.map_err(|_| <D::Error as serde::de::Error>::custom("invalid hex"))?;
+			*byte = u8::from_str_radix(pair, 16)
+				.map_err(|_| <D::Error as serde::de::Error>::custom("invalid hex"))?;
+		}
+		Ok(ret)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<N: ArrayLength<u8>, L> parity_codec::Encode for End<N, L> {
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		dest.write(&self.0)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<N: ArrayLength<u8>, L> parity_codec::Decode for End<N, L> {
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		let mut ret = Self::default();
+		if input.read(&mut ret.0) != N::to_usize() {
+			return None
+		}
+		Some(ret)
+	}
+}
+
+impl<N: ArrayLength<u8>, L> From<GenericArray<u8, N>> for End<N, L> {
+	fn from(array: GenericArray<u8, N>) -> Self {
+		Self::new(array)
 	}
 }
 
 /// Intermediate type for 256-bit ssz binary merkle tree.
 pub type Intermediate = H256;
 
-/// Special type for le-compatible construct.
-pub trait CompatibleConstruct: Construct<Value=Value> { }
+/// Size of chunks that basic values are packed into under construct
+/// `C`, the byte length of its values.
+pub type ChunkSize<C> = <C as CompatibleConstruct>::ChunkSize;
+
+/// Layout of the integers committed under construct `C`.
+pub type LayoutOf<C> = <C as CompatibleConstruct>::Layout;
 
-impl<C: Construct<Value=Value>> CompatibleConstruct for C { }
+/// Special type for le-compatible construct, whose values are `End`s.
+pub trait CompatibleConstruct: Construct<Value=End<
+	<Self as CompatibleConstruct>::ChunkSize,
+	<Self as CompatibleConstruct>::Layout,
+>> {
+	/// Byte length of the values of the construct, and the size of
+	/// chunks that basic values are packed into.
+	type ChunkSize: ArrayLength<u8>;
+	/// Byte order and chunk layout of the integers committed.
+	type Layout: Layout;
+}
+
+impl<C: Construct<Value=End<N, L>>, N: ArrayLength<u8>, L: Layout> CompatibleConstruct for C {
+	type ChunkSize = N;
+	type Layout = L;
+}
 
 /// Traits for type converting into a tree structure.
 pub trait IntoTree {
@@ -105,6 +296,14 @@ pub trait IntoTree {
 		db: &mut DB
 	) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct;
+
+	/// Calculate the ssz merkle tree root with 256-bit digest `D`,
+	/// dismissing the tree.
+	fn root_h256<D: Digest<OutputSize=typenum::U32>>(&self) -> H256 where
+		Self: Sized,
+	{
+		tree_root::<D, _>(self)
+	}
 }
 
 /// Traits for type converting from a tree structure.
@@ -116,6 +315,120 @@ pub trait FromTree: Sized {
 		db: &mut DB
 	) -> Result<Self, Error<DB::Error>> where
 		DB::Construct: CompatibleConstruct;
+
+	/// Convert this type from merkle tree, recomputing the hash of
+	/// each node read from its children, and failing with
+	/// `VerifiedBackendError::Mismatch` on a corrupted database.
+	fn from_tree_verified<DB: ReadBackend + ?Sized>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB
+	) -> Result<Self, Error<bm::VerifiedBackendError<DB::Error>>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		Self::from_tree(root, &mut bm::VerifiedBackend::new(db))
+	}
+
+	/// Convert this type from merkle tree within `limits`, failing
+	/// with `LimitedBackendError::TooManyNodes` once more nodes were
+	/// read than `max_nodes`, with `Error::MaxLenExceeded` on a vector
+	/// or list over `max_len`, and with `Error::AccessOverflowed` on one
+	/// over `max_depth`. `from_tree` is unlimited.
+	fn from_tree_limited<DB: ReadBackend + ?Sized>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		limits: &DecodeLimits,
+	) -> Result<Self, Error<bm::LimitedBackendError<DB::Error>>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		Self::from_tree(root, &mut bm::LimitedBackend::with_limits(db, limits))
+	}
+
+	/// Convert this type from merkle tree like `from_tree`, adding the
+	/// work done to `stats`, also when decoding fails.
+	fn from_tree_with_stats<DB: ReadBackend + ?Sized>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		stats: &mut DecodeStats,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut metered = bm::MeteredBackend::new(db, root.clone());
+		let ret = Self::from_tree(root, &mut metered);
+		stats.merge(&metered.stats());
+		ret
+	}
+
+	/// Convert this type from merkle tree like `from_tree_verified`,
+	/// adding the work done to `stats`, also when decoding fails.
+	fn from_tree_verified_with_stats<DB: ReadBackend + ?Sized>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		stats: &mut DecodeStats,
+	) -> Result<Self, Error<bm::VerifiedBackendError<DB::Error>>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut metered = bm::MeteredBackend::new(db, root.clone());
+		let mut verified = bm::VerifiedBackend::new(&mut metered);
+		let ret = Self::from_tree(root, &mut verified);
+		let hashes = verified.verified();
+		stats.merge(&DecodeStats { hashes, ..metered.stats() });
+		ret
+	}
+
+	/// Convert this type from merkle tree like `from_tree_limited`,
+	/// adding the work done to `stats`, also when decoding fails.
+	fn from_tree_limited_with_stats<DB: ReadBackend + ?Sized>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		limits: &DecodeLimits,
+		stats: &mut DecodeStats,
+	) -> Result<Self, Error<bm::LimitedBackendError<DB::Error>>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut metered = bm::MeteredBackend::new(db, root.clone());
+		let ret = Self::from_tree(root, &mut bm::LimitedBackend::with_limits(&mut metered, limits));
+		stats.merge(&metered.stats());
+		ret
+	}
+}
+
+/// Traits for type converting from a tree structure, with maximum
+/// lengths given by a runtime config.
+pub trait FromTreeWithConfig<C>: Sized {
+	/// Convert this type from merkle tree, reading nodes from the
+	/// given database, and using maximum lengths in the config.
+	fn from_tree_with_config<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		config: &C,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct;
+}
+
+/// Maximum length read from a runtime config, the config counterpart
+/// of a `typenum` maximum length. Decoding a list longer than it fails
+/// with `Error::MaxLenExceeded`.
+pub trait MaxLenFromConfig<C> {
+	/// Maximum length given by the config.
+	fn max_len_from_config(config: &C) -> u64;
+}
+
+impl<C, U: typenum::Unsigned> MaxLenFromConfig<C> for U {
+	fn max_len_from_config(_config: &C) -> u64 {
+		U::to_u64()
+	}
+}
+
+/// Traits for calculating the signing root of a container, which is the
+/// container's root with its signature fields truncated.
+pub trait SigningRoot {
+	/// Convert this type into merkle tree without signature fields,
+	/// writing nodes into the given database.
+	fn signing_root<DB: WriteBackend>(
+		&self,
+		db: &mut DB
+	) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct;
 }
 
 /// Indicate that the current value should be serialized and
@@ -130,18 +443,134 @@ pub struct CompactRef<'a, T>(pub &'a T);
 #[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Compact<T>(pub T);
 
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Encode> parity_codec::Encode for Compact<T> {
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		self.0.encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<T: parity_codec::Decode> parity_codec::Decode for Compact<T> {
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		Some(Self(parity_codec::Decode::decode(input)?))
+	}
+}
+
 impl<T> From<T> for Compact<T> {
 	fn from(t: T) -> Self {
 		Self(t)
 	}
 }
 
-/// Calculate a ssz merkle tree root, dismissing the tree.
+/// Calculate a ssz merkle tree root with 256-bit digest `D`, dismissing
+/// the tree. Roots of other digests are the values of `into_tree` over
+/// `DigestConstruct<D>`.
 pub fn tree_root<D, T>(value: &T) -> H256 where
 	T: IntoTree,
-	D: Digest<OutputSize=U32>,
+	D: Digest<OutputSize=typenum::U32>,
 {
-	value.into_tree(&mut NoopBackend::<DigestConstruct<D>>::default())
+	#[cfg(feature = "tracing")]
+	let _span = tracing::trace_span!(target: "bm", "into_tree", ty = core::any::type_name::<T>()).entered();
+	value.into_tree(&mut HashOnlyBackend::<DigestConstruct<D>>::new())
 		.map(|ret| H256::from_slice(ret.as_ref()))
-		.expect("Noop backend never fails in set; qed")
+		.expect("Hash-only backend never fails; qed")
+}
+
+/// Calculate the ssz `hash_tree_root` with sha256, dismissing the tree.
+#[cfg(feature = "sha2")]
+pub fn hash_tree_root<T: IntoTree>(value: &T) -> H256 {
+	tree_root::<sha2::Sha256, T>(value)
+}
+
+/// Calculate a ssz signing root with 256-bit digest `D`, dismissing the
+/// tree.
+pub fn signing_root<D, T>(value: &T) -> H256 where
+	T: SigningRoot,
+	D: Digest<OutputSize=typenum::U32>,
+{
+	value.signing_root(&mut HashOnlyBackend::<DigestConstruct<D>>::new())
+		.map(|ret| H256::from_slice(ret.as_ref()))
+		.expect("Hash-only backend never fails; qed")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::convert::TryFrom;
+	use sha2::Sha256;
+
+	#[test]
+	fn test_h256_conversions() {
+		let hash = H256::from([0xab; 32]);
+		let value = Value::from(hash);
+		assert_eq!(H256::from(value.clone()), hash);
+		assert_eq!(Value::try_from(hash.as_bytes()).unwrap(), value);
+		assert!(Value::try_from(&hash.as_bytes()[..31]).is_err());
+		assert_eq!(value.to_string(), format!("0x{}", "ab".repeat(32)));
+
+		let root = 5u64.into_tree(&mut bm::InMemoryBackend::<DigestConstruct<Sha256>>::default()).unwrap();
+		assert_eq!(5u64.root_h256::<Sha256>(), H256::from(root));
+		assert_eq!(5u64.root_h256::<Sha256>(), tree_root::<Sha256, _>(&5u64));
+	}
+
+	#[test]
+	fn test_from_tree_verified() {
+		let mut db = bm::InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let value = MaxVec::<u64, typenum::U8>::from(vec![1, 2, 3]);
+		let root = value.into_tree(&mut db).unwrap();
+		assert_eq!(MaxVec::<u64, typenum::U8>::from_tree_verified(&root, &mut db).unwrap(), value);
+
+		let (vector, _) = db.get(&root).unwrap().unwrap();
+		db.populate(core::iter::once((root.clone(), (vector, Value::from(2u64)))).collect());
+		assert_eq!(MaxVec::<u64, typenum::U8>::from_tree_verified(&root, &mut db).err(),
+				   Some(Error::Backend(bm::VerifiedBackendError::Mismatch)));
+	}
+
+	#[test]
+	fn test_from_tree_with_stats() {
+		let mut db = bm::InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let value = MaxVec::<u64, typenum::U16>::from(vec![1, 2, 3, 4, 5]);
+		let root = value.into_tree(&mut db).unwrap();
+
+		let mut stats = DecodeStats::default();
+		assert_eq!(MaxVec::<u64, typenum::U16>::from_tree_with_stats(&root, &mut db, &mut stats).unwrap(), value);
+		assert_eq!(stats, DecodeStats { nodes: 9, hashes: 0, depth: 5, bytes: 6 * 32 });
+
+		let mut verified = DecodeStats::default();
+		assert_eq!(MaxVec::<u64, typenum::U16>::from_tree_verified_with_stats(&root, &mut db, &mut verified).unwrap(),
+				   value);
+		assert_eq!(verified, DecodeStats { hashes: 9, ..stats });
+
+		let mut total = stats;
+		total.merge(&verified);
+		assert_eq!(total.nodes, 2 * stats.nodes);
+		assert_eq!(total.depth, stats.depth);
+	}
+
+	#[test]
+	fn test_wide_chunks() {
+		type Wide = bm::InheritedDigestConstruct<sha2::Sha512, End<typenum::U64>>;
+		let mut db = bm::InMemoryBackend::<Wide>::default();
+
+		let values = (1..=8u64).collect::<Vec<_>>();
+		let root = ElementalFixedVecRef(&values).into_compact_vector_tree(&mut db, None).unwrap();
+		let mut chunk = End::<typenum::U64>::default();
+		for (i, value) in values.iter().enumerate() {
+			chunk.0[(i * 8)..((i + 1) * 8)].copy_from_slice(&value.to_le_bytes());
+		}
+		assert_eq!(root, chunk);
+
+		let list = Compact(MaxVec::<u64, typenum::U32>::from((0..20).collect::<Vec<_>>()));
+		let root = list.into_tree(&mut db).unwrap();
+		assert_eq!(Compact::<MaxVec<u64, typenum::U32>>::from_tree(&root, &mut db).unwrap(), list);
+		assert_eq!(Value::from(1u64).into_tree(&mut db), Err(Error::InvalidParameter));
+	}
+
+	#[cfg(feature = "sha2")]
+	#[test]
+	fn test_hash_tree_root() {
+		let value = (MaxVec::<u64, typenum::U8>::from(vec![1, 2, 3]), true);
+		assert_eq!(hash_tree_root(&value), tree_root::<Sha256, _>(&value));
+	}
 }
@@ -0,0 +1,110 @@
+use bm::{ReadBackend, WriteBackend, Construct, Error};
+use bm::utils::vector_tree;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+use crate::{ElementalVariableVecRef, ElementalVariableVec, IntoCompositeListTree,
+			FromCompositeListTree, IntoTree, FromTree, CompatibleConstruct};
+
+fn entries_into_tree<'a, K, V, I, DB: WriteBackend>(
+	entries: I,
+	db: &mut DB
+) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+	K: IntoTree + 'a,
+	V: IntoTree + 'a,
+	I: Iterator<Item=(&'a K, &'a V)>,
+	DB::Construct: CompatibleConstruct,
+{
+	let mut roots = Vec::new();
+	for (key, value) in entries {
+		let entry = [key.into_tree(db)?, value.into_tree(db)?];
+		roots.push(vector_tree(&entry, db, None)?);
+	}
+
+	ElementalVariableVecRef(&roots).into_composite_list_tree(db, None)
+}
+
+fn entries_from_tree<K, V, DB: ReadBackend>(
+	root: &<DB::Construct as Construct>::Value,
+	db: &mut DB
+) -> Result<Vec<(K, V)>, Error<DB::Error>> where
+	K: FromTree + Ord,
+	V: FromTree,
+	DB::Construct: CompatibleConstruct,
+{
+	let entries = ElementalVariableVec::<(K, V)>::from_composite_list_tree(root, db, None)?.0;
+	if entries.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+		return Err(Error::corrupted())
+	}
+
+	Ok(entries)
+}
+
+/// Map is merkleized as a list of key-value containers, in ascending
+/// order of keys. Decoding rejects lists not in that canonical order.
+impl<K: IntoTree + Ord, V: IntoTree> IntoTree for BTreeMap<K, V> {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		entries_into_tree(self.iter(), db)
+	}
+}
+
+impl<K: FromTree + Ord, V: FromTree> FromTree for BTreeMap<K, V> {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		Ok(entries_from_tree(root, db)?.into_iter().collect())
+	}
+}
+
+/// Map is merkleized as a list of key-value containers, in ascending
+/// order of keys. Decoding rejects lists not in that canonical order.
+#[cfg(feature = "std")]
+impl<K: IntoTree + Ord + Hash, V: IntoTree> IntoTree for HashMap<K, V> {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let mut entries = self.iter().collect::<Vec<_>>();
+		entries.sort_by(|a, b| a.0.cmp(b.0));
+		entries_into_tree(entries.into_iter(), db)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<K: FromTree + Ord + Hash, V: FromTree> FromTree for HashMap<K, V> {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		Ok(entries_from_tree(root, db)?.into_iter().collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::DigestConstruct;
+
+	use bm::InMemoryBackend;
+	use sha2::Sha256;
+
+	#[test]
+	fn test_map() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let entries = vec![(1u64, 10u32), (2, 20), (5, 50)];
+
+		let btree = entries.iter().cloned().collect::<BTreeMap<_, _>>();
+		let hash = entries.iter().rev().cloned().collect::<HashMap<_, _>>();
+		let root = btree.into_tree(&mut db).unwrap();
+		assert_eq!(root, entries.into_tree(&mut db).unwrap());
+		assert_eq!(root, hash.into_tree(&mut db).unwrap());
+		assert_eq!(BTreeMap::<u64, u32>::from_tree(&root, &mut db).unwrap(), btree);
+		assert_eq!(HashMap::<u64, u32>::from_tree(&root, &mut db).unwrap(), hash);
+
+		let unsorted = vec![(2u64, 20u32), (1, 10)].into_tree(&mut db).unwrap();
+		assert_eq!(BTreeMap::<u64, u32>::from_tree(&unsorted, &mut db), Err(Error::corrupted()));
+	}
+}
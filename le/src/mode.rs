@@ -0,0 +1,233 @@
+use bm::{Error, DecodeLimits};
+use bm::utils::required_depth;
+
+/// How strictly trees are validated when decoding. `FromTree` always
+/// decodes strictly, and bitfields can be decoded leniently with their
+/// `from_tree_with_mode`.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum DecodeMode {
+	/// Reject trees whose unused padding bits are set with
+	/// `Error::NonCanonical`, as they do not have canonical roots.
+	Strict,
+	/// Ignore unused padding bits, for trees written by encoders that
+	/// did not clear them.
+	Lenient,
+}
+
+/// Byte order of integers committed in leaves.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Endianness {
+	/// Little-endian integers, left-aligned in their slot, as in ssz.
+	Little,
+	/// Big-endian integers, right-aligned in their slot, for chains
+	/// committing integers big-endian.
+	Big,
+}
+
+/// Layout of basic integers in the chunks of compact vectors and lists.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ChunkLayout {
+	/// Integers are packed together into chunks, as in ssz.
+	Packed,
+	/// Each integer takes a chunk of its own, like a single leaf.
+	/// Lenses and generalized indices assume the packed layout.
+	Padded,
+}
+
+/// Byte order and chunk layout of the integers committed in a tree,
+/// given by the `End` values of its construct, as in
+/// `DigestConstruct<D, BigEndian>`.
+pub trait Layout {
+	/// Byte order of integers in leaves, including `U256` and mixed-in
+	/// lengths.
+	const ENDIANNESS: Endianness;
+	/// Layout of integers in compact vectors and lists.
+	const CHUNK_LAYOUT: ChunkLayout;
+}
+
+/// Little-endian integers packed into chunks, as in ssz. This is the
+/// default layout.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct Ssz;
+
+impl Layout for Ssz {
+	const ENDIANNESS: Endianness = Endianness::Little;
+	const CHUNK_LAYOUT: ChunkLayout = ChunkLayout::Packed;
+}
+
+/// Big-endian integers packed into chunks.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct BigEndian;
+
+impl Layout for BigEndian {
+	const ENDIANNESS: Endianness = Endianness::Big;
+	const CHUNK_LAYOUT: ChunkLayout = ChunkLayout::Packed;
+}
+
+/// Little-endian integers, each in a chunk of its own.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct Padded;
+
+impl Layout for Padded {
+	const ENDIANNESS: Endianness = Endianness::Little;
+	const CHUNK_LAYOUT: ChunkLayout = ChunkLayout::Padded;
+}
+
+/// Big-endian integers, each in a chunk of its own.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct BigEndianPadded;
+
+impl Layout for BigEndianPadded {
+	const ENDIANNESS: Endianness = Endianness::Big;
+	const CHUNK_LAYOUT: ChunkLayout = ChunkLayout::Padded;
+}
+
+/// Check the length and maximum length of a decoded sequence against
+/// `limits`, as given by the backend read from.
+pub(crate) fn check_limits<E>(limits: &DecodeLimits, len: u64, max_len: Option<u64>) -> Result<(), Error<E>> {
+	if len > limits.max_len {
+		return Err(Error::MaxLenExceeded { len, max_len: limits.max_len })
+	}
+	if required_depth(max_len.unwrap_or(len)) > limits.max_depth {
+		return Err(Error::overflowed())
+	}
+
+	Ok(())
+}
+
+/// Write an integer given as little-endian bytes into `slot`, with the
+/// byte order of layout `L`.
+pub(crate) fn write_uint<L: Layout>(le: &[u8], slot: &mut [u8]) {
+	match L::ENDIANNESS {
+		Endianness::Little => slot[..le.len()].copy_from_slice(le),
+		Endianness::Big => for (i, byte) in le.iter().enumerate() {
+			slot[slot.len() - 1 - i] = *byte;
+		},
+	}
+}
+
+/// Read an integer from `slot` into little-endian bytes, with the byte
+/// order of layout `L`.
+pub(crate) fn read_uint<L: Layout>(slot: &[u8], le: &mut [u8]) {
+	match L::ENDIANNESS {
+		Endianness::Little => le.copy_from_slice(&slot[..le.len()]),
+		Endianness::Big => for (i, byte) in le.iter_mut().enumerate() {
+			*byte = slot[slot.len() - 1 - i];
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{IntoTree, FromTree, Compact, MaxVec, DigestConstruct, Lens};
+
+	use bm::{InMemoryBackend, DanglingRaw, Index, Tree};
+	use sha2::{Digest, Sha256};
+	use primitive_types::{H256, U256};
+	use typenum::{U2, U4, U16};
+
+	fn chunk(data: &[u8], right_aligned: bool) -> H256 {
+		let mut ret = [0; 32];
+		if right_aligned {
+			ret[(32 - data.len())..].copy_from_slice(data);
+		} else {
+			ret[..data.len()].copy_from_slice(data);
+		}
+
+		H256::from(ret)
+	}
+
+	fn h(a: &[u8], b: &[u8]) -> H256 {
+		let mut hash = Sha256::new();
+		hash.input(a);
+		hash.input(b);
+		H256::from_slice(hash.result().as_slice())
+	}
+
+	fn roundtrip<L: Layout, T: IntoTree + FromTree + core::fmt::Debug + PartialEq>(value: T) -> H256 {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256, L>>::default();
+		let root = value.into_tree(&mut db).unwrap();
+		assert_eq!(T::from_tree(&root, &mut db).unwrap(), value);
+		root.into()
+	}
+
+	#[test]
+	fn test_little_endian() {
+		let uints = vec![U256::from(1), U256::from(0x0102)];
+		assert_eq!(roundtrip::<Ssz, _>(0x0102u64), chunk(&[0x02, 0x01], false));
+		let vector = h(&chunk(&[0x01], false)[..], &chunk(&[0x02, 0x01], false)[..]);
+		assert_eq!(roundtrip::<Ssz, _>(uints.clone()), h(&vector[..], &chunk(&[2], false)[..]));
+		assert_eq!(roundtrip::<Ssz, _>(Compact(MaxVec::<U256, U2>::from(uints))), h(&vector[..], &chunk(&[2], false)[..]));
+		assert_eq!(roundtrip::<Ssz, _>(Compact(MaxVec::<u32, U4>::from(vec![1, 2, 3]))),
+				   h(&chunk(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0], false)[..], &chunk(&[3], false)[..]));
+	}
+
+	#[test]
+	fn test_big_endian() {
+		let uints = vec![U256::from(1), U256::from(0x0102)];
+		assert_eq!(roundtrip::<BigEndian, _>(0x0102u64), chunk(&[0x01, 0x02], true));
+		assert_eq!(roundtrip::<BigEndian, _>(U256::from(0x0102)), chunk(&[0x01, 0x02], true));
+		let vector = h(&chunk(&[0x01], true)[..], &chunk(&[0x01, 0x02], true)[..]);
+		assert_eq!(roundtrip::<BigEndian, _>(uints), h(&vector[..], &chunk(&[2], true)[..]));
+		assert_eq!(roundtrip::<BigEndian, _>(Compact(MaxVec::<u16, U16>::from(vec![0x0102, 0x0304]))),
+				   h(&chunk(&[0x01, 0x02, 0x03, 0x04], false)[..], &chunk(&[2], true)[..]));
+		assert_eq!(roundtrip::<BigEndian, _>(Compact(MaxVec::<u16, U16>::default())),
+				   h(&[0; 32], &[0; 32]));
+	}
+
+	#[test]
+	fn test_padded() {
+		assert_eq!(roundtrip::<BigEndianPadded, _>(Compact(MaxVec::<u16, U2>::from(vec![0x0102, 0x0304]))),
+				   h(&h(&chunk(&[0x01, 0x02], true)[..], &chunk(&[0x03, 0x04], true)[..])[..], &chunk(&[2], true)[..]));
+
+		let values = Compact(MaxVec::<u32, U4>::from(vec![1, 2, 3]));
+		let leaves = values.0.iter()
+			.map(|value| value.into_tree(&mut InMemoryBackend::<DigestConstruct<Sha256, Padded>>::default()).unwrap())
+			.collect::<Vec<_>>();
+		let vector = h(&h(&leaves[0].0[..], &leaves[1].0[..])[..], &h(&leaves[2].0[..], &[0; 32])[..]);
+		assert_eq!(roundtrip::<Padded, _>(values), h(&vector[..], &chunk(&[3], false)[..]));
+		assert_eq!(roundtrip::<Padded, _>(Compact(MaxVec::<u8, U2>::from(vec![7]))),
+				   h(&h(&chunk(&[7], false)[..], &[0; 32])[..], &chunk(&[1], false)[..]));
+	}
+
+	#[test]
+	fn test_layouts_side_by_side() {
+		let value = Compact(MaxVec::<u64, U4>::from(vec![1, 2]));
+		let mut ssz = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let mut big = InMemoryBackend::<DigestConstruct<Sha256, BigEndian>>::default();
+		let ssz_root = value.into_tree(&mut ssz).unwrap();
+		let big_root = value.into_tree(&mut big).unwrap();
+		assert_eq!(H256::from(ssz_root.clone()), crate::tree_root::<Sha256, _>(&value));
+		assert_ne!(ssz_root.0, big_root.0);
+		assert_eq!(Compact::<MaxVec<u64, U4>>::from_tree(&ssz_root, &mut ssz).unwrap(), value);
+		assert_eq!(Compact::<MaxVec<u64, U4>>::from_tree(&big_root, &mut big).unwrap(), value);
+	}
+
+	#[test]
+	fn test_packed_lens() {
+		let value = Compact(MaxVec::<u16, U16>::from(vec![0x0102, 0x0304]));
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256, BigEndian>>::default();
+		let mut raw = DanglingRaw::<DigestConstruct<Sha256, BigEndian>>::new(value.into_tree(&mut db).unwrap());
+		let lens = Lens::<Compact<MaxVec<u16, U16>>>::new(Index::root());
+		assert_eq!(lens.at(1).get(&raw, &mut db).unwrap(), 0x0304);
+
+		lens.at(1).set(&mut raw, &mut db, &0x0506).unwrap();
+		assert_eq!(raw.root(), Compact(MaxVec::<u16, U16>::from(vec![0x0102, 0x0506])).into_tree(&mut db).unwrap());
+	}
+
+	#[test]
+	fn test_check_limits() {
+		let limits = DecodeLimits { max_depth: 4, max_len: 16, max_nodes: 0 };
+		assert_eq!(check_limits::<()>(&limits, 0, None), Ok(()));
+		assert_eq!(check_limits::<()>(&limits, 0, Some(16)), Ok(()));
+		assert_eq!(check_limits::<()>(&limits, 16, None), Ok(()));
+		assert_eq!(check_limits::<()>(&limits, 17, None), Err(Error::MaxLenExceeded { len: 17, max_len: 16 }));
+		assert_eq!(check_limits::<()>(&limits, 2, Some(17)), Err(Error::overflowed()));
+		assert_eq!(check_limits::<()>(&DecodeLimits::unlimited(), u64::MAX, None), Ok(()));
+
+		let empty = Compact(MaxVec::<u16, U16>::default());
+		assert_eq!(roundtrip::<Ssz, _>(empty.clone()), roundtrip::<BigEndian, _>(empty));
+		roundtrip::<Padded, _>(MaxVec::<u64, U16>::default());
+	}
+}
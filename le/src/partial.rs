@@ -1,6 +1,8 @@
 use bm::{Index, Error, ReadBackend, RootStatus, Raw, DanglingList, Tree, WriteBackend};
 use primitive_types::{U256, H256};
 use core::mem;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use alloc::collections::BTreeMap as Map;
 #[cfg(feature = "std")]
@@ -75,11 +77,11 @@ impl PartialIndex {
 			PartialSubIndex::Raw(raw) => return Ok(parent.sub(raw)),
 			PartialSubIndex::Vector(index, len) => (index, len),
 			PartialSubIndex::List(index) => {
-				let len_root = raw.get(db, parent.right())?.ok_or(Error::CorruptedDatabase)?;
+				let len_root = raw.get_existing(db, parent.right())?;
 				let len = U256::from_tree(&len_root, db)?;
 
 				if len > U256::from(usize::max_value()) {
-					return Err(Error::CorruptedDatabase)
+					return Err(Error::corrupted())
 				} else {
 					(index, len.as_usize())
 				}
@@ -116,7 +118,7 @@ impl<T: FromTree> PartialValue<T> {
 		DB::Construct: CompatibleConstruct
 	{
 		let index = self.index.resolve(raw, db)?;
-		let index_root = raw.get(db, index)?.ok_or(Error::CorruptedDatabase)?;
+		let index_root = raw.get_existing(db, index)?;
 		let value = T::from_tree(&index_root, db)?;
 
 		self.value = Some(value);
@@ -0,0 +1,588 @@
+use primitive_types::{H160, H256, H512, U256};
+use generic_array::{GenericArray, ArrayLength};
+use vecarray::VecArray;
+use typenum::Unsigned;
+use core::convert::TryFrom;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::string::String;
+use crate::{MaxVec, BitList, BitVector, Union, H384, H768, MAX_UNION_SELECTOR};
+
+/// Length of an offset in ssz serialization.
+pub const OFFSET_LEN: usize = 4;
+
+/// Error when decoding ssz bytes.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DecodeError {
+	/// Bytes length does not match the type.
+	InvalidLength,
+	/// Offsets are out of range or not in order.
+	InvalidOffset,
+	/// Bytes are not a valid value of the type.
+	InvalidValue,
+	/// Bytes are not validly compressed.
+	InvalidCompression,
+}
+
+/// Traits for type serializing into ssz bytes.
+pub trait Encode {
+	/// Serialized length if the type is fixed-size, or `None` if it is
+	/// variable-size.
+	fn fixed_len() -> Option<usize>;
+
+	/// Serialize this type, appending the bytes to `out`.
+	fn encode_to(&self, out: &mut Vec<u8>);
+
+	/// Serialize this type into bytes.
+	fn encode(&self) -> Vec<u8> {
+		let mut out = Vec::new();
+		self.encode_to(&mut out);
+		out
+	}
+}
+
+/// Traits for type deserializing from ssz bytes.
+pub trait Decode: Sized {
+	/// Serialized length if the type is fixed-size, or `None` if it is
+	/// variable-size.
+	fn fixed_len() -> Option<usize>;
+
+	/// Deserialize this type from bytes, which must be consumed in full.
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError>;
+}
+
+fn encode_offset(offset: usize, out: &mut [u8]) {
+	out.copy_from_slice(&(offset as u32).to_le_bytes());
+}
+
+fn decode_offset(bytes: &[u8]) -> usize {
+	let mut offset = [0u8; OFFSET_LEN];
+	offset.copy_from_slice(bytes);
+	u32::from_le_bytes(offset) as usize
+}
+
+/// Encoder of composite values, writing fixed-size parts and offsets
+/// first, and variable-size parts afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct CompositeEncoder {
+	fixed: Vec<u8>,
+	variable: Vec<u8>,
+	offsets: Vec<(usize, usize)>,
+}
+
+impl CompositeEncoder {
+	/// Create a new composite encoder.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Append a part of the composite value.
+	pub fn append<T: Encode>(&mut self, value: &T) {
+		if T::fixed_len().is_some() {
+			value.encode_to(&mut self.fixed);
+		} else {
+			self.offsets.push((self.fixed.len(), self.variable.len()));
+			self.fixed.extend_from_slice(&[0u8; OFFSET_LEN]);
+			value.encode_to(&mut self.variable);
+		}
+	}
+
+	/// Finish encoding, appending the bytes to `out`.
+	pub fn finish(mut self, out: &mut Vec<u8>) {
+		let fixed_len = self.fixed.len();
+		for (position, offset) in self.offsets {
+			encode_offset(fixed_len + offset, &mut self.fixed[position..(position + OFFSET_LEN)]);
+		}
+		out.append(&mut self.fixed);
+		out.append(&mut self.variable);
+	}
+}
+
+/// Split bytes of a composite value into its parts, given the fixed
+/// length of each part, or `None` for variable-size parts.
+pub fn decode_composite<'a>(
+	bytes: &'a [u8],
+	lens: &[Option<usize>]
+) -> Result<Vec<&'a [u8]>, DecodeError> {
+	let mut position = 0;
+	let mut ranges = Vec::with_capacity(lens.len());
+	let mut offsets = Vec::new();
+
+	for len in lens {
+		let part_len = len.unwrap_or(OFFSET_LEN);
+		if bytes.len() < position + part_len {
+			return Err(DecodeError::InvalidLength)
+		}
+
+		match len {
+			Some(_) => ranges.push(Some((position, position + part_len))),
+			None => {
+				offsets.push((ranges.len(), decode_offset(&bytes[position..(position + part_len)])));
+				ranges.push(None);
+			},
+		}
+		position += part_len;
+	}
+
+	if offsets.is_empty() {
+		if bytes.len() != position {
+			return Err(DecodeError::InvalidLength)
+		}
+	} else if offsets[0].1 != position {
+		return Err(DecodeError::InvalidOffset)
+	}
+
+	for (i, (index, start)) in offsets.iter().cloned().enumerate() {
+		let end = offsets.get(i + 1).map(|o| o.1).unwrap_or(bytes.len());
+		if start > end || end > bytes.len() {
+			return Err(DecodeError::InvalidOffset)
+		}
+		ranges[index] = Some((start, end));
+	}
+
+	Ok(ranges.into_iter()
+	   .map(|range| {
+		   let (start, end) = range.expect("All ranges are filled by either fixed length or offset; qed");
+		   &bytes[start..end]
+	   })
+	   .collect())
+}
+
+fn encode_sequence<T: Encode>(values: &[T], out: &mut Vec<u8>) {
+	if T::fixed_len().is_some() {
+		for value in values {
+			value.encode_to(out);
+		}
+	} else {
+		let mut encoder = CompositeEncoder::new();
+		for value in values {
+			encoder.append(value);
+		}
+		encoder.finish(out);
+	}
+}
+
+fn decode_sequence<T: Decode>(bytes: &[u8]) -> Result<Vec<T>, DecodeError> {
+	match T::fixed_len() {
+		Some(0) => Err(DecodeError::InvalidValue),
+		Some(len) => {
+			if !bytes.len().is_multiple_of(len) {
+				return Err(DecodeError::InvalidLength)
+			}
+
+			bytes.chunks(len).map(T::decode).collect()
+		},
+		None => {
+			if bytes.is_empty() {
+				return Ok(Vec::new())
+			}
+			if bytes.len() < OFFSET_LEN {
+				return Err(DecodeError::InvalidLength)
+			}
+
+			let first = decode_offset(&bytes[..OFFSET_LEN]);
+			if first == 0 || !first.is_multiple_of(OFFSET_LEN) {
+				return Err(DecodeError::InvalidOffset)
+			}
+
+			let lens = alloc::vec![None; first / OFFSET_LEN];
+			decode_composite(bytes, &lens)?.into_iter().map(T::decode).collect()
+		},
+	}
+}
+
+fn decode_fixed_sequence<T: Decode>(bytes: &[u8], len: usize) -> Result<Vec<T>, DecodeError> {
+	match T::fixed_len() {
+		Some(0) => Err(DecodeError::InvalidValue),
+		Some(item_len) => {
+			if bytes.len() != item_len * len {
+				return Err(DecodeError::InvalidLength)
+			}
+
+			bytes.chunks(item_len).map(T::decode).collect()
+		},
+		None => {
+			let lens = alloc::vec![None; len];
+			decode_composite(bytes, &lens)?.into_iter().map(T::decode).collect()
+		},
+	}
+}
+
+fn fixed_sequence_len(item_len: Option<usize>, len: usize) -> Option<usize> {
+	item_len.map(|item_len| item_len * len)
+}
+
+impl Encode for bool {
+	fn fixed_len() -> Option<usize> { Some(1) }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		out.push(*self as u8);
+	}
+}
+
+impl Decode for bool {
+	fn fixed_len() -> Option<usize> { Some(1) }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		match bytes {
+			[0] => Ok(false),
+			[1] => Ok(true),
+			[_] => Err(DecodeError::InvalidValue),
+			_ => Err(DecodeError::InvalidLength),
+		}
+	}
+}
+
+macro_rules! impl_builtin_uint {
+	( $( $t:ty ),* ) => { $(
+		impl Encode for $t {
+			fn fixed_len() -> Option<usize> { Some(core::mem::size_of::<$t>()) }
+
+			fn encode_to(&self, out: &mut Vec<u8>) {
+				out.extend_from_slice(&self.to_le_bytes());
+			}
+		}
+
+		impl Decode for $t {
+			fn fixed_len() -> Option<usize> { Some(core::mem::size_of::<$t>()) }
+
+			fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+				let bytes = <[u8; core::mem::size_of::<$t>()]>::try_from(bytes)
+					.map_err(|_| DecodeError::InvalidLength)?;
+				Ok(<$t>::from_le_bytes(bytes))
+			}
+		}
+	)* }
+}
+
+impl_builtin_uint!(u8, u16, u32, u64, u128);
+
+impl Encode for U256 {
+	fn fixed_len() -> Option<usize> { Some(32) }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		let mut bytes = [0u8; 32];
+		self.to_little_endian(&mut bytes);
+		out.extend_from_slice(&bytes);
+	}
+}
+
+impl Decode for U256 {
+	fn fixed_len() -> Option<usize> { Some(32) }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		if bytes.len() != 32 {
+			return Err(DecodeError::InvalidLength)
+		}
+
+		Ok(U256::from_little_endian(bytes))
+	}
+}
+
+macro_rules! impl_fixed_hash {
+	( $( $t:ty => $len:expr ),* ) => { $(
+		impl Encode for $t {
+			fn fixed_len() -> Option<usize> { Some($len) }
+
+			fn encode_to(&self, out: &mut Vec<u8>) {
+				out.extend_from_slice(self.as_ref());
+			}
+		}
+
+		impl Decode for $t {
+			fn fixed_len() -> Option<usize> { Some($len) }
+
+			fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+				if bytes.len() != $len {
+					return Err(DecodeError::InvalidLength)
+				}
+
+				Ok(<$t>::from_slice(bytes))
+			}
+		}
+	)* }
+}
+
+impl_fixed_hash!(H160 => 20, H256 => 32, H384 => 48, H512 => 64, H768 => 96);
+
+impl<T: Encode, L: ArrayLength<T>> Encode for GenericArray<T, L> {
+	fn fixed_len() -> Option<usize> { fixed_sequence_len(T::fixed_len(), L::to_usize()) }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		encode_sequence(&self[..], out)
+	}
+}
+
+impl<T: Decode, L: ArrayLength<T>> Decode for GenericArray<T, L> {
+	fn fixed_len() -> Option<usize> { fixed_sequence_len(T::fixed_len(), L::to_usize()) }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		let values = decode_fixed_sequence::<T>(bytes, L::to_usize())?;
+		GenericArray::from_exact_iter(values).ok_or(DecodeError::InvalidLength)
+	}
+}
+
+impl<T: Encode, L: Unsigned> Encode for VecArray<T, L> {
+	fn fixed_len() -> Option<usize> { fixed_sequence_len(T::fixed_len(), L::to_usize()) }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		encode_sequence(&self[..], out)
+	}
+}
+
+impl<T: Decode, L: Unsigned> Decode for VecArray<T, L> {
+	fn fixed_len() -> Option<usize> { fixed_sequence_len(T::fixed_len(), L::to_usize()) }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		let values = decode_fixed_sequence::<T>(bytes, L::to_usize())?;
+		VecArray::try_from(values).map_err(|_| DecodeError::InvalidLength)
+	}
+}
+
+impl<T: Encode> Encode for Vec<T> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		encode_sequence(&self[..], out)
+	}
+}
+
+impl<T: Decode> Decode for Vec<T> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		decode_sequence(bytes)
+	}
+}
+
+impl Encode for String {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		out.extend_from_slice(self.as_bytes());
+	}
+}
+
+impl Decode for String {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidValue)
+	}
+}
+
+impl<T: Encode, ML> Encode for MaxVec<T, ML> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		encode_sequence(&self.0[..], out)
+	}
+}
+
+impl<T: Decode, ML: Unsigned> Decode for MaxVec<T, ML> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		let values = decode_sequence::<T>(bytes)?;
+		if values.len() > ML::to_usize() {
+			return Err(DecodeError::InvalidLength)
+		}
+
+		Ok(MaxVec::from(values))
+	}
+}
+
+impl<ML: Unsigned> Encode for BitList<ML> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		out.append(&mut self.to_bytes());
+	}
+}
+
+impl<ML: Unsigned> Decode for BitList<ML> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		Self::from_bytes(bytes).ok_or(DecodeError::InvalidValue)
+	}
+}
+
+impl<L: Unsigned> Encode for BitVector<L> {
+	fn fixed_len() -> Option<usize> { Some(L::to_usize().div_ceil(8)) }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		out.append(&mut self.to_bytes());
+	}
+}
+
+impl<L: Unsigned> Decode for BitVector<L> {
+	fn fixed_len() -> Option<usize> { Some(L::to_usize().div_ceil(8)) }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		if bytes.len() != L::to_usize().div_ceil(8) {
+			return Err(DecodeError::InvalidLength)
+		}
+
+		Self::from_bytes(bytes).ok_or(DecodeError::InvalidValue)
+	}
+}
+
+impl<T: Encode> Encode for Union<T> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		out.push(self.selector());
+		self.value().encode_to(out);
+	}
+}
+
+impl<T: Decode> Decode for Union<T> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		let (selector, value) = bytes.split_first().ok_or(DecodeError::InvalidLength)?;
+		if *selector > MAX_UNION_SELECTOR {
+			return Err(DecodeError::InvalidValue)
+		}
+
+		Ok(Union::new(*selector, T::decode(value)?)
+		   .expect("Selector is checked not to be larger than the maximum; qed"))
+	}
+}
+
+impl<T: Encode> Encode for Option<T> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		match self {
+			None => out.push(0),
+			Some(value) => {
+				out.push(1);
+				value.encode_to(out);
+			},
+		}
+	}
+}
+
+impl<T: Decode> Decode for Option<T> {
+	fn fixed_len() -> Option<usize> { None }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		match bytes.split_first() {
+			Some((0, [])) => Ok(None),
+			Some((0, _)) => Err(DecodeError::InvalidLength),
+			Some((1, value)) => Ok(Some(T::decode(value)?)),
+			Some(_) => Err(DecodeError::InvalidValue),
+			None => Err(DecodeError::InvalidLength),
+		}
+	}
+}
+
+impl<T: Encode> Encode for Box<T> {
+	fn fixed_len() -> Option<usize> { T::fixed_len() }
+
+	fn encode_to(&self, out: &mut Vec<u8>) {
+		self.as_ref().encode_to(out)
+	}
+}
+
+impl<T: Decode> Decode for Box<T> {
+	fn fixed_len() -> Option<usize> { T::fixed_len() }
+
+	fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+		Ok(Box::new(T::decode(bytes)?))
+	}
+}
+
+macro_rules! impl_tuple {
+	($($i:ident => $t:ident),+) => {
+		impl<$($t: Encode),+> Encode for ($($t,)+) {
+			fn fixed_len() -> Option<usize> {
+				Some(0 $(+ <$t as Encode>::fixed_len()?)+)
+			}
+
+			fn encode_to(&self, out: &mut Vec<u8>) {
+				let ($($i,)+) = self;
+				let mut encoder = CompositeEncoder::new();
+				$(encoder.append($i);)+
+				encoder.finish(out);
+			}
+		}
+
+		impl<$($t: Decode),+> Decode for ($($t,)+) {
+			fn fixed_len() -> Option<usize> {
+				Some(0 $(+ <$t as Decode>::fixed_len()?)+)
+			}
+
+			fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+				let parts = decode_composite(bytes, &[$(<$t as Decode>::fixed_len()),+])?;
+				let mut parts = parts.into_iter();
+				Ok(($(
+					<$t>::decode(parts.next().expect("Parts have the same length as the tuple; qed"))?,
+				)+))
+			}
+		}
+	}
+}
+
+impl_tuple!(a => A);
+impl_tuple!(a => A, b => B);
+impl_tuple!(a => A, b => B, c => C);
+impl_tuple!(a => A, b => B, c => C, d => D);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E, f => F);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E, f => F, g => G);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I, j => J);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I, j => J, k => K);
+impl_tuple!(a => A, b => B, c => C, d => D, e => E, f => F, g => G, h => H, i => I, j => J, k => K, l => L);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use typenum::{U2, U4, U10};
+
+	#[test]
+	fn test_basic() {
+		assert_eq!(0x01020304u32.encode(), vec![4, 3, 2, 1]);
+		assert_eq!(u32::decode(&[4, 3, 2, 1]), Ok(0x01020304));
+		assert_eq!(u32::decode(&[4, 3, 2]), Err(DecodeError::InvalidLength));
+		assert_eq!(bool::decode(&[2]), Err(DecodeError::InvalidValue));
+		assert_eq!(U256::decode(&U256::from(258).encode()), Ok(U256::from(258)));
+	}
+
+	#[test]
+	fn test_composite() {
+		let value = (1u16, vec![2u8, 3], 4u8, vec![vec![5u16], vec![]]);
+		let bytes = value.encode();
+		assert_eq!(bytes, vec![
+			1, 0, 11, 0, 0, 0, 4, 13, 0, 0, 0,
+			2, 3,
+			8, 0, 0, 0, 10, 0, 0, 0, 5, 0,
+		]);
+		assert_eq!(<(u16, Vec<u8>, u8, Vec<Vec<u16>>)>::decode(&bytes), Ok(value));
+
+		assert_eq!(<(u16, Vec<u8>)>::decode(&[1, 0, 7, 0, 0, 0]), Err(DecodeError::InvalidOffset));
+		assert_eq!(<(u16, u8)>::decode(&[1, 0, 2, 3]), Err(DecodeError::InvalidLength));
+	}
+
+	#[test]
+	fn test_fixed_and_limited() {
+		let array = GenericArray::<u16, U2>::from_exact_iter(vec![1, 2]).unwrap();
+		assert_eq!(<GenericArray<u16, U2> as Encode>::fixed_len(), Some(4));
+		assert_eq!(GenericArray::<u16, U2>::decode(&array.encode()), Ok(array));
+
+		let list = MaxVec::<u8, U4>::from(vec![1, 2, 3, 4, 5]);
+		assert_eq!(MaxVec::<u8, U4>::decode(&list.encode()), Err(DecodeError::InvalidLength));
+
+		let mut bits = BitVector::<U10>::new();
+		bits.set(9, true).unwrap();
+		assert_eq!(BitVector::<U10>::decode(&bits.encode()), Ok(bits));
+
+		assert_eq!(Option::<u8>::decode(&[0]), Ok(None));
+		assert_eq!(Option::<u8>::decode(&Some(3u8).encode()), Ok(Some(3)));
+		assert_eq!(Option::<u8>::decode(&[2, 3]), Err(DecodeError::InvalidValue));
+	}
+}
@@ -0,0 +1,36 @@
+use std::io::{Read, Write};
+use alloc::vec::Vec;
+use crate::{Encode, Decode, DecodeError};
+
+/// Serialize a value into ssz bytes compressed in snappy frame format,
+/// as the `ssz_snappy` encoding.
+pub fn encode_snappy<T: Encode>(value: &T) -> Vec<u8> {
+	let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+	encoder.write_all(&value.encode())
+		.expect("Writing into a vec never fails; qed");
+	encoder.into_inner()
+		.expect("Flushing into a vec never fails; qed")
+}
+
+/// Deserialize a value from ssz bytes compressed in snappy frame
+/// format, as the `ssz_snappy` encoding.
+pub fn decode_snappy<T: Decode>(bytes: &[u8]) -> Result<T, DecodeError> {
+	let mut decoded = Vec::new();
+	snap::read::FrameDecoder::new(bytes).read_to_end(&mut decoded)
+		.map_err(|_| DecodeError::InvalidCompression)?;
+	T::decode(&decoded)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_snappy() {
+		let value = (1u64, vec![0u8; 256]);
+		let bytes = encode_snappy(&value);
+		assert!(bytes.len() < value.encode().len());
+		assert_eq!(decode_snappy::<(u64, Vec<u8>)>(&bytes), Ok(value));
+		assert_eq!(decode_snappy::<u64>(&[0, 1, 2]), Err(DecodeError::InvalidCompression));
+	}
+}
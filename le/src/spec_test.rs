@@ -0,0 +1,159 @@
+//! Runner of consensus-spec-tests ssz vectors.
+//!
+//! A case directory with `roots.yaml` and `serialized.ssz_snappy` is a
+//! valid case, whose bytes must decode, re-encode to the same bytes, and
+//! merkleize to the given root. A case directory with only
+//! `serialized.ssz_snappy` is an invalid case, whose bytes must fail to
+//! decode.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use alloc::vec::Vec;
+use primitive_types::H256;
+use sha2::Sha256;
+use crate::{IntoTree, Encode, Decode, tree_root};
+
+const ROOTS_FILE: &str = "roots.yaml";
+const SERIALIZED_FILE: &str = "serialized.ssz_snappy";
+
+/// Error when running spec test vectors.
+#[derive(Debug)]
+pub enum SpecTestError {
+	/// Reading the vector failed.
+	Io(PathBuf, io::Error),
+	/// The vector is not in the expected format.
+	InvalidVector(PathBuf),
+	/// The implementation does not match the vector.
+	Mismatch(PathBuf, &'static str),
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, SpecTestError> {
+	fs::read(path).map_err(|e| SpecTestError::Io(path.to_path_buf(), e))
+}
+
+fn read_serialized(case: &Path) -> Result<Vec<u8>, SpecTestError> {
+	let path = case.join(SERIALIZED_FILE);
+	let compressed = read(&path)?;
+	snap::raw::Decoder::new().decompress_vec(&compressed)
+		.map_err(|_| SpecTestError::InvalidVector(path))
+}
+
+fn read_root(case: &Path) -> Result<H256, SpecTestError> {
+	let path = case.join(ROOTS_FILE);
+	let content = String::from_utf8(read(&path)?)
+		.map_err(|_| SpecTestError::InvalidVector(path.clone()))?;
+
+	let hex = content.find("root:")
+		.map(|start| &content[(start + "root:".len())..])
+		.and_then(|value| value.split(|c| c == '}' || c == '\n').next())
+		.map(|value| value.trim().trim_matches(|c| c == '\'' || c == '"'))
+		.and_then(|value| value.strip_prefix("0x"))
+		.ok_or_else(|| SpecTestError::InvalidVector(path.clone()))?;
+	if hex.len() != 64 {
+		return Err(SpecTestError::InvalidVector(path))
+	}
+
+	let mut root = [0u8; 32];
+	for (i, byte) in root.iter_mut().enumerate() {
+		*byte = u8::from_str_radix(&hex[(i * 2)..(i * 2 + 2)], 16)
+			.map_err(|_| SpecTestError::InvalidVector(path.clone()))?;
+	}
+	Ok(H256::from(root))
+}
+
+/// Run a single case directory of ssz vectors against type `T`.
+pub fn run_ssz_case<T>(case: &Path) -> Result<(), SpecTestError> where
+	T: IntoTree + Encode + Decode,
+{
+	let serialized = read_serialized(case)?;
+
+	if !case.join(ROOTS_FILE).exists() {
+		return match T::decode(&serialized) {
+			Ok(_) => Err(SpecTestError::Mismatch(case.to_path_buf(), "invalid bytes decoded")),
+			Err(_) => Ok(()),
+		}
+	}
+
+	let root = read_root(case)?;
+	let value = T::decode(&serialized)
+		.map_err(|_| SpecTestError::Mismatch(case.to_path_buf(), "valid bytes failed to decode"))?;
+	if value.encode() != serialized {
+		return Err(SpecTestError::Mismatch(case.to_path_buf(), "serialization"))
+	}
+	if tree_root::<Sha256, _>(&value) != root {
+		return Err(SpecTestError::Mismatch(case.to_path_buf(), "root"))
+	}
+
+	Ok(())
+}
+
+/// Run all case directories found under `dir` against type `T`,
+/// returning the number of cases run. For `ssz_static` vectors, `dir`
+/// is the directory of a type, for example
+/// `tests/mainnet/phase0/ssz_static/Checkpoint`.
+pub fn run_ssz_cases<T>(dir: &Path) -> Result<usize, SpecTestError> where
+	T: IntoTree + Encode + Decode,
+{
+	if dir.join(SERIALIZED_FILE).exists() {
+		run_ssz_case::<T>(dir)?;
+		return Ok(1)
+	}
+
+	let mut entries = fs::read_dir(dir)
+		.map_err(|e| SpecTestError::Io(dir.to_path_buf(), e))?
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|e| SpecTestError::Io(dir.to_path_buf(), e))?;
+	entries.sort();
+
+	let mut count = 0;
+	for entry in entries {
+		if entry.is_dir() {
+			count += run_ssz_cases::<T>(&entry)?;
+		}
+	}
+	Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::MaxVec;
+	use typenum::U4;
+
+	type Checkpoint = (u64, H256);
+
+	fn write_case(dir: &Path, bytes: &[u8], root: Option<H256>) {
+		fs::create_dir_all(dir).unwrap();
+		fs::write(
+			dir.join(SERIALIZED_FILE),
+			snap::raw::Encoder::new().compress_vec(bytes).unwrap()
+		).unwrap();
+		if let Some(root) = root {
+			fs::write(dir.join(ROOTS_FILE), format!("{{root: '{:?}'}}\n", root)).unwrap();
+		}
+	}
+
+	#[test]
+	fn test_run_cases() {
+		let dir = std::env::temp_dir().join(format!("bm-le-spec-test-{}", std::process::id()));
+		let value: Checkpoint = (3, H256::repeat_byte(0x11));
+		write_case(
+			&dir.join("ssz_random/case_0"),
+			&value.encode(),
+			Some(tree_root::<Sha256, _>(&value))
+		);
+		write_case(&dir.join("ssz_invalid/case_0"), &[0u8; 5], None);
+		assert_eq!(run_ssz_cases::<Checkpoint>(&dir).unwrap(), 2);
+
+		write_case(&dir.join("ssz_random/case_1"), &value.encode(), Some(H256::zero()));
+		match run_ssz_cases::<Checkpoint>(&dir) {
+			Err(SpecTestError::Mismatch(_, "root")) => (),
+			other => panic!("unexpected result {:?}", other),
+		}
+		assert!(run_ssz_case::<MaxVec<u8, U4>>(&dir.join("ssz_invalid/case_0")).is_ok());
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
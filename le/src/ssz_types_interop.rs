@@ -0,0 +1,146 @@
+//! Conversions between this crate's list, vector and bitfield types and
+//! their `ssz_types` equivalents, so that projects using `ssz_types`
+//! can move values into merklist's backing trees one type at a time.
+//!
+//! `MaxVec`, `BitList` and `BitVector` convert with `From` and
+//! `TryFrom`. `VecArray` is defined outside of this crate, so it
+//! converts with `to_fixed_vector` and `from_fixed_vector` instead. Use
+//! `roots_match` to check that a value and its converted counterpart
+//! still merkleize to the same root.
+
+use core::convert::TryFrom;
+use alloc::vec::Vec;
+use typenum::Unsigned;
+use vecarray::VecArray;
+use ssz_types::{FixedVector, VariableList, BitList as SszBitList, BitVector as SszBitVector};
+use tree_hash::TreeHash;
+
+use crate::{MaxVec, BitList, BitVector, IntoTree, tree_root};
+
+/// Error of `ssz_types`, returned when a value does not fit the
+/// length of the target type.
+pub use ssz_types::Error;
+
+impl<T, N: Unsigned> From<VariableList<T, N>> for MaxVec<T, N> {
+	fn from(list: VariableList<T, N>) -> Self {
+		MaxVec::from(Vec::from(list))
+	}
+}
+
+/// `MaxVec` does not check its maximum length, so converting a longer
+/// one fails instead of truncating it.
+impl<T, N: Unsigned> TryFrom<MaxVec<T, N>> for VariableList<T, N> {
+	type Error = Error;
+
+	fn try_from(vec: MaxVec<T, N>) -> Result<Self, Error> {
+		VariableList::new(vec.0)
+	}
+}
+
+impl<N: Unsigned + Clone> From<SszBitList<N>> for BitList<N> {
+	fn from(list: SszBitList<N>) -> Self {
+		BitList::from_bits(list.iter().collect())
+			.expect("ssz_types bitlist has at most N bits; qed")
+	}
+}
+
+impl<N: Unsigned + Clone> From<BitList<N>> for SszBitList<N> {
+	fn from(list: BitList<N>) -> Self {
+		let mut ret = SszBitList::with_capacity(list.len())
+			.expect("Bitlist has at most N bits; qed");
+		for (i, bit) in list.bits().iter().enumerate() {
+			ret.set(i, *bit).expect("Index is within capacity; qed");
+		}
+		ret
+	}
+}
+
+impl<N: Unsigned + Clone> From<SszBitVector<N>> for BitVector<N> {
+	fn from(vector: SszBitVector<N>) -> Self {
+		BitVector::from_bits(vector.iter().collect())
+			.expect("ssz_types bitvector has N bits; qed")
+	}
+}
+
+impl<N: Unsigned + Clone> From<BitVector<N>> for SszBitVector<N> {
+	fn from(vector: BitVector<N>) -> Self {
+		let mut ret = SszBitVector::new();
+		for (i, bit) in vector.bits().iter().enumerate() {
+			ret.set(i, *bit).expect("Bitvector has N bits; qed");
+		}
+		ret
+	}
+}
+
+/// Convert a `VecArray` into a `FixedVector` of the same length.
+pub fn to_fixed_vector<T: Clone, N: Unsigned>(array: &VecArray<T, N>) -> FixedVector<T, N> {
+	FixedVector::new(array.to_vec()).expect("VecArray has N elements; qed")
+}
+
+/// Convert a `FixedVector` into a `VecArray` of the same length.
+pub fn from_fixed_vector<T, N: Unsigned>(vector: FixedVector<T, N>) -> VecArray<T, N> {
+	VecArray::try_from(Vec::from(vector))
+		.unwrap_or_else(|_| panic!("FixedVector has N elements; qed"))
+}
+
+/// Whether the sha256 root of `value` merkleized by this crate equals
+/// the `tree_hash` root of `other`. Lists and vectors of basic values
+/// are packed in `ssz_types`, so wrap their counterparts in `Compact`.
+pub fn roots_match<T: IntoTree, U: TreeHash>(value: &T, other: &U) -> bool {
+	tree_root::<sha2::Sha256, T>(value).as_bytes() == other.tree_hash_root().as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Compact;
+	use primitive_types::H256;
+	use typenum::{U4, U8, U10, U1024};
+
+	#[test]
+	fn test_lists() {
+		let values = MaxVec::<u64, U1024>::from(vec![1, 2, 3, 4, 5]);
+		let list = VariableList::try_from(values.clone()).unwrap();
+		assert!(roots_match(&Compact(values.clone()), &list));
+		assert_eq!(MaxVec::from(list), values);
+
+		let empty = MaxVec::<u64, U1024>::default();
+		assert!(roots_match(&Compact(empty.clone()), &VariableList::try_from(empty).unwrap()));
+
+		let hashes = MaxVec::<H256, U8>::from(vec![H256::repeat_byte(1), H256::repeat_byte(2)]);
+		let list = VariableList::<tree_hash::Hash256, U8>::from(
+			hashes.iter().map(|h| tree_hash::Hash256::from_slice(h.as_bytes())).collect::<Vec<_>>()
+		);
+		assert!(roots_match(&hashes, &list));
+
+		assert!(VariableList::try_from(MaxVec::<u64, U4>::from(vec![0; 5])).is_err());
+	}
+
+	#[test]
+	fn test_vectors() {
+		let array = VecArray::<u16, U10>::try_from((0..10).collect::<Vec<_>>())
+			.unwrap_or_else(|_| panic!());
+		let vector = to_fixed_vector(&array);
+		assert!(roots_match(&Compact(array.clone()), &vector));
+		assert_eq!(from_fixed_vector(vector), array);
+	}
+
+	#[test]
+	fn test_bits() {
+		let bits = vec![true, false, true, true, false, false, true, false, true];
+		let list = BitList::<U1024>::from_bits(bits.clone()).unwrap();
+		let ssz_list = SszBitList::from(list.clone());
+		assert!(roots_match(&list, &ssz_list));
+		assert_eq!(list.to_bytes(), ssz_list.clone().into_bytes().to_vec());
+		assert_eq!(BitList::from(ssz_list), list);
+
+		let empty = BitList::<U8>::default();
+		assert!(roots_match(&empty, &SszBitList::from(empty.clone())));
+
+		let vector = BitVector::<U10>::from_bits(bits.into_iter().chain(Some(true)).collect()).unwrap();
+		let ssz_vector = SszBitVector::from(vector.clone());
+		assert!(roots_match(&vector, &ssz_vector));
+		assert_eq!(vector.to_bytes(), ssz_vector.clone().into_bytes().to_vec());
+		assert_eq!(BitVector::from(ssz_vector), vector);
+	}
+}
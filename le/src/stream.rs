@@ -0,0 +1,126 @@
+//! Streaming merkleization of byte inputs too large to hold in memory,
+//! such as multi-gigabyte files.
+//!
+//! Bytes are read from an `io::Read` one chunk at a time and pushed to a
+//! `VectorTreeBuilder` over a `NoopBackend`, which keeps one pending node
+//! per level, so memory stays proportional to the depth of the tree. To
+//! merkleize other leaves from an iterator, use
+//! `bm::utils::vector_tree_from_iter`.
+
+use std::io::{self, Read};
+use digest::Digest;
+use primitive_types::{H256, U256};
+use bm::NoopBackend;
+use bm::utils::{VectorTreeBuilder, host_max_len};
+
+use generic_array::GenericArray;
+
+use crate::{DigestConstruct, Value, ChunkSize, IntoTree};
+
+/// Read until `block` is full or the reader is exhausted, returning the
+/// number of bytes read.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8]) -> io::Result<usize> {
+	let mut filled = 0;
+	while filled < block.len() {
+		match reader.read(&mut block[filled..]) {
+			Ok(0) => break,
+			Ok(read) => filled += read,
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(err),
+		}
+	}
+	Ok(filled)
+}
+
+/// Merkleize all bytes of `reader` as packed chunks, failing once more
+/// than `max_len` bytes are read. Returns the root of the chunks and the
+/// number of bytes.
+fn chunks_root<D: Digest<OutputSize=typenum::U32>, R: Read>(
+	mut reader: R,
+	max_len: Option<u64>,
+) -> io::Result<(Value, u64)> {
+	let mut db = NoopBackend::<DigestConstruct<D>>::default();
+	let mut builder = VectorTreeBuilder::new();
+	let mut len = 0u64;
+	let mut block = GenericArray::<u8, ChunkSize<DigestConstruct<D>>>::default();
+
+	loop {
+		let read = read_block(&mut reader, &mut block)?;
+		if read == 0 {
+			break
+		}
+		len += read as u64;
+		if max_len.map(|max_len| len > max_len).unwrap_or(false) {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "input longer than maximum length"))
+		}
+
+		let mut chunk = Value::default();
+		chunk.0.as_mut()[..read].copy_from_slice(&block[..read]);
+		builder.push(&mut db, chunk).expect("Noop backend never fails in set; qed");
+
+		if read < block.len() {
+			break
+		}
+	}
+
+	let max_chunks = max_len.map(host_max_len::<ChunkSize<DigestConstruct<D>>, typenum::U1>);
+	let root = builder.finish(&mut db, max_chunks).expect("Noop backend never fails in set; qed");
+	Ok((root, len))
+}
+
+/// Calculate the root of all bytes of `reader` as a ssz byte vector,
+/// equal to the root of an `ElementalFixedVec<u8>` of the same bytes.
+pub fn bytes_vector_root<D: Digest<OutputSize=typenum::U32>, R: Read>(reader: R) -> io::Result<H256> {
+	let (root, _) = chunks_root::<D, R>(reader, None)?;
+	Ok(root.into())
+}
+
+/// Calculate the root of all bytes of `reader` as a ssz byte list with
+/// maximum length `max_len`, with the number of bytes mixed in. Fails
+/// with `InvalidData` once more than `max_len` bytes are read.
+pub fn bytes_list_root<D: Digest<OutputSize=typenum::U32>, R: Read>(reader: R, max_len: u64) -> io::Result<H256> {
+	let (root, len) = chunks_root::<D, R>(reader, Some(max_len))?;
+	let root = (root, U256::from(len)).into_tree(&mut NoopBackend::<DigestConstruct<D>>::default())
+		.expect("Noop backend never fails in set; qed");
+	Ok(root.into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{tree_root, Compact, MaxVec, ElementalFixedVecRef, IntoCompactVectorTree};
+
+	use sha2::Sha256;
+	use typenum::U1024;
+
+	/// Reader returning at most 5 bytes at a time, to exercise short reads.
+	struct Trickle<'a>(&'a [u8]);
+
+	impl<'a> Read for Trickle<'a> {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			let len = buf.len().min(5).min(self.0.len());
+			buf[..len].copy_from_slice(&self.0[..len]);
+			self.0 = &self.0[len..];
+			Ok(len)
+		}
+	}
+
+	#[test]
+	fn test_bytes_root() {
+		for len in [0, 1, 31, 32, 33, 100, 1000] {
+			let bytes = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+
+			let vector = ElementalFixedVecRef(&bytes)
+				.into_compact_vector_tree(&mut NoopBackend::<DigestConstruct<Sha256>>::default(), None)
+				.unwrap();
+			assert_eq!(bytes_vector_root::<Sha256, _>(Trickle(&bytes)).unwrap(), vector.into());
+
+			let list = tree_root::<Sha256, _>(&Compact(MaxVec::<u8, U1024>::from(bytes.clone())));
+			assert_eq!(bytes_list_root::<Sha256, _>(Trickle(&bytes), 1024).unwrap(), list);
+			assert_eq!(bytes_list_root::<Sha256, _>(&bytes[..], 1024).unwrap(), list);
+		}
+
+		let err = bytes_list_root::<Sha256, _>(&[0u8; 65][..], 64).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	}
+}
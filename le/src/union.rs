@@ -0,0 +1,84 @@
+use bm::{ReadBackend, WriteBackend, Construct, Error};
+use crate::{IntoTree, FromTree, CompatibleConstruct};
+use crate::utils::{mix_in_selector, decode_with_selector};
+
+/// Maximum selector of an ssz union.
+pub const MAX_UNION_SELECTOR: u8 = 127;
+
+/// Union with an explicit selector. In `ssz`'s definition, this is a
+/// "union", whose root mixes the selector into the root of the selected
+/// value. For unions of different types, derive `IntoTree` and
+/// `FromTree` on an enum instead.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Union<T> {
+	selector: u8,
+	value: T,
+}
+
+impl<T> Union<T> {
+	/// Create a new union. Return `None` if the selector is larger than
+	/// `MAX_UNION_SELECTOR`.
+	pub fn new(selector: u8, value: T) -> Option<Self> {
+		if selector > MAX_UNION_SELECTOR {
+			return None
+		}
+
+		Some(Self { selector, value })
+	}
+
+	/// Selector of the union.
+	pub fn selector(&self) -> u8 {
+		self.selector
+	}
+
+	/// Selected value of the union.
+	pub fn value(&self) -> &T {
+		&self.value
+	}
+
+	/// Convert the union into its selected value.
+	pub fn into_value(self) -> T {
+		self.value
+	}
+}
+
+impl<T: IntoTree> IntoTree for Union<T> {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let root = self.value.into_tree(db)?;
+		mix_in_selector(&root, db, self.selector)
+	}
+}
+
+impl<T: FromTree> FromTree for Union<T> {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let (inner, selector) = decode_with_selector(root, db)?;
+		Ok(Self { selector, value: T::from_tree(&inner, db)? })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::DigestConstruct;
+
+	use bm::InMemoryBackend;
+	use sha2::Sha256;
+
+	#[test]
+	fn test_union() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		assert_eq!(Union::new(128, 1u64), None);
+
+		let union = Union::new(2, 0x1234u64).unwrap();
+		let root = union.into_tree(&mut db).unwrap();
+		assert_eq!(root, (0x1234u64, 2u8).into_tree(&mut db).unwrap());
+		assert_eq!(Union::<u64>::from_tree(&root, &mut db).unwrap(), union);
+
+		let invalid = (0x1234u64, 128u8).into_tree(&mut db).unwrap();
+		assert_eq!(Union::<u64>::from_tree(&invalid, &mut db), Err(Error::corrupted()));
+	}
+}
@@ -2,10 +2,16 @@
 
 use bm::{ReadBackend, WriteBackend, Construct, Error};
 use primitive_types::U256;
-use crate::{CompatibleConstruct, IntoTree, FromTree};
+use crate::mode::check_limits;
+use crate::{CompatibleConstruct, IntoTree, FromTree, MAX_UNION_SELECTOR,
+			ElementalVariableVec, FromCompositeListTree};
 
 pub use bm::utils::*;
 
+/// `Vec`, referenced by derived code in `no_std` crates.
+#[doc(hidden)]
+pub use alloc::vec::Vec;
+
 /// Mix in type.
 pub fn mix_in_type<T, DB: WriteBackend>(value: &T, db: &mut DB, ty: usize) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 	T: IntoTree,
@@ -25,14 +31,48 @@ pub fn decode_with_type<DB: ReadBackend, F, R>(root: &<DB::Construct as Construc
 	let (value, ty) = <(<DB::Construct as Construct>::Value, U256)>::from_tree(root, db)?;
 
 	if ty > U256::from(usize::max_value()) {
-		Err(Error::CorruptedDatabase)
+		Err(Error::corrupted())
 	} else {
 		f(&value, db, ty.as_usize())
 	}
 }
 
+/// Mix in selector to a subtree root, as `ssz`'s `mix_in_selector`.
+/// Return `InvalidParameter` if the selector is larger than
+/// `MAX_UNION_SELECTOR`.
+pub fn mix_in_selector<DB: WriteBackend>(
+	root: &<DB::Construct as Construct>::Value,
+	db: &mut DB,
+	selector: u8
+) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+	DB::Construct: CompatibleConstruct,
+{
+	if selector > MAX_UNION_SELECTOR {
+		return Err(Error::InvalidParameter)
+	}
+
+	(root.clone(), <DB::Construct as Construct>::Value::from(selector as u64)).into_tree(db)
+}
+
+/// Decode selector and the subtree root. Return `CorruptedDatabase` if
+/// the selector is larger than `MAX_UNION_SELECTOR`.
+pub fn decode_with_selector<DB: ReadBackend>(
+	root: &<DB::Construct as Construct>::Value,
+	db: &mut DB
+) -> Result<(<DB::Construct as Construct>::Value, u8), Error<DB::Error>> where
+	DB::Construct: CompatibleConstruct,
+{
+	decode_with_type(root, db, |inner, _, ty| {
+		if ty > MAX_UNION_SELECTOR as usize {
+			return Err(Error::corrupted())
+		}
+
+		Ok((inner.clone(), ty as u8))
+	})
+}
+
 /// Mix in length.
-pub fn mix_in_length<T, DB: WriteBackend>(value: &T, db: &mut DB, len: usize) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+pub fn mix_in_length<T, DB: WriteBackend>(value: &T, db: &mut DB, len: u64) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
 	T: IntoTree,
 	DB::Construct: CompatibleConstruct,
 {
@@ -43,15 +83,59 @@ pub fn mix_in_length<T, DB: WriteBackend>(value: &T, db: &mut DB, len: usize) ->
 }
 
 /// Decode length.
-pub fn decode_with_length<T, DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<(T, usize), Error<DB::Error>> where
+pub fn decode_with_length<T, DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<(T, u64), Error<DB::Error>> where
 	T: FromTree,
 	DB::Construct: CompatibleConstruct,
 {
 	let (value, len) = <(T, U256)>::from_tree(root, db)?;
 
-	if len > U256::from(usize::max_value()) {
-		Err(Error::CorruptedDatabase)
-	} else {
-		Ok((value, len.as_usize()))
+	if len > U256::from(u64::max_value()) {
+		return Err(Error::corrupted())
+	}
+	check_limits(&db.decode_limits(), len.as_u64(), None)?;
+
+	Ok((value, len.as_u64()))
+}
+
+/// Decode a list of lists, decoding each item of the outer list from
+/// its root with `f`, so that each nesting level can use its own maximum
+/// length.
+pub fn from_nested_list_tree<T, F, DB: ReadBackend>(
+	root: &<DB::Construct as Construct>::Value,
+	db: &mut DB,
+	max_len: Option<u64>,
+	mut f: F
+) -> Result<Vec<T>, Error<DB::Error>> where
+	F: FnMut(&<DB::Construct as Construct>::Value, &mut DB) -> Result<T, Error<DB::Error>>,
+	DB::Construct: CompatibleConstruct,
+{
+	let roots = ElementalVariableVec::<<DB::Construct as Construct>::Value>::from_composite_list_tree(root, db, max_len)?;
+	roots.0.iter().map(|root| f(root, db)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{DigestConstruct, Value};
+
+	use bm::InMemoryBackend;
+	use primitive_types::H256;
+	use sha2::{Digest, Sha256};
+
+	#[test]
+	fn test_mix_in() {
+		let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+		let root = Value::from(H256::repeat_byte(0x11));
+
+		let mut hash = Sha256::new();
+		hash.input(root.as_ref());
+		hash.input(Value::from(5).as_ref());
+		let expected = Value::from(H256::from_slice(hash.result().as_slice()));
+
+		assert_eq!(mix_in_length(&root, &mut db, 5).unwrap(), expected);
+		assert_eq!(mix_in_selector(&root, &mut db, 5).unwrap(), expected);
+		assert_eq!(decode_with_selector(&expected, &mut db).unwrap(), (root.clone(), 5));
+		assert_eq!(decode_with_length::<Value, _>(&expected, &mut db).unwrap(), (root.clone(), 5));
+		assert_eq!(mix_in_selector(&root, &mut db, 128), Err(Error::InvalidParameter));
 	}
 }
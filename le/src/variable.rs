@@ -1,12 +1,15 @@
 use typenum::Unsigned;
 use bm::{Error, Construct, ReadBackend, WriteBackend};
 use core::marker::PhantomData;
-use core::ops::{Deref, DerefMut};
+use core::iter::FromIterator;
+use core::ops::{Deref, DerefMut, Index, IndexMut};
+use core::slice::SliceIndex;
 use alloc::vec::Vec;
+use alloc::string::String;
 use crate::{ElementalVariableVecRef, ElementalVariableVec,
 			IntoTree, IntoCompactListTree, IntoCompositeListTree,
 			FromTree, FromCompactListTree, FromCompositeListTree,
-			Compact, CompactRef, CompatibleConstruct};
+			Compact, CompactRef, CompatibleConstruct, Basic};
 
 /// Vec value with maximum length.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -32,6 +35,59 @@ impl<T, ML> AsRef<[T]> for MaxVec<T, ML> {
 	}
 }
 
+impl<T, ML> FromIterator<T> for MaxVec<T, ML> {
+	fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+		Self(iter.into_iter().collect(), PhantomData)
+	}
+}
+
+impl<T, ML> Extend<T> for MaxVec<T, ML> {
+	fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		self.0.extend(iter)
+	}
+}
+
+impl<T, ML> IntoIterator for MaxVec<T, ML> {
+	type Item = T;
+	type IntoIter = alloc::vec::IntoIter<T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<'a, T, ML> IntoIterator for &'a MaxVec<T, ML> {
+	type Item = &'a T;
+	type IntoIter = core::slice::Iter<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter()
+	}
+}
+
+impl<'a, T, ML> IntoIterator for &'a mut MaxVec<T, ML> {
+	type Item = &'a mut T;
+	type IntoIter = core::slice::IterMut<'a, T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.iter_mut()
+	}
+}
+
+impl<T, ML, I: SliceIndex<[T]>> Index<I> for MaxVec<T, ML> {
+	type Output = I::Output;
+
+	fn index(&self, index: I) -> &I::Output {
+		&self.0[index]
+	}
+}
+
+impl<T, ML, I: SliceIndex<[T]>> IndexMut<I> for MaxVec<T, ML> {
+	fn index_mut(&mut self, index: I) -> &mut I::Output {
+		&mut self.0[index]
+	}
+}
+
 impl<T, ML> Default for MaxVec<T, ML> {
 	fn default() -> Self {
 		Self(Vec::new(), PhantomData)
@@ -116,6 +172,7 @@ impl<T, ML: Unsigned> FromTree for MaxVec<T, ML> where
 }
 
 impl<'a, T, ML: Unsigned> IntoTree for CompactRef<'a, MaxVec<T, ML>> where
+	T: Basic,
 	for<'b> ElementalVariableVecRef<'b, T>: IntoCompactListTree,
 {
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
@@ -126,6 +183,7 @@ impl<'a, T, ML: Unsigned> IntoTree for CompactRef<'a, MaxVec<T, ML>> where
 }
 
 impl<T, ML: Unsigned> IntoTree for Compact<MaxVec<T, ML>> where
+	T: Basic,
 	for<'b> ElementalVariableVecRef<'b, T>: IntoCompactListTree,
 {
 	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
@@ -136,6 +194,7 @@ impl<T, ML: Unsigned> IntoTree for Compact<MaxVec<T, ML>> where
 }
 
 impl<T, ML: Unsigned> FromTree for Compact<MaxVec<T, ML>> where
+	T: Basic,
 	for<'a> ElementalVariableVec<T>: FromCompactListTree,
 {
 	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
@@ -177,3 +236,108 @@ impl<T> FromTree for Vec<T> where
 		ElementalVariableVec::from_composite_list_tree(root, db, None).map(|ret| ret.0)
 	}
 }
+
+impl<T> FromCompositeListTree for Vec<T> where
+	ElementalVariableVec<T>: FromCompositeListTree,
+{
+	fn from_composite_list_tree<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		max_len: Option<u64>,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalVariableVec::from_composite_list_tree(root, db, max_len).map(|ret| ret.0)
+	}
+}
+
+impl<T> FromCompactListTree for Vec<T> where
+	ElementalVariableVec<T>: FromCompactListTree,
+{
+	fn from_compact_list_tree<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		max_len: Option<u64>,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalVariableVec::from_compact_list_tree(root, db, max_len).map(|ret| ret.0)
+	}
+}
+
+impl<'a, T> IntoTree for CompactRef<'a, Vec<T>> where
+	T: Basic,
+	for<'b> ElementalVariableVecRef<'b, T>: IntoCompactListTree,
+{
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalVariableVecRef(self.0).into_compact_list_tree(db, None)
+	}
+}
+
+impl<T> IntoTree for Compact<Vec<T>> where
+	T: Basic,
+	for<'a> ElementalVariableVecRef<'a, T>: IntoCompactListTree,
+{
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalVariableVecRef(&self.0).into_compact_list_tree(db, None)
+	}
+}
+
+impl<T> FromTree for Compact<Vec<T>> where
+	T: Basic,
+	ElementalVariableVec<T>: FromCompactListTree,
+{
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalVariableVec::from_compact_list_tree(root, db, None).map(|ret| Compact(ret.0))
+	}
+}
+
+/// String is merkleized as a byte list of its UTF-8 encoding.
+impl IntoTree for String {
+	fn into_tree<DB: WriteBackend>(&self, db: &mut DB) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		ElementalVariableVecRef(self.as_bytes()).into_compact_list_tree(db, None)
+	}
+}
+
+impl FromTree for String {
+	fn from_tree<DB: ReadBackend>(root: &<DB::Construct as Construct>::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		String::from_compact_list_tree(root, db, None)
+	}
+}
+
+impl FromCompactListTree for String {
+	fn from_compact_list_tree<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		max_len: Option<u64>,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		let bytes = ElementalVariableVec::<u8>::from_compact_list_tree(root, db, max_len)?;
+		String::from_utf8(bytes.0).map_err(|_| Error::corrupted())
+	}
+}
+
+/// String only has the byte list form, so this decodes the same as
+/// `FromCompactListTree`.
+impl FromCompositeListTree for String {
+	fn from_composite_list_tree<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB,
+		max_len: Option<u64>,
+	) -> Result<Self, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct,
+	{
+		String::from_compact_list_tree(root, db, max_len)
+	}
+}
@@ -0,0 +1,226 @@
+//! Deterministic test vectors, for cross-checking other ssz
+//! implementations against bm-le.
+//!
+//! `generate` draws types, lengths, maximum lengths and values from a
+//! seed, so that the same seed always gives the same vectors. Each
+//! vector is printed as a JSON line, with the ssz type name, the
+//! serialized value and the expected root, both as `0x` hex.
+
+use core::fmt;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use digest::Digest;
+use primitive_types::{H256, U256};
+use bm::{NoopBackend, NoopBackendError, Error};
+
+use crate::{DigestConstruct, Encode, IntoTree, ElementalFixedVecRef, ElementalVariableVecRef,
+			IntoCompactVectorTree, IntoCompactListTree, IntoCompositeVectorTree,
+			IntoCompositeListTree, tree_root};
+
+/// Maximum lengths drawn for lists and vectors.
+const MAX_LENS: [u64; 10] = [1, 2, 3, 4, 7, 8, 16, 31, 100, 1024];
+/// Maximum number of elements in a generated list.
+const MAX_ELEMENTS: u64 = 64;
+
+/// A generated test vector.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TestVector {
+	/// Name of the ssz type, for example `List[uint16,37]`. `Checkpoint`
+	/// is the container of an `uint64` and a `Bytes32`.
+	pub name: String,
+	/// Serialized value.
+	pub serialized: Vec<u8>,
+	/// Expected root of the value.
+	pub root: H256,
+}
+
+impl fmt::Display for TestVector {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{{\"type\":\"{}\",\"serialized\":\"", self.name)?;
+		write_hex(&self.serialized, f)?;
+		write!(f, "\",\"root\":\"")?;
+		write_hex(self.root.as_bytes(), f)?;
+		write!(f, "\"}}")
+	}
+}
+
+fn write_hex(bytes: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "0x")?;
+	for byte in bytes {
+		write!(f, "{:02x}", byte)?;
+	}
+	Ok(())
+}
+
+/// Deterministic xorshift generator.
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	fn below(&mut self, bound: u64) -> u64 {
+		self.next() % bound
+	}
+
+	fn values<T, F: FnMut(&mut Self) -> T>(&mut self, len: u64, mut value: F) -> Vec<T> {
+		(0..len).map(|_| value(self)).collect()
+	}
+}
+
+fn root_of<D: Digest<OutputSize=typenum::U32>, F>(f: F) -> H256 where
+	F: FnOnce(&mut NoopBackend<DigestConstruct<D>>) -> Result<crate::Value, Error<NoopBackendError>>,
+{
+	f(&mut NoopBackend::default())
+		.map(|root| H256::from_slice(root.as_ref()))
+		.expect("Noop backend never fails in set; qed")
+}
+
+fn basic<D: Digest<OutputSize=typenum::U32>, T: Encode + IntoTree>(name: &str, value: T) -> TestVector {
+	TestVector {
+		name: String::from(name),
+		serialized: value.encode(),
+		root: tree_root::<D, _>(&value),
+	}
+}
+
+fn packed<D: Digest<OutputSize=typenum::U32>, T: Encode>(
+	name: &str,
+	values: Vec<T>,
+	max_len: Option<u64>,
+) -> TestVector where
+	for<'a> ElementalFixedVecRef<'a, T>: IntoCompactVectorTree,
+	for<'a> ElementalVariableVecRef<'a, T>: IntoCompactListTree,
+{
+	let (name, root) = match max_len {
+		Some(max_len) => (
+			format!("List[{},{}]", name, max_len),
+			root_of::<D, _>(|db| ElementalVariableVecRef(&values).into_compact_list_tree(db, Some(max_len))),
+		),
+		None => (
+			format!("Vector[{},{}]", name, values.len()),
+			root_of::<D, _>(|db| ElementalFixedVecRef(&values).into_compact_vector_tree(db, None)),
+		),
+	};
+
+	TestVector { name, serialized: values.encode(), root }
+}
+
+fn composite<D: Digest<OutputSize=typenum::U32>, T: Encode + IntoTree + Sync>(
+	name: &str,
+	values: Vec<T>,
+	max_len: Option<u64>,
+) -> TestVector {
+	let (name, root) = match max_len {
+		Some(max_len) => (
+			format!("List[{},{}]", name, max_len),
+			root_of::<D, _>(|db| ElementalVariableVecRef(&values).into_composite_list_tree(db, Some(max_len))),
+		),
+		None => (
+			format!("Vector[{},{}]", name, values.len()),
+			root_of::<D, _>(|db| ElementalFixedVecRef(&values).into_composite_vector_tree(db, None)),
+		),
+	};
+
+	TestVector { name, serialized: values.encode(), root }
+}
+
+fn u128_of(rng: &mut Rng) -> u128 {
+	(u128::from(rng.next()) << 64) | u128::from(rng.next())
+}
+
+fn u256_of(rng: &mut Rng) -> U256 {
+	U256([rng.next(), rng.next(), rng.next(), rng.next()])
+}
+
+fn checkpoint_of(rng: &mut Rng) -> (u64, H256) {
+	let mut root = H256::zero();
+	for chunk in root.as_bytes_mut().chunks_mut(8) {
+		chunk.copy_from_slice(&rng.next().to_le_bytes());
+	}
+	(rng.next(), root)
+}
+
+fn sequence<D: Digest<OutputSize=typenum::U32>>(rng: &mut Rng) -> TestVector {
+	let max_len = MAX_LENS[rng.below(MAX_LENS.len() as u64) as usize];
+	let (len, max_len) = if rng.below(2) == 0 {
+		(rng.below(max_len.min(MAX_ELEMENTS) + 1), Some(max_len))
+	} else {
+		(1 + rng.below(max_len.min(MAX_ELEMENTS)), None)
+	};
+
+	match rng.below(8) {
+		0 => packed::<D, _>("boolean", rng.values(len, |rng| rng.below(2) == 1), max_len),
+		1 => packed::<D, _>("uint8", rng.values(len, |rng| rng.next() as u8), max_len),
+		2 => packed::<D, _>("uint16", rng.values(len, |rng| rng.next() as u16), max_len),
+		3 => packed::<D, _>("uint32", rng.values(len, |rng| rng.next() as u32), max_len),
+		4 => packed::<D, _>("uint64", rng.values(len, Rng::next), max_len),
+		5 => packed::<D, _>("uint128", rng.values(len, u128_of), max_len),
+		6 => packed::<D, _>("uint256", rng.values(len, u256_of), max_len),
+		_ => composite::<D, _>("Checkpoint", rng.values(len, checkpoint_of), max_len),
+	}
+}
+
+/// Generate `count` test vectors from `seed`, with roots computed by
+/// digest `D`. Vectors are basic values, and packed or composite lists
+/// and vectors of various lengths and maximum lengths.
+pub fn generate<D: Digest<OutputSize=typenum::U32>>(seed: u64, count: usize) -> Vec<TestVector> {
+	let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+
+	(0..count).map(|_| match rng.below(10) {
+		0 => basic::<D, _>("boolean", rng.below(2) == 1),
+		1 => basic::<D, _>("uint8", rng.next() as u8),
+		2 => basic::<D, _>("uint16", rng.next() as u16),
+		3 => basic::<D, _>("uint32", rng.next() as u32),
+		4 => basic::<D, _>("uint64", rng.next()),
+		5 => basic::<D, _>("uint128", u128_of(&mut rng)),
+		6 => basic::<D, _>("uint256", u256_of(&mut rng)),
+		_ => sequence::<D>(&mut rng),
+	}).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Decode, MaxVec, Compact};
+	use alloc::string::ToString;
+	use sha2::Sha256;
+	use typenum::{U31, U1024};
+
+	#[test]
+	fn test_generate() {
+		let vectors = generate::<Sha256>(0, 200);
+		assert_eq!(vectors, generate::<Sha256>(0, 200));
+		assert_ne!(vectors, generate::<Sha256>(1, 200));
+		assert!(vectors.iter().any(|vector| vector.name.starts_with("List[Checkpoint")));
+		assert!(vectors.iter().any(|vector| vector.name.starts_with("Vector[uint256")));
+
+		let mut checked = 0;
+		for vector in &vectors {
+			let root = match vector.name.as_str() {
+				"uint64" => tree_root::<Sha256, _>(&u64::decode(&vector.serialized).unwrap()),
+				"List[uint32,1024]" =>
+					tree_root::<Sha256, _>(&Compact(MaxVec::<u32, U1024>::decode(&vector.serialized).unwrap())),
+				"List[Checkpoint,31]" =>
+					tree_root::<Sha256, _>(&MaxVec::<(u64, H256), U31>::decode(&vector.serialized).unwrap()),
+				_ => continue,
+			};
+			assert_eq!(vector.root, root);
+			checked += 1;
+		}
+		assert!(checked > 4);
+
+		let line = TestVector {
+			name: "uint8".to_string(),
+			serialized: vec![0xab],
+			root: H256::repeat_byte(0x01),
+		}.to_string();
+		assert_eq!(line, format!("{{\"type\":\"uint8\",\"serialized\":\"0xab\",\"root\":\"0x{}\"}}",
+								 "01".repeat(32)));
+	}
+}
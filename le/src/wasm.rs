@@ -0,0 +1,95 @@
+//! Root computation and proof verification exported with `wasm-bindgen`,
+//! for light clients running in browsers. Roots are sha256 ssz roots.
+
+use alloc::vec::Vec;
+use bm::{CompactValue, Index};
+use core::convert::TryFrom;
+use generic_array::GenericArray;
+use sha2::Sha256;
+use wasm_bindgen::prelude::*;
+use crate::{DigestConstruct, NoopBackend, Value, End, ElementalFixedVecRef, ElementalVariableVecRef,
+			IntoCompactVectorTree, IntoCompactListTree};
+
+type Construct = DigestConstruct<Sha256>;
+
+fn value(bytes: &[u8]) -> Option<Value> {
+	Value::try_from(bytes).ok()
+}
+
+/// Root of `data` as a ssz byte vector, whose length is fixed to the
+/// length of `data`.
+#[wasm_bindgen(js_name = hashTreeRoot)]
+pub fn hash_tree_root(data: &[u8]) -> Vec<u8> {
+	ElementalFixedVecRef(data)
+		.into_compact_vector_tree(&mut NoopBackend::<Construct>::default(), None)
+		.expect("Noop backend never fails in set; qed")
+		.0.to_vec()
+}
+
+/// Root of `data` as a ssz byte list of maximum length `max_len`, or
+/// `undefined` if `data` is longer than `max_len`.
+#[wasm_bindgen(js_name = hashTreeRootList)]
+pub fn hash_tree_root_list(data: &[u8], max_len: u32) -> Option<Vec<u8>> {
+	if data.len() as u64 > max_len as u64 {
+		return None
+	}
+
+	Some(ElementalVariableVecRef(data)
+		 .into_compact_list_tree(&mut NoopBackend::<Construct>::default(), Some(max_len as u64))
+		 .expect("Noop backend never fails in set; qed")
+		 .0.to_vec())
+}
+
+/// Verify that `leaf` is at generalized index `index` of the tree with
+/// `root`. `branch` is the concatenation of the 32-byte sibling nodes,
+/// from the leaf up to the root. Malformed input fails the verification.
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof(root: &[u8], leaf: &[u8], branch: &[u8], index: u64) -> bool {
+	let (root, leaf) = match (value(root), value(leaf)) {
+		(Some(root), Some(leaf)) => (root, leaf),
+		_ => return false,
+	};
+	let chunks = branch.chunks_exact(32);
+	if !chunks.remainder().is_empty() {
+		return false
+	}
+	let index = match Index::from_u128(index as u128) {
+		Some(index) => index,
+		None => return false,
+	};
+
+	let proofs = chunks.map(|chunk| End::new(GenericArray::clone_from_slice(chunk)));
+	match CompactValue::from_plain(leaf, proofs, index) {
+		Some(compact) => compact.root::<Construct>() == root,
+		None => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{tree_root, MaxVec, Compact};
+	use primitive_types::H512;
+	use typenum::U32;
+
+	#[test]
+	fn test_roots_and_proof() {
+		let data = (0..64u8).collect::<Vec<_>>();
+		assert_eq!(hash_tree_root(&data),
+				   tree_root::<Sha256, _>(&H512::from_slice(&data)).as_bytes().to_vec());
+
+		let list = Compact(MaxVec::<u8, U32>::from((0..10u8).collect::<Vec<_>>()));
+		assert_eq!(hash_tree_root_list(&list.0, 32),
+				   Some(tree_root::<Sha256, _>(&list).as_bytes().to_vec()));
+		assert_eq!(hash_tree_root_list(&data, 32), None);
+
+		let data = (0..128u8).collect::<Vec<_>>();
+		let root = hash_tree_root(&data);
+		let mut branch = data[0..32].to_vec();
+		branch.extend(hash_tree_root(&data[64..128]));
+		assert!(verify_proof(&root, &data[32..64], &branch, 5));
+		assert!(!verify_proof(&root, &data[32..64], &branch, 4));
+		assert!(!verify_proof(&root, &data[32..64], &branch[..32], 5));
+		assert!(!verify_proof(&root, &data[32..64], &branch[..40], 5));
+	}
+}
@@ -0,0 +1,58 @@
+//! Partial decoding of containers from a witness.
+
+use bm::{ReadBackend, Construct, Error, Index, DanglingRaw};
+use crate::gindex::PathElement;
+use crate::CompatibleConstruct;
+
+/// Field of a container missing from a witness.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MissingField {
+	/// Name of the field.
+	pub name: &'static str,
+	/// Generalized index of the field, relative to the container.
+	pub index: Index,
+}
+
+/// Container that can be partially decoded from a witness, a database
+/// populated with the proofs of a multiproof.
+pub trait FromWitness: Sized {
+	/// Partially decoded container, whose fields are either decoded or
+	/// missing.
+	type Witness;
+
+	/// Decode the fields whose subtrees are present in the witness.
+	fn from_witness<DB: ReadBackend>(
+		root: &<DB::Construct as Construct>::Value,
+		db: &mut DB
+	) -> Result<Self::Witness, Error<DB::Error>> where
+		DB::Construct: CompatibleConstruct;
+}
+
+/// Decode a field at position `field` of a container with `total`
+/// fields, if its subtree is present in the witness. Backend errors are
+/// still returned as errors.
+pub fn field_from_witness<T, F, DB: ReadBackend>(
+	root: &<DB::Construct as Construct>::Value,
+	db: &mut DB,
+	name: &'static str,
+	field: usize,
+	total: usize,
+	f: F,
+) -> Result<Result<T, MissingField>, Error<DB::Error>> where
+	F: FnOnce(&<DB::Construct as Construct>::Value, &mut DB) -> Result<T, Error<DB::Error>>,
+	DB::Construct: CompatibleConstruct,
+{
+	let index = PathElement::Field { index: field, total }.index();
+	let missing = MissingField { name, index };
+
+	let field_root = match DanglingRaw::<DB::Construct>::new(root.clone()).get(db, index)? {
+		Some(field_root) => field_root,
+		None => return Ok(Err(missing)),
+	};
+
+	match f(&field_root, db) {
+		Ok(value) => Ok(Ok(value)),
+		Err(Error::CorruptedDatabase(_)) => Ok(Err(missing)),
+		Err(err) => Err(err),
+	}
+}
@@ -0,0 +1,72 @@
+use sha2::Sha256;
+use typenum::*;
+
+use bm::{InMemoryBackend, ReadBackend, Error, LimitedBackendError, VerifiedBackend};
+use bm_le::{IntoTree, FromTree, Compact, MaxVec, DigestConstruct, Value, DecodeLimits, DecodeStats};
+
+type Construct = DigestConstruct<Sha256>;
+
+#[test]
+fn length_and_depth() {
+	let mut db = InMemoryBackend::<Construct>::default();
+	let value = MaxVec::<u64, U1024>::from((0..100).collect::<Vec<_>>());
+	let root = value.into_tree(&mut db).unwrap();
+
+	let (vector, _) = db.get(&root).unwrap().unwrap();
+	let claimed = (vector, Value::from(1u64 << 40)).into_tree(&mut db).unwrap();
+	let unbounded = MaxVec::<u64, U1099511627776>::from(vec![1, 2, 3]);
+	let unbounded_root = unbounded.into_tree(&mut db).unwrap();
+	let bytes = Compact(MaxVec::<u8, U64>::from(vec![7; 64]));
+	let bytes_root = bytes.into_tree(&mut db).unwrap();
+
+	let limits = DecodeLimits { max_depth: 16, max_len: 1000, max_nodes: 2000 };
+	assert_eq!(MaxVec::<u64, U1024>::from_tree_limited(&root, &mut db, &limits).unwrap(), value);
+	assert_eq!(MaxVec::<u64, U1024>::from_tree_limited(&claimed, &mut db, &limits).err(),
+			   Some(Error::MaxLenExceeded { len: 1 << 40, max_len: 1000 }));
+	assert_eq!(MaxVec::<u64, U1099511627776>::from_tree_limited(&unbounded_root, &mut db, &limits).err(),
+			   Some(Error::overflowed()));
+	assert_eq!(Compact::<MaxVec<u8, U64>>::from_tree_limited(&bytes_root, &mut db, &limits).unwrap(), bytes);
+
+	assert_eq!(MaxVec::<u64, U1099511627776>::from_tree(&unbounded_root, &mut db).unwrap(), unbounded);
+	assert!(MaxVec::<u64, U1024>::from_tree(&claimed, &mut db).is_err());
+
+	let exact = DecodeLimits { max_len: 100, ..DecodeLimits::unlimited() };
+	assert_eq!(MaxVec::<u64, U1024>::from_tree_limited(&root, &mut db, &exact).unwrap(), value);
+	let short = DecodeLimits { max_len: 99, ..DecodeLimits::unlimited() };
+	assert_eq!(MaxVec::<u64, U1024>::from_tree_limited(&root, &mut db, &short).err(),
+			   Some(Error::MaxLenExceeded { len: 100, max_len: 99 }));
+}
+
+#[test]
+fn nodes() {
+	let mut db = InMemoryBackend::<Construct>::default();
+	let value = MaxVec::<u64, U1024>::from((0..100).collect::<Vec<_>>());
+	let root = value.into_tree(&mut db).unwrap();
+
+	let limits = DecodeLimits { max_nodes: 8, ..DecodeLimits::unlimited() };
+	assert_eq!(MaxVec::<u64, U1024>::from_tree_limited(&root, &mut db, &limits).err(),
+			   Some(Error::Backend(LimitedBackendError::TooManyNodes)));
+	let mut stats = DecodeStats::default();
+	assert!(MaxVec::<u64, U1024>::from_tree_limited_with_stats(&root, &mut db, &limits, &mut stats).is_err());
+	assert_eq!(stats.nodes, 8);
+
+	assert_eq!(MaxVec::<u64, U1024>::from_tree_limited(&root, &mut db, &DecodeLimits::unlimited()).unwrap(), value);
+	let empty = MaxVec::<u64, U1024>::default();
+	let empty_root = empty.into_tree(&mut db).unwrap();
+	let none = DecodeLimits { max_nodes: 0, ..DecodeLimits::unlimited() };
+	assert_eq!(MaxVec::<u64, U1024>::from_tree_limited(&empty_root, &mut db, &none).err(),
+			   Some(Error::Backend(LimitedBackendError::TooManyNodes)));
+}
+
+#[test]
+fn through_wrappers() {
+	let mut db = InMemoryBackend::<Construct>::default();
+	let value = MaxVec::<u64, U1024>::from((0..100).collect::<Vec<_>>());
+	let root = value.into_tree(&mut db).unwrap();
+
+	let limits = DecodeLimits { max_len: 10, ..DecodeLimits::unlimited() };
+	let mut limited = bm::LimitedBackend::with_limits(&mut db, &limits);
+	let mut verified = VerifiedBackend::new(&mut limited);
+	assert_eq!(verified.decode_limits(), limits);
+	assert_eq!(MaxVec::<u64, U1024>::from_tree(&root, &mut verified).err(), Some(Error::MaxLenExceeded { len: 100, max_len: 10 }));
+}
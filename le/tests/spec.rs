@@ -6,7 +6,7 @@ use typenum::*;
 
 use bm::InMemoryBackend;
 use generic_array::GenericArray;
-use bm_le::{IntoTree, FromTree, Compact, MaxVec, DigestConstruct};
+use bm_le::{IntoTree, FromTree, Compact, MaxVec, DigestConstruct, tree_root};
 
 fn chunk(data: &[u8]) -> H256 {
 	let mut ret = [0; 32];
@@ -31,6 +31,7 @@ fn t<T>(value: T, expected: H256) where
 	assert_eq!(H256::from_slice(actual.as_ref()), expected);
 	let decoded = T::from_tree(&actual, &mut db).unwrap();
 	assert_eq!(value, decoded);
+	assert_eq!(tree_root::<Sha256, _>(&value), expected);
 }
 
 #[test]
@@ -104,6 +105,80 @@ fn spec() {
 	t(GenericArray::<H256, U0>::from_exact_iter(vec![]).unwrap(), H256::from_str("0000000000000000000000000000000000000000000000000000000000000000").unwrap());
 }
 
+#[test]
+fn byte_lists() {
+	let expected = h(&chunk(b"abc")[..], &chunk(&[0x03])[..]);
+	t(Compact(b"abc".to_vec()), expected);
+	t("abc".to_string(), expected);
+}
+
+#[test]
+fn tuples() {
+	t((5u64,), chunk(&[0x05]));
+	t((1u8, 2u16), h(&chunk(&[0x01])[..], &chunk(&[0x02, 0x00])[..]));
+
+	let value = (1u64, 2u64, 3u64, 4u64, 5u64, 6u64, 7u64, 8u64, 9u64, 10u64, 11u64, 12u64);
+	let expected = tree_root::<Sha256, _>(&GenericArray::<u64, U12>::from_exact_iter((1..13).collect::<Vec<_>>()).unwrap());
+	t(value, expected);
+}
+
+#[test]
+fn fixed_hashes() {
+	use primitive_types::{H160, H512};
+	use bm_le::{H384, H768};
+
+	let bytes = (0..96u8).collect::<Vec<_>>();
+	let c = |i: usize| chunk(&bytes[(i * 32)..((i + 1) * 32)]);
+
+	t(H160::from_slice(&bytes[..20]), chunk(&bytes[..20]));
+	t(H256::from_slice(&bytes[..32]), c(0));
+	t(H384::from_slice(&bytes[..48]), h(&c(0)[..], &chunk(&bytes[32..48])[..]));
+	t(H512::from_slice(&bytes[..64]), h(&c(0)[..], &c(1)[..]));
+	t(H768::from_slice(&bytes[..96]), h(&h(&c(0)[..], &c(1)[..])[..], &h(&c(2)[..], &chunk(&[])[..])[..]));
+}
+
+#[test]
+fn wide_digest() {
+	use sha2::Sha512;
+
+	let value = Compact(GenericArray::<u64, U16>::from_exact_iter((1..17u32).map(u64::from)).unwrap());
+	let mut left = [0u8; 64];
+	let mut right = [0u8; 64];
+	for i in 0..8 {
+		left[(i * 8)..((i + 1) * 8)].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+		right[(i * 8)..((i + 1) * 8)].copy_from_slice(&(i as u64 + 9).to_le_bytes());
+	}
+	let mut hash = Sha512::new();
+	hash.input(&left[..]);
+	hash.input(&right[..]);
+	let expected = hash.result();
+
+	let mut db = InMemoryBackend::<DigestConstruct<Sha512>>::default();
+	let actual = value.into_tree(&mut db).unwrap();
+	assert_eq!(actual.0, expected);
+	assert_eq!(Compact::<GenericArray<u64, U16>>::from_tree(&actual, &mut db).unwrap(), value);
+}
+
+#[test]
+fn arrays() {
+	use bm_le::{FromCompositeVectorTree, IntoCompositeVectorTree};
+
+	let expected = tree_root::<Sha256, _>(&GenericArray::<u64, U3>::from([1, 2, 3]));
+	t([1u64, 2, 3], expected);
+	t(Compact([0x4567u16, 0x0123]), chunk(&[0x67, 0x45, 0x23, 0x01]));
+
+	let long = [7u8; 40];
+	let expected = tree_root::<Sha256, _>(&GenericArray::<u8, U40>::from_exact_iter(long.iter().cloned()).unwrap());
+	t(long, expected);
+
+	let mut db = InMemoryBackend::<DigestConstruct<Sha256>>::default();
+	let root = [1u64, 2, 3].into_composite_vector_tree(&mut db, Some(4)).unwrap();
+	assert_eq!(<[u64; 3]>::from_composite_vector_tree(&root, &mut db, 3, Some(4)).unwrap(), [1, 2, 3]);
+	assert!(<[u64; 3]>::from_composite_vector_tree(&root, &mut db, 4, Some(4)).is_err());
+	assert_eq!(GenericArray::<u64, U3>::from_composite_vector_tree(&root, &mut db, 3, Some(4)).unwrap(),
+			   GenericArray::<u64, U3>::from([1, 2, 3]));
+}
+
 // test_data = [
 //	   ("long bitlist", Bitlist[512](1),
 //		"03", h(h(chunk("01"), chunk("")), chunk("01"))),
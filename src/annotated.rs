@@ -0,0 +1,116 @@
+use core::marker::PhantomData;
+
+use crate::{Construct, WriteBackend};
+
+/// Auxiliary data maintained alongside each node, such as leaf counts
+/// or sums of a merkle sum tree.
+pub trait Annotation<V> {
+	/// Auxiliary data of a node.
+	type Data: Clone + Default;
+
+	/// Get the data of an intermediate node from its children.
+	fn combine(left: &Self::Data, right: &Self::Data) -> Self::Data;
+	/// Get the value committing to the data of both children, which is
+	/// hashed into their parent so that the root binds all data.
+	fn commit(left: &Self::Data, right: &Self::Data) -> V;
+}
+
+/// Value of an annotated construct, a hash with its auxiliary data.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct AnnotatedValue<V, D> {
+	/// Hash of the node.
+	pub hash: V,
+	/// Auxiliary data of the node.
+	pub data: D,
+}
+
+/// Construct extending `C` with auxiliary data of annotation `A`. The
+/// hash of an intermediate node is the hash of its children's hashes
+/// together with the commitment of their data, so data is maintained
+/// automatically on every `set` or `push`.
+pub struct AnnotatedConstruct<C, A>(PhantomData<(C, A)>);
+
+impl<C: Construct, A: Annotation<C::Value>> Construct for AnnotatedConstruct<C, A> {
+	type Value = AnnotatedValue<C::Value, A::Data>;
+
+	fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value {
+		let hash = C::intermediate_of(&left.hash, &right.hash);
+		AnnotatedValue {
+			hash: C::intermediate_of(&hash, &A::commit(&left.data, &right.data)),
+			data: A::combine(&left.data, &right.data),
+		}
+	}
+
+	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
+		db: &mut DB,
+		depth_to_bottom: usize
+	) -> Result<Self::Value, DB::Error> {
+		let mut current = Self::Value::default();
+		for _ in 0..depth_to_bottom {
+			let value = (current.clone(), current);
+			let key = Self::intermediate_of(&value.0, &value.1);
+			db.insert(key.clone(), value)?;
+			current = key;
+		}
+		Ok(current)
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		C::fmt_value(&value.hash, f)
+	}
+}
+
+/// Annotation summing `u64` amounts of leaves, for merkle sum trees.
+/// Sums wrap on overflow, so callers should bound the leaf amounts.
+pub struct LeafSum;
+
+impl<V: Default + AsMut<[u8]>> Annotation<V> for LeafSum {
+	type Data = u64;
+
+	fn combine(left: &u64, right: &u64) -> u64 {
+		left.wrapping_add(*right)
+	}
+
+	fn commit(left: &u64, right: &u64) -> V {
+		let mut value = V::default();
+		value.as_mut()[..8].copy_from_slice(&left.to_le_bytes());
+		value.as_mut()[8..16].copy_from_slice(&right.to_le_bytes());
+		value
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type Construct = AnnotatedConstruct<InheritedDigestConstruct<Sha256>, LeafSum>;
+
+	fn value(i: u8, amount: u64) -> AnnotatedValue<GenericArray<u8, typenum::U32>, u64> {
+		let mut hash = GenericArray::default();
+		hash[0] = i;
+		AnnotatedValue { hash, data: amount }
+	}
+
+	#[test]
+	fn test_leaf_sum() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 1..6 {
+			vector.push(&mut db, value(i, i as u64 * 10)).unwrap();
+		}
+		assert_eq!(vector.root().data, 150);
+
+		let before = vector.root();
+		vector.set(&mut db, 2, value(3, 5)).unwrap();
+		assert_eq!(vector.root().data, 125);
+		assert_ne!(vector.root().hash, before.hash);
+
+		assert_eq!(vector.pop(&mut db).unwrap(), Some(value(5, 50)));
+		assert_eq!(vector.root().data, 75);
+		assert_eq!(vector.get(&mut db, 2).unwrap(), value(3, 5));
+	}
+}
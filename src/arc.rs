@@ -0,0 +1,143 @@
+use alloc::sync::Arc;
+use core::hash::Hash;
+
+use crate::{Construct, Backend, ReadBackend, RefReadBackend, WriteBackend, InMemoryBackend, NodePair, EmptyValues};
+
+/// In-memory merkle database whose clones share the underlying nodes.
+/// Cloning is constant time, so a tree and its backend can be handed
+/// to other threads or cloned for speculative work. Nodes are copied
+/// on write: the first write through a clone that still shares its
+/// nodes copies them once, leaving the other clones unchanged.
+pub struct ArcBackend<C: Construct>(Arc<InMemoryBackend<C>>);
+
+impl<C: Construct> ArcBackend<C> where
+	C::Value: Eq + Hash + Ord,
+{
+	/// Create a new backend from an in-memory backend.
+	pub fn new(db: InMemoryBackend<C>) -> Self {
+		Self(Arc::new(db))
+	}
+
+	/// Whether the nodes are shared with another clone, so that the
+	/// next write copies them.
+	pub fn is_shared(&self) -> bool {
+		Arc::strong_count(&self.0) > 1
+	}
+
+	/// Get the in-memory backend, copying the nodes if they are shared.
+	pub fn into_inner(self) -> InMemoryBackend<C> {
+		Arc::try_unwrap(self.0).unwrap_or_else(|shared| (*shared).clone())
+	}
+
+	fn make_mut(&mut self) -> &mut InMemoryBackend<C> {
+		Arc::make_mut(&mut self.0)
+	}
+}
+
+impl<C: Construct> Clone for ArcBackend<C> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<C: Construct> Default for ArcBackend<C> where
+	C::Value: Eq + Hash + Ord,
+{
+	fn default() -> Self {
+		Self::new(InMemoryBackend::default())
+	}
+}
+
+impl<C: Construct> From<InMemoryBackend<C>> for ArcBackend<C> where
+	C::Value: Eq + Hash + Ord,
+{
+	fn from(db: InMemoryBackend<C>) -> Self {
+		Self::new(db)
+	}
+}
+
+impl<C: Construct> AsRef<InMemoryBackend<C>> for ArcBackend<C> {
+	fn as_ref(&self) -> &InMemoryBackend<C> {
+		&self.0
+	}
+}
+
+impl<C: Construct> Backend for ArcBackend<C> {
+	type Construct = C;
+	type Error = <InMemoryBackend<C> as Backend>::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.0.empty_values()
+	}
+}
+
+impl<C: Construct> ReadBackend for ArcBackend<C> where
+	C::Value: Eq + Hash + Ord,
+{
+	fn get(&mut self, key: &C::Value) -> Result<Option<NodePair<Self>>, Self::Error> {
+		self.0.get_ref(key)
+	}
+}
+
+impl<C: Construct> RefReadBackend for ArcBackend<C> where
+	C::Value: Eq + Hash + Ord,
+{
+	fn get_ref(&self, key: &C::Value) -> Result<Option<NodePair<Self>>, Self::Error> {
+		self.0.get_ref(key)
+	}
+}
+
+impl<C: Construct> WriteBackend for ArcBackend<C> where
+	C::Value: Eq + Hash + Ord,
+{
+	fn rootify(&mut self, key: &C::Value) -> Result<(), Self::Error> {
+		self.make_mut().rootify(key)
+	}
+
+	fn unrootify(&mut self, key: &C::Value) -> Result<(), Self::Error> {
+		self.make_mut().unrootify(key)
+	}
+
+	fn insert(&mut self, key: C::Value, value: NodePair<Self>) -> Result<(), Self::Error> {
+		self.make_mut().insert(key, value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, Vector, Owned, Dangling, Leak};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn test_arc_backend() {
+		let mut db = ArcBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..8 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let metadata = vector.metadata();
+
+		let mut speculative = db.clone();
+		assert!(db.is_shared());
+		let mut branch = Vector::<Dangling, Construct>::from_leaked(metadata);
+		branch.set(&mut speculative, 3, value(42)).unwrap();
+		assert_eq!(branch.get(&mut speculative, 3).unwrap(), value(42));
+		assert!(!db.is_shared());
+		assert_eq!(vector.get(&mut db, 3).unwrap(), value(3));
+
+		let reader = db.clone();
+		let handle = std::thread::spawn(move || {
+			let mut reader = reader;
+			let vector = Vector::<Dangling, Construct>::from_leaked(metadata);
+			vector.get(&mut reader, 7).unwrap()
+		});
+		assert_eq!(handle.join().unwrap(), value(7));
+		assert!(db.into_inner().as_ref().len() < speculative.as_ref().as_ref().len());
+	}
+}
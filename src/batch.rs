@@ -0,0 +1,122 @@
+use generic_array::GenericArray;
+use core::marker::PhantomData;
+use alloc::vec::Vec;
+
+use crate::{Construct, WriteBackend};
+
+/// Hasher of many 64-byte inputs at once, such as multi-buffer SIMD,
+/// GPU or FPGA hashing. Each input is the concatenation of two 32-byte
+/// children.
+pub trait BatchHasher {
+	/// Hash each input into the 32-byte output at the same position of
+	/// `out`, which has the same length as `inputs`.
+	fn hash_batch(inputs: &[[u8; 64]], out: &mut [[u8; 32]]);
+}
+
+/// Concatenate two 32-byte children into a hash input.
+fn input_of<V: AsRef<[u8]>>(left: &V, right: &V) -> [u8; 64] {
+	let mut input = [0u8; 64];
+	input[..32].copy_from_slice(left.as_ref());
+	input[32..].copy_from_slice(right.as_ref());
+	input
+}
+
+/// Construct hashing through batch hasher `H`, with inherited empty.
+/// `vector_tree`, bulk sets and buffered flushes hash each level of
+/// the tree with a single call to `H::hash_batch`.
+pub struct BatchConstruct<H, V=GenericArray<u8, typenum::U32>>(PhantomData<(H, V)>);
+
+impl<H: BatchHasher, V> Construct for BatchConstruct<H, V> where
+	V: From<GenericArray<u8, typenum::U32>> + AsRef<[u8]> + Default + Clone,
+{
+	type Value = V;
+
+	fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value {
+		let mut hash = [[0u8; 32]];
+		H::hash_batch(&[input_of(left, right)], &mut hash);
+		GenericArray::from(hash[0]).into()
+	}
+
+	fn intermediates_of(values: &[Self::Value], out: &mut Vec<Self::Value>) {
+		assert!(values.len() % 2 == 0, "intermediates_of takes an even number of values");
+		let inputs = values.chunks_exact(2)
+			.map(|pair| input_of(&pair[0], &pair[1]))
+			.collect::<Vec<_>>();
+
+		let mut hashes = alloc::vec![[0u8; 32]; inputs.len()];
+		H::hash_batch(&inputs, &mut hashes);
+		out.extend(hashes.iter().map(|hash| GenericArray::from(*hash).into()));
+	}
+
+	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
+		db: &mut DB,
+		depth_to_bottom: usize
+	) -> Result<Self::Value, DB::Error> {
+		let mut current = Self::Value::default();
+		for _ in 0..depth_to_bottom {
+			let value = (current.clone(), current);
+			let key = Self::intermediate_of(&value.0, &value.1);
+			db.insert(key.clone(), value)?;
+			current = key;
+		}
+		Ok(current)
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		crate::utils::fmt_hex(value.as_ref(), f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, BufferedVector, Vector, Owned, Tree};
+	use crate::utils::vector_tree;
+
+	use core::sync::atomic::{AtomicUsize, Ordering};
+	use sha2::{Digest, Sha256};
+
+	static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+	struct Sha256Batch;
+
+	impl BatchHasher for Sha256Batch {
+		fn hash_batch(inputs: &[[u8; 64]], out: &mut [[u8; 32]]) {
+			CALLS.fetch_add(1, Ordering::SeqCst);
+			for (input, hash) in inputs.iter().zip(out.iter_mut()) {
+				hash.copy_from_slice(&Sha256::digest(&input[..]));
+			}
+		}
+	}
+
+	type Construct = BatchConstruct<Sha256Batch>;
+
+	#[test]
+	fn test_batch_construct() {
+		let values = (0..16).map(value).collect::<Vec<_>>();
+		let mut db = InMemoryBackend::<Construct>::default();
+		CALLS.store(0, Ordering::SeqCst);
+		let root = vector_tree(&values, &mut db, None).unwrap();
+		assert_eq!(CALLS.load(Ordering::SeqCst), 4);
+
+		let mut digest_db = InMemoryBackend::<InheritedDigestConstruct<Sha256>>::default();
+		assert_eq!(root, vector_tree(&values, &mut digest_db, None).unwrap());
+
+		let mut buffered = BufferedVector::new(Vector::<Owned, Construct>::create_from(&mut db, &values, None).unwrap());
+		for i in 0..8 {
+			buffered.set::<()>(i * 2, value(100 + i as u8)).unwrap();
+		}
+		CALLS.store(0, Ordering::SeqCst);
+		buffered.flush(&mut db).unwrap();
+		assert_eq!(CALLS.load(Ordering::SeqCst), 4);
+
+		let mut expected = values.clone();
+		for i in 0..8 {
+			expected[i * 2] = value(100 + i as u8);
+		}
+		let vector = buffered.into_vector(&mut db).unwrap();
+		assert_eq!(vector.root(), vector_tree(&expected, &mut digest_db, None).unwrap());
+		vector.drop(&mut db).unwrap();
+	}
+}
@@ -0,0 +1,141 @@
+use alloc::vec::Vec;
+use generic_array::GenericArray;
+use core::marker::PhantomData;
+
+use crate::{Construct, Backend, WriteBackend};
+use crate::empty::EmptyTable;
+
+/// BLAKE3 construct, with inherited empty. Faster than the sha256
+/// constructs, for use cases that do not need ssz compatibility.
+pub struct Blake3Construct<V=GenericArray<u8, typenum::U32>>(PhantomData<V>);
+
+impl<V> Construct for Blake3Construct<V> where
+	V: From<GenericArray<u8, typenum::U32>> + AsRef<[u8]> + Default + Clone,
+{
+	type Value = V;
+
+	fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value {
+		let mut hasher = blake3::Hasher::new();
+		hasher.update(left.as_ref());
+		hasher.update(right.as_ref());
+		GenericArray::from(*hasher.finalize().as_bytes()).into()
+	}
+
+	fn intermediates_of(values: &[Self::Value], out: &mut Vec<Self::Value>) {
+		assert!(values.len() % 2 == 0, "intermediates_of takes an even number of values");
+		#[cfg(feature = "parallel")]
+		if values.len() > crate::utils::PARALLEL_BATCH_LEN {
+			use rayon::prelude::*;
+
+			// Values are copied out as bytes, so that they need not be
+			// `Send` or `Sync` themselves.
+			let bytes = values.iter()
+				.map(|value| GenericArray::<u8, typenum::U32>::clone_from_slice(value.as_ref()))
+				.collect::<Vec<_>>();
+			let hashes = bytes.par_chunks(crate::utils::PARALLEL_BATCH_LEN).flat_map_iter(|batch| {
+				let mut out = Vec::with_capacity(batch.len() / 2);
+				Blake3Construct::<GenericArray<u8, typenum::U32>>::intermediates_of(batch, &mut out);
+				out
+			}).collect::<Vec<_>>();
+			out.extend(hashes.into_iter().map(Into::into));
+			return
+		}
+
+		let mut hasher = blake3::Hasher::new();
+		out.extend(values.chunks_exact(2).map(|pair| {
+			hasher.update(pair[0].as_ref());
+			hasher.update(pair[1].as_ref());
+			let value = GenericArray::from(*hasher.finalize().as_bytes()).into();
+			hasher.reset();
+			value
+		}));
+	}
+
+	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
+		db: &mut DB,
+		depth_to_bottom: usize
+	) -> Result<Self::Value, DB::Error> {
+		let mut current = Self::Value::default();
+		for depth in 0..depth_to_bottom {
+			let value = (current.clone(), current);
+			let key = match db.empty_values().and_then(|values| values.table::<Self>().at(depth + 1)) {
+				Some(key) => key,
+				None => Self::intermediate_of(&value.0, &value.1),
+			};
+			db.insert(key.clone(), value)?;
+			current = key;
+		}
+		Ok(current)
+	}
+
+	fn lazy_empty_at<DB: Backend<Construct=Self> + ?Sized>(
+		db: &DB,
+		depth_to_bottom: usize
+	) -> Option<Self::Value> {
+		db.empty_values()?.table::<Self>().at(depth_to_bottom)
+	}
+
+	fn empty_children<DB: Backend<Construct=Self> + ?Sized>(
+		db: &DB,
+		value: &Self::Value
+	) -> Option<(Self::Value, Self::Value)> {
+		match db.empty_values() {
+			Some(values) => values.table::<Self>().children(value),
+			None => EmptyTable::new::<Self>().children(value),
+		}
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		crate::utils::fmt_hex(value.as_ref(), f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InMemoryBackend, NoopBackend, Vector, Owned, Tree};
+
+	#[test]
+	fn test_blake3() {
+		let mut db = InMemoryBackend::<Blake3Construct>::default();
+		let mut vector = Vector::<Owned, Blake3Construct>::create(&mut db, 0, None).unwrap();
+		let mut value = GenericArray::<u8, typenum::U32>::default();
+		value[0] = 1;
+		vector.push(&mut db, value).unwrap();
+		vector.push(&mut db, value).unwrap();
+
+		let mut input = [0u8; 64];
+		input[0] = 1;
+		input[32] = 1;
+		assert_eq!(&vector.root()[..], blake3::hash(&input).as_bytes());
+	}
+
+	#[test]
+	fn test_intermediates_of() {
+		let values = (0..2048).map(|i| value(i as u8)).collect::<Vec<_>>();
+		let mut out = Vec::new();
+		Blake3Construct::intermediates_of(&values, &mut out);
+		assert_eq!(out, values.chunks(2)
+			.map(|pair| Blake3Construct::intermediate_of(&pair[0], &pair[1]))
+			.collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn test_lazy_empty() {
+		let noop = NoopBackend::<Blake3Construct>::default();
+		let empty = Blake3Construct::<GenericArray<u8, typenum::U32>>::empty_at(
+			&mut noop.clone(), 40
+		).unwrap();
+		assert_eq!(Blake3Construct::lazy_empty_at(&noop, 40), Some(empty));
+		let child = Blake3Construct::lazy_empty_at(&noop, 39).unwrap();
+		assert_eq!(Blake3Construct::empty_children(&noop, &empty), Some((child, child)));
+		assert_eq!(Blake3Construct::empty_children(&noop, &value(1)), None);
+
+		let mut db = InMemoryBackend::<Blake3Construct>::default();
+		let vector = Vector::<Owned, Blake3Construct>::create(&mut db, 1 << 40, None).unwrap();
+		assert_eq!(vector.root(), empty);
+		assert_eq!(vector.get(&mut db, 12345).unwrap(), value(0));
+		vector.drop(&mut db).unwrap();
+	}
+}
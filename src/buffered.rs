@@ -0,0 +1,147 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::traits::{ReadBackend, WriteBackend, Construct, RootStatus, Owned, Dangling, Error, Tree};
+use crate::vector::Vector;
+
+/// `BufferedVector` with owned root.
+pub type OwnedBufferedVector<C> = BufferedVector<Owned, C>;
+
+/// `BufferedVector` with dangling root.
+pub type DanglingBufferedVector<C> = BufferedVector<Dangling, C>;
+
+/// Binary merkle tuple with buffered writes. Values set are only
+/// recorded as dirty, and intermediate nodes are recomputed once on
+/// `flush`.
+pub struct BufferedVector<R: RootStatus, C: Construct> {
+	vector: Vector<R, C>,
+	dirty: BTreeMap<u64, C::Value>,
+}
+
+impl<R: RootStatus, C: Construct> BufferedVector<R, C> {
+	/// Create a buffered vector from an existing vector.
+	pub fn new(vector: Vector<R, C>) -> Self {
+		Self { vector, dirty: BTreeMap::new() }
+	}
+
+	/// Get value at index, including values not yet flushed.
+	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: u64
+	) -> Result<C::Value, Error<DB::Error>> {
+		match self.dirty.get(&index) {
+			Some(value) => Ok(value.clone()),
+			None => self.vector.get(db, index),
+		}
+	}
+
+	/// Set value at index. The value is only recorded, and written to
+	/// the tree on `flush`, so no database is accessed.
+	pub fn set<E>(
+		&mut self,
+		index: u64,
+		value: C::Value
+	) -> Result<(), Error<E>> {
+		if index >= self.len() {
+			return Err(self.vector.overflowed(index))
+		}
+
+		self.dirty.insert(index, value);
+		Ok(())
+	}
+
+	/// Get the length of the tuple.
+	pub fn len(&self) -> u64 {
+		self.vector.len()
+	}
+
+	/// Whether the tuple is empty.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Whether there are values not yet flushed.
+	pub fn is_dirty(&self) -> bool {
+		!self.dirty.is_empty()
+	}
+
+	/// Write all dirty values to the tree, recomputing each affected
+	/// intermediate node only once.
+	pub fn flush<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB
+	) -> Result<(), Error<DB::Error>> {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::trace_span!(target: "bm", "flush", dirty = self.dirty.len()).entered();
+		let updates = self.dirty.iter()
+			.map(|(index, value)| (*index, value.clone()))
+			.collect::<Vec<_>>();
+		self.vector.set_sorted(db, &updates)?;
+		self.dirty.clear();
+		Ok(())
+	}
+
+	/// Flush the dirty values, and get the merkle root.
+	pub fn root<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB
+	) -> Result<C::Value, Error<DB::Error>> {
+		self.flush(db)?;
+		Ok(self.vector.root())
+	}
+
+	/// Flush the dirty values, and return the underlying vector.
+	pub fn into_vector<DB: WriteBackend<Construct=C> + ?Sized>(
+		mut self,
+		db: &mut DB
+	) -> Result<Vector<R, C>, Error<DB::Error>> {
+		self.flush(db)?;
+		Ok(self.vector)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, ErrorContext, Index};
+	use crate::memory::InMemoryBackend;
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type InMemory = InMemoryBackend<Construct>;
+
+	#[test]
+	fn test_flush() {
+		let mut db = InMemory::default();
+		let mut expected = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..13 {
+			expected.push(&mut db, value(i)).unwrap();
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let mut buffered = BufferedVector::new(vector);
+
+		for &i in &[7u8, 2, 11, 2, 12, 0] {
+			expected.set(&mut db, i as u64, value(100 + i)).unwrap();
+			buffered.set::<()>(i as u64, value(100 + i)).unwrap();
+		}
+		assert!(buffered.is_dirty());
+		assert_eq!(buffered.get(&mut db, 7).unwrap(), value(107));
+		assert_eq!(buffered.get(&mut db, 8).unwrap(), value(8));
+		assert_eq!(buffered.set::<()>(13, value(0)), Err(Error::AccessOverflowed(ErrorContext {
+			index: Some(Index::from_depth(13, 4)), depth: Some(4), node: None,
+		})));
+		assert!(!buffered.is_empty());
+
+		assert_eq!(buffered.root(&mut db).unwrap(), expected.root());
+		assert!(!buffered.is_dirty());
+
+		let vector = buffered.into_vector(&mut db).unwrap();
+		for i in 0..13 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), expected.get(&mut db, i).unwrap());
+		}
+	}
+}
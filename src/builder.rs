@@ -0,0 +1,138 @@
+use core::hash::Hash;
+use alloc::vec::Vec;
+use rayon::prelude::*;
+
+use crate::{Construct, WriteBackend, Error, InMemoryBackend, InMemoryBackendError};
+use crate::utils::{vector_tree, required_depth};
+
+/// Multi-threaded builder of a vector tree from all of its leaves, for
+/// the initial merkleization of large states. The leaves are split into
+/// one subtree for each thread, each hashed independently into its own
+/// in-memory overlay, and the overlays are then merged into the target
+/// database. The resulting root equals the one of `vector_tree`.
+#[derive(Debug, Clone)]
+pub struct TreeBuilder<V> {
+	leaves: Vec<V>,
+	threads: Option<usize>,
+}
+
+impl<V> TreeBuilder<V> {
+	/// Create a new builder of the tree of `leaves`.
+	pub fn new(leaves: Vec<V>) -> Self {
+		Self { leaves, threads: None }
+	}
+
+	/// Split the leaves into subtrees for `threads` threads, instead of
+	/// the number of threads of the current rayon pool.
+	pub fn with_threads(mut self, threads: usize) -> Self {
+		self.threads = Some(threads);
+		self
+	}
+
+	/// Number of leaves.
+	pub fn len(&self) -> usize {
+		self.leaves.len()
+	}
+
+	/// Whether there are no leaves.
+	pub fn is_empty(&self) -> bool {
+		self.leaves.is_empty()
+	}
+
+	/// Build the tree into `db`, padding it with empty nodes up to
+	/// `max_len`, or up to the next power of two of the length if not
+	/// given.
+	pub fn build<DB: WriteBackend + ?Sized>(self, db: &mut DB, max_len: Option<u64>) -> Result<V, Error<DB::Error>> where
+		DB::Construct: Construct<Value=V>,
+		V: Clone + Eq + Hash + Ord + Send + Sync,
+	{
+		let len = self.leaves.len() as u64;
+		if max_len.map(|max_len| len > max_len).unwrap_or(false) {
+			return Err(Error::InvalidParameter)
+		}
+		if self.leaves.is_empty() {
+			return vector_tree(&self.leaves, db, max_len)
+		}
+
+		let total_depth = required_depth(max_len.unwrap_or(len));
+		let threads = self.threads.unwrap_or_else(rayon::current_num_threads).max(1) as u64;
+		let subtree_depth = required_depth(len.div_ceil(threads)).min(total_depth);
+		let subtree_len = 1usize << subtree_depth;
+
+		let subtrees = self.leaves.par_chunks(subtree_len).map(|leaves| {
+			let mut overlay = InMemoryBackend::<DB::Construct>::default();
+			let root = vector_tree(leaves, &mut overlay, Some(subtree_len as u64))?;
+			Ok((root, overlay))
+		}).collect::<Result<Vec<_>, Error<InMemoryBackendError>>>().map_err(|err| match err {
+			Error::Backend(_) => Error::corrupted(),
+			Error::CorruptedDatabase(context) => Error::CorruptedDatabase(context),
+			Error::AccessOverflowed(context) => Error::AccessOverflowed(context),
+			Error::InvalidParameter => Error::InvalidParameter,
+			Error::NonCanonical => Error::NonCanonical,
+			Error::MaxLenExceeded { len, max_len } => Error::MaxLenExceeded { len, max_len },
+		})?;
+
+		let mut current = Vec::with_capacity(subtrees.len());
+		for (root, overlay) in subtrees {
+			for (key, (value, _)) in overlay.as_ref() {
+				if let Some(value) = value {
+					db.insert(key.clone(), value.clone())?;
+				}
+			}
+			current.push(root);
+		}
+
+		let mut next = Vec::new();
+		for depth_to_bottom in subtree_depth..total_depth {
+			if current.len() % 2 == 1 {
+				current.push(<DB::Construct as Construct>::empty_at(db, depth_to_bottom)?);
+			}
+
+			<DB::Construct as Construct>::intermediates_of(&current, &mut next);
+			for (key, pair) in next.iter().zip(current.chunks(2)) {
+				db.insert(key.clone(), (pair[0].clone(), pair[1].clone()))?;
+			}
+			current.clear();
+			core::mem::swap(&mut current, &mut next);
+		}
+
+		Ok(current.swap_remove(0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils;
+	use crate::InheritedDigestConstruct;
+
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	fn value(i: u8) -> GenericArray<u8, typenum::U32> {
+		test_utils::value(i + 1)
+	}
+
+	#[test]
+	fn test_tree_builder() {
+		for (len, max_len) in [(0, None), (1, None), (5, None), (100, None), (100, Some(1000)), (3, Some(4)), (37, Some(64))] {
+			let values = (0..len).map(|i| value(i as u8)).collect::<Vec<_>>();
+			let mut expected_db = InMemoryBackend::<Construct>::default();
+			let expected = vector_tree(&values, &mut expected_db, max_len).unwrap();
+
+			for threads in [1, 3, 4, 16] {
+				let mut db = InMemoryBackend::<Construct>::default();
+				let builder = TreeBuilder::new(values.clone()).with_threads(threads);
+				assert_eq!(builder.len(), len);
+				assert_eq!(builder.build(&mut db, max_len).unwrap(), expected);
+				assert_eq!(db.as_ref().keys().collect::<alloc::collections::BTreeSet<_>>(),
+						   expected_db.as_ref().keys().collect());
+			}
+		}
+
+		let mut db = InMemoryBackend::<Construct>::default();
+		assert_eq!(TreeBuilder::new(vec![value(0); 5]).build(&mut db, Some(4)), Err(Error::InvalidParameter));
+	}
+}
@@ -0,0 +1,236 @@
+use crate::{Backend, ReadBackend, WriteBackend, Construct, DecodeLimits, EmptyValues};
+use core::hash::Hash;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+type Pair<DB> = (<<DB as Backend>::Construct as Construct>::Value, <<DB as Backend>::Construct as Construct>::Value);
+
+/// Caching merkle database, keeping the most recently used nodes of the
+/// underlying database in memory.
+pub struct CachedBackend<DB: Backend> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord
+{
+	db: DB,
+	capacity: usize,
+	tick: u64,
+	cache: Map<<DB::Construct as Construct>::Value, (Pair<DB>, u64)>,
+	order: BTreeMap<u64, <DB::Construct as Construct>::Value>,
+	hits: usize,
+	misses: usize,
+}
+
+impl<DB: Backend> CachedBackend<DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	/// Create a new caching database, keeping at most `capacity` nodes.
+	pub fn new(db: DB, capacity: usize) -> Self {
+		Self {
+			db, capacity,
+			tick: 0,
+			cache: Default::default(),
+			order: Default::default(),
+			hits: 0,
+			misses: 0,
+		}
+	}
+
+	/// Get a reference to the underlying database.
+	pub fn inner(&self) -> &DB {
+		&self.db
+	}
+
+	/// Convert the backend into the underlying database.
+	pub fn into_inner(self) -> DB {
+		self.db
+	}
+
+	/// Number of nodes currently cached.
+	pub fn len(&self) -> usize {
+		self.cache.len()
+	}
+
+	/// Whether the cache is empty.
+	pub fn is_empty(&self) -> bool {
+		self.cache.is_empty()
+	}
+
+	/// Number of reads served from the cache.
+	pub fn hits(&self) -> usize {
+		self.hits
+	}
+
+	/// Number of reads served from the underlying database.
+	pub fn misses(&self) -> usize {
+		self.misses
+	}
+
+	/// Clear all cached nodes.
+	pub fn clear(&mut self) {
+		self.cache.clear();
+		self.order.clear();
+	}
+
+	fn touch(&mut self, key: &<DB::Construct as Construct>::Value) -> Option<Pair<DB>> {
+		self.tick += 1;
+		let tick = self.tick;
+		let (value, old_tick) = self.cache.get_mut(key)?;
+		let old_tick = core::mem::replace(old_tick, tick);
+		let value = value.clone();
+
+		self.order.remove(&old_tick);
+		self.order.insert(tick, key.clone());
+		Some(value)
+	}
+
+	fn cache(&mut self, key: <DB::Construct as Construct>::Value, value: Pair<DB>) {
+		if self.capacity == 0 || self.touch(&key).is_some() {
+			return
+		}
+
+		self.order.insert(self.tick, key.clone());
+		self.cache.insert(key, (value, self.tick));
+
+		while self.cache.len() > self.capacity {
+			let oldest = match self.order.keys().next() {
+				Some(oldest) => *oldest,
+				None => break,
+			};
+			if let Some(key) = self.order.remove(&oldest) {
+				self.cache.remove(&key);
+			}
+		}
+	}
+}
+
+impl<DB: Backend> Backend for CachedBackend<DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.db.empty_values()
+	}
+}
+
+impl<DB: ReadBackend> ReadBackend for CachedBackend<DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	fn get(
+		&mut self,
+		key: &<DB::Construct as Construct>::Value
+	) -> Result<Option<Pair<DB>>, Self::Error> {
+		if let Some(value) = self.touch(key) {
+			self.hits += 1;
+			return Ok(Some(value))
+		}
+
+		self.misses += 1;
+		let value = self.db.get(key)?;
+		if let Some(value) = value.clone() {
+			self.cache(key.clone(), value);
+		}
+		Ok(value)
+	}
+
+	fn prefetch(&mut self, keys: &[<DB::Construct as Construct>::Value]) {
+		let missing = keys.iter().filter(|key| !self.cache.contains_key(key)).cloned().collect::<Vec<_>>();
+		if !missing.is_empty() {
+			self.db.prefetch(&missing);
+		}
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.db.decode_limits()
+	}
+}
+
+impl<DB: WriteBackend> WriteBackend for CachedBackend<DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	fn rootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.db.rootify(key)
+	}
+
+	fn unrootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.db.unrootify(key)
+	}
+
+	fn insert(
+		&mut self,
+		key: <DB::Construct as Construct>::Value,
+		value: Pair<DB>
+	) -> Result<(), Self::Error> {
+		self.db.insert(key.clone(), value.clone())?;
+		self.cache(key, value);
+		Ok(())
+	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		self.db.begin_batch()
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		self.db.commit_batch()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree, Error};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_cache() {
+		let mut db = CachedBackend::new(InMemoryBackend::<Construct>::default(), 4);
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..16 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		assert_eq!(db.len(), 4);
+
+		db.clear();
+		let (hits, misses) = (db.hits(), db.misses());
+		assert_eq!(vector.get(&mut db, 0).unwrap(), value(0));
+		assert_eq!((db.hits() - hits, db.misses() - misses), (0, 4));
+		assert_eq!(vector.get(&mut db, 1).unwrap(), value(1));
+		assert_eq!((db.hits() - hits, db.misses() - misses), (4, 4));
+		assert_eq!(vector.get(&mut db, 15).unwrap(), value(15));
+		assert_eq!((db.hits() - hits, db.misses() - misses), (5, 7));
+		assert_eq!(db.len(), 4);
+	}
+
+	#[test]
+	fn test_cache_empty_and_missing() {
+		let mut db = CachedBackend::new(InMemoryBackend::<Construct>::default(), 4);
+		assert!(db.empty_values().is_some());
+
+		let empty = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		assert!(matches!(empty.get(&mut db, 0), Err(Error::AccessOverflowed(_))));
+		let deep = Vector::<Owned, Construct>::create(&mut db, 1 << 40, None).unwrap();
+		assert_eq!(deep.get(&mut db, (1 << 40) - 1).unwrap(), value(0));
+		assert!(db.is_empty());
+
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..4 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let mut other = CachedBackend::new(InMemoryBackend::<Construct>::default(), 4);
+		assert!(matches!(vector.get(&mut other, 0), Err(Error::CorruptedDatabase(_))));
+		assert_eq!(other.misses(), 1);
+		assert!(other.is_empty());
+
+		empty.drop(&mut db).unwrap();
+		deep.drop(&mut db).unwrap();
+		vector.drop(&mut db).unwrap();
+	}
+}
@@ -0,0 +1,206 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+use crate::{Error, CopyError};
+use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp};
+
+/// Number of write operations applied at once when rewriting a store.
+const BATCH: usize = 4096;
+
+/// Statistics of a compaction.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct CompactStats {
+	/// Number of nodes reachable from the live roots.
+	pub live: usize,
+	/// Number of dead nodes removed.
+	pub removed: usize,
+}
+
+fn children(raw: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+	let children = raw.get(8..).filter(|children| !children.is_empty())?;
+	let (left, right) = children.split_at(children.len() / 2);
+	Some((left.to_vec(), right.to_vec()))
+}
+
+fn with_refcount(raw: Option<&[u8]>, refcount: u64) -> Vec<u8> {
+	let mut ret = refcount.to_le_bytes().to_vec();
+	if let Some(children) = raw.and_then(|raw| raw.get(8..)) {
+		ret.extend_from_slice(children);
+	}
+	ret
+}
+
+/// Mark all nodes reachable from `roots`, with their reference count
+/// recomputed from the live roots and intermediate nodes only.
+fn mark<D: KeyValueDB + ?Sized, V: AsRef<[u8]>>(
+	db: &D,
+	roots: &[V],
+) -> Result<Map<Vec<u8>, u64>, D::Error> {
+	let mut live = Map::new();
+	let mut stack = Vec::new();
+
+	let reference = |live: &mut Map<Vec<u8>, u64>, stack: &mut Vec<Vec<u8>>, key: Vec<u8>| {
+		let refcount = live.entry(key.clone()).or_insert(0);
+		*refcount += 1;
+		if *refcount == 1 {
+			stack.push(key);
+		}
+	};
+
+	for root in roots {
+		reference(&mut live, &mut stack, root.as_ref().to_vec());
+	}
+	while let Some(key) = stack.pop() {
+		if let Some((left, right)) = db.get_with(&key, children)?.flatten() {
+			reference(&mut live, &mut stack, left);
+			reference(&mut live, &mut stack, right);
+		}
+	}
+
+	Ok(live)
+}
+
+fn write_all<D: KeyValueDB + ?Sized>(db: &mut D, mut ops: Vec<KeyValueOp>) -> Result<(), D::Error> {
+	while !ops.is_empty() {
+		let rest = ops.split_off(ops.len().min(BATCH));
+		db.write(ops)?;
+		ops = rest;
+	}
+	Ok(())
+}
+
+/// Offline mark-and-sweep compaction of a key-value store of merkle
+/// nodes. All nodes reachable from `roots` are kept, with reference
+/// counts rewritten as if `roots` were each rootified once, and all
+/// other nodes are deleted, such as subtrees leaked by roots that were
+/// never unrootified. The store must not be used while compacting.
+/// Writes are applied in batches, so an interrupted compaction never
+/// loses live nodes and can simply be run again.
+pub fn compact<D: IterableKeyValueDB + ?Sized, V: AsRef<[u8]>>(
+	db: &mut D,
+	roots: &[V],
+) -> Result<CompactStats, D::Error> {
+	#[cfg(feature = "tracing")]
+	let _span = tracing::debug_span!(target: "bm", "compact", roots = roots.len()).entered();
+	let mut live = mark(db, roots)?;
+	let mut stats = CompactStats { live: live.len(), removed: 0 };
+
+	let mut ops = Vec::new();
+	db.for_each_entry(|key, raw| match live.remove(key) {
+		Some(refcount) => {
+			if raw.get(..8) != Some(&refcount.to_le_bytes()[..]) {
+				ops.push(KeyValueOp::Put(key.to_vec(), with_refcount(Some(raw), refcount)));
+			}
+		},
+		None => {
+			ops.push(KeyValueOp::Delete(key.to_vec()));
+			stats.removed += 1;
+		},
+	})?;
+	for (key, refcount) in live {
+		ops.push(KeyValueOp::Put(key, with_refcount(None, refcount)));
+	}
+
+	write_all(db, ops)?;
+	Ok(stats)
+}
+
+/// Compact a key-value store of merkle nodes by copying only the nodes
+/// reachable from `roots` into a new store, such as a fresh file, with
+/// reference counts as in `compact`. This works for stores that cannot
+/// be iterated. Returns the number of nodes written.
+pub fn compact_into<Src: KeyValueDB + ?Sized, Dst: KeyValueDB + ?Sized, V: AsRef<[u8]>>(
+	src: &Src,
+	dst: &mut Dst,
+	roots: &[V],
+) -> Result<usize, CopyError<Src::Error, Dst::Error>> {
+	#[cfg(feature = "tracing")]
+	let _span = tracing::debug_span!(target: "bm", "compact_into", roots = roots.len()).entered();
+	let live = mark(src, roots).map_err(|e| CopyError::Source(Error::Backend(e)))?;
+	let count = live.len();
+
+	let mut ops = Vec::with_capacity(BATCH);
+	for (key, refcount) in live {
+		let value = src.get_with(&key, |raw| with_refcount(Some(raw), refcount))
+			.map_err(|e| CopyError::Source(Error::Backend(e)))?
+			.unwrap_or_else(|| with_refcount(None, refcount));
+		ops.push(KeyValueOp::Put(key, value));
+
+		if ops.len() == BATCH {
+			dst.write(core::mem::take(&mut ops)).map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+		}
+	}
+	write_all(dst, ops).map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+
+	Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{KvBackend, NamespacedDB, InheritedDigestConstruct, Vector, Owned, Tree};
+
+	use alloc::rc::Rc;
+	use core::cell::RefCell;
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type Store = Map<Vec<u8>, Vec<u8>>;
+
+	fn vector<D: KeyValueDB>(db: &mut KvBackend<Construct, D>, offset: u8) -> Vector<Owned, Construct> where
+		D::Error: core::fmt::Debug,
+	{
+		let mut vector = Vector::<Owned, Construct>::create(db, 0, Some(16)).unwrap();
+		for i in 0..11 {
+			vector.push(db, value(offset + i)).unwrap();
+		}
+		vector
+	}
+
+	#[test]
+	fn test_compact() {
+		let mut expected = KvBackend::<Construct, Store>::from_db(Store::default());
+		let kept = vector(&mut expected, 0);
+
+		let mut db = KvBackend::<Construct, Store>::from_db(Store::default());
+		let _leaked = vector(&mut db, 100);
+		let _ = vector(&mut db, 0);
+		let before = db.db().len();
+
+		let mut into = Store::default();
+		assert_eq!(compact_into(db.db(), &mut into, &[kept.root()]).unwrap(), expected.db().len());
+		assert_eq!(&into, expected.db());
+
+		let mut store = db.into_store().into_db();
+		let stats = compact(&mut store, &[kept.root()]).unwrap();
+		assert_eq!(stats, CompactStats { live: expected.db().len(), removed: before - expected.db().len() });
+		assert_eq!(&store, expected.db());
+		assert_eq!(compact(&mut store, &[kept.root()]).unwrap().removed, 0);
+
+		let mut db = KvBackend::<Construct, Store>::from_db(store);
+		assert_eq!(kept.get(&mut db, 7).unwrap(), value(7));
+		kept.drop(&mut db).unwrap();
+		assert!(db.db().is_empty());
+	}
+
+	#[test]
+	fn test_compact_namespaced() {
+		let shared = Rc::new(RefCell::new(Store::default()));
+		let mut other = KvBackend::<Construct, _>::from_db(NamespacedDB::new(shared.clone(), b"other"));
+		let other_vector = vector(&mut other, 50);
+		let mut db = KvBackend::<Construct, _>::from_db(NamespacedDB::new(shared.clone(), b"db"));
+		let _leaked = vector(&mut db, 100);
+
+		let mut store = db.into_store().into_db();
+		let roots: [GenericArray<u8, typenum::U32>; 0] = [];
+		assert!(compact(&mut store, &roots).unwrap().removed > 0);
+		assert_eq!(other_vector.get(&mut other, 3).unwrap(), value(53));
+		other_vector.drop(&mut other).unwrap();
+		assert!(shared.borrow().is_empty());
+	}
+}
@@ -0,0 +1,187 @@
+use alloc::vec::Vec;
+
+use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp};
+
+const RAW: u8 = 0;
+const SNAPPY: u8 = 1;
+
+/// Error of a compressed key-value database.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum CompressedDBError<E> {
+	/// The underlying database failed.
+	Backend(E),
+	/// A stored value could not be decompressed.
+	Corrupted,
+}
+
+/// Key-value database compressing each stored value with snappy. Wrap
+/// the database of a persistent backend, such as `RocksDB`, to shrink
+/// nodes whose children are mostly zero bytes or equal, such as empty
+/// subtrees and small leaves, at the cost of compressing on write and
+/// decompressing on read.
+///
+/// Values are compressed one by one rather than per write batch, so
+/// only those nodes benefit. Intermediate nodes whose children are
+/// hashes do not shrink and are stored as is with a one-byte header,
+/// so a tree of hashed leaves takes slightly more space than without
+/// compression. See `benches/compressed.rs` for a comparison.
+pub struct CompressedDB<D> {
+	db: D,
+}
+
+impl<D> CompressedDB<D> {
+	/// Create a new compressed database over `db`.
+	pub fn new(db: D) -> Self {
+		Self { db }
+	}
+
+	/// Get a reference to the underlying database.
+	pub fn db(&self) -> &D {
+		&self.db
+	}
+
+	/// Convert into the underlying database.
+	pub fn into_db(self) -> D {
+		self.db
+	}
+}
+
+impl<D: Default> Default for CompressedDB<D> {
+	fn default() -> Self {
+		Self::new(D::default())
+	}
+}
+
+fn compress(value: Vec<u8>) -> Vec<u8> {
+	let mut ret = Vec::with_capacity(value.len() + 1);
+	match snap::raw::Encoder::new().compress_vec(&value) {
+		Ok(compressed) if compressed.len() < value.len() => {
+			ret.push(SNAPPY);
+			ret.extend_from_slice(&compressed);
+		},
+		_ => {
+			ret.push(RAW);
+			ret.extend_from_slice(&value);
+		},
+	}
+	ret
+}
+
+fn decompress_with<R, F: FnOnce(&[u8]) -> R>(stored: &[u8], f: F) -> Option<R> {
+	match stored.split_first() {
+		Some((&RAW, value)) => Some(f(value)),
+		Some((&SNAPPY, compressed)) => snap::raw::Decoder::new().decompress_vec(compressed).ok()
+			.map(|value| f(&value)),
+		_ => None,
+	}
+}
+
+impl<D: KeyValueDB> KeyValueDB for CompressedDB<D> {
+	type Error = CompressedDBError<D::Error>;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.get_with(key, |value| value.to_vec())
+	}
+
+	fn get_with<R, F: FnOnce(&[u8]) -> R>(&self, key: &[u8], f: F) -> Result<Option<R>, Self::Error> {
+		match self.db.get_with(key, |stored| decompress_with(stored, f)) {
+			Ok(Some(Some(value))) => Ok(Some(value)),
+			Ok(Some(None)) => Err(CompressedDBError::Corrupted),
+			Ok(None) => Ok(None),
+			Err(err) => Err(CompressedDBError::Backend(err)),
+		}
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let ops = ops.into_iter().map(|op| match op {
+			KeyValueOp::Put(key, value) => KeyValueOp::Put(key, compress(value)),
+			KeyValueOp::Delete(key) => KeyValueOp::Delete(key),
+		}).collect();
+		self.db.write(ops).map_err(CompressedDBError::Backend)
+	}
+}
+
+impl<D: IterableKeyValueDB> IterableKeyValueDB for CompressedDB<D> {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, mut f: F) -> Result<(), Self::Error> {
+		let mut corrupted = false;
+		self.db.for_each_entry(|key, stored| {
+			corrupted |= decompress_with(stored, |value| f(key, value)).is_none();
+		}).map_err(CompressedDBError::Backend)?;
+
+		if corrupted {
+			return Err(CompressedDBError::Corrupted)
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{RefCountBackend, KeyValueStore, InheritedDigestConstruct, Vector, Owned, Tree, Error};
+
+	use std::collections::HashMap;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type Plain = RefCountBackend<Construct, KeyValueStore<HashMap<Vec<u8>, Vec<u8>>>>;
+	type Compressed = RefCountBackend<Construct, KeyValueStore<CompressedDB<HashMap<Vec<u8>, Vec<u8>>>>>;
+
+	fn stored_bytes(db: &HashMap<Vec<u8>, Vec<u8>>) -> usize {
+		db.values().map(|value| value.len()).sum()
+	}
+
+	#[test]
+	fn test_compressed() {
+		let mut plain = Plain::default();
+		let mut db = Compressed::default();
+		let values = (0..16).map(value).collect::<Vec<_>>();
+		let plain_vector = Vector::<Owned, Construct>::create_from(&mut plain, &values, Some(1024)).unwrap();
+		let vector = Vector::<Owned, Construct>::create_from(&mut db, &values, Some(1024)).unwrap();
+		assert_eq!(vector.root(), plain_vector.root());
+		for i in 0..16 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+
+		let plain_bytes = stored_bytes(plain.store().db());
+		let compressed_bytes = stored_bytes(db.store().db().db());
+		assert_eq!(plain.store().db().len(), db.store().db().db().len());
+		assert!(compressed_bytes < plain_bytes);
+
+		let mut corrupted = CompressedDB::new(HashMap::new());
+		corrupted.db.insert(b"key".to_vec(), vec![SNAPPY, 0xff]);
+		assert_eq!(corrupted.get(b"key"), Err(CompressedDBError::Corrupted));
+	}
+
+	#[test]
+	fn test_compressed_edge_cases() {
+		let mut db = CompressedDB::new(HashMap::new());
+		db.write(vec![KeyValueOp::Put(b"empty".to_vec(), Vec::new())]).unwrap();
+		assert_eq!(db.db().get(&b"empty"[..]), Some(&vec![RAW]));
+		assert_eq!(db.get(b"empty"), Ok(Some(Vec::new())));
+		assert_eq!(db.get(b"missing"), Ok(None));
+
+		db.db.insert(b"headerless".to_vec(), Vec::new());
+		assert_eq!(db.get(b"headerless"), Err(CompressedDBError::Corrupted));
+		db.db.insert(b"headerless".to_vec(), vec![0xff, 1, 2]);
+		assert_eq!(db.get(b"headerless"), Err(CompressedDBError::Corrupted));
+		assert_eq!(db.for_each_entry(|_, _| ()), Err(CompressedDBError::Corrupted));
+
+		let mut db = Compressed::default();
+		let deep = Vector::<Owned, Construct>::create(&mut db, 1 << 40, None).unwrap();
+		assert_eq!(deep.get(&mut db, (1 << 40) - 1).unwrap(), value(0));
+		assert!(matches!(deep.get(&mut db, 1 << 40), Err(Error::AccessOverflowed(_))));
+
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..4 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let mut other = Compressed::default();
+		assert!(matches!(vector.get(&mut other, 0), Err(Error::CorruptedDatabase(_))));
+
+		deep.drop(&mut db).unwrap();
+		vector.drop(&mut db).unwrap();
+		assert!(db.store().db().db().is_empty());
+	}
+}
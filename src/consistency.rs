@@ -0,0 +1,210 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::{Construct, ReadBackend, CompactValue, Error, ErrorContext, NoopBackend};
+use crate::raw::children;
+
+/// Whether the subtree of `depth_to_bottom` starting at `offset` holds
+/// both leaves before and after `old_len`.
+fn splits(depth_to_bottom: usize, offset: u64, old_len: u64) -> bool {
+	let end = 1u64.checked_shl(depth_to_bottom as u32)
+		.and_then(|width| offset.checked_add(width))
+		.unwrap_or(u64::MAX);
+	offset < old_len && old_len < end
+}
+
+fn half(depth_to_bottom: usize, offset: u64) -> u64 {
+	1u64.checked_shl(depth_to_bottom as u32 - 1)
+		.and_then(|half| offset.checked_add(half))
+		.unwrap_or(u64::MAX)
+}
+
+fn prove_node<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	node: <DB::Construct as Construct>::Value,
+	depth_to_bottom: usize,
+	offset: u64,
+	old_len: u64,
+) -> Result<CompactValue<<DB::Construct as Construct>::Value>, Error<DB::Error>> {
+	if depth_to_bottom == 0 || !splits(depth_to_bottom, offset, old_len) {
+		return Ok(CompactValue::Single(node))
+	}
+
+	let (left, right) = children(db, &node)?
+		.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<DB::Construct>(&node)))?;
+	if depth_to_bottom > 1 {
+		db.prefetch(&[left.clone(), right.clone()]);
+	}
+	let left = prove_node(db, left, depth_to_bottom - 1, offset, old_len)?;
+	let right = prove_node(db, right, depth_to_bottom - 1, half(depth_to_bottom, offset), old_len)?;
+	Ok(CompactValue::Combined(Box::new((left, right))))
+}
+
+/// Prove that the append-only tree under `root`, whose leaves are at
+/// `depth`, extends the tree holding its first `old_len` leaves, in the
+/// style of Certificate Transparency. The proof holds the largest
+/// subtrees before and after `old_len`, so its size is logarithmic.
+pub fn prove_consistency<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	depth: usize,
+	old_len: u64,
+) -> Result<CompactValue<<DB::Construct as Construct>::Value>, Error<DB::Error>> {
+	#[cfg(feature = "tracing")]
+	let _span = tracing::trace_span!(target: "bm", "prove_consistency", old_len).entered();
+	if let Some(width) = 1u64.checked_shl(depth as u32) {
+		if old_len > width {
+			return Err(Error::overflowed())
+		}
+	}
+
+	prove_node(db, root.clone(), depth, 0, old_len)
+}
+
+struct Verifier<'a, V> {
+	old_len: u64,
+	old_depth: usize,
+	empties: &'a [V],
+	old_root: Option<V>,
+}
+
+impl<'a, V: Clone> Verifier<'a, V> {
+	/// Recompute the new hash of a node, and its old hash if it is at
+	/// most `old_depth` above the bottom.
+	fn node<C: Construct<Value=V>>(
+		&mut self,
+		proof: CompactValue<V>,
+		depth_to_bottom: usize,
+		offset: u64,
+	) -> Option<(V, Option<V>)> {
+		let (new, old) = match proof {
+			CompactValue::Single(value) => {
+				if depth_to_bottom > 0 && splits(depth_to_bottom, offset, self.old_len) {
+					return None
+				}
+				if offset == 0 && self.old_len == 0 && depth_to_bottom > self.old_depth {
+					self.old_root = Some(self.empties[self.old_depth].clone());
+				}
+
+				let old = self.empties.get(depth_to_bottom).map(|empty| {
+					if offset < self.old_len { value.clone() } else { empty.clone() }
+				});
+				(value, old)
+			},
+			CompactValue::Combined(boxed) => {
+				if depth_to_bottom == 0 || !splits(depth_to_bottom, offset, self.old_len) {
+					return None
+				}
+
+				let (left, right) = *boxed;
+				let (new_left, old_left) = self.node::<C>(left, depth_to_bottom - 1, offset)?;
+				let (new_right, old_right) = self.node::<C>(
+					right, depth_to_bottom - 1, half(depth_to_bottom, offset)
+				)?;
+				let old = match (old_left, old_right) {
+					(Some(left), Some(right)) if depth_to_bottom <= self.old_depth =>
+						Some(C::intermediate_of(&left, &right)),
+					_ => None,
+				};
+				(C::intermediate_of(&new_left, &new_right), old)
+			},
+		};
+
+		if offset == 0 && depth_to_bottom == self.old_depth {
+			self.old_root = old.clone();
+		}
+		Some((new, old))
+	}
+}
+
+/// Verify a proof of `prove_consistency`, that the tree under
+/// `new_root` with leaves at `new_depth` extends the tree under
+/// `old_root` with `old_len` leaves at `old_depth`. Leaves of the old
+/// tree after `old_len` are expected to be empty.
+pub fn verify_consistency<C: Construct>(
+	proof: CompactValue<C::Value>,
+	old_root: &C::Value,
+	old_depth: usize,
+	old_len: u64,
+	new_root: &C::Value,
+	new_depth: usize,
+) -> bool where
+	C::Value: Eq + Hash + Ord,
+{
+	if old_depth > new_depth {
+		return false
+	}
+	if let Some(width) = 1u64.checked_shl(old_depth as u32) {
+		if old_len > width {
+			return false
+		}
+	}
+
+	let empties = (0..=old_depth).map(|depth| {
+		C::empty_at(&mut NoopBackend::default(), depth)
+			.expect("Noop backend never fails in set; qed")
+	}).collect::<Vec<_>>();
+	let mut verifier = Verifier {
+		old_len,
+		old_depth,
+		empties: &empties,
+		old_root: None,
+	};
+
+	match verifier.node::<C>(proof, new_depth, 0) {
+		Some((new, _)) => new == *new_root && verifier.old_root.as_ref() == Some(old_root),
+		None => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	fn value(i: u8) -> GenericArray<u8, typenum::U32> {
+		test_utils::value(i + 1)
+	}
+
+	#[test]
+	fn test_consistency() {
+		for max_len in [None, Some(32)] {
+			let mut db = InMemoryBackend::<Construct>::default();
+			let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, max_len).unwrap();
+			let mut history = Vec::new();
+			for i in 0..21 {
+				history.push((vector.root(), vector.depth(), vector.len()));
+				vector.push(&mut db, value(i)).unwrap();
+			}
+
+			for (old_root, old_depth, old_len) in &history {
+				let proof = vector.prove_consistency(&mut db, *old_len).unwrap();
+				assert!(proof.len() <= 2 * vector.depth() + 1);
+				assert!(verify_consistency::<Construct>(
+					proof.clone(), old_root, *old_depth, *old_len, &vector.root(), vector.depth()
+				));
+				assert!(!verify_consistency::<Construct>(
+					proof.clone(), old_root, *old_depth, *old_len + 1, &vector.root(), vector.depth()
+				));
+			}
+
+			let (old_root, old_depth, old_len) = history[13];
+			let mut forked = Vector::<Owned, Construct>::create(&mut db, 0, max_len).unwrap();
+			for i in 0..21 {
+				forked.push(&mut db, value(if i == 7 { 42 } else { i })).unwrap();
+			}
+			let proof = forked.prove_consistency(&mut db, old_len).unwrap();
+			assert!(!verify_consistency::<Construct>(
+				proof, &old_root, old_depth, old_len, &forked.root(), forked.depth()
+			));
+			forked.drop(&mut db).unwrap();
+		}
+	}
+}
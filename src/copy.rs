@@ -0,0 +1,109 @@
+use alloc::vec::Vec;
+
+use crate::{Construct, ReadBackend, WriteBackend, Error};
+
+/// Error when copying a tree between backends.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum CopyError<SrcError, DstError> {
+	/// Reading from the source backend failed.
+	Source(Error<SrcError>),
+	/// Writing to the destination backend failed.
+	Destination(Error<DstError>),
+}
+
+impl<SrcError, DstError> core::fmt::Display for CopyError<SrcError, DstError> where
+	Error<SrcError>: core::fmt::Display,
+	Error<DstError>: core::fmt::Display,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self {
+			CopyError::Source(err) => write!(f, "reading source failed: {}", err),
+			CopyError::Destination(err) => write!(f, "writing destination failed: {}", err),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<SrcError, DstError> std::error::Error for CopyError<SrcError, DstError> where
+	SrcError: std::error::Error + 'static,
+	DstError: std::error::Error + 'static,
+{
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			CopyError::Source(err) => err.source(),
+			CopyError::Destination(err) => err.source(),
+		}
+	}
+}
+
+/// Deep-copy all nodes reachable from `root` into another backend,
+/// skipping subtrees already present in the destination, in a single
+/// batch. Children are inserted before their parents. The root is not
+/// rootified in the destination. Returns the number of nodes copied.
+pub fn copy_tree<Src: ReadBackend + ?Sized, Dst: WriteBackend<Construct=Src::Construct> + ?Sized>(
+	src: &mut Src,
+	dst: &mut Dst,
+	root: &<Src::Construct as Construct>::Value,
+) -> Result<usize, CopyError<Src::Error, Dst::Error>> {
+	let mut count = 0;
+
+	dst.begin_batch().map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+
+	let mut stack = Vec::new();
+	stack.push((root.clone(), None));
+	while let Some((key, children)) = stack.pop() {
+		match children {
+			Some(children) => {
+				dst.insert(key, children).map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+				count += 1;
+			},
+			None => {
+				if dst.get(&key).map_err(|e| CopyError::Destination(Error::Backend(e)))?.is_some() {
+					continue
+				}
+
+				if let Some((left, right)) = src.get(&key).map_err(|e| CopyError::Source(Error::Backend(e)))? {
+					stack.push((key, Some((left.clone(), right.clone()))));
+					stack.push((right, None));
+					stack.push((left, None));
+				}
+			},
+		}
+	}
+
+	dst.commit_batch().map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+
+	Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, InMemoryRefCountBackend, Vector,
+				Owned, Dangling, Tree, Leak};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_copy_tree() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..8 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let mut dst = InMemoryRefCountBackend::<Construct>::default();
+		assert_eq!(copy_tree(&mut db, &mut dst, &vector.root()).unwrap(), 7);
+		let copied = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		for i in 0..8 {
+			assert_eq!(copied.get(&mut dst, i).unwrap(), value(i as u8));
+		}
+
+		vector.set(&mut db, 6, value(60)).unwrap();
+		assert_eq!(copy_tree(&mut db, &mut dst, &vector.root()).unwrap(), 3);
+		assert_eq!(copy_tree(&mut db, &mut dst, &vector.root()).unwrap(), 0);
+	}
+}
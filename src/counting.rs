@@ -0,0 +1,198 @@
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU64, Ordering};
+use alloc::vec::Vec;
+
+use crate::{Backend, ReadBackend, WriteBackend, Construct, NodePair, DecodeLimits, EmptyValues};
+
+static HASHES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of hashes computed by all `CountingConstruct`s of the process.
+pub fn hash_count() -> u64 {
+	HASHES.load(Ordering::Relaxed)
+}
+
+/// Construct counting the intermediate nodes hashed by construct `C`,
+/// to attribute hashing cost to the structures using it. Empty subtrees
+/// created by `C` are not counted.
+pub struct CountingConstruct<C>(PhantomData<C>);
+
+impl<C: Construct> Construct for CountingConstruct<C> {
+	type Value = C::Value;
+
+	fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value {
+		HASHES.fetch_add(1, Ordering::Relaxed);
+		C::intermediate_of(left, right)
+	}
+
+	fn intermediates_of(values: &[Self::Value], out: &mut Vec<Self::Value>) {
+		HASHES.fetch_add(values.len() as u64 / 2, Ordering::Relaxed);
+		C::intermediates_of(values, out)
+	}
+
+	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
+		db: &mut DB,
+		depth_to_bottom: usize
+	) -> Result<Self::Value, DB::Error> {
+		C::empty_at(&mut Forward::<C, DB>(db, PhantomData), depth_to_bottom)
+	}
+
+	fn lazy_empty_at<DB: Backend<Construct=Self> + ?Sized>(
+		db: &DB,
+		depth_to_bottom: usize
+	) -> Option<Self::Value> {
+		C::lazy_empty_at(&Peek::<C, DB>(db, PhantomData), depth_to_bottom)
+	}
+
+	fn empty_children<DB: Backend<Construct=Self> + ?Sized>(
+		db: &DB,
+		value: &Self::Value
+	) -> Option<(Self::Value, Self::Value)> {
+		C::empty_children(&Peek::<C, DB>(db, PhantomData), value)
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		C::fmt_value(value, f)
+	}
+}
+
+/// Backend of the inner construct, writing to the database of the
+/// counting construct.
+struct Forward<'a, C, DB: ?Sized>(&'a mut DB, PhantomData<C>);
+
+impl<'a, C: Construct, DB: Backend<Construct=CountingConstruct<C>> + ?Sized> Backend for Forward<'a, C, DB> {
+	type Construct = C;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		self.0.empty_values()
+	}
+}
+
+/// Backend of the inner construct, only giving access to the empty
+/// values kept by the database of the counting construct.
+struct Peek<'a, C, DB: ?Sized>(&'a DB, PhantomData<C>);
+
+impl<'a, C: Construct, DB: Backend<Construct=CountingConstruct<C>> + ?Sized> Backend for Peek<'a, C, DB> {
+	type Construct = C;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		self.0.empty_values()
+	}
+}
+
+impl<'a, C: Construct, DB: ReadBackend<Construct=CountingConstruct<C>> + ?Sized> ReadBackend for Forward<'a, C, DB> {
+	fn get(&mut self, key: &C::Value) -> Result<Option<NodePair<DB>>, Self::Error> {
+		self.0.get(key)
+	}
+
+	fn prefetch(&mut self, keys: &[C::Value]) {
+		self.0.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.0.decode_limits()
+	}
+}
+
+impl<'a, C: Construct, DB: WriteBackend<Construct=CountingConstruct<C>> + ?Sized> WriteBackend for Forward<'a, C, DB> {
+	fn rootify(&mut self, key: &C::Value) -> Result<(), Self::Error> {
+		self.0.rootify(key)
+	}
+
+	fn unrootify(&mut self, key: &C::Value) -> Result<(), Self::Error> {
+		self.0.unrootify(key)
+	}
+
+	fn insert(&mut self, key: C::Value, value: NodePair<DB>) -> Result<(), Self::Error> {
+		self.0.insert(key, value)
+	}
+}
+
+/// Meter of the hashes computed by `CountingConstruct`s since it was
+/// started, with an optional budget, such as the hashes allowed for one
+/// structure in a slot. The count is process-wide, so operations of
+/// other threads in the meantime are included.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct HashMeter {
+	start: u64,
+	budget: Option<u64>,
+}
+
+impl HashMeter {
+	/// Start a new meter without budget.
+	pub fn start() -> Self {
+		Self { start: hash_count(), budget: None }
+	}
+
+	/// Start a new meter with a budget of `budget` hashes.
+	pub fn with_budget(budget: u64) -> Self {
+		Self { start: hash_count(), budget: Some(budget) }
+	}
+
+	/// Hashes computed since the meter was started.
+	pub fn hashes(&self) -> u64 {
+		hash_count().wrapping_sub(self.start)
+	}
+
+	/// Hashes left in the budget, if any.
+	pub fn remaining(&self) -> Option<u64> {
+		self.budget.map(|budget| budget.saturating_sub(self.hashes()))
+	}
+
+	/// Whether more hashes than the budget were computed.
+	pub fn is_exceeded(&self) -> bool {
+		self.budget.map(|budget| self.hashes() > budget).unwrap_or(false)
+	}
+
+	/// Stop the meter and get the hashes computed. With the `tracing`
+	/// feature, they are also emitted as a debug event named `name`.
+	pub fn finish(self, name: &str) -> u64 {
+		let hashes = self.hashes();
+		#[cfg(feature = "tracing")]
+		tracing::debug!(target: "bm", name, hashes, budget = ?self.budget, "hash meter");
+		#[cfg(not(feature = "tracing"))]
+		let _ = name;
+		hashes
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+	use crate::utils::vector_tree;
+
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type Construct = CountingConstruct<InheritedDigestConstruct<Sha256>>;
+
+	fn value(i: u8) -> GenericArray<u8, typenum::U32> {
+		test_utils::value(i + 1)
+	}
+
+	#[test]
+	fn test_counting() {
+		let values = (0..16).map(value).collect::<Vec<_>>();
+		let mut db = InMemoryBackend::<Construct>::default();
+		let meter = HashMeter::with_budget(20);
+		let root = vector_tree(&values, &mut db, Some(32)).unwrap();
+		assert_eq!(meter.hashes(), 16);
+		assert!(!meter.is_exceeded());
+
+		let mut expected_db = InMemoryBackend::<InheritedDigestConstruct<Sha256>>::default();
+		assert_eq!(root, vector_tree(&values, &mut expected_db, Some(32)).unwrap());
+		assert_eq!(db.as_ref().len(), expected_db.as_ref().len());
+
+		let mut vector = Vector::<Owned, Construct>::create_from(&mut db, &values, Some(32)).unwrap();
+		let meter = HashMeter::with_budget(2);
+		vector.set(&mut db, 3, value(42)).unwrap();
+		assert_eq!(meter.hashes(), 5);
+		assert!(meter.is_exceeded());
+		assert_eq!(meter.remaining(), Some(0));
+		assert_eq!(meter.finish("vector"), 5);
+		vector.drop(&mut db).unwrap();
+	}
+}
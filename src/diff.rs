@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+
+use crate::{Backend, Construct, ReadBackend, Error, Index};
+
+/// Leaf differing between two trees.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DiffEntry<V> {
+	/// Generalized index of the leaf.
+	pub index: Index,
+	/// Value in the first tree.
+	pub a: V,
+	/// Value in the second tree.
+	pub b: V,
+}
+
+/// Differing leaves of two trees in the given backend.
+pub type DiffEntries<DB> = Vec<DiffEntry<<<DB as Backend>::Construct as Construct>::Value>>;
+
+/// Walk two trees simultaneously, skipping identical subtrees by hash,
+/// and return the differing leaves ordered from left to right. When one
+/// tree has a leaf where the other has an intermediate node, the leaf
+/// and the intermediate node's hash are returned at that index.
+pub fn diff<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root_a: &<DB::Construct as Construct>::Value,
+	root_b: &<DB::Construct as Construct>::Value,
+) -> Result<DiffEntries<DB>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: Eq,
+{
+	let mut ret = Vec::new();
+
+	let mut stack = Vec::new();
+	stack.push((Index::root(), root_a.clone(), root_b.clone()));
+	while let Some((index, a, b)) = stack.pop() {
+		if a == b {
+			continue
+		}
+
+		match (db.get(&a)?, db.get(&b)?) {
+			(Some((a_left, a_right)), Some((b_left, b_right))) => {
+				stack.push((index.right(), a_right, b_right));
+				stack.push((index.left(), a_left, b_left));
+			},
+			_ => ret.push(DiffEntry { index, a, b }),
+		}
+	}
+
+	Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_diff() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut a = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		let mut b = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..8 {
+			a.push(&mut db, value(i)).unwrap();
+			b.push(&mut db, value(i)).unwrap();
+		}
+		assert_eq!(diff(&mut db, &a.root(), &b.root()).unwrap(), Vec::new());
+
+		b.set(&mut db, 6, value(60)).unwrap();
+		b.set(&mut db, 1, value(10)).unwrap();
+		assert_eq!(diff(&mut db, &a.root(), &b.root()).unwrap(), vec![
+			DiffEntry { index: Index::from_depth(1, 3), a: value(1), b: value(10) },
+			DiffEntry { index: Index::from_depth(6, 3), a: value(6), b: value(60) },
+		]);
+	}
+}
@@ -0,0 +1,92 @@
+use alloc::vec::Vec;
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::{Construct, ReadBackend, Error};
+
+/// Number of leading bytes shown for an intermediate node.
+const HASH_PREFIX_LEN: usize = 4;
+
+fn write_hex(out: &mut String, bytes: &[u8]) {
+	out.push_str("0x");
+	for byte in bytes {
+		let _ = write!(out, "{:02x}", byte);
+	}
+}
+
+/// Render the tree under `root` as Graphviz DOT, down to `max_depth`.
+/// Nodes are named by their generalized index. Intermediate nodes show
+/// the leading bytes of their hash, and leaves show their value with
+/// trailing zero bytes omitted. Intermediate nodes at `max_depth` are
+/// drawn dashed, as their subtrees are not rendered.
+pub fn to_dot<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	max_depth: usize,
+) -> Result<String, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]>,
+{
+	let mut out = String::new();
+	out.push_str("digraph tree {\n");
+
+	let mut stack = Vec::new();
+	stack.push((1usize, 0usize, root.clone()));
+	while let Some((index, depth, value)) = stack.pop() {
+		let bytes = value.as_ref();
+		let _ = write!(out, "\tn{} [label=\"{}: ", index, index);
+
+		match db.get(&value)? {
+			Some((left, right)) => {
+				write_hex(&mut out, &bytes[..core::cmp::min(bytes.len(), HASH_PREFIX_LEN)]);
+				if depth < max_depth {
+					out.push_str("..\"];\n");
+					let _ = writeln!(out, "\tn{} -> n{};", index, index * 2);
+					let _ = writeln!(out, "\tn{} -> n{};", index, index * 2 + 1);
+					stack.push((index * 2 + 1, depth + 1, right));
+					stack.push((index * 2, depth + 1, left));
+				} else {
+					out.push_str("..\", style=dashed];\n");
+				}
+			},
+			None => {
+				let len = bytes.iter().rposition(|byte| *byte != 0).map(|i| i + 1).unwrap_or(1);
+				write_hex(&mut out, &bytes[..core::cmp::min(bytes.len(), len)]);
+				out.push_str("\", shape=box];\n");
+			},
+		}
+	}
+
+	out.push_str("}\n");
+	Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_to_dot() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 1..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let dot = to_dot(&mut db, &vector.root(), 2).unwrap();
+		assert!(dot.starts_with("digraph tree {\n\tn1 [label=\"1: 0x"));
+		assert!(dot.contains("\tn1 -> n2;\n\tn1 -> n3;\n"));
+		assert!(dot.contains("\tn4 [label=\"4: 0x01\", shape=box];\n"));
+		assert!(dot.contains("\tn7 [label=\"7: 0x04\", shape=box];\n"));
+		assert!(dot.ends_with("}\n"));
+
+		let truncated = to_dot(&mut db, &vector.root(), 1).unwrap();
+		assert!(truncated.contains("..\", style=dashed];\n"));
+		assert!(!truncated.contains("n4"));
+	}
+}
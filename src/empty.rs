@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::OnceLock as OnceCell;
+#[cfg(not(feature = "std"))]
+use core::cell::OnceCell;
+
+use crate::Construct;
+
+/// Number of depths kept in the empty value table of each construct.
+pub(crate) const EMPTY_TABLE_LEN: usize = 65;
+
+/// Empty values of a construct from the bottom up to `EMPTY_TABLE_LEN`,
+/// with the depths above the bottom sorted by value.
+#[derive(Clone)]
+pub(crate) struct EmptyTable<V> {
+	values: Vec<V>,
+	sorted: Vec<usize>,
+}
+
+impl<V: Clone + Default + AsRef<[u8]>> EmptyTable<V> {
+	pub fn new<C: Construct<Value=V>>() -> Self {
+		let mut values = Vec::with_capacity(EMPTY_TABLE_LEN);
+		values.push(V::default());
+		for depth in 1..EMPTY_TABLE_LEN {
+			let value = C::intermediate_of(&values[depth - 1], &values[depth - 1]);
+			values.push(value);
+		}
+
+		let mut sorted = (1..EMPTY_TABLE_LEN).collect::<Vec<_>>();
+		sorted.sort_by(|a, b| values[*a].as_ref().cmp(values[*b].as_ref()));
+		Self { values, sorted }
+	}
+
+	/// Get the empty value at a depth below `EMPTY_TABLE_LEN`.
+	pub fn at(&self, depth_to_bottom: usize) -> Option<V> {
+		self.values.get(depth_to_bottom).cloned()
+	}
+
+	/// Get the children of `value` if it is an empty value above the
+	/// bottom.
+	pub fn children(&self, value: &V) -> Option<(V, V)> {
+		self.sorted.binary_search_by(|depth| self.values[*depth].as_ref().cmp(value.as_ref()))
+			.ok()
+			.map(|i| {
+				let child = self.values[self.sorted[i] - 1].clone();
+				(child.clone(), child)
+			})
+	}
+}
+
+/// Empty values of the construct of a backend, built on first use and
+/// kept for as long as the backend. Backends return it from
+/// `Backend::empty_values`, and constructs with inherited empty values
+/// read their empty subtrees from it.
+pub struct EmptyValues<V>(OnceCell<EmptyTable<V>>);
+
+impl<V> EmptyValues<V> {
+	/// Create empty values that are not built yet.
+	pub fn new() -> Self {
+		Self(OnceCell::new())
+	}
+}
+
+impl<V: Clone + Default + AsRef<[u8]>> EmptyValues<V> {
+	/// Get the table of construct `C`, building it on first use. Only
+	/// the construct of the backend owning these values builds it.
+	pub(crate) fn table<C: Construct<Value=V>>(&self) -> &EmptyTable<V> {
+		self.0.get_or_init(EmptyTable::new::<C>)
+	}
+}
+
+impl<V> Default for EmptyValues<V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<V: Clone> Clone for EmptyValues<V> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<V> fmt::Debug for EmptyValues<V> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("EmptyValues").field(&self.0.get().is_some()).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Backend, Vector, Owned, Tree};
+
+	use sha2::{Sha256, Sha512};
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_empty_table() {
+		let values = EmptyValues::new();
+		let table = values.table::<Construct>();
+		assert!(core::ptr::eq(table, values.table::<Construct>()));
+
+		let mut current = value(0);
+		for depth in 0..EMPTY_TABLE_LEN {
+			assert_eq!(table.at(depth), Some(current));
+			let parent = <Construct as crate::Construct>::intermediate_of(&current, &current);
+			assert_eq!(table.children(&parent), (depth + 1 < EMPTY_TABLE_LEN).then_some((current, current)));
+			current = parent;
+		}
+		assert_eq!(table.at(EMPTY_TABLE_LEN), None);
+		assert_eq!(table.children(&value(0)), None);
+		assert_eq!(table.children(&value(1)), None);
+	}
+
+	#[test]
+	fn test_empty_values_per_backend() {
+		let values = EmptyValues::new();
+		assert_eq!(format!("{:?}", values), "EmptyValues(false)");
+		let cloned = values.clone();
+		values.table::<Construct>();
+		assert_eq!(format!("{:?}", values), "EmptyValues(true)");
+		assert_eq!(format!("{:?}", cloned), "EmptyValues(false)");
+		assert_eq!(format!("{:?}", values.clone()), "EmptyValues(true)");
+
+		let wide = EmptyValues::new();
+		let table = wide.table::<InheritedDigestConstruct<Sha512>>();
+		assert_eq!(table.at(0), Some(Default::default()));
+		assert_eq!(table.at(1).map(|v| v.len()), Some(64));
+	}
+
+	#[test]
+	fn test_without_empty_values() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let vector = Vector::<Owned, Construct>::create(&mut db, 1 << 40, None).unwrap();
+		assert_eq!(db.as_ref().len(), 2);
+		assert_eq!(<Construct as crate::Construct>::lazy_empty_at(&db, 40), Some(vector.root()));
+
+		// Reads through a `RefCell` see no empty values, and rebuild
+		// the table to find the virtual empty subtree.
+		let cell = core::cell::RefCell::new(db);
+		assert!(cell.empty_values().is_none());
+		assert_eq!(<Construct as crate::Construct>::lazy_empty_at(&&cell, 40), None);
+		assert_eq!(vector.get(&mut &cell, (1 << 40) - 1).unwrap(), value(0));
+
+		let mut db = cell.into_inner();
+		vector.drop(&mut db).unwrap();
+		assert_eq!(db.as_ref().len(), 1);
+	}
+}
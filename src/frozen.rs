@@ -0,0 +1,237 @@
+use core::marker::PhantomData;
+use core::cmp::Ordering;
+use alloc::vec::Vec;
+use alloc::collections::BTreeMap;
+
+use crate::{Construct, Backend, ReadBackend, RefReadBackend, Error, EmptyValues};
+
+const MAGIC: &[u8; 8] = b"bmfrozen";
+const HEADER_LEN: usize = 20;
+
+/// Export all nodes reachable from the given roots into a frozen
+/// snapshot, to be served by `FrozenBackend`. Nodes are stored as
+/// fixed-size records sorted by key.
+pub fn export<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	roots: &[<DB::Construct as Construct>::Value]
+) -> Result<Vec<u8>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]>,
+{
+	let mut nodes = BTreeMap::<Vec<u8>, Vec<u8>>::new();
+	let mut value_len = None;
+
+	let mut stack = roots.to_vec();
+	while let Some(key) = stack.pop() {
+		if nodes.contains_key(key.as_ref()) {
+			continue
+		}
+
+		if let Some((left, right)) = db.get(&key)? {
+			for value in &[&key, &left, &right] {
+				if *value_len.get_or_insert(value.as_ref().len()) != value.as_ref().len() {
+					return Err(Error::InvalidParameter)
+				}
+			}
+
+			let mut record = Vec::with_capacity(left.as_ref().len() * 2);
+			record.extend_from_slice(left.as_ref());
+			record.extend_from_slice(right.as_ref());
+			nodes.insert(key.as_ref().to_vec(), record);
+
+			stack.push(left);
+			stack.push(right);
+		}
+	}
+
+	let value_len = value_len.unwrap_or(0);
+	let mut ret = Vec::with_capacity(HEADER_LEN + nodes.len() * value_len * 3);
+	ret.extend_from_slice(MAGIC);
+	ret.extend_from_slice(&(value_len as u32).to_le_bytes());
+	ret.extend_from_slice(&(nodes.len() as u64).to_le_bytes());
+	for (key, record) in nodes {
+		ret.extend_from_slice(&key);
+		ret.extend_from_slice(&record);
+	}
+
+	Ok(ret)
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+/// Frozen backend error.
+pub enum FrozenBackendError {
+	/// The snapshot header is invalid.
+	InvalidHeader,
+	/// The snapshot length does not match its header.
+	InvalidLength,
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for FrozenBackendError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{:?}", self)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FrozenBackendError { }
+
+/// Read-only merkle database serving nodes from a snapshot produced by
+/// `export`. Lookups are binary searches over the snapshot bytes, so
+/// the snapshot can be memory-mapped with no startup cost.
+pub struct FrozenBackend<C: Construct, B> {
+	bytes: B,
+	value_len: usize,
+	count: usize,
+	empty: EmptyValues<C::Value>,
+	_marker: PhantomData<C>,
+}
+
+impl<C: Construct, B: AsRef<[u8]>> FrozenBackend<C, B> {
+	/// Create a new backend from snapshot bytes.
+	pub fn new(bytes: B) -> Result<Self, FrozenBackendError> {
+		let raw = bytes.as_ref();
+		if raw.len() < HEADER_LEN || &raw[..8] != MAGIC {
+			return Err(FrozenBackendError::InvalidHeader)
+		}
+
+		let mut value_len = [0u8; 4];
+		value_len.copy_from_slice(&raw[8..12]);
+		let value_len = u32::from_le_bytes(value_len) as usize;
+		let mut count = [0u8; 8];
+		count.copy_from_slice(&raw[12..20]);
+		let count = u64::from_le_bytes(count) as usize;
+
+		if raw.len() != HEADER_LEN + count * value_len * 3 {
+			return Err(FrozenBackendError::InvalidLength)
+		}
+
+		Ok(Self { bytes, value_len, count, empty: EmptyValues::new(), _marker: PhantomData })
+	}
+
+	/// Number of nodes in the snapshot.
+	pub fn len(&self) -> usize {
+		self.count
+	}
+
+	/// Whether the snapshot contains no nodes.
+	pub fn is_empty(&self) -> bool {
+		self.count == 0
+	}
+
+	fn record(&self, index: usize) -> &[u8] {
+		let record_len = self.value_len * 3;
+		let start = HEADER_LEN + index * record_len;
+		&self.bytes.as_ref()[start..(start + record_len)]
+	}
+}
+
+#[cfg(feature = "mmap")]
+impl<C: Construct> FrozenBackend<C, memmap2::Mmap> {
+	/// Open a snapshot file, memory-mapping it.
+	pub fn open<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+		let file = std::fs::File::open(path)?;
+		let map = unsafe { memmap2::Mmap::map(&file)? };
+		Self::new(map).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+	}
+}
+
+impl<C: Construct, B> Backend for FrozenBackend<C, B> {
+	type Construct = C;
+	type Error = FrozenBackendError;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		Some(&self.empty)
+	}
+}
+
+impl<C: Construct, B: AsRef<[u8]>> ReadBackend for FrozenBackend<C, B> where
+	C::Value: AsRef<[u8]> + AsMut<[u8]>,
+{
+	fn get(&mut self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		self.get_ref(key)
+	}
+}
+
+impl<C: Construct, B: AsRef<[u8]>> RefReadBackend for FrozenBackend<C, B> where
+	C::Value: AsRef<[u8]> + AsMut<[u8]>,
+{
+	fn get_ref(&self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		let key = key.as_ref();
+		if key.len() != self.value_len {
+			return Ok(None)
+		}
+
+		let (mut low, mut high) = (0, self.count);
+		while low < high {
+			let mid = low + (high - low) / 2;
+			let record = self.record(mid);
+			match record[..self.value_len].cmp(key) {
+				Ordering::Less => low = mid + 1,
+				Ordering::Greater => high = mid,
+				Ordering::Equal => {
+					let mut left = C::Value::default();
+					let mut right = C::Value::default();
+					left.as_mut().copy_from_slice(&record[self.value_len..(self.value_len * 2)]);
+					right.as_mut().copy_from_slice(&record[(self.value_len * 2)..]);
+					return Ok(Some((left, right)))
+				},
+			}
+		}
+
+		Ok(None)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Dangling, Tree, Leak};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_export() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let mut other = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		other.push(&mut db, value(100)).unwrap();
+		other.push(&mut db, value(101)).unwrap();
+
+		let bytes = export(&mut db, &[vector.root()]).unwrap();
+		let mut frozen = FrozenBackend::<Construct, _>::new(bytes).unwrap();
+		let frozen_vector = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		for i in 0..9 {
+			assert_eq!(frozen_vector.get(&mut frozen, i).unwrap(), value(i as u8));
+		}
+		assert_eq!(frozen.get(&other.root()).unwrap(), None);
+
+		assert_eq!(FrozenBackend::<Construct, _>::new(&b"bmfrozen"[..]).err(), Some(FrozenBackendError::InvalidHeader));
+	}
+	#[test]
+	#[cfg(feature = "mmap")]
+	fn test_open() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let path = std::env::temp_dir().join(format!("bm-frozen-{}", std::process::id()));
+		std::fs::write(&path, export(&mut db, &[vector.root()]).unwrap()).unwrap();
+
+		let mut frozen = FrozenBackend::<Construct, _>::open(&path).unwrap();
+		let frozen_vector = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		for i in 0..5 {
+			assert_eq!(frozen_vector.get(&mut frozen, i).unwrap(), value(i as u8));
+		}
+
+		drop(frozen);
+		std::fs::remove_file(&path).unwrap();
+	}
+}
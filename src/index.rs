@@ -1,3 +1,5 @@
+use core::fmt;
+use core::str::FromStr;
 use alloc::vec::Vec;
 
 /// Merkle selection.
@@ -34,32 +36,78 @@ impl IndexRoute {
 	}
 }
 
-/// Raw merkle index.
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub struct Index(usize);
+/// Number of 64-bit limbs of an index.
+const LIMBS: usize = 5;
+
+/// Maximum depth addressable by an index, enough for sparse trees keyed
+/// by 256-bit hashes.
+pub const MAX_INDEX_DEPTH: usize = LIMBS * 64 - 1;
+
+/// Raw merkle index. It is stored as a fixed-width generalized index of
+/// little-endian limbs, so that depths up to `MAX_INDEX_DEPTH` can be
+/// addressed.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Index([u64; LIMBS]);
 
 impl Index {
 	/// Root merkle index.
 	pub const fn root() -> Self {
-		Self(1)
+		let mut limbs = [0; LIMBS];
+		limbs[0] = 1;
+		Self(limbs)
 	}
 
 	/// Get left child of current index.
 	pub const fn left(&self) -> Self {
-		Self(2 * self.0)
+		assert!(self.depth() < MAX_INDEX_DEPTH, "index depth overflowed");
+
+		let mut limbs = [0; LIMBS];
+		let mut carry = 0;
+		let mut i = 0;
+		while i < LIMBS {
+			limbs[i] = (self.0[i] << 1) | carry;
+			carry = self.0[i] >> 63;
+			i += 1;
+		}
+		Self(limbs)
 	}
 
 	/// Get right child of current index.
 	pub const fn right(&self) -> Self {
-		Self(2 * self.0 + 1)
+		let mut ret = self.left();
+		ret.0[0] |= 1;
+		ret
+	}
+
+	/// Depth of current index, where root is considered depth 0.
+	pub const fn depth(&self) -> usize {
+		let mut i = LIMBS;
+		while i > 0 {
+			i -= 1;
+			if self.0[i] != 0 {
+				return i * 64 + 63 - self.0[i].leading_zeros() as usize
+			}
+		}
+		0
+	}
+
+	/// Whether current index selects the right child at given depth,
+	/// counting from the root at depth 0.
+	fn bit(&self, depth: usize) -> bool {
+		let bit = self.depth() - depth;
+		self.0[bit / 64] & (1 << (bit % 64)) != 0
 	}
 
 	/// Get the parent of current merkle index.
 	pub fn parent(&self) -> Option<Self> {
-		if self.0 == 1 {
+		if *self == Self::root() {
 			None
 		} else {
-			Some(Self(self.0 / 2))
+			let mut limbs = [0; LIMBS];
+			for (i, limb) in limbs.iter_mut().enumerate() {
+				*limb = (self.0[i] >> 1) | self.0.get(i + 1).map(|next| next << 63).unwrap_or(0);
+			}
+			Some(Self(limbs))
 		}
 	}
 
@@ -82,46 +130,117 @@ impl Index {
 		if value == 0 {
 			None
 		} else {
-			Some(Self(value))
+			let mut limbs = [0; LIMBS];
+			limbs[0] = value as u64;
+			Some(Self(limbs))
 		}
 	}
 
 	/// From zero-based index.
 	pub fn from_zero(value: usize) -> Self {
-		Self(value + 1)
+		let mut limbs = [0; LIMBS];
+		let (value, carry) = (value as u64).overflowing_add(1);
+		limbs[0] = value;
+		limbs[1] = carry as u64;
+		Self(limbs)
 	}
 
 	/// From depth.
-	pub fn from_depth(index: usize, depth: usize) -> Self {
-		Self((1 << depth) + index)
+	pub fn from_depth(index: u64, depth: usize) -> Self {
+		assert!(depth <= MAX_INDEX_DEPTH, "index depth overflowed");
+
+		let mut limbs = [0u64; LIMBS];
+		limbs[depth / 64] = 1 << (depth % 64);
+		let mut carry = index;
+		for limb in limbs.iter_mut() {
+			let (sum, overflowed) = limb.overflowing_add(carry);
+			*limb = sum;
+			carry = overflowed as u64;
+		}
+		Self(limbs)
+	}
+
+	/// From selections starting from the root, such as the bits of a
+	/// hash key of a sparse tree.
+	pub fn from_selections<I: IntoIterator<Item=IndexSelection>>(selections: I) -> Self {
+		let mut current = Self::root();
+		for selection in selections {
+			current = match selection {
+				IndexSelection::Left => current.left(),
+				IndexSelection::Right => current.right(),
+			};
+		}
+		current
+	}
+
+	/// Convert into a one-based index, if it fits.
+	pub fn to_one(&self) -> Option<usize> {
+		if self.0[1..].iter().any(|limb| *limb != 0) || self.0[0] > usize::MAX as u64 {
+			None
+		} else {
+			Some(self.0[0] as usize)
+		}
+	}
+
+	/// From a generalized index integer.
+	pub fn from_u128(value: u128) -> Option<Self> {
+		if value == 0 {
+			None
+		} else {
+			let mut limbs = [0; LIMBS];
+			limbs[0] = value as u64;
+			limbs[1] = (value >> 64) as u64;
+			Some(Self(limbs))
+		}
+	}
+
+	/// Convert into a generalized index integer, if it fits.
+	pub fn to_u128(&self) -> Option<u128> {
+		if self.0[2..].iter().any(|limb| *limb != 0) {
+			None
+		} else {
+			Some(self.0[0] as u128 | (self.0[1] as u128) << 64)
+		}
+	}
+
+	/// Iterate over selections from the root to current index.
+	pub fn path(&self) -> IndexPath {
+		IndexPath { index: *self, depth: 1 }
 	}
 
 	/// Get selections from current index.
 	pub fn route(&self) -> IndexRoute {
-		let mut value = self.0;
-		let mut selections = Vec::<IndexSelection>::new();
+		if *self == Self::root() {
+			IndexRoute::Root
+		} else {
+			IndexRoute::Select(self.path().collect())
+		}
+	}
 
-		loop {
-			if value >> 1 == 0 {
-				debug_assert!(value == 1);
+	/// Get the sibling of current index, the other child of its parent.
+	pub fn sibling(&self) -> Option<Self> {
+		self.parent().map(|_| {
+			let mut ret = *self;
+			ret.0[0] ^= 1;
+			ret
+		})
+	}
 
-				if selections.is_empty() {
-					return IndexRoute::Root
-				} else {
-					selections.reverse();
-					return IndexRoute::Select(selections)
-				}
-			}
+	/// Get the ancestor of current index at given depth, or itself if
+	/// the depth is its own.
+	pub fn ancestor(&self, depth: usize) -> Option<Self> {
+		if depth > self.depth() {
+			return None
+		}
 
-			let sel = value & 0b1;
-			if sel == 0 {
-				selections.push(IndexSelection::Left)
-			} else {
-				selections.push(IndexSelection::Right)
-			}
+		Some(Self::from_selections(self.path().take(depth)))
+	}
 
-			value = value >> 1;
-		}
+	/// Get the deepest common ancestor of current index and the other.
+	pub fn common_ancestor(&self, other: &Index) -> Index {
+		Self::from_selections(self.path().zip(other.path())
+			.take_while(|(a, b)| a == b)
+			.map(|(a, _)| a))
 	}
 
 	/// Get sub from current index.
@@ -145,6 +264,128 @@ impl Index {
 	}
 }
 
+/// Iterator over selections from the root to an index.
+pub struct IndexPath {
+	index: Index,
+	depth: usize,
+}
+
+impl Iterator for IndexPath {
+	type Item = IndexSelection;
+
+	fn next(&mut self) -> Option<IndexSelection> {
+		if self.depth > self.index.depth() {
+			return None
+		}
+
+		let selection = if self.index.bit(self.depth) {
+			IndexSelection::Right
+		} else {
+			IndexSelection::Left
+		};
+		self.depth += 1;
+		Some(selection)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = (self.index.depth() + 1).saturating_sub(self.depth);
+		(remaining, Some(remaining))
+	}
+}
+
+impl ExactSizeIterator for IndexPath { }
+
+/// Error when parsing an index.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ParseIndexError {
+	/// The string is neither a `0b` prefixed binary generalized index,
+	/// nor a `root` prefixed path of `left` and `right` selections.
+	Invalid,
+	/// The index is deeper than `MAX_INDEX_DEPTH`.
+	TooDeep,
+}
+
+/// Formats as the binary generalized index, such as `0b1011`.
+impl fmt::Display for Index {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("0b1")?;
+		for selection in self.path() {
+			f.write_str(match selection {
+				IndexSelection::Left => "0",
+				IndexSelection::Right => "1",
+			})?;
+		}
+		Ok(())
+	}
+}
+
+/// Parses a binary generalized index such as `0b1011`, or a path such
+/// as `root/left/right`.
+impl FromStr for Index {
+	type Err = ParseIndexError;
+
+	fn from_str(s: &str) -> Result<Self, ParseIndexError> {
+		let selections = if let Some(bits) = s.strip_prefix("0b") {
+			let bits = bits.trim_start_matches('0');
+			let mut chars = bits.chars();
+			if chars.next() != Some('1') {
+				return Err(ParseIndexError::Invalid)
+			}
+			chars.map(|c| match c {
+				'0' => Ok(IndexSelection::Left),
+				'1' => Ok(IndexSelection::Right),
+				_ => Err(ParseIndexError::Invalid),
+			}).collect::<Result<Vec<_>, _>>()?
+		} else {
+			let mut parts = s.split('/');
+			if parts.next() != Some("root") {
+				return Err(ParseIndexError::Invalid)
+			}
+			parts.map(|part| match part {
+				"left" => Ok(IndexSelection::Left),
+				"right" => Ok(IndexSelection::Right),
+				_ => Err(ParseIndexError::Invalid),
+			}).collect::<Result<Vec<_>, _>>()?
+		};
+
+		if selections.len() > MAX_INDEX_DEPTH {
+			return Err(ParseIndexError::TooDeep)
+		}
+
+		Ok(Self::from_selections(selections))
+	}
+}
+
+impl fmt::Debug for Index {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Index(0x")?;
+		let mut started = false;
+		for limb in self.0.iter().rev() {
+			if started {
+				write!(f, "{:016x}", limb)?;
+			} else if *limb != 0 {
+				write!(f, "{:x}", limb)?;
+				started = true;
+			}
+		}
+		f.write_str(")")
+	}
+}
+
+/// Orders as generalized indexes, so that shallower indexes come first
+/// and indexes at the same depth are ordered from left to right.
+impl Ord for Index {
+	fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+		self.0.iter().rev().cmp(other.0.iter().rev())
+	}
+}
+
+impl PartialOrd for Index {
+	fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -154,4 +395,56 @@ mod tests {
 		assert!(Index::root().left().has_descendant(&Index::root().left().right().left().right().right()));
 		assert!(!Index::root().left().has_descendant(&Index::root().right().right().left().right().right()));
 	}
+
+	#[test]
+	fn test_wide_index() {
+		let selections = (0..256).map(|i| if i % 3 == 0 { IndexSelection::Right } else { IndexSelection::Left })
+			.collect::<Vec<_>>();
+		let index = Index::from_selections(selections.clone());
+		assert_eq!(index.depth(), 256);
+		assert_eq!(index.route(), IndexRoute::Select(selections.clone()));
+		assert_eq!(index.to_one(), None);
+		assert_eq!(index.parent(), Some(Index::from_selections(selections[..255].to_vec())));
+		assert!(Index::root().right().has_descendant(&index));
+
+		assert_eq!(Index::from_depth(5, 3).to_one(), Some(13));
+		assert_eq!(Index::from_depth(0, 64).depth(), 64);
+		assert_eq!(Index::from_depth(0, 64).parent(), Some(Index::from_depth(0, 63)));
+		assert_eq!(alloc::format!("{:?}", Index::from_depth(1, 64)), "Index(0x10000000000000001)");
+		assert!(Index::from_depth(0, 64) > Index::from_depth(7, 3));
+		assert!(Index::root().right() < Index::root().left().left());
+	}
+
+	#[test]
+	fn test_path_utilities() {
+		let index = Index::root().left().right().right();
+		assert_eq!(index.path().collect::<Vec<_>>(),
+				   vec![IndexSelection::Left, IndexSelection::Right, IndexSelection::Right]);
+		assert_eq!(index.path().len(), 3);
+		assert_eq!(Index::root().path().next(), None);
+
+		assert_eq!(index.to_u128(), Some(0b1011));
+		assert_eq!(Index::from_u128(0b1011), Some(index));
+		assert_eq!(Index::from_u128(0), None);
+		assert_eq!(Index::from_u128(1 << 100).unwrap().depth(), 100);
+
+		assert_eq!(alloc::format!("{}", index), "0b1011");
+		assert_eq!(alloc::format!("{}", Index::root()), "0b1");
+		assert_eq!("0b1011".parse(), Ok(index));
+		assert_eq!("root/left/right/right".parse(), Ok(index));
+		assert_eq!("root".parse(), Ok(Index::root()));
+		assert_eq!("0b0".parse::<Index>(), Err(ParseIndexError::Invalid));
+		assert_eq!("left/right".parse::<Index>(), Err(ParseIndexError::Invalid));
+		assert_eq!("root/up".parse::<Index>(), Err(ParseIndexError::Invalid));
+		let deep = alloc::format!("0b1{}", "0".repeat(MAX_INDEX_DEPTH + 1));
+		assert_eq!(deep.parse::<Index>(), Err(ParseIndexError::TooDeep));
+
+		assert_eq!(index.sibling(), Some(Index::root().left().right().left()));
+		assert_eq!(Index::root().sibling(), None);
+		assert_eq!(index.ancestor(1), Some(Index::root().left()));
+		assert_eq!(index.ancestor(3), Some(index));
+		assert_eq!(index.ancestor(4), None);
+		assert_eq!(index.common_ancestor(&Index::root().left().left()), Index::root().left());
+		assert_eq!(index.common_ancestor(&Index::root().right()), Index::root());
+	}
 }
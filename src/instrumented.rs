@@ -0,0 +1,156 @@
+use crate::{Backend, ReadBackend, WriteBackend, Construct, NodePair, DecodeLimits, EmptyValues};
+
+/// Counters collected by `InstrumentedBackend`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct BackendStats {
+	/// Number of node reads.
+	pub reads: usize,
+	/// Number of node reads answered with a node.
+	pub hits: usize,
+	/// Number of writes, including rootify and unrootify.
+	pub writes: usize,
+	/// Number of hash invocations, one for each inserted node.
+	pub hashes: usize,
+	/// Number of nodes hinted through `prefetch`.
+	pub prefetched: usize,
+}
+
+/// Instrumented merkle database, counting accesses to the underlying
+/// database. With the `tracing` feature, every access is also emitted
+/// as a trace event.
+pub struct InstrumentedBackend<DB> {
+	db: DB,
+	stats: BackendStats,
+}
+
+impl<DB> InstrumentedBackend<DB> {
+	/// Create a new instrumented database.
+	pub fn new(db: DB) -> Self {
+		Self { db, stats: Default::default() }
+	}
+
+	/// Get a reference to the underlying database.
+	pub fn inner(&self) -> &DB {
+		&self.db
+	}
+
+	/// Convert the backend into the underlying database.
+	pub fn into_inner(self) -> DB {
+		self.db
+	}
+
+	/// Counters collected so far.
+	pub fn stats(&self) -> BackendStats {
+		self.stats
+	}
+
+	/// Reset all counters.
+	pub fn reset(&mut self) {
+		self.stats = Default::default();
+	}
+}
+
+impl<DB: Backend> Backend for InstrumentedBackend<DB> {
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.db.empty_values()
+	}
+}
+
+impl<DB: ReadBackend> ReadBackend for InstrumentedBackend<DB> {
+	fn get(
+		&mut self,
+		key: &<DB::Construct as Construct>::Value
+	) -> Result<Option<NodePair<DB>>, Self::Error> {
+		let value = self.db.get(key)?;
+		self.stats.reads += 1;
+		if value.is_some() {
+			self.stats.hits += 1;
+		}
+		#[cfg(feature = "tracing")]
+		tracing::trace!(target: "bm", found = value.is_some(), "get");
+		Ok(value)
+	}
+
+	fn prefetch(&mut self, keys: &[<DB::Construct as Construct>::Value]) {
+		#[cfg(feature = "tracing")]
+		tracing::trace!(target: "bm", keys = keys.len(), "prefetch");
+		self.stats.prefetched += keys.len();
+		self.db.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.db.decode_limits()
+	}
+}
+
+impl<DB: WriteBackend> WriteBackend for InstrumentedBackend<DB> {
+	fn rootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.db.rootify(key)?;
+		self.stats.writes += 1;
+		#[cfg(feature = "tracing")]
+		tracing::trace!(target: "bm", "rootify");
+		Ok(())
+	}
+
+	fn unrootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.db.unrootify(key)?;
+		self.stats.writes += 1;
+		#[cfg(feature = "tracing")]
+		tracing::trace!(target: "bm", "unrootify");
+		Ok(())
+	}
+
+	fn insert(
+		&mut self,
+		key: <DB::Construct as Construct>::Value,
+		value: NodePair<DB>
+	) -> Result<(), Self::Error> {
+		self.db.insert(key, value)?;
+		self.stats.writes += 1;
+		self.stats.hashes += 1;
+		#[cfg(feature = "tracing")]
+		tracing::trace!(target: "bm", "insert");
+		Ok(())
+	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		self.db.begin_batch()
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		self.db.commit_batch()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_stats() {
+		let mut db = InstrumentedBackend::new(InMemoryBackend::<Construct>::default());
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..4 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		assert!(db.stats().hashes > 0);
+
+		db.reset();
+		assert_eq!(db.stats(), BackendStats::default());
+		assert_eq!(vector.get(&mut db, 2).unwrap(), value(2));
+		assert_eq!(db.stats(), BackendStats { reads: 2, hits: 2, writes: 0, hashes: 0, prefetched: 0 });
+
+		vector.set(&mut db, 2, value(5)).unwrap();
+		let stats = db.stats();
+		assert_eq!((stats.hashes, stats.writes), (2, 4));
+	}
+}
@@ -0,0 +1,174 @@
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeSet as Set;
+#[cfg(feature = "std")]
+use std::collections::HashSet as Set;
+use core::hash::Hash;
+
+use crate::{Construct, ReadBackend, WriteBackend, NoopBackend, Error};
+
+/// Report of an integrity check.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IntegrityReport<V> {
+	/// Number of distinct intermediate nodes checked.
+	pub nodes: usize,
+	/// Intermediate nodes whose children do not hash to their key.
+	pub mismatched: Vec<V>,
+	/// Nodes above the leaf depth missing from the backend.
+	pub missing: Vec<V>,
+	/// Missing nodes that are empty subtrees, with their depth to the
+	/// bottom, so that they can be derived again.
+	pub repairable: Vec<(V, usize)>,
+}
+
+impl<V> IntegrityReport<V> {
+	/// Whether no problem was found.
+	pub fn is_ok(&self) -> bool {
+		self.mismatched.is_empty() && self.missing.is_empty() && self.repairable.is_empty()
+	}
+}
+
+/// Check the tree under `root`, visiting each distinct intermediate
+/// node once and verifying that its children hash to its key. With the
+/// `depth` of the leaves, nodes above it missing from the backend are
+/// also reported. Virtual empty nodes of the construct are not missing.
+pub fn check_integrity<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	depth: Option<usize>,
+) -> Result<IntegrityReport<<DB::Construct as Construct>::Value>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	let mut report = IntegrityReport {
+		nodes: 0,
+		mismatched: Vec::new(),
+		missing: Vec::new(),
+		repairable: Vec::new(),
+	};
+	let mut visited = Set::new();
+
+	let mut stack = Vec::new();
+	stack.push((root.clone(), 0));
+	while let Some((key, current)) = stack.pop() {
+		if !visited.insert(key.clone()) {
+			continue
+		}
+
+		match db.get(&key)? {
+			Some((left, right)) => {
+				report.nodes += 1;
+				if DB::Construct::intermediate_of(&left, &right) != key {
+					report.mismatched.push(key);
+				}
+				stack.push((right, current + 1));
+				stack.push((left, current + 1));
+			},
+			None => {
+				let depth_to_bottom = match depth {
+					Some(depth) if current < depth => depth - current,
+					_ => continue,
+				};
+				if DB::Construct::empty_children(&*db, &key).is_some() {
+					continue
+				}
+
+				let empty = DB::Construct::empty_at(&mut NoopBackend::default(), depth_to_bottom)
+					.expect("Noop backend never fails in set; qed");
+				if empty == key {
+					report.repairable.push((key, depth_to_bottom));
+				} else {
+					report.missing.push(key);
+				}
+			},
+		}
+	}
+
+	Ok(report)
+}
+
+/// Check the tree under `root` as `check_integrity`, deriving missing
+/// empty subtrees again, and return the report after repair.
+pub fn repair_integrity<DB: WriteBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	depth: Option<usize>,
+) -> Result<IntegrityReport<<DB::Construct as Construct>::Value>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	let report = check_integrity(db, root, depth)?;
+	if report.repairable.is_empty() {
+		return Ok(report)
+	}
+
+	for (_, depth_to_bottom) in &report.repairable {
+		DB::Construct::empty_at(db, *depth_to_bottom)?;
+	}
+	check_integrity(db, root, depth)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	/// Digest construct storing its empty subtrees, without virtual
+	/// empty nodes.
+	struct Construct;
+
+	impl crate::Construct for Construct {
+		type Value = GenericArray<u8, typenum::U32>;
+
+		fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value {
+			InheritedDigestConstruct::<Sha256>::intermediate_of(left, right)
+		}
+
+		fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
+			db: &mut DB,
+			depth_to_bottom: usize
+		) -> Result<Self::Value, DB::Error> {
+			let mut current = Self::Value::default();
+			for _ in 0..depth_to_bottom {
+				let key = Self::intermediate_of(&current, &current);
+				db.insert(key, (current, current))?;
+				current = key;
+			}
+			Ok(current)
+		}
+	}
+
+	#[test]
+	fn test_integrity() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, Some(8)).unwrap();
+		for i in 1..3 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let root = vector.root();
+		let report = check_integrity(&mut db, &root, Some(3)).unwrap();
+		assert!(report.is_ok());
+		assert_eq!(report.nodes, 5);
+		assert!(db.unreachable(&[root]).is_empty());
+
+		let (left, right) = db.get(&root).unwrap().unwrap();
+		let mut partial = InMemoryBackend::<Construct>::default();
+		partial.populate(core::iter::once((root, (left, right))).collect());
+		let report = check_integrity(&mut partial, &root, Some(3)).unwrap();
+		assert_eq!(report.missing, vec![left]);
+		assert_eq!(report.repairable, vec![(right, 2)]);
+		assert_eq!(check_integrity(&mut partial, &root, None).unwrap().missing, vec![]);
+
+		let report = repair_integrity(&mut partial, &root, Some(3)).unwrap();
+		assert_eq!(report.missing, vec![left]);
+		assert!(report.repairable.is_empty());
+
+		let (old, right) = db.get(&left).unwrap().unwrap();
+		db.populate(core::iter::once((left, (value(42), right))).collect());
+		let report = check_integrity(&mut db, &root, Some(3)).unwrap();
+		assert_eq!(report.mismatched, vec![left]);
+		assert!(db.unreachable(&[root]).contains(&old));
+	}
+}
@@ -0,0 +1,502 @@
+use alloc::vec::Vec;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+use crate::Construct;
+use crate::refcount::{RefCountBackend, RefCountStore, RefCountEntry};
+
+/// Write operation on a key-value database.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum KeyValueOp {
+	/// Put a value at key.
+	Put(Vec<u8>, Vec<u8>),
+	/// Delete the value at key.
+	Delete(Vec<u8>),
+}
+
+/// Raw key-value database, used as persistent storage of merkle nodes.
+pub trait KeyValueDB {
+	/// Error type for database access.
+	type Error;
+
+	/// Get the value at key.
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+	/// Get the value at key, passing it by reference to `f`. Databases
+	/// able to borrow values can override this to avoid copying.
+	fn get_with<R, F: FnOnce(&[u8]) -> R>(&self, key: &[u8], f: F) -> Result<Option<R>, Self::Error> {
+		Ok(self.get(key)?.map(|value| f(&value)))
+	}
+	/// Apply all write operations atomically.
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error>;
+}
+
+/// Key-value database whose entries can be iterated, so that nodes no
+/// longer reachable can be swept by `compact`.
+pub trait IterableKeyValueDB: KeyValueDB {
+	/// Call `f` with every key and value of the database, in no
+	/// particular order.
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, f: F) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+/// In-memory key-value database error.
+pub enum InMemoryKeyValueDBError { }
+
+impl core::fmt::Display for InMemoryKeyValueDBError {
+	fn fmt(&self, _f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match *self { }
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InMemoryKeyValueDBError { }
+
+impl KeyValueDB for Map<Vec<u8>, Vec<u8>> {
+	type Error = InMemoryKeyValueDBError;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		Ok(Map::get(self, key).cloned())
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		for op in ops {
+			match op {
+				KeyValueOp::Put(key, value) => { self.insert(key, value); },
+				KeyValueOp::Delete(key) => { self.remove(&key); },
+			}
+		}
+		Ok(())
+	}
+}
+
+impl IterableKeyValueDB for Map<Vec<u8>, Vec<u8>> {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, mut f: F) -> Result<(), Self::Error> {
+		for (key, value) in self {
+			f(key, value);
+		}
+		Ok(())
+	}
+}
+
+/// Shared key-value database, so that several namespaces can be opened
+/// over one physical store.
+impl<D: KeyValueDB> KeyValueDB for Rc<RefCell<D>> {
+	type Error = D::Error;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.borrow().get(key)
+	}
+
+	fn get_with<R, F: FnOnce(&[u8]) -> R>(&self, key: &[u8], f: F) -> Result<Option<R>, Self::Error> {
+		self.borrow().get_with(key, f)
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		self.borrow_mut().write(ops)
+	}
+}
+
+impl<D: IterableKeyValueDB> IterableKeyValueDB for Rc<RefCell<D>> {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, f: F) -> Result<(), Self::Error> {
+		self.borrow().for_each_entry(f)
+	}
+}
+
+/// Key-value database storing all keys under a namespace, so that
+/// independent merkle databases can share one physical store. Each
+/// namespace keeps its own copy and reference count of nodes, so trees
+/// with overlapping node hashes are pruned without interfering with
+/// each other.
+pub struct NamespacedDB<D> {
+	db: D,
+	prefix: Vec<u8>,
+}
+
+impl<D> NamespacedDB<D> {
+	/// Create a new database over `db` under the given namespace. The
+	/// namespace is stored with its length, so that no namespace can
+	/// collide with another.
+	pub fn new(db: D, namespace: &[u8]) -> Self {
+		let mut prefix = (namespace.len() as u32).to_le_bytes().to_vec();
+		prefix.extend_from_slice(namespace);
+		Self { db, prefix }
+	}
+
+	/// Get the namespace.
+	pub fn namespace(&self) -> &[u8] {
+		&self.prefix[4..]
+	}
+
+	/// Get a reference to the underlying database.
+	pub fn db(&self) -> &D {
+		&self.db
+	}
+
+	/// Convert into the underlying database.
+	pub fn into_db(self) -> D {
+		self.db
+	}
+
+	fn key(&self, key: &[u8]) -> Vec<u8> {
+		let mut ret = self.prefix.clone();
+		ret.extend_from_slice(key);
+		ret
+	}
+}
+
+impl<D: KeyValueDB> KeyValueDB for NamespacedDB<D> {
+	type Error = D::Error;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.db.get(&self.key(key))
+	}
+
+	fn get_with<R, F: FnOnce(&[u8]) -> R>(&self, key: &[u8], f: F) -> Result<Option<R>, Self::Error> {
+		self.db.get_with(&self.key(key), f)
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let ops = ops.into_iter().map(|op| match op {
+			KeyValueOp::Put(key, value) => KeyValueOp::Put(self.key(&key), value),
+			KeyValueOp::Delete(key) => KeyValueOp::Delete(self.key(&key)),
+		}).collect();
+		self.db.write(ops)
+	}
+}
+
+impl<D: IterableKeyValueDB> IterableKeyValueDB for NamespacedDB<D> {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, mut f: F) -> Result<(), Self::Error> {
+		self.db.for_each_entry(|key, value| {
+			if let Some(key) = key.strip_prefix(self.prefix.as_slice()) {
+				f(key, value);
+			}
+		})
+	}
+}
+
+/// Decode a node stored in a key-value database, or `None` if the
+/// stored bytes are truncated or do not fit two values.
+pub(crate) fn decode_entry<V: AsRef<[u8]> + AsMut<[u8]> + Default>(raw: &[u8]) -> Option<RefCountEntry<V>> {
+	if raw.len() < 8 {
+		return None
+	}
+	let mut refcount = [0u8; 8];
+	refcount.copy_from_slice(&raw[..8]);
+	let refcount = u64::from_le_bytes(refcount) as usize;
+
+	let children = if raw.len() > 8 {
+		let mut left = V::default();
+		let mut right = V::default();
+		let half = left.as_ref().len();
+		if raw.len() - 8 != half * 2 {
+			return None
+		}
+		left.as_mut().copy_from_slice(&raw[8..(8 + half)]);
+		right.as_mut().copy_from_slice(&raw[(8 + half)..]);
+		Some((left, right))
+	} else {
+		None
+	};
+
+	Some(RefCountEntry { children, refcount })
+}
+
+type Overlay = Map<Vec<u8>, Option<Vec<u8>>>;
+
+/// Error of a refcount store over a key-value database.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum KeyValueStoreError<E> {
+	/// The underlying database failed.
+	Backend(E),
+	/// The entry stored at key is truncated or does not fit two values.
+	CorruptedEntry(Vec<u8>),
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for KeyValueStoreError<E> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self {
+			KeyValueStoreError::Backend(err) => write!(f, "database failed: {}", err),
+			KeyValueStoreError::CorruptedEntry(key) => write!(f, "corrupted entry at key {:02x?}", key),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for KeyValueStoreError<E> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			KeyValueStoreError::Backend(err) => Some(err),
+			KeyValueStoreError::CorruptedEntry(_) => None,
+		}
+	}
+}
+
+/// Refcount store over a key-value database. Each node is stored as
+/// its reference count in little endian, followed by its left and right
+/// child if it is an intermediate node.
+///
+/// Batches nest: writes of a batch begun inside another one are only
+/// applied, in a single database write, when the outermost batch is
+/// committed.
+pub struct KeyValueStore<D> {
+	db: D,
+	batch: Option<(Vec<KeyValueOp>, Overlay)>,
+	depth: usize,
+}
+
+impl<D> KeyValueStore<D> {
+	/// Create a new store over the given database.
+	pub fn new(db: D) -> Self {
+		Self { db, batch: None, depth: 0 }
+	}
+
+	/// Get a reference to the underlying database.
+	pub fn db(&self) -> &D {
+		&self.db
+	}
+
+	#[cfg(feature = "std")]
+	pub(crate) fn db_mut(&mut self) -> &mut D {
+		&mut self.db
+	}
+
+	/// Convert the store into the underlying database.
+	pub fn into_db(self) -> D {
+		self.db
+	}
+
+	/// Number of batches begun and not yet committed.
+	pub fn batch_depth(&self) -> usize {
+		self.depth
+	}
+}
+
+impl<D: Default> Default for KeyValueStore<D> {
+	fn default() -> Self {
+		Self::new(D::default())
+	}
+}
+
+impl<D: KeyValueDB> KeyValueStore<D> {
+	fn write(&mut self, op: KeyValueOp) -> Result<(), KeyValueStoreError<D::Error>> {
+		match self.batch.as_mut() {
+			Some((ops, overlay)) => {
+				match &op {
+					KeyValueOp::Put(key, value) => { overlay.insert(key.clone(), Some(value.clone())); },
+					KeyValueOp::Delete(key) => { overlay.insert(key.clone(), None); },
+				}
+				ops.push(op);
+				Ok(())
+			},
+			None => self.db.write(alloc::vec![op]).map_err(KeyValueStoreError::Backend),
+		}
+	}
+}
+
+impl<V, D: KeyValueDB> RefCountStore<V> for KeyValueStore<D> where
+	V: AsRef<[u8]> + AsMut<[u8]> + Default,
+{
+	type Error = KeyValueStoreError<D::Error>;
+
+	/// A malformed stored entry fails with
+	/// `KeyValueStoreError::CorruptedEntry` instead of panicking.
+	fn fetch(&mut self, key: &V) -> Result<Option<RefCountEntry<V>>, Self::Error> {
+		let entry = match self.batch.as_ref().and_then(|(_, overlay)| overlay.get(key.as_ref())) {
+			Some(raw) => raw.as_ref().map(|raw| decode_entry(raw)),
+			None => self.db.get_with(key.as_ref(), decode_entry).map_err(KeyValueStoreError::Backend)?,
+		};
+		match entry {
+			Some(Some(entry)) => Ok(Some(entry)),
+			Some(None) => Err(KeyValueStoreError::CorruptedEntry(key.as_ref().to_vec())),
+			None => Ok(None),
+		}
+	}
+
+	fn store(&mut self, key: V, entry: RefCountEntry<V>) -> Result<(), Self::Error> {
+		let mut raw = (entry.refcount as u64).to_le_bytes().to_vec();
+		if let Some((left, right)) = entry.children {
+			raw.extend_from_slice(left.as_ref());
+			raw.extend_from_slice(right.as_ref());
+		}
+
+		self.write(KeyValueOp::Put(key.as_ref().to_vec(), raw))
+	}
+
+	fn remove(&mut self, key: &V) -> Result<(), Self::Error> {
+		self.write(KeyValueOp::Delete(key.as_ref().to_vec()))
+	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		if self.depth == 0 {
+			self.batch = Some(Default::default());
+		}
+		self.depth += 1;
+		Ok(())
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		match self.depth {
+			0 => Ok(()),
+			1 => {
+				self.depth = 0;
+				match self.batch.take() {
+					Some((ops, _)) => self.db.write(ops).map_err(KeyValueStoreError::Backend),
+					None => Ok(()),
+				}
+			},
+			_ => {
+				self.depth -= 1;
+				Ok(())
+			},
+		}
+	}
+}
+
+/// Reference-counted merkle database over any key-value database, so
+/// that an embedded store only needs to implement `KeyValueDB`.
+pub type KvBackend<C, D> = RefCountBackend<C, KeyValueStore<D>>;
+
+impl<C: Construct, D> RefCountBackend<C, KeyValueStore<D>> {
+	/// Create a new backend over the given key-value database.
+	pub fn from_db(db: D) -> Self {
+		Self::new(KeyValueStore::new(db))
+	}
+
+	/// Get a reference to the underlying key-value database.
+	pub fn db(&self) -> &D {
+		self.store().db()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{RefCountBackend, InheritedDigestConstruct, InMemoryBackend, Vector, Owned,
+				Tree, Leak, WriteBackend, ReadBackend, Error};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type KeyValue = RefCountBackend<Construct, KeyValueStore<Map<Vec<u8>, Vec<u8>>>>;
+
+	#[test]
+	fn test_key_value_store() {
+		let mut db = KeyValue::default();
+		let mut expected_db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		let mut expected = Vector::<Owned, Construct>::create(&mut expected_db, 0, None).unwrap();
+
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+			expected.push(&mut expected_db, value(i)).unwrap();
+		}
+		assert_eq!(vector.root(), expected.root());
+
+		db.begin_batch().unwrap();
+		vector.set(&mut db, 3, value(42)).unwrap();
+		assert_eq!(vector.get(&mut db, 3).unwrap(), value(42));
+		assert!(KeyValueDB::get(db.store().db(), vector.root().as_ref()).unwrap().is_none());
+		db.begin_batch().unwrap();
+		assert_eq!(db.store().batch_depth(), 2);
+		db.commit_batch().unwrap();
+		assert!(KeyValueDB::get(db.store().db(), vector.root().as_ref()).unwrap().is_none());
+		db.commit_batch().unwrap();
+		assert_eq!(db.store().batch_depth(), 0);
+		assert!(KeyValueDB::get(db.store().db(), vector.root().as_ref()).unwrap().is_some());
+		db.commit_batch().unwrap();
+
+		for i in 0..9 {
+			let expected = if i == 3 { value(42) } else { value(i as u8) };
+			assert_eq!(vector.get(&mut db, i).unwrap(), expected);
+		}
+
+		vector.drop(&mut db).unwrap();
+		assert!(db.store().db().is_empty());
+	}
+
+	#[test]
+	fn test_corrupted_entry() {
+		let mut db = KeyValue::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..4 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let (left, _) = db.get(&vector.root()).unwrap().unwrap();
+
+		let mut map = db.into_store().into_db();
+		map.get_mut(left.as_ref()).unwrap().truncate(40);
+		let mut db = KeyValue::new(KeyValueStore::new(map));
+		assert_eq!(vector.get(&mut db, 0), Err(Error::Backend(KeyValueStoreError::CorruptedEntry(left.to_vec()))));
+		assert_eq!(vector.get(&mut db, 3).unwrap(), value(3));
+		vector.leak();
+	}
+
+	#[test]
+	fn test_kv_backend() {
+		#[derive(Default)]
+		struct Log(Vec<(Vec<u8>, Option<Vec<u8>>)>);
+
+		impl KeyValueDB for Log {
+			type Error = ();
+
+			fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ()> {
+				Ok(self.0.iter().rev().find(|(k, _)| k == key).and_then(|(_, value)| value.clone()))
+			}
+
+			fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), ()> {
+				self.0.extend(ops.into_iter().map(|op| match op {
+					KeyValueOp::Put(key, value) => (key, Some(value)),
+					KeyValueOp::Delete(key) => (key, None),
+				}));
+				Ok(())
+			}
+		}
+
+		let mut db = KvBackend::<Construct, Log>::from_db(Log::default());
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		for i in 0..9 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+		assert!(!db.db().0.is_empty());
+		vector.drop(&mut db).unwrap();
+	}
+
+	#[test]
+	fn test_namespaced() {
+		type Namespaced = RefCountBackend<Construct, KeyValueStore<NamespacedDB<Rc<RefCell<Map<Vec<u8>, Vec<u8>>>>>>>;
+
+		let shared = Rc::new(RefCell::new(Map::<Vec<u8>, Vec<u8>>::new()));
+		let mut first = Namespaced::new(KeyValueStore::new(NamespacedDB::new(shared.clone(), b"first")));
+		let mut second = Namespaced::new(KeyValueStore::new(NamespacedDB::new(shared.clone(), b"second")));
+		assert_eq!(first.store().db().namespace(), b"first");
+
+		let mut vectors = Vec::new();
+		for db in [&mut first, &mut second] {
+			let mut vector = Vector::<Owned, Construct>::create(db, 0, None).unwrap();
+			for i in 0..5 {
+				vector.push(db, value(i)).unwrap();
+			}
+			vectors.push(vector);
+		}
+		let second_vector = vectors.pop().unwrap();
+		let first_vector = vectors.pop().unwrap();
+		assert_eq!(first_vector.root(), second_vector.root());
+		let len = shared.borrow().len();
+
+		first_vector.drop(&mut first).unwrap();
+		assert_eq!(shared.borrow().len(), len / 2);
+		for i in 0..5 {
+			assert_eq!(second_vector.get(&mut second, i).unwrap(), value(i as u8));
+		}
+		second_vector.drop(&mut second).unwrap();
+		assert!(shared.borrow().is_empty());
+	}
+}
@@ -0,0 +1,96 @@
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::traits::Construct;
+
+/// Action taken in debug builds when an owned tree is dropped without
+/// calling `Tree::drop`, leaking its nodes in the backend.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum LeakPolicy {
+	/// Do nothing.
+	Ignore,
+	/// Print the leaked root to stderr with the `std` feature, or emit
+	/// a warning event with the `tracing` feature.
+	Log,
+	/// Panic with the leaked root, unless the thread is already
+	/// panicking.
+	Panic,
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(0);
+
+/// Set the action taken in debug builds for leaked owned trees. The
+/// default is `LeakPolicy::Ignore`, as dropping a tree rebuilt with
+/// `from_leaked` is a normal pattern; opt in to `Log` or `Panic` to
+/// catch forgotten `Tree::drop` calls.
+pub fn set_leak_policy(policy: LeakPolicy) {
+	POLICY.store(match policy {
+		LeakPolicy::Ignore => 0,
+		LeakPolicy::Log => 1,
+		LeakPolicy::Panic => 2,
+	}, Ordering::Relaxed);
+}
+
+/// Action taken in debug builds for leaked owned trees.
+pub fn leak_policy() -> LeakPolicy {
+	match POLICY.load(Ordering::Relaxed) {
+		0 => LeakPolicy::Ignore,
+		1 => LeakPolicy::Log,
+		_ => LeakPolicy::Panic,
+	}
+}
+
+/// Formats a value with `Construct::fmt_value`.
+pub(crate) struct DisplayValue<'a, C: Construct>(pub(crate) &'a C::Value);
+
+impl<'a, C: Construct> fmt::Display for DisplayValue<'a, C> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		C::fmt_value(self.0, f)
+	}
+}
+
+/// Marks whether an owned root was released with `Tree::drop`. It has
+/// no size in release builds, where leaks are not checked.
+#[derive(Default, Clone, Copy, Debug)]
+pub(crate) struct LeakGuard {
+	#[cfg(debug_assertions)]
+	released: bool,
+}
+
+impl LeakGuard {
+	/// Mark the root as released.
+	pub(crate) fn release(&mut self) {
+		#[cfg(debug_assertions)]
+		{
+			self.released = true;
+		}
+	}
+
+	/// Report the root as leaked if it was not released.
+	#[allow(unused_variables)]
+	pub(crate) fn check<C: Construct>(&self, root: &C::Value) {
+		#[cfg(debug_assertions)]
+		{
+			if self.released {
+				return
+			}
+
+			match leak_policy() {
+				LeakPolicy::Ignore => (),
+				LeakPolicy::Log => {
+					#[cfg(feature = "tracing")]
+					tracing::warn!(target: "bm", root = %DisplayValue::<C>(root), "owned tree leaked");
+					#[cfg(all(feature = "std", not(feature = "tracing")))]
+					std::eprintln!("bm: owned tree with root {} leaked", DisplayValue::<C>(root));
+				},
+				LeakPolicy::Panic => {
+					#[cfg(feature = "std")]
+					if std::thread::panicking() {
+						return
+					}
+					panic!("bm: owned tree with root {} leaked", DisplayValue::<C>(root));
+				},
+			}
+		}
+	}
+}
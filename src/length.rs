@@ -11,7 +11,7 @@ pub struct LengthMixed<R: RootStatus, C: Construct, S: Sequence<Construct=C, Roo
 
 impl<R: RootStatus, C: Construct, S> LengthMixed<R, C, S> where
 	S: Sequence<Construct=C, RootStatus=Dangling>,
-	C::Value: From<usize> + Into<usize>,
+	C::Value: From<u64> + Into<u64>,
 {
 	/// Reconstruct the mixed-length tree.
 	pub fn reconstruct<DB: WriteBackend<Construct=C> + ?Sized, F>(
@@ -19,16 +19,16 @@ impl<R: RootStatus, C: Construct, S> LengthMixed<R, C, S> where
 		db: &mut DB,
 		f: F
 	) -> Result<Self, Error<DB::Error>> where
-		F: FnOnce(Raw<Dangling, C>, &mut DB, usize) -> Result<S, Error<DB::Error>>,
+		F: FnOnce(Raw<Dangling, C>, &mut DB, u64) -> Result<S, Error<DB::Error>>,
 	{
-		let raw = Raw::<R, C>::from_leaked(root);
-		let len: usize = raw.get(db, LEN_INDEX)?
-			.ok_or(Error::CorruptedDatabase)?
-			.into();
-		let inner_raw = raw.subtree(db, ITEM_ROOT_INDEX)?;
+		// Read through a dangling root, so that an error does not drop
+		// an owned root that was never released.
+		let reader = Raw::<Dangling, C>::from_leaked(root);
+		let len: u64 = reader.get_existing(db, LEN_INDEX)?.into();
+		let inner_raw = reader.subtree(db, ITEM_ROOT_INDEX)?;
 
 		let inner = f(inner_raw, db, len)?;
-		Ok(Self { inner, raw })
+		Ok(Self { inner, raw: Raw::from_leaked(reader.leak()) })
 	}
 
 	/// Deconstruct the mixed-length tree.
@@ -38,7 +38,7 @@ impl<R: RootStatus, C: Construct, S> LengthMixed<R, C, S> where
 	) -> Result<C::Value, Error<DB::Error>> {
 		self.raw.get(db, LEN_INDEX)?;
 		self.raw.get(db, ITEM_ROOT_INDEX)?;
-		Ok(self.raw.root())
+		Ok(self.raw.leak())
 	}
 
 	/// Call with the inner sequence.
@@ -73,7 +73,7 @@ impl<R: RootStatus, C: Construct, S> LengthMixed<R, C, S> where
 
 impl<C: Construct, S> LengthMixed<Owned, C, S> where
 	S: Sequence<Construct=C, RootStatus=Dangling> + Leak,
-	C::Value: From<usize> + Into<usize>,
+	C::Value: From<u64> + Into<u64>,
 {
 	/// Create a new mixed-length tree.
 	pub fn create<DB: WriteBackend<Construct=C> + ?Sized, OS, F>(
@@ -124,7 +124,7 @@ impl<R: RootStatus, C: Construct, S> Tree for LengthMixed<R, C, S> where
 impl<R: RootStatus, C: Construct, S> Sequence for LengthMixed<R, C, S> where
 	S: Sequence<Construct=C, RootStatus=Dangling>,
 {
-	fn len(&self) -> usize {
+	fn len(&self) -> u64 {
 		self.inner.len()
 	}
 }
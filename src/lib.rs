@@ -7,23 +7,151 @@ extern crate alloc;
 
 mod traits;
 mod memory;
+mod empty;
+mod arc;
+mod batch;
+#[cfg(feature = "sha256")]
+mod sha256;
+#[cfg(feature = "parallel")]
+mod builder;
+mod counting;
+#[cfg(feature = "blake3")]
+mod blake3_construct;
+#[cfg(feature = "poseidon")]
+mod poseidon;
+mod refcount;
+mod kv;
+#[cfg(feature = "snappy")]
+mod compressed;
+#[cfg(feature = "std")]
+mod wal;
+mod frozen;
+#[cfg(feature = "rocksdb")]
+mod rocks;
+#[cfg(feature = "sled")]
+mod sled_db;
+#[cfg(feature = "parity-db")]
+mod paritydb;
+#[cfg(feature = "redb")]
+mod redb_db;
+#[cfg(feature = "lmdb")]
+mod lmdb;
 mod raw;
+mod view;
 mod index;
 mod vector;
+mod buffered;
 mod list;
+mod map;
 mod packed;
 mod length;
 mod proving;
+mod range;
+mod consistency;
+mod stateless;
+mod proof_cache;
+mod cached;
+mod instrumented;
+mod verified;
+mod limited;
+mod metered;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "server")]
+mod server;
+mod transaction;
+mod overlay;
+mod snapshot;
+mod versioned;
+mod diff;
+mod copy;
+mod rehash;
+mod compact;
+mod portable;
+mod dot;
+mod leak;
+mod visit;
+mod stats;
+mod integrity;
+mod annotated;
+mod sum;
 
 pub mod utils;
+pub mod testing;
+#[cfg(test)]
+mod test_utils;
 
-pub use crate::traits::{Backend, ReadBackend, WriteBackend, Construct, Dangling, Owned, RootStatus, Error, Sequence, Tree, Leak, DynBackend};
-pub use crate::memory::{EmptyStatus, UnitEmpty, InheritedEmpty, UnitDigestConstruct, InheritedDigestConstruct, InMemoryBackend, InMemoryBackendError, NoopBackend, NoopBackendError};
+pub use crate::traits::{Backend, ReadBackend, RefReadBackend, WriteBackend, Construct, Dangling, Owned, RootStatus, Error, Sequence, Tree, Leak, ErrorContext, DynBackend, BoxedBackend, AsyncReadBackend, AsyncWriteBackend, BackendFuture, NodePair};
+pub use crate::memory::{EmptyStatus, UnitEmpty, InheritedEmpty, UnitDigestConstruct, InheritedDigestConstruct, DigestConstruct, InMemoryBackend, InMemoryBackendError, NoopBackend, NoopBackendError};
+pub use crate::empty::EmptyValues;
+pub use crate::arc::ArcBackend;
+pub use crate::batch::{BatchHasher, BatchConstruct};
+#[cfg(feature = "sha256")]
+pub use crate::sha256::{Sha256Batch, Sha256Construct};
+#[cfg(feature = "parallel")]
+pub use crate::builder::TreeBuilder;
+pub use crate::counting::{CountingConstruct, HashMeter, hash_count};
+#[cfg(feature = "keccak")]
+pub use crate::memory::KeccakConstruct;
+#[cfg(feature = "blake3")]
+pub use crate::blake3_construct::Blake3Construct;
+#[cfg(feature = "poseidon")]
+pub use crate::poseidon::{PoseidonConstruct, PoseidonParams, CircomBn254};
+pub use crate::refcount::{RefCountBackend, InMemoryRefCountBackend, RefCountStore, RefCountEntry, InMemoryRefCountStoreError};
+pub use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp, KeyValueStore, KeyValueStoreError, KvBackend, NamespacedDB, InMemoryKeyValueDBError};
+#[cfg(feature = "snappy")]
+pub use crate::compressed::{CompressedDB, CompressedDBError};
+#[cfg(feature = "std")]
+pub use crate::wal::{WalDB, WalDBError, WalBackend};
+pub use crate::frozen::{FrozenBackend, FrozenBackendError, export};
+#[cfg(feature = "rocksdb")]
+pub use crate::rocks::{RocksDB, RocksBackend};
+#[cfg(feature = "sled")]
+pub use crate::sled_db::{SledDB, SledBackend};
+#[cfg(feature = "parity-db")]
+pub use crate::paritydb::{ParityDB, ParityBackend};
+#[cfg(feature = "redb")]
+pub use crate::redb_db::{RedbDB, RedbBackend, RedbReadBackend};
+#[cfg(feature = "lmdb")]
+pub use crate::lmdb::{LmdbDB, LmdbBackend, LmdbReadBackend};
 pub use crate::raw::{Raw, OwnedRaw, DanglingRaw};
-pub use crate::index::{Index, IndexSelection, IndexRoute};
-pub use crate::vector::{Vector, OwnedVector, DanglingVector};
+pub use crate::view::RawView;
+pub use crate::index::{Index, IndexSelection, IndexRoute, IndexPath, ParseIndexError, MAX_INDEX_DEPTH};
+pub use crate::vector::{Vector, OwnedVector, DanglingVector, VectorIter, PushCursor};
+pub use crate::buffered::{BufferedVector, OwnedBufferedVector, DanglingBufferedVector};
 pub use crate::list::{List, OwnedList, DanglingList};
+pub use crate::map::{MerkleMap, OwnedMerkleMap, DanglingMerkleMap, MerkleMapIter};
 pub use crate::packed::{PackedVector, OwnedPackedVector, DanglingPackedVector,
-						PackedList, OwnedPackedList, DanglingPackedList};
+						PackedVectorIter, PackedList, OwnedPackedList, DanglingPackedList};
 pub use crate::length::LengthMixed;
-pub use crate::proving::{ProvingBackend, ProvingState, Proofs, CompactValue};
+pub use crate::proving::{ProvingBackend, ProvingState, Proofs, CompactValue, verify_proofs};
+pub use crate::range::{prove_range, verify_range};
+pub use crate::consistency::{prove_consistency, verify_consistency};
+pub use crate::stateless::apply_updates;
+pub use crate::proof_cache::ProofCache;
+pub use crate::cached::CachedBackend;
+pub use crate::instrumented::{InstrumentedBackend, BackendStats};
+pub use crate::verified::{VerifiedBackend, VerifiedBackendError};
+pub use crate::limited::{LimitedBackend, LimitedBackendError, DecodeLimits};
+pub use crate::metered::{MeteredBackend, DecodeStats, MAX_TRACKED_CHILDREN};
+#[cfg(feature = "std")]
+pub use crate::shared::SharedBackend;
+#[cfg(feature = "server")]
+pub use crate::server::{ProofServer, ProofTransport, ProofRequest, ProofResponse, ProofRequestError};
+pub use crate::transaction::{TransactionalBackend, TransactionOp};
+pub use crate::overlay::{OverlayBackend, OverlayChanges};
+pub use crate::snapshot::Snapshot;
+pub use crate::versioned::VersionedVector;
+pub use crate::diff::{diff, DiffEntry, DiffEntries};
+pub use crate::copy::{copy_tree, CopyError};
+pub use crate::rehash::rehash;
+pub use crate::compact::{compact, compact_into, CompactStats};
+pub use crate::portable::{export_tree, export_tree_leaves, export_checkpoint, import_tree,
+						export_delta, apply_delta, ImportError};
+pub use crate::dot::to_dot;
+pub use crate::leak::{LeakPolicy, set_leak_policy, leak_policy};
+pub use crate::visit::{visit, visit_breadth_first, VisitNode, VisitControl};
+pub use crate::stats::{stats, TreeStats};
+pub use crate::integrity::{check_integrity, repair_integrity, IntegrityReport};
+pub use crate::annotated::{Annotation, AnnotatedValue, AnnotatedConstruct, LeafSum};
+pub use crate::sum::{Amount, SumConstruct, SumValue, SumTree, verify_sum_proof};
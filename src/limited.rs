@@ -0,0 +1,139 @@
+use crate::{Backend, ReadBackend, Construct, NodePair, EmptyValues};
+
+/// Limits on the trees decoded from untrusted roots, so that claimed
+/// lengths cannot drive unbounded allocation or traversal.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct DecodeLimits {
+	/// Maximum depth of the tree of a decoded vector or list, computed
+	/// from its maximum length, or from its length if unbounded.
+	pub max_depth: usize,
+	/// Maximum length of a decoded vector or list.
+	pub max_len: u64,
+	/// Maximum number of nodes read.
+	pub max_nodes: u64,
+}
+
+impl DecodeLimits {
+	/// No limit at all.
+	pub const fn unlimited() -> Self {
+		Self {
+			max_depth: usize::MAX,
+			max_len: u64::MAX,
+			max_nodes: u64::MAX,
+		}
+	}
+}
+
+impl Default for DecodeLimits {
+	fn default() -> Self {
+		Self::unlimited()
+	}
+}
+
+/// Error of `LimitedBackend`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LimitedBackendError<E> {
+	/// More nodes were read than allowed.
+	TooManyNodes,
+	/// Backend database error.
+	Backend(E),
+}
+
+/// Read backend failing with `LimitedBackendError::TooManyNodes` once
+/// a given number of nodes were read, to bound the work of traversing
+/// untrusted trees. Its `decode_limits` are checked by decoders against
+/// the lengths and depths they read.
+pub struct LimitedBackend<'a, DB: ?Sized> {
+	db: &'a mut DB,
+	limits: DecodeLimits,
+	remaining: u64,
+}
+
+impl<'a, DB: ?Sized> LimitedBackend<'a, DB> {
+	/// Create a new limited backend reading at most `max_nodes` nodes
+	/// from `db`.
+	pub fn new(db: &'a mut DB, max_nodes: u64) -> Self {
+		Self::with_limits(db, &DecodeLimits { max_nodes, ..DecodeLimits::unlimited() })
+	}
+
+	/// Create a new limited backend reading at most `limits.max_nodes`
+	/// nodes from `db`, and reporting `limits` to decoders.
+	pub fn with_limits(db: &'a mut DB, limits: &DecodeLimits) -> Self {
+		Self { db, limits: *limits, remaining: limits.max_nodes }
+	}
+
+	/// Number of nodes that can still be read.
+	pub fn remaining(&self) -> u64 {
+		self.remaining
+	}
+}
+
+impl<'a, DB: Backend + ?Sized> Backend for LimitedBackend<'a, DB> {
+	type Construct = DB::Construct;
+	type Error = LimitedBackendError<DB::Error>;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.db.empty_values()
+	}
+}
+
+impl<'a, DB: ReadBackend + ?Sized> ReadBackend for LimitedBackend<'a, DB> {
+	fn get(
+		&mut self,
+		key: &<DB::Construct as Construct>::Value
+	) -> Result<Option<NodePair<DB>>, Self::Error> {
+		if self.remaining == 0 {
+			return Err(LimitedBackendError::TooManyNodes)
+		}
+
+		self.remaining -= 1;
+		self.db.get(key).map_err(LimitedBackendError::Backend)
+	}
+
+	fn prefetch(&mut self, keys: &[<DB::Construct as Construct>::Value]) {
+		self.db.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.limits
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree, Error};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_limited() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let values = (0..8).map(value).collect::<Vec<_>>();
+		let vector = Vector::<Owned, Construct>::create_from(&mut db, &values, None).unwrap();
+
+		let mut limited = LimitedBackend::new(&mut db, 3);
+		assert_eq!(vector.get(&mut limited, 5).unwrap(), value(5));
+		assert_eq!(limited.remaining(), 0);
+		assert_eq!(vector.get(&mut limited, 5).err(), Some(Error::Backend(LimitedBackendError::TooManyNodes)));
+		assert_eq!(limited.decode_limits(), DecodeLimits { max_nodes: 3, ..DecodeLimits::unlimited() });
+		vector.drop(&mut db).unwrap();
+	}
+
+	#[test]
+	fn test_limits() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		assert_eq!(db.decode_limits(), DecodeLimits::unlimited());
+		let vector = Vector::<Owned, Construct>::create_from(&mut db, &[value(1), value(2)], None).unwrap();
+
+		let limits = DecodeLimits { max_depth: 4, max_len: 10, max_nodes: 0 };
+		let mut limited = LimitedBackend::with_limits(&mut db, &limits);
+		assert_eq!(limited.decode_limits(), limits);
+		assert_eq!(vector.get(&mut limited, 0).err(), Some(Error::Backend(LimitedBackendError::TooManyNodes)));
+		assert_eq!(limited.remaining(), 0);
+		vector.drop(&mut db).unwrap();
+	}
+}
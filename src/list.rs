@@ -9,22 +9,35 @@ pub type OwnedList<C> = List<Owned, C>;
 /// `List` with dangling root.
 pub type DanglingList<C> = List<Dangling, C>;
 
-/// Binary merkle vector.
+/// Binary merkle list, with its length mixed into the root and its
+/// maximum length, if any, enforced on `push`.
 pub struct List<R: RootStatus, C: Construct>(LengthMixed<R, C, Vector<Dangling, C>>);
 
 impl<R: RootStatus, C: Construct> List<R, C> where
-	C::Value: From<usize> + Into<usize>,
+	C::Value: From<u64> + Into<u64>,
 {
 	/// Get value at index.
-	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(&self, db: &mut DB, index: usize) -> Result<C::Value, Error<DB::Error>> {
+	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(&self, db: &mut DB, index: u64) -> Result<C::Value, Error<DB::Error>> {
 		self.0.with(db, |tuple, db| tuple.get(db, index))
 	}
 
 	/// Set value at index.
-	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: usize, value: C::Value) -> Result<(), Error<DB::Error>> {
+	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: u64, value: C::Value) -> Result<(), Error<DB::Error>> {
 		self.0.with_mut(db, |tuple, db| tuple.set(db, index, value))
 	}
 
+	/// Insert a value at index, shifting all values after it to the
+	/// right.
+	pub fn insert<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: u64, value: C::Value) -> Result<(), Error<DB::Error>> {
+		self.0.with_mut(db, |tuple, db| tuple.insert(db, index, value))
+	}
+
+	/// Remove the value at index, shifting all values after it to the
+	/// left.
+	pub fn remove<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: u64) -> Result<C::Value, Error<DB::Error>> {
+		self.0.with_mut(db, |tuple, db| tuple.remove(db, index))
+	}
+
 	/// Push a new value to the vector.
 	pub fn push<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, value: C::Value) -> Result<(), Error<DB::Error>> {
 		self.0.with_mut(db, |tuple, db| tuple.push(db, value))
@@ -40,21 +53,34 @@ impl<R: RootStatus, C: Construct> List<R, C> where
 		self.0.deconstruct(db)
 	}
 
-	/// Reconstruct the vector from a single hash value.
+	/// Reconstruct the vector from a single hash value. Fails with
+	/// `Error::MaxLenExceeded` if the mixed-in length exceeds `max_len`.
 	pub fn reconstruct<DB: WriteBackend<Construct=C> + ?Sized>(root: C::Value, db: &mut DB, max_len: Option<u64>) -> Result<Self, Error<DB::Error>> {
 		Ok(Self(LengthMixed::reconstruct(root, db, |tuple_raw, _db, len| {
+			if let Some(max_len) = max_len {
+				if len > max_len {
+					return Err(Error::MaxLenExceeded { len, max_len })
+				}
+			}
 			Ok(Vector::<Dangling, C>::from_raw(tuple_raw, len, max_len))
 		})?))
 	}
 
+	/// Maximum length of the list, or `None` if it is unbounded.
+	pub fn max_len(&self) -> Option<u64> {
+		let (_, (_, _, max_len)) = self.0.metadata();
+		max_len
+	}
+
 	/// Create a list from raw merkle tree.
-	pub fn from_raw(raw: Raw<R, C>, len: usize, max_len: Option<u64>) -> Self {
-		Self::from_leaked((raw.metadata(), (raw.metadata(), len, max_len)))
+	pub fn from_raw(raw: Raw<R, C>, len: u64, max_len: Option<u64>) -> Self {
+		let root = raw.leak();
+		Self::from_leaked((root.clone(), (root, len, max_len)))
 	}
 }
 
 impl<R: RootStatus, C: Construct> Tree for List<R, C> where
-	C::Value: From<usize> + Into<usize>,
+	C::Value: From<u64> + Into<u64>,
 {
 	type RootStatus = R;
 	type Construct = C;
@@ -73,15 +99,15 @@ impl<R: RootStatus, C: Construct> Tree for List<R, C> where
 }
 
 impl<R: RootStatus, C: Construct> Sequence for List<R, C> where
-	C::Value: From<usize> + Into<usize>,
+	C::Value: From<u64> + Into<u64>,
 {
-	fn len(&self) -> usize {
+	fn len(&self) -> u64 {
 		self.0.len()
 	}
 }
 
 impl<R: RootStatus, C: Construct> Leak for List<R, C> where
-	C::Value: From<usize> + Into<usize>,
+	C::Value: From<u64> + Into<u64>,
 {
 	type Metadata = <LengthMixed<R, C, Vector<Dangling, C>> as Leak>::Metadata;
 
@@ -95,9 +121,9 @@ impl<R: RootStatus, C: Construct> Leak for List<R, C> where
 }
 
 impl<C: Construct> List<Owned, C> where
-	C::Value: From<usize> + Into<usize>
+	C::Value: From<u64> + Into<u64>
 {
-	/// Create a new vector.
+	/// Create a new empty list, with an optional maximum length.
 	pub fn create<DB: WriteBackend<Construct=C> + ?Sized>(
 		db: &mut DB,
 		max_len: Option<u64>
@@ -108,13 +134,83 @@ impl<C: Construct> List<Owned, C> where
 
 impl<R: RootStatus, C: Construct> Raw<R, C> {
 	/// Convert the current value to a list.
-	pub fn into_list(self, len: usize, max_len: Option<u64>) -> List<R, C> where
-		C::Value: From<usize> + Into<usize>
+	pub fn into_list(self, len: u64, max_len: Option<u64>) -> List<R, C> where
+		C::Value: From<u64> + Into<u64>
 	{
 		List::from_raw(self, len, max_len)
 	}
 }
 
+/// Serialized metadata of a list.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ListMetadata<V> {
+	root: V,
+	items: crate::vector::VectorMetadata<V>,
+}
+
+/// Serialize a list handle as its root, and the root, length and
+/// maximum length of its items.
+#[cfg(feature = "serde")]
+impl<R: RootStatus, C: Construct> serde::Serialize for List<R, C> where
+	C::Value: serde::Serialize + From<u64> + Into<u64>,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+		S: serde::Serializer,
+	{
+		let (root, (items_root, len, max_len)) = self.metadata();
+		ListMetadata {
+			root,
+			items: crate::vector::VectorMetadata { root: items_root, len, max_len },
+		}.serialize(serializer)
+	}
+}
+
+/// Deserialize a list handle. Only dangling handles can be
+/// deserialized, as the root is not rootified.
+#[cfg(feature = "serde")]
+impl<'de, C: Construct> serde::Deserialize<'de> for List<Dangling, C> where
+	C::Value: serde::Deserialize<'de> + From<u64> + Into<u64>,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+		D: serde::Deserializer<'de>,
+	{
+		let metadata = ListMetadata::<C::Value>::deserialize(deserializer)?;
+		let items = metadata.items;
+		if items.max_len.map(|max_len| items.len > max_len).unwrap_or(false) {
+			return Err(<D::Error as serde::de::Error>::custom("invalid length"))
+		}
+
+		Ok(Self::from_leaked((metadata.root, (items.root, items.len, items.max_len))))
+	}
+}
+
+/// Encode a list handle as its root, and the root, length and maximum
+/// length of its items.
+#[cfg(feature = "parity-codec")]
+impl<R: RootStatus, C: Construct> parity_codec::Encode for List<R, C> where
+	C::Value: parity_codec::Encode + From<u64> + Into<u64>,
+{
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		let (root, (items_root, len, max_len)) = self.metadata();
+		(root, items_root, len, max_len).encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<C: Construct> parity_codec::Decode for List<Dangling, C> where
+	C::Value: parity_codec::Decode + From<u64> + Into<u64>,
+{
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		let (root, items_root, len, max_len) = <(C::Value, C::Value, u64, Option<u64>)>::decode(input)?;
+		if max_len.map(|max_len| len > max_len).unwrap_or(false) {
+			return None
+		}
+
+		Some(Self::from_leaked((root, (items_root, len, max_len))))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -139,17 +235,17 @@ mod tests {
 		}
 	}
 
-	impl From<usize> for ListValue {
-		fn from(value: usize) -> Self {
-			ListValue((&(value as u64).to_le_bytes()[..]).into())
+	impl From<u64> for ListValue {
+		fn from(value: u64) -> Self {
+			ListValue((&value.to_le_bytes()[..]).into())
 		}
 	}
 
-	impl Into<usize> for ListValue {
-		fn into(self) -> usize {
+	impl Into<u64> for ListValue {
+		fn into(self) -> u64 {
 			let mut raw = [0u8; 8];
 			(&mut raw).copy_from_slice(&self.0[0..8]);
-			u64::from_le_bytes(raw) as usize
+			u64::from_le_bytes(raw)
 		}
 	}
 
@@ -213,6 +309,39 @@ mod tests {
 		for i in 0..100 {
 			assert_eq!(vec.get(&mut db, i).unwrap(), i.into());
 		}
+
+		let root = vec.root();
+		vec.insert(&mut db, 10, 1000.into()).unwrap();
+		assert_eq!(vec.len(), 101);
+		assert_eq!(vec.get(&mut db, 10).unwrap(), 1000.into());
+		assert_eq!(vec.get(&mut db, 100).unwrap(), 99.into());
+		assert_eq!(vec.remove(&mut db, 10).unwrap(), 1000.into());
+		assert_eq!(vec.root(), root);
+	}
+
+	#[test]
+	fn test_max_len() {
+		let mut db = InheritedInMemory::default();
+		let mut vec = OwnedList::create(&mut db, Some(4)).unwrap();
+		assert_eq!(vec.max_len(), Some(4));
+
+		for i in 0..4 {
+			vec.push(&mut db, i.into()).unwrap();
+		}
+		assert!(matches!(vec.push(&mut db, 4.into()), Err(Error::AccessOverflowed(_))));
+		assert_eq!(vec.len(), 4);
+
+		let mut vector_db = InheritedInMemory::default();
+		let values = (0..4).map(ListValue::from).collect::<Vec<_>>();
+		let vector = Vector::<Owned, _>::create_from(&mut vector_db, &values, Some(4)).unwrap();
+		assert_eq!(vec.root(), <crate::InheritedDigestConstruct<Sha256, ListValue> as Construct>::intermediate_of(
+			&vector.root(), &4.into()
+		));
+
+		let vec_hash = vec.deconstruct(&mut db).unwrap();
+		assert!(matches!(OwnedList::reconstruct(vec_hash.clone(), &mut db, Some(2)), Err(Error::MaxLenExceeded { len: 4, max_len: 2 })));
+		let vec = OwnedList::reconstruct(vec_hash, &mut db, Some(4)).unwrap();
+		assert_eq!(vec.get(&mut db, 3).unwrap(), 3.into());
 	}
 
 	#[test]
@@ -0,0 +1,159 @@
+use core::marker::PhantomData;
+use alloc::vec::Vec;
+
+use heed::types::Bytes;
+
+use crate::{Construct, Backend, ReadBackend, RefReadBackend, EmptyValues};
+use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp, KeyValueStore, decode_entry};
+use crate::refcount::RefCountBackend;
+
+/// Key-value database over an LMDB database. Reads decode nodes
+/// directly from the memory map, without copying the stored value.
+pub struct LmdbDB {
+	env: heed::Env,
+	db: heed::Database<Bytes, Bytes>,
+}
+
+impl LmdbDB {
+	/// Create a new database over the given named database of an LMDB
+	/// environment. The database is created if it does not yet exist.
+	pub fn new(env: heed::Env, name: Option<&str>) -> Result<Self, heed::Error> {
+		let mut txn = env.write_txn()?;
+		let db = env.create_database(&mut txn, name)?;
+		txn.commit()?;
+
+		Ok(Self { env, db })
+	}
+
+	/// Get a reference to the underlying LMDB environment.
+	pub fn env(&self) -> &heed::Env {
+		&self.env
+	}
+
+	/// Open a read transaction, as a read backend. Nodes are decoded
+	/// directly from the memory map.
+	pub fn reader<C: Construct>(&self) -> Result<LmdbReadBackend<'_, C>, heed::Error> {
+		Ok(LmdbReadBackend {
+			txn: self.env.read_txn()?,
+			db: self.db,
+			empty: EmptyValues::new(),
+			_marker: PhantomData,
+		})
+	}
+}
+
+impl KeyValueDB for LmdbDB {
+	type Error = heed::Error;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.get_with(key, |value| value.to_vec())
+	}
+
+	fn get_with<R, F: FnOnce(&[u8]) -> R>(&self, key: &[u8], f: F) -> Result<Option<R>, Self::Error> {
+		let txn = self.env.read_txn()?;
+		Ok(self.db.get(&txn, key)?.map(f))
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let mut txn = self.env.write_txn()?;
+		for op in ops {
+			match op {
+				KeyValueOp::Put(key, value) => self.db.put(&mut txn, &key, &value)?,
+				KeyValueOp::Delete(key) => { self.db.delete(&mut txn, &key)?; },
+			}
+		}
+		txn.commit()
+	}
+}
+
+impl IterableKeyValueDB for LmdbDB {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, mut f: F) -> Result<(), Self::Error> {
+		let txn = self.env.read_txn()?;
+		for entry in self.db.iter(&txn)? {
+			let (key, value) = entry?;
+			f(key, value);
+		}
+		Ok(())
+	}
+}
+
+/// Reference-counted merkle database persisted in LMDB.
+pub type LmdbBackend<C> = RefCountBackend<C, KeyValueStore<LmdbDB>>;
+
+/// Read backend over an LMDB read transaction.
+pub struct LmdbReadBackend<'a, C: Construct> {
+	txn: heed::RoTxn<'a>,
+	db: heed::Database<Bytes, Bytes>,
+	empty: EmptyValues<C::Value>,
+	_marker: PhantomData<C>,
+}
+
+impl<'a, C: Construct> Backend for LmdbReadBackend<'a, C> {
+	type Construct = C;
+	type Error = heed::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		Some(&self.empty)
+	}
+}
+
+impl<'a, C: Construct> ReadBackend for LmdbReadBackend<'a, C> where
+	C::Value: AsRef<[u8]> + AsMut<[u8]>,
+{
+	fn get(&mut self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		self.get_ref(key)
+	}
+}
+
+impl<'a, C: Construct> RefReadBackend for LmdbReadBackend<'a, C> where
+	C::Value: AsRef<[u8]> + AsMut<[u8]>,
+{
+	fn get_ref(&self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		let value = self.db.get(&self.txn, key.as_ref())?;
+		Ok(value.and_then(|value| decode_entry::<C::Value>(value)).and_then(|entry| entry.children))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, Vector, Owned, Dangling, Tree, Leak, WriteBackend};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_lmdb_backend() {
+		let path = std::env::temp_dir().join(format!("bm-lmdb-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+
+		let env = unsafe {
+			heed::EnvOpenOptions::new().map_size(1 << 24).max_dbs(1).open(&path).unwrap()
+		};
+		let mut db = LmdbBackend::<Construct>::new(KeyValueStore::new(LmdbDB::new(env, Some("merkle")).unwrap()));
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		db.begin_batch().unwrap();
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		db.commit_batch().unwrap();
+
+		let old = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		{
+			let mut reader = db.store().db().reader::<Construct>().unwrap();
+			for i in 0..9 {
+				assert_eq!(old.get(&mut reader, i).unwrap(), value(i as u8));
+			}
+		}
+
+		vector.set(&mut db, 0, value(100)).unwrap();
+		assert_eq!(vector.get(&mut db, 0).unwrap(), value(100));
+		vector.drop(&mut db).unwrap();
+
+		drop(db);
+		std::fs::remove_dir_all(&path).unwrap();
+	}
+}
@@ -0,0 +1,378 @@
+use core::ops::{Bound, RangeBounds};
+
+use crate::traits::{ReadBackend, WriteBackend, Construct, RootStatus, Dangling, Owned, Leak, Error, ErrorContext, Tree, Sequence,
+					NodePair};
+use crate::list::List;
+use crate::raw::Raw;
+
+/// `MerkleMap` with owned root.
+pub type OwnedMerkleMap<C> = MerkleMap<Owned, C>;
+
+/// `MerkleMap` with dangling root.
+pub type DanglingMerkleMap<C> = MerkleMap<Dangling, C>;
+
+/// Ordered binary merkle map. Entries are kept sorted by key in a
+/// list, where each item is the intermediate node of key and value.
+pub struct MerkleMap<R: RootStatus, C: Construct>(List<R, C>);
+
+impl<R: RootStatus, C: Construct> MerkleMap<R, C> where
+	C::Value: From<u64> + Into<u64> + Ord,
+{
+	fn entry_at<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: u64
+	) -> Result<NodePair<DB>, Error<DB::Error>> {
+		let entry = self.0.get(db, index)?;
+		db.get(&entry)?.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<C>(&entry)))
+	}
+
+	fn entry_of<DB: WriteBackend<Construct=C> + ?Sized>(
+		db: &mut DB,
+		key: C::Value,
+		value: C::Value
+	) -> Result<C::Value, Error<DB::Error>> {
+		let entry = C::intermediate_of(&key, &value);
+		db.insert(entry.clone(), (key, value))?;
+		Ok(entry)
+	}
+
+	/// Binary search the key. Returns `Ok` with the index of the entry
+	/// if found, or `Err` with the index where it should be inserted.
+	fn search<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		key: &C::Value
+	) -> Result<Result<u64, u64>, Error<DB::Error>> {
+		let mut low = 0;
+		let mut high = self.len();
+
+		while low < high {
+			let mid = low + (high - low) / 2;
+			let (mid_key, _) = self.entry_at(db, mid)?;
+
+			if &mid_key == key {
+				return Ok(Ok(mid))
+			} else if &mid_key < key {
+				low = mid + 1;
+			} else {
+				high = mid;
+			}
+		}
+
+		Ok(Err(low))
+	}
+
+	/// Get value of the given key.
+	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		key: &C::Value
+	) -> Result<Option<C::Value>, Error<DB::Error>> {
+		match self.search(db, key)? {
+			Ok(index) => Ok(Some(self.entry_at(db, index)?.1)),
+			Err(_) => Ok(None),
+		}
+	}
+
+	/// Whether the map contains the given key.
+	pub fn contains_key<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		key: &C::Value
+	) -> Result<bool, Error<DB::Error>> {
+		Ok(self.search(db, key)?.is_ok())
+	}
+
+	/// Insert a key value pair into the map, returning the old value
+	/// if the key already exists.
+	pub fn insert<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		key: C::Value,
+		value: C::Value
+	) -> Result<Option<C::Value>, Error<DB::Error>> {
+		match self.search(db, &key)? {
+			Ok(index) => {
+				let (_, old_value) = self.entry_at(db, index)?;
+				let entry = Self::entry_of(db, key, value)?;
+				self.0.set(db, index, entry)?;
+				Ok(Some(old_value))
+			},
+			Err(index) => {
+				let entry = Self::entry_of(db, key, value)?;
+				self.0.insert(db, index, entry)?;
+				Ok(None)
+			},
+		}
+	}
+
+	/// Remove a key from the map, returning its value if it exists.
+	pub fn remove<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		key: &C::Value
+	) -> Result<Option<C::Value>, Error<DB::Error>> {
+		match self.search(db, key)? {
+			Ok(index) => {
+				let (_, old_value) = self.entry_at(db, index)?;
+				self.0.remove(db, index)?;
+
+				Ok(Some(old_value))
+			},
+			Err(_) => Ok(None),
+		}
+	}
+
+	/// Iterate over all entries of the map, in key order.
+	pub fn iter<'a, DB: ReadBackend<Construct=C> + ?Sized>(
+		&'a self,
+		db: &'a mut DB
+	) -> MerkleMapIter<'a, R, C, DB> {
+		let end = self.len();
+		MerkleMapIter { map: self, db, index: 0, end }
+	}
+
+	/// Iterate over entries of the map whose keys are within the
+	/// given range, in key order.
+	pub fn range<'a, DB: ReadBackend<Construct=C> + ?Sized, B: RangeBounds<C::Value>>(
+		&'a self,
+		db: &'a mut DB,
+		range: B
+	) -> Result<MerkleMapIter<'a, R, C, DB>, Error<DB::Error>> {
+		let start = match range.start_bound() {
+			Bound::Included(key) => match self.search(db, key)? {
+				Ok(index) => index,
+				Err(index) => index,
+			},
+			Bound::Excluded(key) => match self.search(db, key)? {
+				Ok(index) => index + 1,
+				Err(index) => index,
+			},
+			Bound::Unbounded => 0,
+		};
+		let end = match range.end_bound() {
+			Bound::Included(key) => match self.search(db, key)? {
+				Ok(index) => index + 1,
+				Err(index) => index,
+			},
+			Bound::Excluded(key) => match self.search(db, key)? {
+				Ok(index) => index,
+				Err(index) => index,
+			},
+			Bound::Unbounded => self.len(),
+		};
+
+		Ok(MerkleMapIter { map: self, db, index: start, end: core::cmp::max(start, end) })
+	}
+
+	/// Deconstruct the map into one single hash value, and leak only the hash value.
+	pub fn deconstruct<DB: ReadBackend<Construct=C> + ?Sized>(self, db: &mut DB) -> Result<C::Value, Error<DB::Error>> {
+		self.0.deconstruct(db)
+	}
+
+	/// Reconstruct the map from a single hash value.
+	pub fn reconstruct<DB: WriteBackend<Construct=C> + ?Sized>(root: C::Value, db: &mut DB) -> Result<Self, Error<DB::Error>> {
+		Ok(Self(List::reconstruct(root, db, None)?))
+	}
+}
+
+/// Iterator over entries of a merkle map.
+pub struct MerkleMapIter<'a, R: RootStatus, C: Construct, DB: ?Sized> {
+	map: &'a MerkleMap<R, C>,
+	db: &'a mut DB,
+	index: u64,
+	end: u64,
+}
+
+impl<'a, R: RootStatus, C: Construct, DB: ReadBackend<Construct=C> + ?Sized> Iterator for MerkleMapIter<'a, R, C, DB> where
+	C::Value: From<u64> + Into<u64> + Ord,
+{
+	type Item = Result<(C::Value, C::Value), Error<DB::Error>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index >= self.end {
+			return None
+		}
+
+		let ret = self.map.entry_at(self.db, self.index);
+		self.index += 1;
+		Some(ret)
+	}
+}
+
+impl<R: RootStatus, C: Construct> Tree for MerkleMap<R, C> where
+	C::Value: From<u64> + Into<u64>,
+{
+	type RootStatus = R;
+	type Construct = C;
+
+	fn root(&self) -> C::Value {
+		self.0.root()
+	}
+
+	fn drop<DB: WriteBackend<Construct=C> + ?Sized>(self, db: &mut DB) -> Result<(), Error<DB::Error>> {
+		self.0.drop(db)
+	}
+
+	fn into_raw(self) -> Raw<R, C> {
+		self.0.into_raw()
+	}
+}
+
+impl<R: RootStatus, C: Construct> Sequence for MerkleMap<R, C> where
+	C::Value: From<u64> + Into<u64>,
+{
+	fn len(&self) -> u64 {
+		self.0.len()
+	}
+}
+
+impl<R: RootStatus, C: Construct> Leak for MerkleMap<R, C> where
+	C::Value: From<u64> + Into<u64>,
+{
+	type Metadata = <List<R, C> as Leak>::Metadata;
+
+	fn metadata(&self) -> Self::Metadata {
+		self.0.metadata()
+	}
+
+	fn from_leaked(metadata: Self::Metadata) -> Self {
+		Self(List::from_leaked(metadata))
+	}
+}
+
+impl<C: Construct> MerkleMap<Owned, C> where
+	C::Value: From<u64> + Into<u64>,
+{
+	/// Create a new map.
+	pub fn create<DB: WriteBackend<Construct=C> + ?Sized>(
+		db: &mut DB
+	) -> Result<Self, Error<DB::Error>> {
+		Ok(Self(List::create(db, None)?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type InMemory = crate::memory::InMemoryBackend<crate::InheritedDigestConstruct<Sha256, MapValue>>;
+
+	#[derive(Clone, PartialEq, Eq, Debug, Default, Ord, PartialOrd, Hash)]
+	struct MapValue([u8; 32]);
+
+	impl AsRef<[u8]> for MapValue {
+		fn as_ref(&self) -> &[u8] {
+			self.0.as_ref()
+		}
+	}
+
+	impl From<u64> for MapValue {
+		fn from(value: u64) -> Self {
+			let mut ret = [0u8; 32];
+			ret[24..32].copy_from_slice(&value.to_be_bytes());
+			MapValue(ret)
+		}
+	}
+
+	impl From<MapValue> for u64 {
+		fn from(value: MapValue) -> u64 {
+			let mut raw = [0u8; 8];
+			raw.copy_from_slice(&value.0[24..32]);
+			u64::from_be_bytes(raw)
+		}
+	}
+
+	impl From<GenericArray<u8, typenum::U32>> for MapValue {
+		fn from(array: GenericArray<u8, typenum::U32>) -> MapValue {
+			let mut ret = [0u8; 32];
+			ret.copy_from_slice(&array[0..32]);
+			MapValue(ret)
+		}
+	}
+
+	#[test]
+	fn test_insert_get_remove() {
+		let mut db = InMemory::default();
+		let mut map = OwnedMerkleMap::create(&mut db).unwrap();
+
+		for i in [5u64, 3, 8, 1, 9, 4].iter() {
+			assert_eq!(map.insert(&mut db, (*i).into(), (i * 10).into()).unwrap(), None);
+		}
+		assert_eq!(map.len(), 6);
+		assert_eq!(map.insert(&mut db, 3u64.into(), 31u64.into()).unwrap(), Some(30u64.into()));
+		assert_eq!(map.len(), 6);
+
+		assert_eq!(map.get(&mut db, &3u64.into()).unwrap(), Some(31u64.into()));
+		assert_eq!(map.get(&mut db, &8u64.into()).unwrap(), Some(80u64.into()));
+		assert_eq!(map.get(&mut db, &7u64.into()).unwrap(), None);
+
+		assert_eq!(map.remove(&mut db, &5u64.into()).unwrap(), Some(50u64.into()));
+		assert_eq!(map.remove(&mut db, &5u64.into()).unwrap(), None);
+		assert!(!map.contains_key(&mut db, &5u64.into()).unwrap());
+		assert_eq!(map.len(), 5);
+	}
+
+	#[test]
+	fn test_iter_range() {
+		let mut db = InMemory::default();
+		let mut map = OwnedMerkleMap::create(&mut db).unwrap();
+
+		for i in (0..20u64).rev() {
+			map.insert(&mut db, (i * 2).into(), i.into()).unwrap();
+		}
+
+		let keys = map.iter(&mut db)
+			.map(|entry| entry.map(|(key, _)| key.into()))
+			.collect::<Result<Vec<u64>, _>>().unwrap();
+		assert_eq!(keys, (0..20).map(|i| i * 2).collect::<Vec<_>>());
+
+		let values = map.range(&mut db, MapValue::from(5)..=MapValue::from(12)).unwrap()
+			.map(|entry| entry.map(|(_, value)| value.into()))
+			.collect::<Result<Vec<u64>, _>>().unwrap();
+		assert_eq!(values, vec![3, 4, 5, 6]);
+	}
+
+	#[test]
+	fn test_insertion_order_independent() {
+		let mut db1 = InMemory::default();
+		let mut db2 = InMemory::default();
+		let mut map1 = OwnedMerkleMap::create(&mut db1).unwrap();
+		let mut map2 = OwnedMerkleMap::create(&mut db2).unwrap();
+
+		for i in 0..10u64 {
+			map1.insert(&mut db1, i.into(), i.into()).unwrap();
+		}
+		for i in (0..10u64).rev() {
+			map2.insert(&mut db2, i.into(), i.into()).unwrap();
+		}
+		assert_eq!(map1.root(), map2.root());
+	}
+
+	#[test]
+	fn test_empty_and_missing() {
+		let mut db = InMemory::default();
+		let mut map = OwnedMerkleMap::create(&mut db).unwrap();
+		assert_eq!(map.len(), 0);
+		assert_eq!(map.get(&mut db, &1u64.into()).unwrap(), None);
+		assert_eq!(map.remove(&mut db, &1u64.into()).unwrap(), None);
+		assert_eq!(map.iter(&mut db).count(), 0);
+		assert_eq!(map.range(&mut db, MapValue::from(0)..).unwrap().count(), 0);
+
+		let root = map.deconstruct(&mut db).unwrap();
+		let mut map = OwnedMerkleMap::reconstruct(root, &mut db).unwrap();
+		assert_eq!(map.len(), 0);
+		for i in 0..5u64 {
+			map.insert(&mut db, i.into(), i.into()).unwrap();
+		}
+
+		let root = map.deconstruct(&mut db).unwrap();
+		let mut empty = InMemory::default();
+		assert!(matches!(OwnedMerkleMap::reconstruct(root.clone(), &mut empty), Err(Error::CorruptedDatabase(_))));
+		let map = OwnedMerkleMap::reconstruct(root, &mut db).unwrap();
+		assert_eq!(map.len(), 5);
+	}
+}
@@ -1,13 +1,15 @@
 #[cfg(feature = "std")]
-use std::collections::HashMap as Map;
+use std::collections::{HashMap as Map, HashSet as Set};
 #[cfg(not(feature = "std"))]
-use alloc::collections::BTreeMap as Map;
+use alloc::collections::{BTreeMap as Map, BTreeSet as Set};
+use alloc::vec::Vec;
 use generic_array::GenericArray;
 use digest::Digest;
 use core::marker::PhantomData;
 use core::hash::Hash;
 
-use crate::{Construct, Backend, ReadBackend, WriteBackend};
+use crate::{Construct, Backend, ReadBackend, RefReadBackend, WriteBackend};
+use crate::empty::{EmptyValues, EmptyTable};
 
 /// Empty status.
 pub trait EmptyStatus {
@@ -46,12 +48,39 @@ impl<D: Digest, V> Construct for UnitDigestConstruct<D, V> where
 		digest.result().into()
 	}
 
+	fn intermediates_of(values: &[Self::Value], out: &mut Vec<Self::Value>) {
+		assert!(values.len() % 2 == 0, "intermediates_of takes an even number of values");
+		let mut digest = D::new();
+		out.extend(values.chunks_exact(2).map(|pair| {
+			digest.input(pair[0].as_ref());
+			digest.input(pair[1].as_ref());
+			digest.result_reset().into()
+		}));
+	}
+
 	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
 		_db: &mut DB,
 		_depth_to_bottom: usize
 	) -> Result<Self::Value, DB::Error> {
 		Ok(Default::default())
 	}
+
+	/// Every empty subtree has the default value as root, and is read
+	/// as two default children.
+	fn empty_children<DB: Backend<Construct=Self> + ?Sized>(
+		_db: &DB,
+		value: &Self::Value
+	) -> Option<(Self::Value, Self::Value)> {
+		if value.as_ref() == V::default().as_ref() {
+			Some(Default::default())
+		} else {
+			None
+		}
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		crate::utils::fmt_hex(value.as_ref(), f)
+	}
 }
 
 /// Inherited Digest construct.
@@ -69,21 +98,65 @@ impl<D: Digest, V> Construct for InheritedDigestConstruct<D, V> where
 		digest.result().into()
 	}
 
+	fn intermediates_of(values: &[Self::Value], out: &mut Vec<Self::Value>) {
+		assert!(values.len() % 2 == 0, "intermediates_of takes an even number of values");
+		let mut digest = D::new();
+		out.extend(values.chunks_exact(2).map(|pair| {
+			digest.input(pair[0].as_ref());
+			digest.input(pair[1].as_ref());
+			digest.result_reset().into()
+		}));
+	}
+
 	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
 		db: &mut DB,
 		depth_to_bottom: usize
 	) -> Result<Self::Value, DB::Error> {
-		let mut current = Self::Value::default();
-		for _ in 0..depth_to_bottom {
+		let mut current = V::default();
+		for depth in 0..depth_to_bottom {
 			let value = (current.clone(), current);
-			let key = Self::intermediate_of(&value.0, &value.1);
+			let key = match db.empty_values().and_then(|values| values.table::<Self>().at(depth + 1)) {
+				Some(key) => key,
+				None => Self::intermediate_of(&value.0, &value.1),
+			};
 			db.insert(key.clone(), value)?;
 			current = key;
 		}
 		Ok(current)
 	}
+
+	fn lazy_empty_at<DB: Backend<Construct=Self> + ?Sized>(
+		db: &DB,
+		depth_to_bottom: usize
+	) -> Option<Self::Value> {
+		db.empty_values()?.table::<Self>().at(depth_to_bottom)
+	}
+
+	/// Backends without empty values have the table built again on
+	/// each call, so that trees written lazily elsewhere stay readable.
+	fn empty_children<DB: Backend<Construct=Self> + ?Sized>(
+		db: &DB,
+		value: &Self::Value
+	) -> Option<(Self::Value, Self::Value)> {
+		match db.empty_values() {
+			Some(values) => values.table::<Self>().children(value),
+			None => EmptyTable::new::<Self>().children(value),
+		}
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		crate::utils::fmt_hex(value.as_ref(), f)
+	}
 }
 
+/// Digest construct for any hasher implementing `digest::Digest`, with
+/// the digest output as value and inherited empty.
+pub type DigestConstruct<D> = InheritedDigestConstruct<D>;
+
+/// Keccak-256 construct, as used by Ethereum mainnet.
+#[cfg(feature = "keccak")]
+pub type KeccakConstruct = DigestConstruct<sha3::Keccak256>;
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 /// Noop DB error.
 pub enum NoopBackendError {
@@ -91,28 +164,49 @@ pub enum NoopBackendError {
 	NotSupported,
 }
 
+#[cfg(feature = "std")]
+impl std::fmt::Display for NoopBackendError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{:?}", self)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NoopBackendError { }
+
 /// Noop merkle database.
 pub struct NoopBackend<C: Construct>(
 	PhantomData<C>,
+	EmptyValues<C::Value>,
 );
 
 impl<C: Construct> Default for NoopBackend<C> where
 	C::Value: Eq + Hash + Ord
 {
 	fn default() -> Self {
-		Self(Default::default())
+		Self::new()
+	}
+}
+
+impl<C: Construct> NoopBackend<C> {
+	pub(crate) fn new() -> Self {
+		Self(PhantomData, EmptyValues::new())
 	}
 }
 
 impl<C: Construct> Clone for NoopBackend<C> {
 	fn clone(&self) -> Self {
-		Self(self.0.clone())
+		Self(self.0.clone(), self.1.clone())
 	}
 }
 
 impl<C: Construct> Backend for NoopBackend<C> {
 	type Construct = C;
 	type Error = NoopBackendError;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		Some(&self.1)
+	}
 }
 
 impl<C: Construct> ReadBackend for NoopBackend<C> {
@@ -166,6 +260,7 @@ impl std::error::Error for InMemoryBackendError { }
 /// In-memory merkle database.
 pub struct InMemoryBackend<C: Construct>(
 	Map<C::Value, (Option<(C::Value, C::Value)>, Option<usize>)>,
+	EmptyValues<C::Value>,
 );
 
 impl<C: Construct> Default for InMemoryBackend<C> where
@@ -175,13 +270,13 @@ impl<C: Construct> Default for InMemoryBackend<C> where
 		let mut map = Map::default();
 		map.insert(Default::default(), (None, None));
 
-		Self(map)
+		Self(map, EmptyValues::new())
 	}
 }
 
 impl<C: Construct> Clone for InMemoryBackend<C> {
 	fn clone(&self) -> Self {
-		Self(self.0.clone())
+		Self(self.0.clone(), self.1.clone())
 	}
 }
 
@@ -210,6 +305,60 @@ impl<C: Construct> InMemoryBackend<C> where
 		Ok(())
 	}
 
+	fn live(&self, live_roots: &[C::Value]) -> Set<C::Value> {
+		let mut live = Set::new();
+		live.insert(C::Value::default());
+
+		let mut stack = live_roots.to_vec();
+		while let Some(key) = stack.pop() {
+			if let Some((Some((left, right)), _)) = self.0.get(&key) {
+				if !live.contains(&key) {
+					stack.push(left.clone());
+					stack.push(right.clone());
+				}
+			}
+			live.insert(key);
+		}
+
+		live
+	}
+
+	/// Get all nodes not reachable from the given live roots, which
+	/// `prune` would remove.
+	pub fn unreachable(&self, live_roots: &[C::Value]) -> Vec<C::Value> {
+		let live = self.live(live_roots);
+		self.0.keys().filter(|key| !live.contains(key)).cloned().collect()
+	}
+
+	/// Remove all nodes not reachable from the given live roots.
+	/// Reference counts of the remaining nodes are adjusted for the
+	/// removed parents.
+	pub fn prune(&mut self, live_roots: &[C::Value]) {
+		let live = self.live(live_roots);
+
+		let mut old_refs = Map::<C::Value, usize>::default();
+		let mut new_refs = Map::<C::Value, usize>::default();
+		for (key, (children, _)) in self.0.iter() {
+			if let Some((left, right)) = children {
+				*old_refs.entry(left.clone()).or_insert(0) += 1;
+				*old_refs.entry(right.clone()).or_insert(0) += 1;
+
+				if live.contains(key) {
+					*new_refs.entry(left.clone()).or_insert(0) += 1;
+					*new_refs.entry(right.clone()).or_insert(0) += 1;
+				}
+			}
+		}
+
+		self.0.retain(|key, _| live.contains(key));
+		for (key, (_, refcount)) in self.0.iter_mut() {
+			if let Some(refcount) = refcount.as_mut() {
+				let roots = refcount.saturating_sub(old_refs.get(key).cloned().unwrap_or(0));
+				*refcount = roots + new_refs.get(key).cloned().unwrap_or(0);
+			}
+		}
+	}
+
 	/// Populate the database with proofs.
 	pub fn populate(&mut self, proofs: Map<C::Value, (C::Value, C::Value)>) {
 		for (key, (left, right)) in proofs {
@@ -229,12 +378,24 @@ impl<C: Construct> AsRef<Map<C::Value, (Option<(C::Value, C::Value)>, Option<usi
 impl<C: Construct> Backend for InMemoryBackend<C> {
 	type Construct = C;
 	type Error = InMemoryBackendError;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		Some(&self.1)
+	}
 }
 
 impl<C: Construct> ReadBackend for InMemoryBackend<C> where
 	C::Value: Eq + Hash + Ord,
 {
 	fn get(&mut self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		self.get_ref(key)
+	}
+}
+
+impl<C: Construct> RefReadBackend for InMemoryBackend<C> where
+	C::Value: Eq + Hash + Ord,
+{
+	fn get_ref(&self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
 		Ok(self.0.get(key).map(|v| v.0.clone()).unwrap_or(None))
 	}
 }
@@ -258,7 +419,7 @@ impl<C: Construct> WriteBackend for InMemoryBackend<C> where
 		key: C::Value,
 		value: (C::Value, C::Value)
 	) -> Result<(), Self::Error> {
-		if self.0.contains_key(&key) {
+		if let Some((Some(_), _)) = self.0.get(&key) {
 			return Ok(())
 		}
 
@@ -269,7 +430,130 @@ impl<C: Construct> WriteBackend for InMemoryBackend<C> where
 		self.0.entry(right.clone()).or_insert((None, Some(0))).1
 			.as_mut().map(|v| *v += 1);
 
-		self.0.insert(key, (Some((left, right)), Some(0)));
+		// A key only known as a child so far keeps its reference count.
+		self.0.entry(key).or_insert((None, Some(0))).0 = Some((left, right));
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{Raw, Index, Owned, Dangling, Tree};
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type InMemory = InMemoryBackend<Construct>;
+
+	#[test]
+	fn test_prune() {
+		let mut db = InMemory::default();
+		let mut expected_db = InMemory::default();
+		let mut history = Raw::<Dangling, Construct>::default();
+		let mut live = Raw::<Owned, Construct>::default();
+		let mut expected = Raw::<Owned, Construct>::default();
+
+		for i in 8..16 {
+			history.set(&mut db, Index::from_one(i).unwrap(), value(i as u8)).unwrap();
+		}
+		for i in 8..12 {
+			live.set(&mut db, Index::from_one(i).unwrap(), value(i as u8)).unwrap();
+			expected.set(&mut expected_db, Index::from_one(i).unwrap(), value(i as u8)).unwrap();
+		}
+
+		db.prune(&[live.root()]);
+		assert_eq!(db.as_ref(), expected_db.as_ref());
+
+		live.drop(&mut db).unwrap();
+		assert_eq!(db.as_ref().len(), 1);
+	}
+
+	#[test]
+	fn test_empty_at() {
+		let mut db = InMemory::default();
+		let mut current = GenericArray::<u8, typenum::U32>::default();
+		for depth in 0..80 {
+			assert_eq!(<Construct as crate::Construct>::empty_at(&mut db, depth).unwrap(), current);
+			let value = (current, current);
+			current = <Construct as crate::Construct>::intermediate_of(&value.0, &value.1);
+			assert_eq!(db.get(&current).unwrap(), None);
+		}
+		assert_eq!(<Construct as crate::Construct>::empty_at(&mut db, 80).unwrap(), current);
+		assert_eq!(db.as_ref().len(), 81);
+	}
+
+	#[test]
+	fn test_digest_construct() {
+		use sha2::Sha512;
+		use crate::{Vector, Construct as _};
+
+		let mut db = InMemoryBackend::<DigestConstruct<Sha512>>::default();
+		let mut vector = Vector::<Owned, DigestConstruct<Sha512>>::create(&mut db, 0, None).unwrap();
+		let values = (0..3u8).map(|i| {
+			let mut value = GenericArray::<u8, typenum::U64>::default();
+			value[0] = i;
+			value
+		}).collect::<Vec<_>>();
+		for value in &values {
+			vector.push(&mut db, *value).unwrap();
+		}
+
+		let empty = GenericArray::<u8, typenum::U64>::default();
+		let left = DigestConstruct::<Sha512>::intermediate_of(&values[0], &values[1]);
+		let right = DigestConstruct::<Sha512>::intermediate_of(&values[2], &empty);
+		assert_eq!(vector.root(), DigestConstruct::<Sha512>::intermediate_of(&left, &right));
+	}
+
+	#[cfg(feature = "keccak")]
+	#[test]
+	fn test_keccak_construct() {
+		let mut db = InMemoryBackend::<KeccakConstruct>::default();
+		let empty = <KeccakConstruct as crate::Construct>::empty_at(&mut db, 1).unwrap();
+		assert_eq!(&empty[..], &[
+			0xad, 0x32, 0x28, 0xb6, 0x76, 0xf7, 0xd3, 0xcd, 0x42, 0x84, 0xa5, 0x44, 0x3f, 0x17, 0xf1, 0x96,
+			0x2b, 0x36, 0xe4, 0x91, 0xb3, 0x0a, 0x40, 0xb2, 0x40, 0x58, 0x49, 0xe5, 0x97, 0xba, 0x5f, 0xb5,
+		][..]);
+	}
+
+	#[test]
+	fn test_intermediates_of() {
+		let values = (0..6).map(value).collect::<Vec<_>>();
+		let mut out = Vec::new();
+		<Construct as crate::Construct>::intermediates_of(&values, &mut out);
+		assert_eq!(out.len(), 3);
+		for (i, pair) in values.chunks(2).enumerate() {
+			assert_eq!(out[i], <Construct as crate::Construct>::intermediate_of(&pair[0], &pair[1]));
+		}
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn test_ref_read() {
+		use crate::{Vector, Leak, CachedBackend};
+
+		let mut db = InMemory::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..16 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let metadata = vector.metadata();
+
+		let db = &db;
+		std::thread::scope(|scope| {
+			for offset in 0..4 {
+				scope.spawn(move || {
+					let vector = Vector::<Dangling, Construct>::from_leaked(metadata);
+					let mut reader = db;
+					for i in (offset..16).step_by(4) {
+						assert_eq!(vector.get(&mut reader, i).unwrap(), value(i as u8));
+					}
+				});
+			}
+		});
+
+		let cached = core::cell::RefCell::new(CachedBackend::new(db.clone(), 4));
+		let vector = Vector::<Dangling, Construct>::from_leaked(metadata);
+		assert_eq!(vector.get(&mut &cached, 3).unwrap(), value(3));
+	}
+}
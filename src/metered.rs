@@ -0,0 +1,156 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+use core::hash::Hash;
+
+use crate::{Backend, ReadBackend, Construct, NodePair, DecodeLimits, EmptyValues};
+
+/// Maximum number of children whose depth `MeteredBackend` tracks.
+pub const MAX_TRACKED_CHILDREN: usize = 1 << 16;
+
+/// Work done to decode a tree, collected by `MeteredBackend`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct DecodeStats {
+	/// Number of node reads, including reads of leaves.
+	pub nodes: u64,
+	/// Number of node hashes recomputed to verify the children read,
+	/// zero unless read through `VerifiedBackend`.
+	pub hashes: u64,
+	/// Depth below the root of the deepest node returned.
+	pub depth: usize,
+	/// Bytes of the leaves returned as children, that is of children
+	/// neither read as nodes nor known empty subtrees.
+	pub bytes: u64,
+}
+
+impl DecodeStats {
+	/// Add the work of `other`, such as of another decode against the
+	/// same quota.
+	pub fn merge(&mut self, other: &DecodeStats) {
+		self.nodes += other.nodes;
+		self.hashes += other.hashes;
+		self.depth = core::cmp::max(self.depth, other.depth);
+		self.bytes += other.bytes;
+	}
+}
+
+/// Read backend collecting `DecodeStats` of the nodes read under a
+/// root, so that callers enforcing quotas on untrusted trees can meter
+/// the actual work instead of estimating it.
+///
+/// The depth of each child returned is kept until it is read, for at
+/// most `MAX_TRACKED_CHILDREN` children. Past that, nodes whose depth
+/// is not known are counted at the deepest depth reached so far, and
+/// their children as leaves.
+pub struct MeteredBackend<'a, DB: ?Sized + Backend> {
+	db: &'a mut DB,
+	root: <DB::Construct as Construct>::Value,
+	stats: DecodeStats,
+	depths: Map<<DB::Construct as Construct>::Value, (usize, bool)>,
+}
+
+impl<'a, DB: Backend + ?Sized> MeteredBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]> + Eq + Hash + Ord,
+{
+	/// Create a new metered backend reading the tree under `root` from
+	/// `db`.
+	pub fn new(db: &'a mut DB, root: <DB::Construct as Construct>::Value) -> Self {
+		Self { db, root, stats: Default::default(), depths: Map::new() }
+	}
+
+	/// Statistics collected so far.
+	pub fn stats(&self) -> DecodeStats {
+		self.stats
+	}
+}
+
+impl<'a, DB: Backend + ?Sized> Backend for MeteredBackend<'a, DB> {
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.db.empty_values()
+	}
+}
+
+impl<'a, DB: ReadBackend + ?Sized> ReadBackend for MeteredBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]> + Eq + Hash + Ord,
+{
+	fn get(
+		&mut self,
+		key: &<DB::Construct as Construct>::Value
+	) -> Result<Option<NodePair<DB>>, Self::Error> {
+		self.stats.nodes += 1;
+		let value = self.db.get(key)?;
+		if let Some((left, right)) = &value {
+			let depth = if key == &self.root {
+				0
+			} else {
+				match self.depths.get_mut(key) {
+					Some((depth, read)) => {
+						if !*read {
+							*read = true;
+							self.stats.bytes -= key.as_ref().len() as u64;
+						}
+						*depth
+					},
+					None => self.stats.depth,
+				}
+			} + 1;
+
+			for child in [left, right] {
+				if let Some((child_depth, _)) = self.depths.get_mut(child) {
+					*child_depth = core::cmp::max(*child_depth, depth);
+					continue
+				}
+				if DB::Construct::empty_children(&*self.db, child).is_some() {
+					continue
+				}
+				self.stats.bytes += child.as_ref().len() as u64;
+				if self.depths.len() < MAX_TRACKED_CHILDREN {
+					self.depths.insert(child.clone(), (depth, false));
+				}
+			}
+			self.stats.depth = core::cmp::max(self.stats.depth, depth);
+		}
+		Ok(value)
+	}
+
+	fn prefetch(&mut self, keys: &[<DB::Construct as Construct>::Value]) {
+		self.db.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.db.decode_limits()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_metered() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let values = (0..8).map(value).collect::<Vec<_>>();
+		let vector = Vector::<Owned, Construct>::create_from(&mut db, &values, None).unwrap();
+
+		let mut metered = MeteredBackend::new(&mut db, vector.root());
+		assert_eq!(vector.get(&mut metered, 5).unwrap(), value(5));
+		assert_eq!(metered.stats(), DecodeStats { nodes: 3, hashes: 0, depth: 3, bytes: 128 });
+		assert_eq!(vector.get_many(&mut metered, &(0..8).collect::<Vec<_>>()).unwrap(), values);
+		assert_eq!(metered.stats(), DecodeStats { nodes: 10, hashes: 0, depth: 3, bytes: 8 * 32 });
+
+		let mut total = metered.stats();
+		total.merge(&DecodeStats { nodes: 1, hashes: 2, depth: 1, bytes: 64 });
+		assert_eq!(total, DecodeStats { nodes: 11, hashes: 2, depth: 3, bytes: 320 });
+		vector.drop(&mut db).unwrap();
+	}
+}
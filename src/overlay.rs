@@ -0,0 +1,164 @@
+use crate::{Backend, ReadBackend, RefReadBackend, WriteBackend, Construct, NodePair, TransactionOp, EmptyValues};
+use crate::transaction::apply_ops;
+use core::hash::Hash;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+/// Merkle database buffering writes in memory on top of a read-only
+/// base. The base is only borrowed shared, so that several overlays,
+/// such as speculative executions of competing blocks, can read it at
+/// once. `commit` returns the buffered writes to apply to the base
+/// once no overlay borrows it, and `discard` drops them.
+pub struct OverlayBackend<'a, DB: Backend + ?Sized> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord
+{
+	base: &'a DB,
+	log: Vec<TransactionOp<<DB::Construct as Construct>::Value>>,
+	inserts: Map<<DB::Construct as Construct>::Value, usize>,
+}
+
+/// Writes buffered by an overlay, to be applied to its base.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct OverlayChanges<V>(Vec<TransactionOp<V>>);
+
+impl<V> OverlayChanges<V> {
+	/// Write operations, in the order they were made.
+	pub fn ops(&self) -> &[TransactionOp<V>] {
+		&self.0
+	}
+
+	/// Apply all write operations to `db`, in a single batch.
+	pub fn apply<DB: WriteBackend + ?Sized>(self, db: &mut DB) -> Result<(), DB::Error> where
+		DB::Construct: Construct<Value=V>,
+	{
+		apply_ops(db, self.0)
+	}
+}
+
+impl<'a, DB: Backend + ?Sized> OverlayBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	/// Create a new overlay on top of `base`.
+	pub fn new(base: &'a DB) -> Self {
+		Self {
+			base,
+			log: Default::default(),
+			inserts: Default::default(),
+		}
+	}
+
+	/// Get the base database.
+	pub fn base(&self) -> &'a DB {
+		self.base
+	}
+
+	/// Whether no write was buffered.
+	pub fn is_empty(&self) -> bool {
+		self.log.is_empty()
+	}
+
+	/// Take the buffered writes, to be applied to the base.
+	pub fn commit(self) -> OverlayChanges<<DB::Construct as Construct>::Value> {
+		OverlayChanges(self.log)
+	}
+
+	/// Drop the buffered writes.
+	pub fn discard(self) { }
+}
+
+impl<'a, DB: Backend + ?Sized> Backend for OverlayBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.base.empty_values()
+	}
+}
+
+impl<'a, DB: RefReadBackend + ?Sized> ReadBackend for OverlayBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	fn get(
+		&mut self,
+		key: &<DB::Construct as Construct>::Value
+	) -> Result<Option<NodePair<DB>>, Self::Error> {
+		match self.inserts.get(key).map(|index| &self.log[*index]) {
+			Some(TransactionOp::Insert(_, value)) => Ok(Some(value.clone())),
+			_ => self.base.get_ref(key),
+		}
+	}
+}
+
+impl<'a, DB: RefReadBackend + ?Sized> WriteBackend for OverlayBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	fn rootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.log.push(TransactionOp::Rootify(key.clone()));
+		Ok(())
+	}
+
+	fn unrootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.log.push(TransactionOp::Unrootify(key.clone()));
+		Ok(())
+	}
+
+	fn insert(
+		&mut self,
+		key: <DB::Construct as Construct>::Value,
+		value: NodePair<DB>,
+	) -> Result<(), Self::Error> {
+		self.inserts.insert(key.clone(), self.log.len());
+		self.log.push(TransactionOp::Insert(key, value));
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InMemoryBackend, InheritedDigestConstruct, Vector, Owned, Dangling, Tree, Leak};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_overlay() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let metadata = vector.metadata();
+		let snapshot = db.as_ref().clone();
+
+		let mut first = OverlayBackend::new(&db);
+		let mut second = OverlayBackend::new(&db);
+		let mut first_vector = Vector::<Dangling, Construct>::from_leaked(metadata);
+		let mut second_vector = Vector::<Dangling, Construct>::from_leaked(metadata);
+		first_vector.set(&mut first, 2, value(42)).unwrap();
+		second_vector.push(&mut second, value(5)).unwrap();
+		assert_eq!(first_vector.get(&mut first, 2).unwrap(), value(42));
+		assert_eq!(second_vector.get(&mut second, 2).unwrap(), value(2));
+		assert_eq!(second_vector.len(), 6);
+
+		second.discard();
+		let changes = first.commit();
+		assert_eq!(db.as_ref(), &snapshot);
+
+		let mut expected_db = db.clone();
+		let mut expected = Vector::<Dangling, Construct>::from_leaked(metadata);
+		expected.set(&mut expected_db, 2, value(42)).unwrap();
+
+		changes.apply(&mut db).unwrap();
+		assert_eq!(first_vector.root(), expected.root());
+		assert_eq!(first_vector.get(&mut db, 2).unwrap(), value(42));
+		assert_eq!(db.as_ref(), expected_db.as_ref());
+	}
+}
@@ -5,18 +5,18 @@ use core::marker::PhantomData;
 use alloc::vec::Vec;
 
 use crate::length::LengthMixed;
-use crate::vector::Vector;
+use crate::vector::{Vector, VectorIter};
 use crate::raw::Raw;
 use crate::traits::{Construct, ReadBackend, WriteBackend, RootStatus, Owned, Dangling, Leak, Tree, Sequence, Error};
 use crate::utils::{host_len, host_max_len};
 
-fn coverings<Host: ArrayLength<u8>, Value: ArrayLength<u8>>(value_index: usize) -> (usize, Vec<Range<usize>>) {
+fn coverings<Host: ArrayLength<u8>, Value: ArrayLength<u8>>(value_index: u64) -> (u64, Vec<Range<usize>>) {
 	let host_len = Host::to_usize();
 	let value_len = Value::to_usize();
 
-	let bytes = value_len * value_index;
-	let host_index = bytes / host_len;
-	let offset = bytes - host_len * host_index;
+	let bytes = Value::to_u64() * value_index;
+	let host_index = bytes / Host::to_u64();
+	let offset = (bytes - Host::to_u64() * host_index) as usize;
 
 	let mut ranges = Vec::new();
 	ranges.push(offset..cmp::min(offset + value_len, host_len));
@@ -37,10 +37,12 @@ pub type OwnedPackedVector<C, T, H, V> = PackedVector<Owned, C, T, H, V>;
 /// `PackedVector` with dangling root.
 pub type DanglingPackedVector<C, T, H, V> = PackedVector<Dangling, C, T, H, V>;
 
-/// Packed merkle tuple.
+/// Packed merkle tuple. Values of `V` bytes are packed into chunks of
+/// `H` bytes, which should be the node size of the construct, such as
+/// `U64` for constructs with 64-byte nodes.
 pub struct PackedVector<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> {
 	tuple: Vector<R, C>,
-	len: usize,
+	len: u64,
 	max_len: Option<u64>,
 	_marker: PhantomData<(T, H, V)>,
 }
@@ -50,13 +52,13 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Pac
 	T: From<GenericArray<u8, V>> + Into<GenericArray<u8, V>>,
 {
 	/// Get value at index.
-	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(&self, db: &mut DB, index: usize) -> Result<T, Error<DB::Error>> {
+	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(&self, db: &mut DB, index: u64) -> Result<T, Error<DB::Error>> {
 		let mut ret = GenericArray::<u8, V>::default();
 		let (covering_base, covering_ranges) = coverings::<H, V>(index);
 
 		let mut value_offset = 0;
 		for (i, range) in covering_ranges.into_iter().enumerate() {
-			let host_value = self.tuple.get(db, covering_base + i)?;
+			let host_value = self.tuple.get(db, covering_base + i as u64)?;
 			(&mut ret[value_offset..(value_offset + range.end - range.start)]).copy_from_slice(&host_value.as_ref()[range.clone()]);
 			value_offset += range.end - range.start;
 		}
@@ -65,15 +67,15 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Pac
 	}
 
 	/// Set value at index.
-	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: usize, value: T) -> Result<(), Error<DB::Error>> {
+	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: u64, value: T) -> Result<(), Error<DB::Error>> {
 		let value: GenericArray<u8, V> = value.into();
 		let (covering_base, covering_ranges) = coverings::<H, V>(index);
 
 		let mut value_offset = 0;
 		for (i, range) in covering_ranges.into_iter().enumerate() {
-			let mut host_value = self.tuple.get(db, covering_base + i)?;
+			let mut host_value = self.tuple.get(db, covering_base + i as u64)?;
 			host_value.as_mut()[range.clone()].copy_from_slice(&value[value_offset..(value_offset + range.end - range.start)]);
-			self.tuple.set(db, covering_base + i, host_value)?;
+			self.tuple.set(db, covering_base + i as u64, host_value)?;
 			value_offset += range.end - range.start;
 		}
 
@@ -85,7 +87,7 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Pac
 		let index = self.len;
 		let (covering_base, covering_ranges) = coverings::<H, V>(index);
 
-		while self.tuple.len() < covering_base + covering_ranges.len() {
+		while self.tuple.len() < covering_base + covering_ranges.len() as u64 {
 			self.tuple.push(db, Default::default())?;
 		}
 		self.set(db, index, value)?;
@@ -110,7 +112,7 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Pac
 			let last_index = index - 1;
 
 			let (covering_base, covering_ranges) = coverings::<H, V>(last_index);
-			while self.tuple.len() > covering_base + covering_ranges.len() {
+			while self.tuple.len() > covering_base + covering_ranges.len() as u64 {
 				self.tuple.pop(db)?;
 			}
 
@@ -124,8 +126,47 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Pac
 		Ok(Some(ret))
 	}
 
+	/// Iterate over values of the packed tuple. Each host value is only
+	/// read once.
+	pub fn iter<'a, DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &'a mut DB
+	) -> PackedVectorIter<'a, C, T, V, DB> {
+		PackedVectorIter {
+			hosts: self.chunks(db),
+			buffer: Vec::new(),
+			remaining: self.len,
+			_marker: PhantomData,
+		}
+	}
+
+	/// Iterate over the underlying host values of the packed tuple.
+	/// Each host value is a chunk containing one or more packed values.
+	pub fn chunks<'a, DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &'a mut DB
+	) -> VectorIter<'a, C, DB> {
+		self.tuple.iter(db)
+	}
+
+	/// Number of underlying host values of the packed tuple.
+	pub fn chunks_len(&self) -> u64 {
+		self.tuple.len()
+	}
+
+	/// Get the packed bytes of all values in the tuple.
+	pub fn to_bytes<DB: ReadBackend<Construct=C> + ?Sized>(&self, db: &mut DB) -> Result<Vec<u8>, Error<DB::Error>> {
+		let mut bytes = Vec::with_capacity(self.tuple.len() as usize * H::to_usize());
+		for host_value in self.chunks(db) {
+			bytes.extend_from_slice(host_value?.as_ref());
+		}
+		bytes.truncate(self.len as usize * V::to_usize());
+
+		Ok(bytes)
+	}
+
 	/// Create a packed tuple from raw merkle tree.
-	pub fn from_raw(raw: Raw<R, C>, len: usize, max_len: Option<u64>) -> Self {
+	pub fn from_raw(raw: Raw<R, C>, len: u64, max_len: Option<u64>) -> Self {
 		let host_max_len = max_len.map(|l| host_max_len::<H, V>(l));
 		let host_len = host_len::<H, V>(len);
 		Self {
@@ -137,6 +178,111 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Pac
 	}
 }
 
+impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>> PackedVector<R, C, T, H, typenum::U8> where
+	C::Value: From<GenericArray<u8, H>> + AsRef<[u8]> + AsMut<[u8]>,
+	T: From<GenericArray<u8, typenum::U8>> + Into<GenericArray<u8, typenum::U8>>,
+{
+	/// Read, modify and write back the little-endian `u64` at `index`,
+	/// reading and writing its chunk only once. Fails with
+	/// `InvalidParameter`, leaving the value unchanged, if `f` returns
+	/// `None`.
+	fn update_u64<DB: WriteBackend<Construct=C> + ?Sized, F: FnOnce(u64) -> Option<u64>>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		f: F,
+	) -> Result<u64, Error<DB::Error>> {
+		if index >= self.len {
+			return Err(Error::overflowed())
+		}
+
+		let (covering_base, covering_ranges) = coverings::<H, typenum::U8>(index);
+		if covering_ranges.len() > 1 {
+			let old: GenericArray<u8, typenum::U8> = self.get(db, index)?.into();
+			let old = u64::from_le_bytes(read_u64(&old));
+			let new = f(old).ok_or(Error::InvalidParameter)?;
+			self.set(db, index, GenericArray::clone_from_slice(&new.to_le_bytes()).into())?;
+			return Ok(new)
+		}
+
+		let mut host_value = self.tuple.get(db, covering_base)?;
+		let bytes = &mut host_value.as_mut()[covering_ranges[0].clone()];
+		let new = f(u64::from_le_bytes(read_u64(bytes))).ok_or(Error::InvalidParameter)?;
+		bytes.copy_from_slice(&new.to_le_bytes());
+		self.tuple.set(db, covering_base, host_value)?;
+		Ok(new)
+	}
+
+	/// Add `delta` to the little-endian `u64` at `index` in place, such
+	/// as a balance, touching only its chunk and the path to it. Returns
+	/// the new value, or `InvalidParameter` on overflow.
+	pub fn add_assign<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		delta: u64,
+	) -> Result<u64, Error<DB::Error>> {
+		self.update_u64(db, index, |value| value.checked_add(delta))
+	}
+
+	/// Subtract `delta` from the little-endian `u64` at `index` in
+	/// place. Returns the new value, or `InvalidParameter` on underflow.
+	pub fn sub_assign<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		delta: u64,
+	) -> Result<u64, Error<DB::Error>> {
+		self.update_u64(db, index, |value| value.checked_sub(delta))
+	}
+}
+
+fn read_u64(bytes: &[u8]) -> [u8; 8] {
+	let mut ret = [0u8; 8];
+	ret.copy_from_slice(bytes);
+	ret
+}
+
+/// Iterator over values of a packed tuple.
+pub struct PackedVectorIter<'a, C: Construct, T, V: ArrayLength<u8>, DB: ?Sized> {
+	hosts: VectorIter<'a, C, DB>,
+	buffer: Vec<u8>,
+	remaining: u64,
+	_marker: PhantomData<(T, V)>,
+}
+
+impl<'a, C: Construct, T, V: ArrayLength<u8>, DB: ReadBackend<Construct=C> + ?Sized> Iterator for PackedVectorIter<'a, C, T, V, DB> where
+	C::Value: AsRef<[u8]>,
+	T: From<GenericArray<u8, V>>,
+{
+	type Item = Result<T, Error<DB::Error>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.remaining == 0 {
+			return None
+		}
+
+		while self.buffer.len() < V::to_usize() {
+			match self.hosts.next() {
+				Some(Ok(host_value)) => self.buffer.extend_from_slice(host_value.as_ref()),
+				Some(Err(err)) => {
+					self.remaining = 0;
+					return Some(Err(err))
+				},
+				None => {
+					self.remaining = 0;
+					return Some(Err(Error::corrupted()))
+				},
+			}
+		}
+
+		let value = GenericArray::<u8, V>::clone_from_slice(&self.buffer[..V::to_usize()]);
+		self.buffer.drain(..V::to_usize());
+		self.remaining -= 1;
+		Some(Ok(value.into()))
+	}
+}
+
 impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Tree for PackedVector<R, C, T, H, V> where
 	C::Value: From<GenericArray<u8, H>>,
 	T: From<GenericArray<u8, V>>,
@@ -161,7 +307,7 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Seq
 	C::Value: From<GenericArray<u8, H>>,
 	T: From<GenericArray<u8, V>>,
 {
-	fn len(&self) -> usize {
+	fn len(&self) -> u64 {
 		self.len
 	}
 }
@@ -170,7 +316,7 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Lea
 	C::Value: From<GenericArray<u8, H>>,
 	T: From<GenericArray<u8, V>>,
 {
-	type Metadata = (C::Value, usize, Option<u64>);
+	type Metadata = (C::Value, u64, Option<u64>);
 
 	fn metadata(&self) -> Self::Metadata {
 		let value_len = self.len();
@@ -194,7 +340,7 @@ impl<C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> PackedVector<Owned
 	T: From<GenericArray<u8, V>>,
 {
 	/// Create a new tuple.
-	pub fn create<DB: WriteBackend<Construct=C> + ?Sized>(db: &mut DB, value_len: usize, value_max_len: Option<u64>) -> Result<Self, Error<DB::Error>> {
+	pub fn create<DB: WriteBackend<Construct=C> + ?Sized>(db: &mut DB, value_len: u64, value_max_len: Option<u64>) -> Result<Self, Error<DB::Error>> {
 		let host_max_len = value_max_len.map(|l| host_max_len::<H, V>(l));
 		let host_len = host_len::<H, V>(value_len);
 
@@ -208,6 +354,37 @@ impl<C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> PackedVector<Owned
 	}
 }
 
+impl<C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> PackedVector<Owned, C, T, H, V> where
+	C::Value: From<GenericArray<u8, H>>,
+	T: From<GenericArray<u8, V>>,
+{
+	/// Create a new tuple from packed bytes, chunking them directly
+	/// into host values. The length of bytes must be a multiple of the
+	/// value size.
+	pub fn from_bytes<DB: WriteBackend<Construct=C> + ?Sized>(db: &mut DB, bytes: &[u8], value_max_len: Option<u64>) -> Result<Self, Error<DB::Error>> {
+		if bytes.len() % V::to_usize() != 0 {
+			return Err(Error::InvalidParameter)
+		}
+
+		let value_len = (bytes.len() / V::to_usize()) as u64;
+		let host_max_len = value_max_len.map(|l| host_max_len::<H, V>(l));
+
+		let hosts = bytes.chunks(H::to_usize()).map(|chunk| {
+			let mut host_value = GenericArray::<u8, H>::default();
+			host_value[..chunk.len()].copy_from_slice(chunk);
+			host_value.into()
+		}).collect::<Vec<C::Value>>();
+
+		let tuple = Vector::create_from(db, &hosts, host_max_len)?;
+		Ok(Self {
+			tuple,
+			len: value_len,
+			max_len: value_max_len,
+			_marker: PhantomData,
+		})
+	}
+}
+
 /// `PackedList` with owned root.
 pub type OwnedPackedList<C, T, H, V> = PackedList<Owned, C, T, H, V>;
 
@@ -222,16 +399,16 @@ pub struct PackedList<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: Arr
 	T: From<GenericArray<u8, V>>;
 
 impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> PackedList<R, C, T, H, V> where
-	C::Value: From<usize> + Into<usize> + From<GenericArray<u8, H>> + AsRef<[u8]> + AsMut<[u8]>,
+	C::Value: From<u64> + Into<u64> + From<GenericArray<u8, H>> + AsRef<[u8]> + AsMut<[u8]>,
 	T: From<GenericArray<u8, V>> + Into<GenericArray<u8, V>>,
 {
 	/// Get value at index.
-	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(&self, db: &mut DB, index: usize) -> Result<T, Error<DB::Error>> {
+	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(&self, db: &mut DB, index: u64) -> Result<T, Error<DB::Error>> {
 		self.0.with(db, |tuple, db| tuple.get(db, index))
 	}
 
 	/// Set value at index.
-	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: usize, value: T) -> Result<(), Error<DB::Error>> {
+	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(&mut self, db: &mut DB, index: u64, value: T) -> Result<(), Error<DB::Error>> {
 		self.0.with_mut(db, |tuple, db| tuple.set(db, index, value))
 	}
 
@@ -246,8 +423,35 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Pac
 	}
 }
 
+impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>> PackedList<R, C, T, H, typenum::U8> where
+	C::Value: From<u64> + Into<u64> + From<GenericArray<u8, H>> + AsRef<[u8]> + AsMut<[u8]>,
+	T: From<GenericArray<u8, typenum::U8>> + Into<GenericArray<u8, typenum::U8>>,
+{
+	/// Add `delta` to the little-endian `u64` at `index` in place.
+	/// Returns the new value, or `InvalidParameter` on overflow.
+	pub fn add_assign<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		delta: u64,
+	) -> Result<u64, Error<DB::Error>> {
+		self.0.with_mut(db, |tuple, db| tuple.add_assign(db, index, delta))
+	}
+
+	/// Subtract `delta` from the little-endian `u64` at `index` in
+	/// place. Returns the new value, or `InvalidParameter` on underflow.
+	pub fn sub_assign<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		delta: u64,
+	) -> Result<u64, Error<DB::Error>> {
+		self.0.with_mut(db, |tuple, db| tuple.sub_assign(db, index, delta))
+	}
+}
+
 impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Tree for PackedList<R, C, T, H, V> where
-	C::Value: From<usize> + Into<usize> + From<GenericArray<u8, H>>,
+	C::Value: From<u64> + Into<u64> + From<GenericArray<u8, H>>,
 	T: From<GenericArray<u8, V>>,
 {
 	type RootStatus = R;
@@ -267,16 +471,16 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Tre
 }
 
 impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Sequence for PackedList<R, C, T, H, V> where
-	C::Value: From<usize> + Into<usize> + From<GenericArray<u8, H>>,
+	C::Value: From<u64> + Into<u64> + From<GenericArray<u8, H>>,
 	T: From<GenericArray<u8, V>>,
 {
-	fn len(&self) -> usize {
+	fn len(&self) -> u64 {
 		self.0.len()
 	}
 }
 
 impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Leak for PackedList<R, C, T, H, V> where
-	C::Value: From<usize> + Into<usize> + From<GenericArray<u8, H>>,
+	C::Value: From<u64> + Into<u64> + From<GenericArray<u8, H>>,
 	T: From<GenericArray<u8, V>>,
 {
 	type Metadata = <LengthMixed<R, C, Vector<Dangling, C>> as Leak>::Metadata;
@@ -291,7 +495,7 @@ impl<R: RootStatus, C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> Lea
 }
 
 impl<C: Construct, T, H: ArrayLength<u8>, V: ArrayLength<u8>> PackedList<Owned, C, T, H, V> where
-	C::Value: From<usize> + Into<usize> + From<GenericArray<u8, H>>,
+	C::Value: From<u64> + Into<u64> + From<GenericArray<u8, H>>,
 	T: From<GenericArray<u8, V>>,
 {
 	/// Create a new vector.
@@ -324,27 +528,27 @@ mod tests {
 		}
 	}
 
-	impl From<usize> for ListValue {
-		fn from(value: usize) -> Self {
+	impl From<u64> for ListValue {
+		fn from(value: u64) -> Self {
 			let mut ret = [0u8; 32];
-			let bytes = (value as u64).to_le_bytes();
+			let bytes = value.to_le_bytes();
 			(&mut ret[0..8]).copy_from_slice(&bytes);
 			ListValue(ret)
 		}
 	}
 
-	impl Into<usize> for ListValue {
-		fn into(self) -> usize {
+	impl Into<u64> for ListValue {
+		fn into(self) -> u64 {
 			let mut raw = [0u8; 8];
 			(&mut raw).copy_from_slice(&self.0[0..8]);
-			u64::from_le_bytes(raw) as usize
+			u64::from_le_bytes(raw)
 		}
 	}
 
 	impl From<GenericArray<u8, U32>> for ListValue {
 		fn from(arr: GenericArray<u8, U32>) -> ListValue {
 			let mut raw = [0u8; 32];
-			(&mut raw[0..8]).copy_from_slice(&arr[0..8]);
+			raw.copy_from_slice(&arr[..]);
 			ListValue(raw)
 		}
 	}
@@ -392,6 +596,106 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn test_iter() {
+		let mut db = InMemory::default();
+		let mut small = PackedVector::<Owned, _, GenericArray<u8, typenum::U8>, U32, typenum::U8>::create(&mut db, 0, None).unwrap();
+		let mut large = PackedVector::<Owned, _, GenericArray<u8, U64>, U32, U64>::create(&mut db, 0, None).unwrap();
+
+		for i in 0..37 {
+			let mut value = GenericArray::<u8, typenum::U8>::default();
+			value[0] = i as u8;
+			small.push(&mut db, value).unwrap();
+
+			let mut value = GenericArray::<u8, U64>::default();
+			value[63] = i as u8;
+			large.push(&mut db, value).unwrap();
+		}
+
+		let small_values = small.iter(&mut db).collect::<Result<Vec<_>, _>>().unwrap();
+		assert_eq!(small_values.len(), 37);
+		for (i, value) in small_values.into_iter().enumerate() {
+			assert_eq!(value, small.get(&mut db, i as u64).unwrap());
+		}
+
+		let large_values = large.iter(&mut db).collect::<Result<Vec<_>, _>>().unwrap();
+		assert_eq!(large_values.len(), 37);
+		for (i, value) in large_values.into_iter().enumerate() {
+			assert_eq!(value, large.get(&mut db, i as u64).unwrap());
+		}
+	}
+
+	#[test]
+	fn test_bytes() {
+		let mut db = InMemory::default();
+		let bytes = (0..100u8).collect::<Vec<_>>();
+
+		let packed = PackedVector::<Owned, _, GenericArray<u8, typenum::U4>, U32, typenum::U4>::from_bytes(&mut db, &bytes, None).unwrap();
+		assert_eq!(packed.len(), 25);
+		assert_eq!(packed.get(&mut db, 3).unwrap().as_slice(), &[12, 13, 14, 15]);
+		assert_eq!(packed.to_bytes(&mut db).unwrap(), bytes);
+
+		let chunks = packed.chunks(&mut db).collect::<Result<Vec<_>, _>>().unwrap();
+		assert_eq!(chunks.len() as u64, packed.chunks_len());
+		assert_eq!(chunks.len(), 4);
+		assert_eq!(&chunks[3].0[..4], &[96, 97, 98, 99]);
+		assert_eq!(&chunks[3].0[4..], &[0; 28]);
+
+		let mut pushed = PackedVector::<Owned, _, GenericArray<u8, typenum::U4>, U32, typenum::U4>::create(&mut db, 0, None).unwrap();
+		for chunk in bytes.chunks(4) {
+			pushed.push(&mut db, GenericArray::clone_from_slice(chunk)).unwrap();
+		}
+		assert_eq!(packed.root(), pushed.root());
+
+		assert!(PackedVector::<Owned, _, GenericArray<u8, typenum::U4>, U32, typenum::U4>::from_bytes(&mut db, &bytes[..10], None).is_err());
+	}
+
+	#[test]
+	fn test_wide_chunks() {
+		type Wide = crate::InheritedDigestConstruct<sha2::Sha512>;
+
+		let mut db = crate::memory::InMemoryBackend::<Wide>::default();
+		let bytes = (0..96u8).collect::<Vec<_>>();
+		let packed = PackedVector::<Owned, Wide, GenericArray<u8, typenum::U8>, U64, typenum::U8>::from_bytes(&mut db, &bytes, None).unwrap();
+		assert_eq!(packed.len(), 12);
+		assert_eq!(packed.chunks_len(), 2);
+		assert_eq!(packed.get(&mut db, 9).unwrap().as_slice(), &bytes[72..80]);
+
+		let mut second = GenericArray::<u8, U64>::default();
+		second[..32].copy_from_slice(&bytes[64..]);
+		assert_eq!(packed.root(), Wide::intermediate_of(&GenericArray::clone_from_slice(&bytes[..64]), &second));
+		assert_eq!(packed.to_bytes(&mut db).unwrap(), bytes);
+	}
+
+	#[test]
+	fn test_add_assign() {
+		type Balances = PackedList<Owned, crate::InheritedDigestConstruct<Sha256, ListValue>, GenericArray<u8, typenum::U8>, U32, typenum::U8>;
+
+		fn balance(value: u64) -> GenericArray<u8, typenum::U8> {
+			GenericArray::clone_from_slice(&value.to_le_bytes())
+		}
+
+		let mut db = InMemory::default();
+		let mut balances = Balances::create(&mut db, None).unwrap();
+		let mut expected = Balances::create(&mut db, None).unwrap();
+		for i in 0..10 {
+			balances.push(&mut db, balance(i * 100)).unwrap();
+			expected.push(&mut db, balance(i * 100)).unwrap();
+		}
+
+		assert_eq!(balances.add_assign(&mut db, 5, 42).unwrap(), 542);
+		assert_eq!(balances.sub_assign(&mut db, 9, 900).unwrap(), 0);
+		expected.set(&mut db, 5, balance(542)).unwrap();
+		expected.set(&mut db, 9, balance(0)).unwrap();
+		assert_eq!(balances.root(), expected.root());
+
+		assert_eq!(balances.sub_assign(&mut db, 9, 1), Err(Error::InvalidParameter));
+		balances.add_assign(&mut db, 2, u64::MAX - 200).unwrap();
+		assert_eq!(balances.add_assign(&mut db, 2, 1), Err(Error::InvalidParameter));
+		assert_eq!(balances.get(&mut db, 2).unwrap(), balance(u64::MAX));
+		assert!(matches!(balances.add_assign(&mut db, 10, 1), Err(Error::AccessOverflowed(_))));
+	}
+
 	#[test]
 	fn test_vec() {
 		let mut db = InMemory::default();
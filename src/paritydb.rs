@@ -0,0 +1,93 @@
+use alloc::vec::Vec;
+use std::path::Path;
+
+use crate::kv::{KeyValueDB, KeyValueOp, KeyValueStore};
+use crate::refcount::RefCountBackend;
+
+/// Key-value database over a parity-db column.
+pub struct ParityDB {
+	db: parity_db::Db,
+	column: u8,
+}
+
+impl ParityDB {
+	/// Create a new database over the given column of an opened
+	/// parity-db instance.
+	pub fn new(db: parity_db::Db, column: u8) -> Self {
+		Self { db, column }
+	}
+
+	/// Options for a parity-db instance at path, where the given column
+	/// is tuned for merkle nodes. Node keys are 32-byte hashes, so the
+	/// column is marked as uniform, and values are not compressed.
+	pub fn options(path: &Path, num_columns: u8, column: u8) -> parity_db::Options {
+		let mut options = parity_db::Options::with_columns(path, num_columns);
+		let column = &mut options.columns[column as usize];
+		column.uniform = true;
+		column.compression = parity_db::CompressionType::NoCompression;
+		options
+	}
+
+	/// Get a reference to the underlying parity-db instance.
+	pub fn db(&self) -> &parity_db::Db {
+		&self.db
+	}
+}
+
+impl KeyValueDB for ParityDB {
+	type Error = parity_db::Error;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.db.get(self.column, key)
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let column = self.column;
+		self.db.commit(ops.into_iter().map(|op| match op {
+			KeyValueOp::Put(key, value) => (column, key, Some(value)),
+			KeyValueOp::Delete(key) => (column, key, None),
+		}))
+	}
+}
+
+/// Reference-counted merkle database persisted in parity-db.
+pub type ParityBackend<C> = RefCountBackend<C, KeyValueStore<ParityDB>>;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_parity_backend() {
+		let path = std::env::temp_dir().join(format!("bm-paritydb-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+
+		let mut memory = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut memory, 0, None).unwrap();
+		for i in 0..9 {
+			vector.push(&mut memory, value(i)).unwrap();
+		}
+
+		{
+			let parity = parity_db::Db::open_or_create(&ParityDB::options(&path, 1, 0)).unwrap();
+			let mut db = ParityBackend::<Construct>::new(KeyValueStore::new(ParityDB::new(parity, 0)));
+			db.migrate_from(&memory).unwrap();
+		}
+
+		let parity = parity_db::Db::open_or_create(&ParityDB::options(&path, 1, 0)).unwrap();
+		let mut db = ParityBackend::<Construct>::new(KeyValueStore::new(ParityDB::new(parity, 0)));
+		for i in 0..9 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+		vector.drop(&mut db).unwrap();
+		drop(db);
+
+		std::fs::remove_dir_all(&path).unwrap();
+	}
+}
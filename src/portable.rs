@@ -0,0 +1,663 @@
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeSet as Set, BTreeMap as Map};
+#[cfg(feature = "std")]
+use std::collections::{HashSet as Set, HashMap as Map};
+use core::convert::TryFrom;
+use core::hash::Hash;
+
+use crate::{Construct, ReadBackend, WriteBackend, Error, ErrorContext, Index, NodePair};
+
+const MAGIC: &[u8; 6] = b"bmtree";
+const VERSION: u8 = 1;
+const LEAVES_VERSION: u8 = 2;
+const DELTA_VERSION: u8 = 3;
+const HEADER_LEN: usize = 19;
+
+const TAG_LEAF: u8 = 0;
+const TAG_BRANCH: u8 = 1;
+const TAG_REPEAT: u8 = 2;
+
+type Nodes<V> = Vec<(V, (V, V))>;
+
+/// Error when importing a tree.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ImportError<DBError> {
+	/// The header is invalid.
+	InvalidHeader,
+	/// The format version is not supported.
+	UnsupportedVersion(u8),
+	/// The length does not match the header, or the value length does
+	/// not match the construct.
+	InvalidLength,
+	/// The imported nodes do not hash to the root in the header.
+	RootMismatch,
+	/// The tree shape of a leaf-only export is malformed.
+	InvalidShape,
+	/// The base root of a delta is not in the backend.
+	MissingBase,
+	/// A node referenced but not stored by a delta is not in the
+	/// backend.
+	MissingNode,
+	/// Writing to the backend failed.
+	Backend(Error<DBError>),
+}
+
+/// Export all nodes reachable from `root` into a portable, versioned
+/// format, to be loaded by `import_tree` into any backend. Only the
+/// children of each node are stored, as the keys are hashed again on
+/// import.
+pub fn export_tree<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+) -> Result<Vec<u8>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]> + Eq + Hash + Ord,
+{
+	let value_len = root.as_ref().len();
+	let mut nodes = Vec::new();
+	let mut visited = Set::new();
+
+	let mut stack = Vec::new();
+	stack.push((root.clone(), None));
+	while let Some((key, children)) = stack.pop() {
+		match children {
+			Some((left, right)) => nodes.push((left, right)),
+			None => {
+				if visited.contains(&key) {
+					continue
+				}
+
+				if let Some((left, right)) = db.get(&key)? {
+					if left.as_ref().len() != value_len || right.as_ref().len() != value_len {
+						return Err(Error::InvalidParameter)
+					}
+
+					visited.insert(key.clone());
+					stack.push((key, Some((left.clone(), right.clone()))));
+					stack.push((right, None));
+					stack.push((left, None));
+				}
+			},
+		}
+	}
+
+	let mut ret = Vec::with_capacity(HEADER_LEN + value_len * (1 + nodes.len() * 2));
+	ret.extend_from_slice(MAGIC);
+	ret.push(VERSION);
+	ret.extend_from_slice(&(value_len as u32).to_le_bytes());
+	ret.extend_from_slice(&(nodes.len() as u64).to_le_bytes());
+	ret.extend_from_slice(root.as_ref());
+	for (left, right) in nodes {
+		ret.extend_from_slice(left.as_ref());
+		ret.extend_from_slice(right.as_ref());
+	}
+
+	Ok(ret)
+}
+
+/// Export the tree under `root` like `export_tree`, but store only its
+/// leaf values and shape, for archives where storage matters more than
+/// load time. `import_tree` hashes all intermediate nodes again. Each
+/// subtree shared within the tree, such as an empty subtree, is only
+/// stored once.
+pub fn export_tree_leaves<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+) -> Result<Vec<u8>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]> + Eq + Hash + Ord,
+{
+	let value_len = root.as_ref().len();
+	let mut body = Vec::new();
+	let mut count = 0u64;
+	let mut branches = Map::new();
+
+	let mut stack = Vec::new();
+	stack.push(root.clone());
+	while let Some(key) = stack.pop() {
+		count += 1;
+		if let Some(id) = branches.get(&key) {
+			body.push(TAG_REPEAT);
+			body.extend_from_slice(&(*id as u64).to_le_bytes());
+			continue
+		}
+
+		match db.get(&key)? {
+			Some((left, right)) => {
+				if left.as_ref().len() != value_len || right.as_ref().len() != value_len {
+					return Err(Error::InvalidParameter)
+				}
+
+				let id = branches.len();
+				branches.insert(key, id);
+				body.push(TAG_BRANCH);
+				stack.push(right);
+				stack.push(left);
+			},
+			None => {
+				body.push(TAG_LEAF);
+				body.extend_from_slice(key.as_ref());
+			},
+		}
+	}
+
+	let mut ret = Vec::with_capacity(HEADER_LEN + value_len + body.len());
+	ret.extend_from_slice(MAGIC);
+	ret.push(LEAVES_VERSION);
+	ret.extend_from_slice(&(value_len as u32).to_le_bytes());
+	ret.extend_from_slice(&count.to_le_bytes());
+	ret.extend_from_slice(root.as_ref());
+	ret.extend_from_slice(&body);
+
+	Ok(ret)
+}
+
+/// Export a partial tree checkpoint for light clients, holding exactly
+/// the nodes needed to reach each of the generalized `indices` from
+/// `root`: the nodes at `indices`, and the siblings along their paths.
+/// It uses the format of `export_tree_leaves`, with every subtree off
+/// the paths stored as a single leaf, so `import_tree` rehydrates it
+/// into a witness backend after checking that it hashes into `root`.
+pub fn export_checkpoint<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	indices: &[Index],
+) -> Result<Vec<u8>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]>,
+{
+	let value_len = root.as_ref().len();
+	let mut body = Vec::new();
+	let mut count = 0u64;
+
+	let mut stack = Vec::new();
+	stack.push((root.clone(), Index::root()));
+	while let Some((key, index)) = stack.pop() {
+		count += 1;
+		if !indices.iter().any(|target| index.has_descendant(target)) {
+			body.push(TAG_LEAF);
+			body.extend_from_slice(key.as_ref());
+			continue
+		}
+
+		let (left, right) = db.get(&key)?
+			.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<DB::Construct>(&key)))?;
+		if left.as_ref().len() != value_len || right.as_ref().len() != value_len {
+			return Err(Error::InvalidParameter)
+		}
+
+		body.push(TAG_BRANCH);
+		stack.push((right, index.right()));
+		stack.push((left, index.left()));
+	}
+
+	let mut ret = Vec::with_capacity(HEADER_LEN + value_len + body.len());
+	ret.extend_from_slice(MAGIC);
+	ret.push(LEAVES_VERSION);
+	ret.extend_from_slice(&(value_len as u32).to_le_bytes());
+	ret.extend_from_slice(&count.to_le_bytes());
+	ret.extend_from_slice(root.as_ref());
+	ret.extend_from_slice(&body);
+
+	Ok(ret)
+}
+
+/// Hash the intermediate nodes of a leaf-only export again into
+/// `nodes`, returning the root.
+fn import_leaves<C: Construct, E>(
+	mut body: &[u8],
+	value_len: usize,
+	count: u64,
+	nodes: &mut Nodes<C::Value>,
+) -> Result<C::Value, ImportError<E>> where
+	C::Value: AsMut<[u8]>,
+{
+	let mut branches: Vec<Option<C::Value>> = Vec::new();
+	let mut stack: Vec<(usize, Option<C::Value>)> = Vec::new();
+	let mut records = 0u64;
+
+	let root = loop {
+		let (tag, rest) = body.split_first().ok_or(ImportError::InvalidShape)?;
+		body = rest;
+		records += 1;
+
+		let mut value = match *tag {
+			TAG_LEAF => {
+				if body.len() < value_len {
+					return Err(ImportError::InvalidShape)
+				}
+				let mut value = C::Value::default();
+				value.as_mut().copy_from_slice(&body[..value_len]);
+				body = &body[value_len..];
+				value
+			},
+			TAG_BRANCH => {
+				stack.push((branches.len(), None));
+				branches.push(None);
+				continue
+			},
+			TAG_REPEAT => {
+				if body.len() < 8 {
+					return Err(ImportError::InvalidShape)
+				}
+				let mut id = [0u8; 8];
+				id.copy_from_slice(&body[..8]);
+				body = &body[8..];
+				let id = usize::try_from(u64::from_le_bytes(id)).map_err(|_| ImportError::InvalidShape)?;
+				branches.get(id).cloned().flatten().ok_or(ImportError::InvalidShape)?
+			},
+			_ => return Err(ImportError::InvalidShape),
+		};
+
+		let root = loop {
+			match stack.last_mut() {
+				None => break Some(value),
+				Some((_, left @ None)) => {
+					*left = Some(value);
+					break None
+				},
+				Some((id, Some(left))) => {
+					let (id, left) = (*id, left.clone());
+					stack.pop();
+					let key = C::intermediate_of(&left, &value);
+					branches[id] = Some(key.clone());
+					nodes.push((key.clone(), (left, value)));
+					value = key;
+				},
+			}
+		};
+
+		if let Some(root) = root {
+			break root
+		}
+	};
+
+	if !body.is_empty() || records != count {
+		return Err(ImportError::InvalidShape)
+	}
+	Ok(root)
+}
+
+/// Import nodes exported by `export_tree`, `export_tree_leaves` or
+/// `export_checkpoint` into the backend, in a single batch, returning
+/// the root. All nodes are checked to hash into the root before any is
+/// inserted. The root is not rootified.
+pub fn import_tree<DB: WriteBackend + ?Sized>(
+	bytes: &[u8],
+	db: &mut DB,
+) -> Result<<DB::Construct as Construct>::Value, ImportError<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]> + AsMut<[u8]> + Eq + Hash + Ord,
+{
+	if bytes.len() < HEADER_LEN || &bytes[..6] != MAGIC {
+		return Err(ImportError::InvalidHeader)
+	}
+	if bytes[6] != VERSION && bytes[6] != LEAVES_VERSION {
+		return Err(ImportError::UnsupportedVersion(bytes[6]))
+	}
+
+	let mut value_len = [0u8; 4];
+	value_len.copy_from_slice(&bytes[7..11]);
+	let value_len = u32::from_le_bytes(value_len) as usize;
+	let mut count = [0u8; 8];
+	count.copy_from_slice(&bytes[11..19]);
+	let count = u64::from_le_bytes(count);
+
+	let mut root = <DB::Construct as Construct>::Value::default();
+	if bytes[6] == LEAVES_VERSION {
+		if root.as_ref().len() != value_len || bytes.len() < HEADER_LEN + value_len {
+			return Err(ImportError::InvalidLength)
+		}
+		root.as_mut().copy_from_slice(&bytes[HEADER_LEN..(HEADER_LEN + value_len)]);
+
+		let mut nodes = Vec::new();
+		let imported = import_leaves::<DB::Construct, _>(
+			&bytes[(HEADER_LEN + value_len)..], value_len, count, &mut nodes
+		)?;
+		if imported != root {
+			return Err(ImportError::RootMismatch)
+		}
+
+		return insert_nodes(db, nodes).map(|()| root)
+	}
+
+	let count = usize::try_from(count).map_err(|_| ImportError::InvalidLength)?;
+	let expected_len = count.checked_mul(2)
+		.and_then(|len| len.checked_add(1))
+		.and_then(|len| len.checked_mul(value_len))
+		.and_then(|len| len.checked_add(HEADER_LEN));
+	if root.as_ref().len() != value_len || expected_len != Some(bytes.len()) {
+		return Err(ImportError::InvalidLength)
+	}
+	root.as_mut().copy_from_slice(&bytes[HEADER_LEN..(HEADER_LEN + value_len)]);
+
+	let nodes = read_nodes::<DB::Construct, _>(&bytes[(HEADER_LEN + value_len)..], value_len, value_len * 2, &root)?;
+	insert_nodes(db, nodes)?;
+	Ok(root)
+}
+
+/// Hash the node records of a full export or a delta, children before
+/// parents, checking that they hash into `root`. Each record starts
+/// with the two children, and is `record_len` long.
+fn read_nodes<C: Construct, E>(
+	records: &[u8],
+	value_len: usize,
+	record_len: usize,
+	root: &C::Value,
+) -> Result<Nodes<C::Value>, ImportError<E>> where
+	C::Value: AsMut<[u8]> + Eq + Hash + Ord,
+{
+	let mut nodes = Vec::with_capacity(records.len() / record_len.max(1));
+	let mut dangling = Set::new();
+	for record in records.chunks(record_len) {
+		let mut left = C::Value::default();
+		let mut right = C::Value::default();
+		left.as_mut().copy_from_slice(&record[..value_len]);
+		right.as_mut().copy_from_slice(&record[value_len..(value_len * 2)]);
+
+		let key = C::intermediate_of(&left, &right);
+		dangling.remove(&left);
+		dangling.remove(&right);
+		dangling.insert(key.clone());
+		nodes.push((key, (left, right)));
+	}
+
+	if !nodes.is_empty() && (dangling.len() != 1 || !dangling.contains(root)) {
+		return Err(ImportError::RootMismatch)
+	}
+	Ok(nodes)
+}
+
+/// Export the nodes of the tree under `root` that are not in the tree
+/// under `base`, an earlier root of the same evolving tree, to be
+/// applied by `apply_delta` on a replica holding `base`. All nodes
+/// under `base` are collected first, so any subtree of the new tree
+/// already in `base`, even at another position, is referenced by its
+/// hash in its parent instead of stored. Each record flags which of
+/// its children are nodes rather than leaves, so that the replica can
+/// check that the referenced nodes are present. The tree under `base`
+/// must still be in the backend, for example kept by a `Snapshot`.
+pub fn export_delta<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	base: &<DB::Construct as Construct>::Value,
+	root: &<DB::Construct as Construct>::Value,
+) -> Result<Vec<u8>, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]> + Eq + Hash + Ord,
+{
+	if base != root && db.get(base)?.is_none() {
+		return Err(Error::CorruptedDatabase(ErrorContext::missing::<DB::Construct>(base)))
+	}
+
+	let mut known = Set::new();
+	let mut stack = Vec::new();
+	stack.push(base.clone());
+	while let Some(key) = stack.pop() {
+		if known.contains(&key) {
+			continue
+		}
+		if let Some((left, right)) = db.get(&key)? {
+			known.insert(key);
+			stack.push(right);
+			stack.push(left);
+		}
+	}
+
+	let value_len = root.as_ref().len();
+	let mut nodes = Vec::new();
+	let mut stack = Vec::new();
+	stack.push((root.clone(), None));
+	while let Some((key, children)) = stack.pop() {
+		match children {
+			Some((left, right)) => {
+				let flags = (known.contains(&left) as u8) | ((known.contains(&right) as u8) << 1);
+				nodes.push((left, right, flags));
+				known.insert(key);
+			},
+			None => {
+				if known.contains(&key) {
+					continue
+				}
+
+				if let Some((left, right)) = db.get(&key)? {
+					if left.as_ref().len() != value_len || right.as_ref().len() != value_len {
+						return Err(Error::InvalidParameter)
+					}
+
+					stack.push((key, Some((left.clone(), right.clone()))));
+					stack.push((right, None));
+					stack.push((left, None));
+				} else if DB::Construct::empty_children(&*db, &key).is_some() {
+					known.insert(key);
+				}
+			},
+		}
+	}
+
+	let mut ret = Vec::with_capacity(HEADER_LEN + value_len * 2 + (value_len * 2 + 1) * nodes.len());
+	ret.extend_from_slice(MAGIC);
+	ret.push(DELTA_VERSION);
+	ret.extend_from_slice(&(value_len as u32).to_le_bytes());
+	ret.extend_from_slice(&(nodes.len() as u64).to_le_bytes());
+	ret.extend_from_slice(root.as_ref());
+	ret.extend_from_slice(base.as_ref());
+	for (left, right, flags) in nodes {
+		ret.extend_from_slice(left.as_ref());
+		ret.extend_from_slice(right.as_ref());
+		ret.push(flags);
+	}
+
+	Ok(ret)
+}
+
+/// Apply a delta exported by `export_delta` to a backend holding its
+/// base tree, in a single batch, returning the new root. The nodes are
+/// checked to hash into the new root, and every node they reference
+/// outside the delta is checked to be in the backend, before any is
+/// inserted. Neither root is rootified or unrootified.
+pub fn apply_delta<DB: WriteBackend + ?Sized>(
+	bytes: &[u8],
+	db: &mut DB,
+) -> Result<<DB::Construct as Construct>::Value, ImportError<DB::Error>> where
+	<DB::Construct as Construct>::Value: AsRef<[u8]> + AsMut<[u8]> + Eq + Hash + Ord,
+{
+	if bytes.len() < HEADER_LEN || &bytes[..6] != MAGIC {
+		return Err(ImportError::InvalidHeader)
+	}
+	if bytes[6] != DELTA_VERSION {
+		return Err(ImportError::UnsupportedVersion(bytes[6]))
+	}
+
+	let mut value_len = [0u8; 4];
+	value_len.copy_from_slice(&bytes[7..11]);
+	let value_len = u32::from_le_bytes(value_len) as usize;
+	let mut count = [0u8; 8];
+	count.copy_from_slice(&bytes[11..19]);
+	let count = usize::try_from(u64::from_le_bytes(count)).map_err(|_| ImportError::InvalidLength)?;
+
+	let mut root = <DB::Construct as Construct>::Value::default();
+	let mut base = <DB::Construct as Construct>::Value::default();
+	let record_len = value_len * 2 + 1;
+	let expected_len = count.checked_mul(record_len)
+		.and_then(|len| len.checked_add(value_len * 2))
+		.and_then(|len| len.checked_add(HEADER_LEN));
+	if root.as_ref().len() != value_len || expected_len != Some(bytes.len()) {
+		return Err(ImportError::InvalidLength)
+	}
+	root.as_mut().copy_from_slice(&bytes[HEADER_LEN..(HEADER_LEN + value_len)]);
+	base.as_mut().copy_from_slice(&bytes[(HEADER_LEN + value_len)..(HEADER_LEN + value_len * 2)]);
+
+	if db.get(&base).map_err(|e| ImportError::Backend(Error::Backend(e)))?.is_none() {
+		return Err(ImportError::MissingBase)
+	}
+
+	let records = &bytes[(HEADER_LEN + value_len * 2)..];
+	let nodes = read_nodes::<DB::Construct, _>(records, value_len, record_len, &root)?;
+
+	let keys = nodes.iter().map(|(key, _)| key).collect::<Set<_>>();
+	for ((_, (left, right)), record) in nodes.iter().zip(records.chunks(record_len)) {
+		let flags = record[value_len * 2];
+		for (child, flag) in [(left, 0b01), (right, 0b10)] {
+			if flags & flag == 0 || keys.contains(child) ||
+				DB::Construct::empty_children(&*db, child).is_some()
+			{
+				continue
+			}
+			if db.get(child).map_err(|e| ImportError::Backend(Error::Backend(e)))?.is_none() {
+				return Err(ImportError::MissingNode)
+			}
+		}
+	}
+
+	insert_nodes(db, nodes)?;
+	Ok(root)
+}
+
+fn insert_nodes<DB: WriteBackend + ?Sized>(
+	db: &mut DB,
+	nodes: Vec<(<DB::Construct as Construct>::Value, NodePair<DB>)>,
+) -> Result<(), ImportError<DB::Error>> {
+	db.begin_batch().map_err(|e| ImportError::Backend(Error::Backend(e)))?;
+	for (key, value) in nodes {
+		db.insert(key, value).map_err(|e| ImportError::Backend(Error::Backend(e)))?;
+	}
+	db.commit_batch().map_err(|e| ImportError::Backend(Error::Backend(e)))?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, InMemoryRefCountBackend, Vector,
+				Owned, Dangling, Tree, Leak};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_export_import() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..6 {
+			vector.push(&mut db, value(i % 2)).unwrap();
+		}
+
+		let bytes = export_tree(&mut db, &vector.root()).unwrap();
+		let mut imported = InMemoryRefCountBackend::<Construct>::default();
+		assert_eq!(import_tree(&bytes, &mut imported).unwrap(), vector.root());
+		let imported_vector = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		for i in 0..6 {
+			assert_eq!(imported_vector.get(&mut imported, i).unwrap(), value((i % 2) as u8));
+		}
+
+		let mut corrupted = bytes.clone();
+		*corrupted.last_mut().unwrap() ^= 1;
+		assert_eq!(import_tree(&corrupted, &mut imported), Err(ImportError::RootMismatch));
+		let mut corrupted = bytes.clone();
+		corrupted[HEADER_LEN + 32] ^= 1;
+		assert_eq!(import_tree(&corrupted, &mut imported), Err(ImportError::RootMismatch));
+		let mut future = bytes.clone();
+		future[6] = 3;
+		assert_eq!(import_tree(&future, &mut imported), Err(ImportError::UnsupportedVersion(3)));
+		assert_eq!(import_tree(&bytes[..20], &mut imported), Err(ImportError::InvalidLength));
+	}
+	#[test]
+	fn test_export_leaves() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, Some(64)).unwrap();
+		for i in 0..20 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let bytes = export_tree_leaves(&mut db, &vector.root()).unwrap();
+		assert!(bytes.len() * 2 < export_tree(&mut db, &vector.root()).unwrap().len());
+		let mut imported = InMemoryBackend::<Construct>::default();
+		assert_eq!(import_tree(&bytes, &mut imported).unwrap(), vector.root());
+		let imported_vector = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		for i in 0..20 {
+			assert_eq!(imported_vector.get(&mut imported, i).unwrap(), value(i as u8));
+		}
+
+		let body = HEADER_LEN + 32;
+		let leaf = body + bytes[body..].iter().position(|tag| *tag == TAG_LEAF).unwrap();
+		let mut corrupted = bytes.clone();
+		corrupted[leaf + 1] ^= 1;
+		assert_eq!(import_tree(&corrupted, &mut imported), Err(ImportError::RootMismatch));
+		assert_eq!(import_tree(&bytes[..(bytes.len() - 1)], &mut imported), Err(ImportError::InvalidShape));
+		let mut trailing = bytes.clone();
+		trailing.push(TAG_LEAF);
+		assert_eq!(import_tree(&trailing, &mut imported), Err(ImportError::InvalidShape));
+	}
+	#[test]
+	fn test_export_checkpoint() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, Some(64)).unwrap();
+		for i in 0..20 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let root = vector.root();
+		let indices = [Index::from_depth(3, 6), Index::from_depth(17, 6), Index::from_depth(1, 2)];
+
+		let bytes = export_checkpoint(&mut db, &root, &indices).unwrap();
+		assert_eq!(vector.depth(), 6);
+		assert!(bytes.len() < export_tree_leaves(&mut db, &root).unwrap().len());
+		let mut witness = InMemoryBackend::<Construct>::default();
+		assert_eq!(import_tree(&bytes, &mut witness).unwrap(), root);
+		let imported = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		assert_eq!(imported.get(&mut witness, 3).unwrap(), value(3));
+		assert_eq!(imported.get(&mut witness, 17).unwrap(), value(17));
+		assert!(imported.get(&mut witness, 4).is_err());
+		assert_eq!(witness.as_ref().iter().filter(|(_, (children, _))| children.is_some()).count(), 10);
+
+		assert_eq!(export_checkpoint(&mut db, &root, &[]).unwrap().len(), HEADER_LEN + 32 + 33);
+		let mut corrupted = bytes.clone();
+		*corrupted.last_mut().unwrap() ^= 1;
+		assert_eq!(import_tree(&corrupted, &mut witness), Err(ImportError::RootMismatch));
+		assert!(export_checkpoint(&mut db, &value(1), &indices).is_err());
+	}
+	#[test]
+	fn test_delta() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..8 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let base = vector.root();
+		db.rootify(&base).unwrap();
+		let mut replica = InMemoryBackend::<Construct>::default();
+		import_tree(&export_tree(&mut db, &base).unwrap(), &mut replica).unwrap();
+
+		vector.set(&mut db, 6, value(60)).unwrap();
+		vector.set(&mut db, 1, value(10)).unwrap();
+		let bytes = export_delta(&mut db, &base, &vector.root()).unwrap();
+		assert_eq!(bytes.len(), HEADER_LEN + 64 + 5 * 65);
+		assert_eq!(apply_delta(&bytes, &mut replica).unwrap(), vector.root());
+		let replicated = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		for i in 0..8 {
+			assert_eq!(replicated.get(&mut replica, i).unwrap(), vector.get(&mut db, i).unwrap());
+		}
+
+		assert_eq!(export_delta(&mut db, &base, &base).unwrap().len(), HEADER_LEN + 64);
+		assert_eq!(apply_delta(&bytes, &mut InMemoryBackend::<Construct>::default()), Err(ImportError::MissingBase));
+		assert_eq!(import_tree(&bytes, &mut replica), Err(ImportError::UnsupportedVersion(DELTA_VERSION)));
+		let mut corrupted = bytes.clone();
+		let last = corrupted.len() - 2;
+		corrupted[last] ^= 1;
+		assert_eq!(apply_delta(&corrupted, &mut replica), Err(ImportError::RootMismatch));
+		assert_eq!(apply_delta(&bytes[..(bytes.len() - 1)], &mut replica), Err(ImportError::InvalidLength));
+
+		let mut partial = InMemoryBackend::<Construct>::default();
+		partial.insert(base, db.get(&base).unwrap().unwrap()).unwrap();
+		assert_eq!(apply_delta(&bytes, &mut partial), Err(ImportError::MissingNode));
+
+		vector.push(&mut db, value(8)).unwrap();
+		let bytes = export_delta(&mut db, &base, &vector.root()).unwrap();
+		assert_eq!(bytes.len(), HEADER_LEN + 64 + 9 * 65);
+		assert_eq!(apply_delta(&bytes, &mut replica).unwrap(), vector.root());
+		let replicated = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		assert_eq!(replicated.get(&mut replica, 8).unwrap(), value(8));
+
+		db.unrootify(&base).unwrap();
+		assert!(export_delta(&mut db, &base, &vector.root()).is_err());
+		vector.drop(&mut db).unwrap();
+	}
+}
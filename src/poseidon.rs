@@ -0,0 +1,76 @@
+use ark_ff::PrimeField;
+use light_poseidon::{Poseidon, PoseidonHasher, PoseidonParameters};
+use core::marker::PhantomData;
+
+use crate::{Construct, WriteBackend};
+
+/// Poseidon parameters used by `PoseidonConstruct`.
+pub trait PoseidonParams {
+	/// Field of all values in the tree.
+	type Field: PrimeField;
+
+	/// Parameters of width 3, hashing two field elements.
+	fn parameters() -> PoseidonParameters<Self::Field>;
+}
+
+/// Circom compatible Poseidon parameters over the BN254 scalar field.
+pub struct CircomBn254;
+
+impl PoseidonParams for CircomBn254 {
+	type Field = ark_bn254::Fr;
+
+	fn parameters() -> PoseidonParameters<ark_bn254::Fr> {
+		light_poseidon::parameters::bn254_x5::get_poseidon_parameters(3)
+			.expect("Width 3 is supported by circom parameters; qed")
+	}
+}
+
+/// Poseidon construct, with field elements as values and inherited
+/// empty. Trees built with it can be proven inside SNARK circuits.
+pub struct PoseidonConstruct<P: PoseidonParams=CircomBn254>(PhantomData<P>);
+
+impl<P: PoseidonParams> Construct for PoseidonConstruct<P> {
+	type Value = P::Field;
+
+	fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value {
+		Poseidon::new(P::parameters()).hash(&[*left, *right])
+			.expect("Parameters are of width 3 and two inputs are given; qed")
+	}
+
+	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
+		db: &mut DB,
+		depth_to_bottom: usize
+	) -> Result<Self::Value, DB::Error> {
+		let mut current = Self::Value::default();
+		for _ in 0..depth_to_bottom {
+			let value = (current, current);
+			let key = Self::intermediate_of(&value.0, &value.1);
+			db.insert(key, value)?;
+			current = key;
+		}
+		Ok(current)
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "{}", value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{InMemoryBackend, Vector, Owned, Tree};
+	use ark_bn254::Fr;
+
+	#[test]
+	fn test_poseidon() {
+		let mut db = InMemoryBackend::<PoseidonConstruct>::default();
+		let mut vector = Vector::<Owned, PoseidonConstruct>::create(&mut db, 0, None).unwrap();
+		vector.push(&mut db, Fr::from(1u64)).unwrap();
+		vector.push(&mut db, Fr::from(2u64)).unwrap();
+
+		let mut poseidon = Poseidon::<Fr>::new_circom(2).unwrap();
+		assert_eq!(vector.root(), poseidon.hash(&[Fr::from(1u64), Fr::from(2u64)]).unwrap());
+		assert_eq!(vector.get(&mut db, 1).unwrap(), Fr::from(2u64));
+	}
+}
@@ -0,0 +1,155 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+use crate::{Construct, ReadBackend, Index, IndexSelection, Error, ErrorContext};
+use crate::raw::children;
+
+/// Cache of the nodes read while proving leaves of a tree, for servers
+/// answering many proof queries against the same root. Paths shared by
+/// several proofs are only read once. When the tree is modified, call
+/// `invalidate` with the modified index, so that only the nodes along
+/// its path are dropped. Proving against another root clears the cache.
+#[derive(Clone, Debug)]
+pub struct ProofCache<V> {
+	root: Option<V>,
+	nodes: Map<Index, V>,
+}
+
+impl<V> Default for ProofCache<V> {
+	fn default() -> Self {
+		Self {
+			root: None,
+			nodes: Default::default(),
+		}
+	}
+}
+
+impl<V: Clone + PartialEq> ProofCache<V> {
+	/// Create a new empty proof cache.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Number of cached nodes.
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	/// Whether no node is cached.
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	/// Drop all cached nodes.
+	pub fn clear(&mut self) {
+		self.root = None;
+		self.nodes.clear();
+	}
+
+	/// Prove the value at `index` of the tree under `root`, returning
+	/// the value and its siblings from the bottom up, as taken by
+	/// `CompactValue::from_plain`.
+	pub fn prove<DB: ReadBackend + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		root: &V,
+		index: Index,
+	) -> Result<(V, Vec<V>), Error<DB::Error>> where
+		DB::Construct: Construct<Value=V>,
+	{
+		#[cfg(feature = "tracing")]
+		let _span = tracing::trace_span!(target: "bm", "prove", index = %index).entered();
+		if self.root.as_ref() != Some(root) {
+			self.clear();
+			self.root = Some(root.clone());
+		}
+
+		let mut current = Index::root();
+		let mut value = root.clone();
+		let mut siblings = Vec::with_capacity(index.depth());
+		for selection in index.path() {
+			let (left_index, right_index) = (current.left(), current.right());
+			let (left, right) = match (self.nodes.get(&left_index), self.nodes.get(&right_index)) {
+				(Some(left), Some(right)) => (left.clone(), right.clone()),
+				_ => {
+					let (left, right) = children(db, &value)?.ok_or_else(|| {
+						Error::CorruptedDatabase(ErrorContext::missing::<DB::Construct>(&value)).at(current)
+					})?;
+					self.nodes.insert(left_index, left.clone());
+					self.nodes.insert(right_index, right.clone());
+					(left, right)
+				},
+			};
+
+			let (next, sibling) = match selection {
+				IndexSelection::Left => ((left_index, left), right),
+				IndexSelection::Right => ((right_index, right), left),
+			};
+			siblings.push(sibling);
+			current = next.0;
+			value = next.1;
+		}
+
+		siblings.reverse();
+		Ok((value, siblings))
+	}
+
+	/// Drop the cached nodes changed by a `set` at `index`, its
+	/// ancestors and descendants, and move the cache to `new_root`.
+	pub fn invalidate(&mut self, index: Index, new_root: V) {
+		if self.root.is_none() {
+			return
+		}
+
+		self.nodes.retain(|key, _| {
+			*key != index && !key.has_descendant(&index) && !index.has_descendant(key)
+		});
+		self.root = Some(new_root);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, InstrumentedBackend, CompactValue, Vector, Owned, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_proof_cache() {
+		let mut db = InstrumentedBackend::new(InMemoryBackend::<Construct>::default());
+		let values = (0..16).map(value).collect::<Vec<_>>();
+		let mut vector = Vector::<Owned, Construct>::create_from(&mut db, &values, Some(16)).unwrap();
+		let mut cache = ProofCache::new();
+
+		db.reset();
+		let (leaf, siblings) = cache.prove(&mut db, &vector.root(), Index::from_depth(5, 4)).unwrap();
+		assert_eq!(leaf, value(5));
+		assert_eq!(CompactValue::from_plain(leaf, siblings.clone(), Index::from_depth(5, 4)).unwrap()
+				   .root::<Construct>(), vector.root());
+		assert_eq!(db.stats().reads, 4);
+		assert_eq!(cache.prove(&mut db, &vector.root(), Index::from_depth(5, 4)).unwrap(), (leaf, siblings));
+		cache.prove(&mut db, &vector.root(), Index::from_depth(4, 4)).unwrap();
+		assert_eq!(db.stats().reads, 4);
+
+		vector.set(&mut db, 3, value(42)).unwrap();
+		cache.invalidate(Index::from_depth(3, 4), vector.root());
+		db.reset();
+		let proof = cache.prove(&mut db, &vector.root(), Index::from_depth(5, 4)).unwrap();
+		assert_eq!(db.stats().reads, 2);
+		assert_eq!(proof, ProofCache::new().prove(&mut db, &vector.root(), Index::from_depth(5, 4)).unwrap());
+
+		let other = Vector::<Owned, Construct>::create_from(&mut db, &values[..8], Some(8)).unwrap();
+		cache.prove(&mut db, &other.root(), Index::from_depth(1, 3)).unwrap();
+		assert_eq!(cache.len(), 6);
+
+		vector.drop(&mut db).unwrap();
+		other.drop(&mut db).unwrap();
+	}
+}
@@ -1,4 +1,4 @@
-use crate::{Backend, ReadBackend, WriteBackend, Construct, Index, IndexRoute, IndexSelection};
+use crate::{Backend, ReadBackend, WriteBackend, Construct, Index, IndexRoute, IndexSelection, DecodeLimits, EmptyValues};
 use core::hash::Hash;
 use core::ops::Deref;
 use core::fmt;
@@ -75,6 +75,10 @@ impl<'a, DB: Backend + ?Sized> Backend for ProvingBackend<'a, DB> where
 {
 	type Construct = DB::Construct;
 	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.db.empty_values()
+	}
 }
 
 impl<'a, DB: ReadBackend + ?Sized> ReadBackend for ProvingBackend<'a, DB> where
@@ -93,6 +97,14 @@ impl<'a, DB: ReadBackend + ?Sized> ReadBackend for ProvingBackend<'a, DB> where
 		}
 		Ok(Some(value))
 	}
+
+	fn prefetch(&mut self, keys: &[<DB::Construct as Construct>::Value]) {
+		self.db.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.db.decode_limits()
+	}
 }
 
 impl<'a, DB: WriteBackend + ?Sized> WriteBackend for ProvingBackend<'a, DB> where
@@ -114,6 +126,14 @@ impl<'a, DB: WriteBackend + ?Sized> WriteBackend for ProvingBackend<'a, DB> wher
 		self.state.inserts.insert(key.clone());
 		self.db.insert(key, value)
 	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		self.db.begin_batch()
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		self.db.commit_batch()
+	}
 }
 
 /// Type of proofs.
@@ -171,6 +191,30 @@ impl<V: Eq + Hash + Ord + Clone + Default> Proofs<V> {
 		}
 	}
 
+	/// Merge proofs of another tree into the current one. Proofs of a
+	/// tree whose root is a leaf of the current tree, such as a list
+	/// inside a container inside a state, combine into a single proof
+	/// anchored at the outermost root.
+	pub fn merge(&mut self, other: Proofs<V>) {
+		self.0.extend(other.0);
+	}
+
+	/// Get the value reached from `root` by following `indices`, each
+	/// relative to the root of the tree reached by the previous one.
+	pub fn get_nested(&self, root: &V, indices: &[Index]) -> Option<V> {
+		let mut current = root.clone();
+		for index in indices {
+			for selection in index.path() {
+				let (left, right) = self.0.get(&current)?;
+				current = match selection {
+					IndexSelection::Left => left.clone(),
+					IndexSelection::Right => right.clone(),
+				};
+			}
+		}
+		Some(current)
+	}
+
 	/// Convert the compact value into full proofs.
 	pub fn from_compact<C: Construct<Value=V>>(compact: CompactValue<V>) -> (Self, V) {
 		compact.fold::<C, Proofs<V>, _>(&|key, (left_proofs, left), (right_proofs, right)| {
@@ -183,6 +227,45 @@ impl<V: Eq + Hash + Ord + Clone + Default> Proofs<V> {
 	}
 }
 
+/// Serialize proofs as a sequence of keys with their children.
+#[cfg(feature = "serde")]
+impl<V: Eq + Hash + Ord + serde::Serialize> serde::Serialize for Proofs<V> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+		S: serde::Serializer,
+	{
+		serializer.collect_seq(self.0.iter().map(|(key, (left, right))| (key, left, right)))
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, V: Eq + Hash + Ord + serde::Deserialize<'de>> serde::Deserialize<'de> for Proofs<V> {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+		D: serde::Deserializer<'de>,
+	{
+		let entries = alloc::vec::Vec::<(V, V, V)>::deserialize(deserializer)?;
+		Ok(Proofs(entries.into_iter().map(|(key, left, right)| (key, (left, right))).collect()))
+	}
+}
+
+/// Encode proofs as a sequence of keys with their children.
+#[cfg(feature = "parity-codec")]
+impl<V: Eq + Hash + Ord + parity_codec::Encode> parity_codec::Encode for Proofs<V> {
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		self.0.iter()
+			.map(|(key, (left, right))| (key, left, right))
+			.collect::<alloc::vec::Vec<_>>()
+			.encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<V: Eq + Hash + Ord + parity_codec::Decode> parity_codec::Decode for Proofs<V> {
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		let entries = alloc::vec::Vec::<(V, V, V)>::decode(input)?;
+		Some(Proofs(entries.into_iter().map(|(key, left, right)| (key, (left, right))).collect()))
+	}
+}
+
 /// Compact proofs.
 #[derive(Clone, Eq, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -234,6 +317,19 @@ impl<V: Default + Clone> CompactValue<V> {
 		self.fold::<C, (), _>(&|_, _, _| ()).1
 	}
 
+	/// Verify the compact proof against `root`, recomputing every
+	/// intermediate node, and get the value reached by following the
+	/// nested `indices` as in `Proofs::get_nested`.
+	pub fn verify_nested<C: Construct<Value=V>>(self, root: &V, indices: &[Index]) -> Option<V> where
+		V: Eq + Hash + Ord,
+	{
+		let (proofs, computed) = Proofs::from_compact::<C>(self);
+		if computed != *root {
+			return None
+		}
+		proofs.get_nested(root, indices)
+	}
+
 	/// Convert from plain proof.
 	pub fn from_plain<I: IntoIterator<Item=V>>(leaf: V, proofs: I, index: Index) -> Option<Self> {
 		let mut proofs = proofs.into_iter();
@@ -277,3 +373,53 @@ impl<V: Default + Clone> CompactValue<V> {
 		}
 	}
 }
+
+/// Root of the union of `proofs` of the same tree, hashing each shared
+/// intermediate node once. A single value covering a subtree that
+/// another proof expands must equal the subtree's computed root.
+fn merged_root<C: Construct>(proofs: &[&CompactValue<C::Value>]) -> Option<C::Value> where
+	C::Value: PartialEq,
+{
+	let mut single = None;
+	let mut lefts = alloc::vec::Vec::new();
+	let mut rights = alloc::vec::Vec::new();
+	for proof in proofs {
+		match proof {
+			CompactValue::Single(value) => match single {
+				Some(single) if single != value => return None,
+				_ => single = Some(value),
+			},
+			CompactValue::Combined(boxed) => {
+				lefts.push(&boxed.0);
+				rights.push(&boxed.1);
+			},
+		}
+	}
+
+	if lefts.is_empty() {
+		return single.cloned()
+	}
+	let key = C::intermediate_of(&merged_root::<C>(&lefts)?, &merged_root::<C>(&rights)?);
+	match single {
+		Some(single) if *single != key => None,
+		_ => Some(key),
+	}
+}
+
+/// Verify many compact proofs against the same `root` at once, such as
+/// the proofs of all client queries of a block. The proofs are merged
+/// so that intermediate nodes shared between them are only hashed
+/// once. Returns `false` if any proof is invalid, or if the proofs
+/// disagree on a node, in which case each can be verified on its own
+/// to find the culprit.
+pub fn verify_proofs<C: Construct>(root: &C::Value, proofs: &[CompactValue<C::Value>]) -> bool where
+	C::Value: PartialEq,
+{
+	if proofs.is_empty() {
+		return true
+	}
+	#[cfg(feature = "tracing")]
+	let _span = tracing::trace_span!(target: "bm", "verify_proofs", proofs = proofs.len()).entered();
+
+	merged_root::<C>(&proofs.iter().collect::<alloc::vec::Vec<_>>()).as_ref() == Some(root)
+}
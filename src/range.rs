@@ -0,0 +1,160 @@
+use core::ops::Range;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::{Construct, ReadBackend, CompactValue, Error, ErrorContext};
+use crate::raw::children;
+
+/// Whether the subtree of `width` leaves starting at `offset` overlaps
+/// `range`.
+fn overlaps(offset: u64, width: Option<u64>, range: &Range<u64>) -> bool {
+	let end = width.and_then(|width| offset.checked_add(width));
+	range.start < range.end && offset < range.end && end.map(|end| range.start < end).unwrap_or(true)
+}
+
+fn prove_node<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	node: <DB::Construct as Construct>::Value,
+	depth_to_bottom: usize,
+	offset: u64,
+	range: &Range<u64>,
+) -> Result<CompactValue<<DB::Construct as Construct>::Value>, Error<DB::Error>> {
+	let width = 1u64.checked_shl(depth_to_bottom as u32);
+	if depth_to_bottom == 0 || !overlaps(offset, width, range) {
+		return Ok(CompactValue::Single(node))
+	}
+
+	let mid = 1u64.checked_shl(depth_to_bottom as u32 - 1)
+		.and_then(|half| offset.checked_add(half))
+		.unwrap_or(u64::MAX);
+	let (left, right) = children(db, &node)?
+		.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<DB::Construct>(&node)))?;
+	if depth_to_bottom > 1 {
+		db.prefetch(&[left.clone(), right.clone()]);
+	}
+	let left = prove_node(db, left, depth_to_bottom - 1, offset, range)?;
+	let right = prove_node(db, right, depth_to_bottom - 1, mid, range)?;
+	Ok(CompactValue::Combined(Box::new((left, right))))
+}
+
+/// Prove the contiguous leaves in `range` of the tree under `root`,
+/// whose leaves are at `depth`. The proof holds the leaves in range,
+/// and only the siblings along the two boundary paths, so it is much
+/// smaller than a general multiproof of the same leaves.
+pub fn prove_range<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	depth: usize,
+	range: Range<u64>,
+) -> Result<CompactValue<<DB::Construct as Construct>::Value>, Error<DB::Error>> {
+	#[cfg(feature = "tracing")]
+	let _span = tracing::trace_span!(target: "bm", "prove_range", start = range.start, end = range.end).entered();
+	if range.start > range.end {
+		return Err(Error::InvalidParameter)
+	}
+	if let Some(width) = 1u64.checked_shl(depth as u32) {
+		if range.end > width {
+			return Err(Error::overflowed())
+		}
+	}
+
+	prove_node(db, root.clone(), depth, 0, &range)
+}
+
+fn verify_node<C: Construct>(
+	proof: CompactValue<C::Value>,
+	depth_to_bottom: usize,
+	offset: u64,
+	range: &Range<u64>,
+	leaves: &mut Vec<C::Value>,
+) -> Option<C::Value> {
+	let width = 1u64.checked_shl(depth_to_bottom as u32);
+	match proof {
+		CompactValue::Single(value) => {
+			if depth_to_bottom == 0 {
+				if range.contains(&offset) {
+					leaves.push(value.clone());
+				}
+			} else if overlaps(offset, width, range) {
+				return None
+			}
+			Some(value)
+		},
+		CompactValue::Combined(boxed) => {
+			if depth_to_bottom == 0 || !overlaps(offset, width, range) {
+				return None
+			}
+
+			let mid = 1u64.checked_shl(depth_to_bottom as u32 - 1)
+				.and_then(|half| offset.checked_add(half))
+				.unwrap_or(u64::MAX);
+			let (left, right) = *boxed;
+			let left = verify_node::<C>(left, depth_to_bottom - 1, offset, range, leaves)?;
+			let right = verify_node::<C>(right, depth_to_bottom - 1, mid, range, leaves)?;
+			Some(C::intermediate_of(&left, &right))
+		},
+	}
+}
+
+/// Verify a proof of `prove_range` against `root`, recomputing every
+/// node along the boundary paths, and get the leaves in `range`.
+pub fn verify_range<C: Construct>(
+	proof: CompactValue<C::Value>,
+	root: &C::Value,
+	depth: usize,
+	range: Range<u64>,
+) -> Option<Vec<C::Value>> where
+	C::Value: PartialEq,
+{
+	let mut leaves = Vec::new();
+	if verify_node::<C>(proof, depth, 0, &range, &mut leaves)? != *root {
+		return None
+	}
+	if leaves.len() as u64 != range.end.saturating_sub(range.start) {
+		return None
+	}
+	Some(leaves)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, ProvingBackend, Proofs, Vector, Owned, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_range() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, Some(16)).unwrap();
+		for i in 0..11 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let root = vector.root();
+
+		let proof = vector.prove_range(&mut db, 3..9).unwrap();
+		assert_eq!(verify_range::<Construct>(proof.clone(), &root, 4, 3..9),
+				   Some((3..9).map(value).collect()));
+		assert_eq!(verify_range::<Construct>(proof.clone(), &root, 4, 3..8), None);
+		assert_eq!(verify_range::<Construct>(proof.clone(), &value(0), 4, 3..9), None);
+
+		let mut proving = ProvingBackend::new(&mut db);
+		for i in 3..9 {
+			vector.get(&mut proving, i).unwrap();
+		}
+		let proofs: Proofs<_> = proving.into();
+		assert!(proof.len() <= proofs.into_compact(root).len());
+		assert_eq!(proof.len(), 6 + 5);
+
+		let proof = vector.prove_range(&mut db, 0..11).unwrap();
+		assert_eq!(verify_range::<Construct>(proof, &root, 4, 0..11),
+				   Some((0..11).map(value).collect()));
+		let proof = vector.prove_range(&mut db, 5..5).unwrap();
+		assert_eq!(proof, CompactValue::Single(root));
+		assert_eq!(verify_range::<Construct>(proof, &root, 4, 5..5), Some(Vec::new()));
+		assert!(vector.prove_range(&mut db, 3..12).is_err());
+	}
+}
@@ -1,9 +1,11 @@
 use core::marker::PhantomData;
 use alloc::vec::Vec;
 
-use crate::index::{Index, IndexSelection, IndexRoute};
-use crate::traits::{Construct, ReadBackend, WriteBackend,
-					RootStatus, Owned, Dangling, Leak, Error, Tree};
+use crate::leak::LeakGuard;
+use crate::memory::NoopBackend;
+use crate::index::{Index, IndexSelection, IndexRoute, MAX_INDEX_DEPTH};
+use crate::traits::{Construct, ReadBackend, WriteBackend, AsyncReadBackend, AsyncWriteBackend,
+					RootStatus, Owned, Dangling, Leak, Error, ErrorContext, Tree, NodePair};
 
 /// `Raw` with owned root.
 pub type OwnedRaw<C> = Raw<Owned, C>;
@@ -11,9 +13,13 @@ pub type OwnedRaw<C> = Raw<Owned, C>;
 /// `Raw` with dangling root.
 pub type DanglingRaw<C> = Raw<Dangling, C>;
 
+/// Leaves of a tree with their indices.
+type Leaves<V> = Vec<(Index, V)>;
+
 /// Raw merkle tree.
 pub struct Raw<R: RootStatus, C: Construct> {
 	root: C::Value,
+	guard: LeakGuard,
 	_marker: PhantomData<(R, C)>,
 }
 
@@ -21,11 +27,20 @@ impl<R: RootStatus, C: Construct> Default for Raw<R, C> {
 	fn default() -> Self {
 		Self {
 			root: Default::default(),
+			guard: LeakGuard::default(),
 			_marker: PhantomData,
 		}
 	}
 }
 
+impl<R: RootStatus, C: Construct> Drop for Raw<R, C> {
+	fn drop(&mut self) {
+		if R::is_owned() {
+			self.guard.check::<C>(&self.root);
+		}
+	}
+}
+
 impl<R: RootStatus, C: Construct> Tree for Raw<R, C> {
 	type RootStatus = R;
 	type Construct = C;
@@ -35,12 +50,13 @@ impl<R: RootStatus, C: Construct> Tree for Raw<R, C> {
 	}
 
 	fn drop<DB: WriteBackend<Construct=C> + ?Sized>(
-		self,
+		mut self,
 		db: &mut DB
 	) -> Result<(), Error<DB::Error>> {
 		if R::is_owned() {
 			db.unrootify(&self.root())?;
 		}
+		self.guard.release();
 		Ok(())
 	}
 
@@ -49,6 +65,18 @@ impl<R: RootStatus, C: Construct> Tree for Raw<R, C> {
 	}
 }
 
+/// Get the children of a node, reading a missing known empty value as
+/// a virtual empty subtree.
+pub(crate) fn children<C: Construct, DB: ReadBackend<Construct=C> + ?Sized>(
+	db: &mut DB,
+	value: &C::Value,
+) -> Result<Option<NodePair<DB>>, DB::Error> {
+	Ok(match db.get(value)? {
+		Some(pair) => Some(pair),
+		None => C::empty_children(&*db, value),
+	})
+}
+
 impl<R: RootStatus, C: Construct> Raw<R, C> {
 	/// Create a new raw from given root.
 	pub fn new(root: C::Value) -> Self {
@@ -61,13 +89,26 @@ impl<R: RootStatus, C: Construct> Raw<R, C> {
 		db: &mut DB,
 		index: Index
 	) -> Result<DanglingRaw<C>, Error<DB::Error>> {
-		let subroot = self.get(db, index)?.ok_or(Error::CorruptedDatabase)?;
+		let subroot = self.get_existing(db, index)?;
 		Ok(Raw {
 			root: subroot,
+			guard: LeakGuard::default(),
 			_marker: PhantomData,
 		})
 	}
 
+	/// Split the subtree at the index into its own owned tree, sharing
+	/// nodes with this tree, so that it can be modified independently.
+	pub fn owned_subtree<DB: WriteBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: Index
+	) -> Result<OwnedRaw<C>, Error<DB::Error>> {
+		let subroot = self.get_existing(db, index)?;
+		db.rootify(&subroot)?;
+		Ok(Raw::from_leaked(subroot))
+	}
+
 	/// Get value from the tree via generalized merkle index.
 	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(
 		&self,
@@ -80,7 +121,7 @@ impl<R: RootStatus, C: Construct> Raw<R, C> {
 				let mut current = self.root.clone();
 
 				for selection in selections {
-					let pair = match db.get(&current)? {
+					let pair = match children(db, &current)? {
 						Some(pair) => pair,
 						None => return Ok(None),
 					};
@@ -95,6 +136,36 @@ impl<R: RootStatus, C: Construct> Raw<R, C> {
 		}
 	}
 
+	/// Get value from the tree via generalized merkle index, returning
+	/// `Error::CorruptedDatabase` with the index, depth and hash of the
+	/// missing node if it cannot be reached.
+	pub fn get_existing<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: Index
+	) -> Result<C::Value, Error<DB::Error>> {
+		let mut current = self.root.clone();
+
+		if let IndexRoute::Select(selections) = index.route() {
+			for (depth, selection) in selections.into_iter().enumerate() {
+				let pair = match children(db, &current)? {
+					Some(pair) => pair,
+					None => return Err(Error::CorruptedDatabase(ErrorContext {
+						index: Some(index),
+						depth: Some(depth),
+						..ErrorContext::missing::<C>(&current)
+					})),
+				};
+				current = match selection {
+					IndexSelection::Left => pair.0,
+					IndexSelection::Right => pair.1,
+				};
+			}
+		}
+
+		Ok(current)
+	}
+
 	/// Set value of the merkle tree via generalized merkle index.
 	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(
 		&mut self,
@@ -115,7 +186,7 @@ impl<R: RootStatus, C: Construct> Raw<R, C> {
 				};
 				match current.clone() {
 					Some(cur) => {
-						let value = db.get(&cur)?;
+						let value = children(db, &cur)?;
 						match value {
 							Some((left, right)) => {
 								values.push((sel, (left.clone(), right.clone())));
@@ -166,6 +237,161 @@ impl<R: RootStatus, C: Construct> Raw<R, C> {
 		self.root = update;
 		Ok(())
 	}
+
+	/// Attach an existing subtree, whose root must already be an
+	/// intermediate node in the database, at the generalized merkle
+	/// index. Only the path from the index to the root is rehashed, and
+	/// the subtree is shared rather than copied.
+	pub fn graft<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: Index,
+		subtree_root: C::Value,
+	) -> Result<(), Error<DB::Error>> {
+		if db.get(&subtree_root)?.is_none() {
+			return Err(Error::CorruptedDatabase(ErrorContext {
+				index: Some(index),
+				..ErrorContext::missing::<C>(&subtree_root)
+			}))
+		}
+
+		self.set(db, index, subtree_root)
+	}
+
+	/// Enumerate the non-empty leaves at `depth` below the root, with
+	/// their generalized indices, in ascending order of index. Subtrees
+	/// whose root is the empty value of their height, or the default
+	/// value, are skipped without being read, so the cost follows the
+	/// number of non-empty leaves rather than the capacity of the tree.
+	pub fn leaves<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		depth: usize,
+	) -> Result<Leaves<C::Value>, Error<DB::Error>> where
+		C::Value: PartialEq,
+	{
+		if depth > MAX_INDEX_DEPTH {
+			return Err(Error::InvalidParameter)
+		}
+
+		let empties = (0..=depth).map(|depth_to_bottom| {
+			C::empty_at(&mut NoopBackend::<C>::new(), depth_to_bottom)
+				.expect("Noop backend never fails in set; qed")
+		}).collect::<Vec<_>>();
+		let zero = C::Value::default();
+		let mut ret = Vec::new();
+		let mut stack = alloc::vec![(self.root.clone(), Index::root(), depth)];
+		while let Some((value, index, depth_to_bottom)) = stack.pop() {
+			if value == empties[depth_to_bottom] || value == zero {
+				continue
+			}
+			if depth_to_bottom == 0 {
+				ret.push((index, value));
+				continue
+			}
+
+			let (left, right) = children(db, &value)?.ok_or_else(|| Error::CorruptedDatabase(ErrorContext {
+				index: Some(index),
+				depth: Some(depth - depth_to_bottom),
+				..ErrorContext::missing::<C>(&value)
+			}))?;
+			stack.push((right, index.right(), depth_to_bottom - 1));
+			stack.push((left, index.left(), depth_to_bottom - 1));
+		}
+
+		Ok(ret)
+	}
+}
+
+impl<R: RootStatus, C: Construct> Raw<R, C> {
+	/// Get value from the tree via generalized merkle index, using an
+	/// async backend.
+	pub async fn get_async<DB: AsyncReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: Index
+	) -> Result<Option<C::Value>, Error<DB::Error>> {
+		match index.route() {
+			IndexRoute::Root => Ok(Some(self.root.clone())),
+			IndexRoute::Select(selections) => {
+				let mut current = self.root.clone();
+
+				for selection in selections {
+					let pair = match db.get_async(&current).await? {
+						Some(pair) => pair,
+						None => match C::empty_children(&*db, &current) {
+							Some(pair) => pair,
+							None => return Ok(None),
+						},
+					};
+					current = match selection {
+						IndexSelection::Left => pair.0.clone(),
+						IndexSelection::Right => pair.1.clone(),
+					};
+				}
+
+				Ok(Some(current))
+			},
+		}
+	}
+
+	/// Set value of the merkle tree via generalized merkle index, using
+	/// an async backend.
+	pub async fn set_async<DB: AsyncWriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: Index,
+		set: C::Value,
+	) -> Result<(), Error<DB::Error>> {
+		let route = index.route();
+		let mut values = Vec::new();
+		let mut current = Some(self.root.clone());
+		let mut depth = 1;
+		while let Some(sel) = route.at_depth(depth) {
+			let value = match current.clone() {
+				Some(cur) => match db.get_async(&cur).await? {
+					Some(pair) => Some(pair),
+					None => C::empty_children(&*db, &cur),
+				},
+				None => None,
+			};
+			match value {
+				Some((left, right)) => {
+					values.push((sel, (left.clone(), right.clone())));
+					current = Some(match sel {
+						IndexSelection::Left => left,
+						IndexSelection::Right => right,
+					});
+				},
+				None => {
+					values.push((sel, Default::default()));
+					current = None;
+				},
+			}
+			depth += 1;
+		}
+
+		let mut update = set;
+		while let Some((sel, mut value)) = values.pop() {
+			match sel {
+				IndexSelection::Left => { value.0 = update.clone(); }
+				IndexSelection::Right => { value.1 = update.clone(); }
+			}
+
+			let intermediate = C::intermediate_of(&value.0, &value.1);
+
+			db.insert_async(intermediate.clone(), value).await?;
+			update = intermediate;
+		}
+
+		if R::is_owned() {
+			db.rootify_async(&update).await?;
+			db.unrootify_async(&self.root).await?;
+		}
+
+		self.root = update;
+		Ok(())
+	}
 }
 
 impl<R: RootStatus, C: Construct> Leak for Raw<R, C> {
@@ -178,31 +404,165 @@ impl<R: RootStatus, C: Construct> Leak for Raw<R, C> {
 	fn from_leaked(root: Self::Metadata) -> Self {
 		Self {
 			root,
+			guard: LeakGuard::default(),
 			_marker: PhantomData,
 		}
 	}
 }
 
+impl<C: Construct> Raw<Owned, C> {
+	/// Clone into an independent owned tree sharing all nodes with the
+	/// current one. The root is rootified again, so that each tree can
+	/// be mutated and dropped on its own.
+	pub fn clone_in<DB: WriteBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB
+	) -> Result<Self, Error<DB::Error>> {
+		db.rootify(&self.root)?;
+		Ok(Self::from_leaked(self.root.clone()))
+	}
+}
+
 impl<R: RootStatus, C: Construct> Raw<R, C> {
 	/// Convert the current value to a dangling raw.
 	pub fn as_dangling(&self) -> Raw<Dangling, C> {
 		Raw {
 			root: self.root.clone(),
+			guard: LeakGuard::default(),
 			_marker: PhantomData,
 		}
 	}
 }
 
+#[cfg(feature = "serde")]
+impl<R: RootStatus, C: Construct> serde::Serialize for Raw<R, C> where
+	C::Value: serde::Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+		S: serde::Serializer,
+	{
+		self.root.serialize(serializer)
+	}
+}
+
+/// Deserialize a raw handle from its root. Only dangling handles can be
+/// deserialized, as the root is not rootified.
+#[cfg(feature = "serde")]
+impl<'de, C: Construct> serde::Deserialize<'de> for Raw<Dangling, C> where
+	C::Value: serde::Deserialize<'de>,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+		D: serde::Deserializer<'de>,
+	{
+		Ok(Self::from_leaked(C::Value::deserialize(deserializer)?))
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<R: RootStatus, C: Construct> parity_codec::Encode for Raw<R, C> where
+	C::Value: parity_codec::Encode,
+{
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		self.root.encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<C: Construct> parity_codec::Decode for Raw<Dangling, C> where
+	C::Value: parity_codec::Decode,
+{
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		Some(Self::from_leaked(C::Value::decode(input)?))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use crate::traits::Owned;
-	use generic_array::{arr, arr_impl};
+	use generic_array::{GenericArray, arr, arr_impl};
 	use sha2::Sha256;
 
 	type Construct = crate::InheritedDigestConstruct<Sha256>;
 	type InMemory = crate::memory::InMemoryBackend<Construct>;
 
+	#[test]
+	fn test_graft() {
+		let mut db = InMemory::default();
+		let mut subtree = DanglingRaw::<Construct>::default();
+		let leaf = |i: u8| {
+			let mut value = GenericArray::<u8, typenum::U32>::default();
+			value[0] = i;
+			value
+		};
+		for i in 0..4 {
+			subtree.set(&mut db, Index::from_depth(i, 2), leaf(i as u8 + 1)).unwrap();
+		}
+
+		let mut raw = DanglingRaw::<Construct>::default();
+		raw.set(&mut db, Index::root().right(), leaf(9)).unwrap();
+		raw.graft(&mut db, Index::root().left(), subtree.root()).unwrap();
+		for i in 0..4 {
+			assert_eq!(raw.get(&mut db, Index::root().left().sub(Index::from_depth(i, 2))).unwrap(), Some(leaf(i as u8 + 1)));
+		}
+		assert_eq!(raw.get(&mut db, Index::root().right()).unwrap(), Some(leaf(9)));
+
+		assert!(matches!(raw.graft(&mut db, Index::root().right(), leaf(10)), Err(Error::CorruptedDatabase(_))));
+	}
+
+	#[test]
+	fn test_leaves() {
+		let mut db = InMemory::default();
+		let leaf = |i: u8| {
+			let mut value = GenericArray::<u8, typenum::U32>::default();
+			value[0] = i;
+			value
+		};
+
+		let mut vector = crate::Vector::<Owned, Construct>::create(&mut db, 0, Some(1 << 40)).unwrap();
+		for i in 0..5 {
+			vector.push(&mut db, leaf(i as u8 + 1)).unwrap();
+		}
+		vector.set(&mut db, 2, Default::default()).unwrap();
+		let raw = DanglingRaw::<Construct>::new(vector.root());
+		let depth = vector.depth();
+		assert_eq!(raw.leaves(&mut db, depth).unwrap(), vec![
+			(Index::from_depth(0, depth), leaf(1)), (Index::from_depth(1, depth), leaf(2)),
+			(Index::from_depth(3, depth), leaf(4)), (Index::from_depth(4, depth), leaf(5)),
+		]);
+		assert_eq!(raw.leaves(&mut db, MAX_INDEX_DEPTH + 1), Err(Error::InvalidParameter));
+		vector.drop(&mut db).unwrap();
+
+		let mut sparse = DanglingRaw::<Construct>::default();
+		sparse.set(&mut db, Index::from_depth(1 << 50, 64), leaf(7)).unwrap();
+		sparse.set(&mut db, Index::from_depth(3, 64), leaf(8)).unwrap();
+		assert_eq!(sparse.leaves(&mut db, 64).unwrap(),
+				   vec![(Index::from_depth(3, 64), leaf(8)), (Index::from_depth(1 << 50, 64), leaf(7))]);
+
+		let deep = Index::from_selections((0..200).map(|i| {
+			if i % 3 == 0 { IndexSelection::Right } else { IndexSelection::Left }
+		}));
+		let mut sparse = DanglingRaw::<Construct>::default();
+		sparse.set(&mut db, deep, leaf(9)).unwrap();
+		assert_eq!(sparse.leaves(&mut db, 200).unwrap(), vec![(deep, leaf(9))]);
+		assert!(matches!(DanglingRaw::<Construct>::new(leaf(9)).leaves(&mut db, 2),
+						 Err(Error::CorruptedDatabase(_))));
+	}
+
+	#[test]
+	fn test_sparse_depth() {
+		let mut db = InMemory::default();
+		let mut raw = DanglingRaw::<Construct>::default();
+		let index = Index::from_selections((0..256).map(|i| {
+			if i % 5 == 0 { IndexSelection::Right } else { IndexSelection::Left }
+		}));
+		let value = arr![u8; 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+						 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+		raw.set(&mut db, index, value.clone()).unwrap();
+		assert_eq!(raw.get(&mut db, index).unwrap(), Some(value));
+		assert_eq!(raw.get(&mut db, index.parent().unwrap().left()).unwrap(), Some(Default::default()));
+	}
+
 	#[test]
 	fn test_merkle_selections() {
 		assert_eq!(Index::root().route(), IndexRoute::Root);
@@ -0,0 +1,164 @@
+use core::marker::PhantomData;
+use alloc::vec::Vec;
+use alloc::string::String;
+
+use redb::ReadableTable;
+
+use crate::{Construct, Backend, ReadBackend, RefReadBackend, EmptyValues};
+use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp, KeyValueStore, decode_entry};
+use crate::refcount::RefCountBackend;
+
+type Table<'a> = redb::TableDefinition<'a, &'static [u8], &'static [u8]>;
+
+/// Key-value database over a redb table.
+pub struct RedbDB {
+	db: redb::Database,
+	table: String,
+}
+
+impl RedbDB {
+	/// Create a new database over the given table of a redb instance.
+	/// The table is created if it does not yet exist.
+	pub fn new(db: redb::Database, table: String) -> Result<Self, redb::Error> {
+		let ret = Self { db, table };
+
+		let txn = ret.db.begin_write()?;
+		txn.open_table(ret.definition())?;
+		txn.commit()?;
+
+		Ok(ret)
+	}
+
+	/// Get a reference to the underlying redb instance.
+	pub fn db(&self) -> &redb::Database {
+		&self.db
+	}
+
+	/// Open a read transaction, as a read backend. Read backends can be
+	/// opened and used concurrently, and each only sees the nodes
+	/// committed before it is opened.
+	pub fn reader<C: Construct>(&self) -> Result<RedbReadBackend<C>, redb::Error> {
+		let txn = self.db.begin_read()?;
+		let table = txn.open_table(self.definition())?;
+
+		Ok(RedbReadBackend { table, empty: EmptyValues::new(), _marker: PhantomData })
+	}
+
+	fn definition(&self) -> Table<'_> {
+		redb::TableDefinition::new(&self.table)
+	}
+}
+
+impl KeyValueDB for RedbDB {
+	type Error = redb::Error;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		let txn = self.db.begin_read()?;
+		let table = txn.open_table(self.definition())?;
+		let value = table.get(key)?.map(|value| value.value().to_vec());
+		Ok(value)
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let txn = self.db.begin_write()?;
+		{
+			let mut table = txn.open_table(self.definition())?;
+			for op in ops {
+				match op {
+					KeyValueOp::Put(key, value) => { table.insert(key.as_slice(), value.as_slice())?; },
+					KeyValueOp::Delete(key) => { table.remove(key.as_slice())?; },
+				}
+			}
+		}
+		txn.commit()?;
+		Ok(())
+	}
+}
+
+impl IterableKeyValueDB for RedbDB {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, mut f: F) -> Result<(), Self::Error> {
+		let txn = self.db.begin_read()?;
+		let table = txn.open_table(self.definition())?;
+		for entry in table.iter()? {
+			let (key, value) = entry?;
+			f(key.value(), value.value());
+		}
+		Ok(())
+	}
+}
+
+/// Reference-counted merkle database persisted in redb.
+pub type RedbBackend<C> = RefCountBackend<C, KeyValueStore<RedbDB>>;
+
+/// Read backend over a redb read transaction.
+pub struct RedbReadBackend<C: Construct> {
+	table: redb::ReadOnlyTable<&'static [u8], &'static [u8]>,
+	empty: EmptyValues<C::Value>,
+	_marker: PhantomData<C>,
+}
+
+impl<C: Construct> Backend for RedbReadBackend<C> {
+	type Construct = C;
+	type Error = redb::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		Some(&self.empty)
+	}
+}
+
+impl<C: Construct> ReadBackend for RedbReadBackend<C> where
+	C::Value: AsRef<[u8]> + AsMut<[u8]>,
+{
+	fn get(&mut self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		self.get_ref(key)
+	}
+}
+
+impl<C: Construct> RefReadBackend for RedbReadBackend<C> where
+	C::Value: AsRef<[u8]> + AsMut<[u8]>,
+{
+	fn get_ref(&self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		let value = self.table.get(key.as_ref())?;
+		Ok(value.and_then(|value| decode_entry::<C::Value>(value.value())).and_then(|entry| entry.children))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, Vector, Owned, Dangling, Tree, Leak, WriteBackend};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_redb_backend() {
+		let path = std::env::temp_dir().join(format!("bm-redb-{}", std::process::id()));
+		let _ = std::fs::remove_file(&path);
+
+		let redb = redb::Database::create(&path).unwrap();
+		let mut db = RedbBackend::<Construct>::new(KeyValueStore::new(RedbDB::new(redb, "merkle".into()).unwrap()));
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		db.begin_batch().unwrap();
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		db.commit_batch().unwrap();
+
+		let old = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		let mut reader = db.store().db().reader::<Construct>().unwrap();
+		vector.set(&mut db, 0, value(100)).unwrap();
+		assert_eq!(vector.get(&mut db, 0).unwrap(), value(100));
+		assert_eq!(old.get(&mut reader, 0).unwrap(), value(0));
+		for i in 1..9 {
+			assert_eq!(old.get(&mut reader, i).unwrap(), value(i as u8));
+		}
+
+		vector.drop(&mut db).unwrap();
+		drop(reader);
+		drop(db);
+		std::fs::remove_file(&path).unwrap();
+	}
+}
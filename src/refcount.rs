@@ -0,0 +1,288 @@
+use core::hash::Hash;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+use crate::{Construct, Backend, ReadBackend, WriteBackend, InMemoryBackend, EmptyValues};
+
+/// Reference-counted merkle node entry.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RefCountEntry<V> {
+	/// Children of the node, if it is an intermediate node.
+	pub children: Option<(V, V)>,
+	/// Number of references to this node.
+	pub refcount: usize,
+}
+
+/// Storage for reference-counted merkle nodes.
+pub trait RefCountStore<V> {
+	/// Error type for store access.
+	type Error;
+
+	/// Fetch an entry by key.
+	fn fetch(&mut self, key: &V) -> Result<Option<RefCountEntry<V>>, Self::Error>;
+	/// Store an entry.
+	fn store(&mut self, key: V, entry: RefCountEntry<V>) -> Result<(), Self::Error>;
+	/// Remove an entry.
+	fn remove(&mut self, key: &V) -> Result<(), Self::Error>;
+	/// Begin a batch of writes.
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+	/// Commit the current batch of writes.
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+/// In-memory refcount store error.
+pub enum InMemoryRefCountStoreError { }
+
+impl core::fmt::Display for InMemoryRefCountStoreError {
+	fn fmt(&self, _f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match *self { }
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InMemoryRefCountStoreError { }
+
+impl<V: Eq + Hash + Ord + Clone> RefCountStore<V> for Map<V, RefCountEntry<V>> {
+	type Error = InMemoryRefCountStoreError;
+
+	fn fetch(&mut self, key: &V) -> Result<Option<RefCountEntry<V>>, Self::Error> {
+		Ok(self.get(key).cloned())
+	}
+
+	fn store(&mut self, key: V, entry: RefCountEntry<V>) -> Result<(), Self::Error> {
+		self.insert(key, entry);
+		Ok(())
+	}
+
+	fn remove(&mut self, key: &V) -> Result<(), Self::Error> {
+		Map::remove(self, key);
+		Ok(())
+	}
+}
+
+/// Reference-counted merkle database. Nodes are freed from the store
+/// once they are no longer referenced by any root or intermediate
+/// node.
+pub struct RefCountBackend<C: Construct, S> {
+	store: S,
+	empty: EmptyValues<C::Value>,
+	_marker: PhantomData<C>,
+}
+
+/// Reference-counted merkle database backed by memory.
+pub type InMemoryRefCountBackend<C> = RefCountBackend<C, Map<<C as Construct>::Value, RefCountEntry<<C as Construct>::Value>>>;
+
+impl<C: Construct, S: Default> Default for RefCountBackend<C, S> {
+	fn default() -> Self {
+		Self::new(S::default())
+	}
+}
+
+impl<C: Construct, S> RefCountBackend<C, S> {
+	/// Create a new backend from the given store.
+	pub fn new(store: S) -> Self {
+		Self { store, empty: EmptyValues::new(), _marker: PhantomData }
+	}
+
+	/// Get a reference to the underlying store.
+	pub fn store(&self) -> &S {
+		&self.store
+	}
+
+	#[cfg(feature = "std")]
+	pub(crate) fn store_mut(&mut self) -> &mut S {
+		&mut self.store
+	}
+
+	/// Convert the backend into the underlying store.
+	pub fn into_store(self) -> S {
+		self.store
+	}
+}
+
+impl<C: Construct, S: RefCountStore<C::Value>> RefCountBackend<C, S> {
+	fn increment(&mut self, key: &C::Value) -> Result<(), S::Error> {
+		let mut entry = self.store.fetch(key)?.unwrap_or(RefCountEntry {
+			children: None,
+			refcount: 0,
+		});
+		entry.refcount += 1;
+		self.store.store(key.clone(), entry)
+	}
+
+	fn decrement(&mut self, key: &C::Value) -> Result<(), S::Error> {
+		let mut entry = match self.store.fetch(key)? {
+			Some(entry) => entry,
+			None => return Ok(()),
+		};
+		entry.refcount = entry.refcount.saturating_sub(1);
+
+		if entry.refcount == 0 {
+			self.store.remove(key)?;
+			if let Some((left, right)) = entry.children {
+				self.decrement(&left)?;
+				self.decrement(&right)?;
+			}
+			Ok(())
+		} else {
+			self.store.store(key.clone(), entry)
+		}
+	}
+}
+
+impl<C: Construct, S: RefCountStore<C::Value>> RefCountBackend<C, S> where
+	C::Value: Eq + Hash + Ord,
+{
+	/// Copy all nodes of an in-memory backend into this backend, in a
+	/// single batch. Reference counts are kept as is.
+	pub fn migrate_from(&mut self, db: &InMemoryBackend<C>) -> Result<(), S::Error> {
+		self.store.begin_batch()?;
+		for (key, (children, refcount)) in db.as_ref() {
+			if children.is_none() && refcount.is_none() {
+				continue
+			}
+
+			self.store.store(key.clone(), RefCountEntry {
+				children: children.clone(),
+				refcount: refcount.unwrap_or(0),
+			})?;
+		}
+		self.store.commit_batch()
+	}
+}
+
+impl<C: Construct, S: RefCountStore<C::Value>> Backend for RefCountBackend<C, S> {
+	type Construct = C;
+	type Error = S::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		Some(&self.empty)
+	}
+}
+
+impl<C: Construct, S: RefCountStore<C::Value>> ReadBackend for RefCountBackend<C, S> {
+	fn get(&mut self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Self::Error> {
+		Ok(self.store.fetch(key)?.and_then(|entry| entry.children))
+	}
+}
+
+impl<C: Construct, S: RefCountStore<C::Value>> WriteBackend for RefCountBackend<C, S> {
+	fn rootify(&mut self, key: &C::Value) -> Result<(), Self::Error> {
+		self.increment(key)
+	}
+
+	fn unrootify(&mut self, key: &C::Value) -> Result<(), Self::Error> {
+		self.decrement(key)
+	}
+
+	fn insert(
+		&mut self,
+		key: C::Value,
+		value: (C::Value, C::Value)
+	) -> Result<(), Self::Error> {
+		if let Some(entry) = self.store.fetch(&key)? {
+			if entry.children.is_some() {
+				return Ok(())
+			}
+		}
+
+		let (left, right) = value;
+		self.increment(&left)?;
+		self.increment(&right)?;
+
+		let refcount = self.store.fetch(&key)?.map(|entry| entry.refcount).unwrap_or(0);
+		self.store.store(key, RefCountEntry {
+			children: Some((left, right)),
+			refcount,
+		})
+	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		self.store.begin_batch()
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		self.store.commit_batch()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, Vector, Owned, Tree, Index, DanglingRaw};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type InMemory = InMemoryRefCountBackend<Construct>;
+
+	#[test]
+	fn test_free_dropped() {
+		let mut db = InMemory::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		for i in 0..9 {
+			vector.set(&mut db, i, value(100 + i as u8)).unwrap();
+		}
+		for i in 0..9 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(100 + i as u8));
+		}
+
+		let mut other = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		other.push(&mut db, value(100)).unwrap();
+
+		vector.drop(&mut db).unwrap();
+		assert_eq!(other.get(&mut db, 0).unwrap(), value(100));
+		other.drop(&mut db).unwrap();
+		assert!(db.store().is_empty());
+	}
+
+	#[test]
+	fn test_owned_subtree() {
+		let mut db = InMemory::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..8 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let mut subtree = DanglingRaw::<Construct>::new(vector.root()).owned_subtree(&mut db, Index::root().left()).unwrap();
+		vector.drop(&mut db).unwrap();
+		for i in 0..4 {
+			assert_eq!(subtree.get(&mut db, Index::from_depth(i, 2)).unwrap(), Some(value(i as u8)));
+		}
+
+		subtree.set(&mut db, Index::from_depth(0, 2), value(100)).unwrap();
+		assert_eq!(subtree.get(&mut db, Index::from_depth(0, 2)).unwrap(), Some(value(100)));
+		subtree.drop(&mut db).unwrap();
+		assert!(db.store().is_empty());
+	}
+
+	#[test]
+	fn test_migrate_from() {
+		let mut memory = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut memory, 0, None).unwrap();
+		for i in 0..9 {
+			vector.push(&mut memory, value(i)).unwrap();
+		}
+
+		let mut db = InMemory::default();
+		db.migrate_from(&memory).unwrap();
+		for i in 0..9 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+		vector.set(&mut db, 0, value(100)).unwrap();
+		vector.drop(&mut db).unwrap();
+		assert!(db.store().is_empty());
+	}
+}
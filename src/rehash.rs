@@ -0,0 +1,119 @@
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+use crate::{Construct, ReadBackend, WriteBackend, Error, ErrorContext, CopyError};
+use crate::raw::children;
+
+type Rehashed<Src, Dst> = Map<(<<Src as crate::Backend>::Construct as Construct>::Value, usize),
+							  <<Dst as crate::Backend>::Construct as Construct>::Value>;
+type RehashResult<Src, Dst> = Result<<<Dst as crate::Backend>::Construct as Construct>::Value,
+									 CopyError<<Src as crate::Backend>::Error, <Dst as crate::Backend>::Error>>;
+
+fn rehash_node<Src: ReadBackend + ?Sized, Dst: WriteBackend + ?Sized, F>(
+	src: &mut Src,
+	dst: &mut Dst,
+	node: <Src::Construct as Construct>::Value,
+	depth_to_bottom: usize,
+	leaf: &mut F,
+	rehashed: &mut Rehashed<Src, Dst>,
+) -> RehashResult<Src, Dst> where
+	<Src::Construct as Construct>::Value: Eq + Hash + Ord,
+	F: FnMut(<Src::Construct as Construct>::Value) -> <Dst::Construct as Construct>::Value,
+{
+	if depth_to_bottom == 0 {
+		return Ok(leaf(node))
+	}
+	if let Some(value) = rehashed.get(&(node.clone(), depth_to_bottom)) {
+		return Ok(value.clone())
+	}
+
+	let (left, right) = children(src, &node)
+		.map_err(|e| CopyError::Source(Error::Backend(e)))?
+		.ok_or_else(|| CopyError::Source(Error::CorruptedDatabase(ErrorContext::missing::<Src::Construct>(&node))))?;
+	let left = rehash_node(src, dst, left, depth_to_bottom - 1, leaf, rehashed)?;
+	let right = rehash_node(src, dst, right, depth_to_bottom - 1, leaf, rehashed)?;
+
+	let key = <Dst::Construct as Construct>::intermediate_of(&left, &right);
+	dst.insert(key.clone(), (left, right)).map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+	rehashed.insert((node, depth_to_bottom), key.clone());
+	Ok(key)
+}
+
+/// Rebuild the tree under `root`, whose leaves are at `depth`, under
+/// another construct, such as when a protocol migrates to a new hash
+/// function. Each leaf is converted by `leaf`, and every intermediate
+/// node is rehashed into `dst` in a single batch, so the new tree has
+/// the same shape and contents as the old one. Subtrees repeated in the
+/// source, such as empty ones, are only rebuilt once. As long as `leaf`
+/// maps the default value to the default value, empty subtrees become
+/// the empty subtrees of the new construct. The new root is returned,
+/// and is not rootified in the destination.
+pub fn rehash<Src: ReadBackend + ?Sized, Dst: WriteBackend + ?Sized, F>(
+	src: &mut Src,
+	dst: &mut Dst,
+	root: &<Src::Construct as Construct>::Value,
+	depth: usize,
+	mut leaf: F,
+) -> RehashResult<Src, Dst> where
+	<Src::Construct as Construct>::Value: Eq + Hash + Ord,
+	F: FnMut(<Src::Construct as Construct>::Value) -> <Dst::Construct as Construct>::Value,
+{
+	#[cfg(feature = "tracing")]
+	let _span = tracing::debug_span!(target: "bm", "rehash", depth).entered();
+	dst.begin_batch().map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+	let root = rehash_node(src, dst, root.clone(), depth, &mut leaf, &mut Map::new())?;
+	dst.commit_batch().map_err(|e| CopyError::Destination(Error::Backend(e)))?;
+
+	Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Dangling, Tree, Leak};
+
+	use generic_array::GenericArray;
+	use sha2::{Sha256, Sha512};
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type WideConstruct = InheritedDigestConstruct<Sha512>;
+
+	fn widen(value: GenericArray<u8, typenum::U32>) -> GenericArray<u8, typenum::U64> {
+		let mut wide = GenericArray::default();
+		wide[..32].copy_from_slice(&value);
+		wide
+	}
+
+	#[test]
+	fn test_rehash() {
+		for max_len in [None, Some(1 << 40)] {
+			let mut db = InMemoryBackend::<Construct>::default();
+			let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, max_len).unwrap();
+			let mut expected_db = InMemoryBackend::<WideConstruct>::default();
+			let mut expected = Vector::<Owned, WideConstruct>::create(&mut expected_db, 0, max_len).unwrap();
+			for i in 1..6 {
+				vector.push(&mut db, value(i)).unwrap();
+				expected.push(&mut expected_db, widen(value(i))).unwrap();
+			}
+
+			let mut dst = InMemoryBackend::<WideConstruct>::default();
+			let root = rehash(&mut db, &mut dst, &vector.root(), vector.depth(), widen).unwrap();
+			assert_eq!(root, expected.root());
+
+			let (_, len, max_len) = vector.metadata();
+			let rehashed = Vector::<Dangling, WideConstruct>::from_leaked((root, len, max_len));
+			for i in 0..5 {
+				assert_eq!(rehashed.get(&mut dst, i).unwrap(), widen(value(i as u8 + 1)));
+			}
+		}
+
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut dst = InMemoryBackend::<WideConstruct>::default();
+		assert!(matches!(rehash(&mut db, &mut dst, &value(1), 2, widen),
+						 Err(CopyError::Source(Error::CorruptedDatabase(_)))));
+	}
+}
@@ -0,0 +1,135 @@
+use alloc::vec::Vec;
+use alloc::string::String;
+
+use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp, KeyValueStore};
+use crate::refcount::RefCountBackend;
+
+/// Key-value database over RocksDB, optionally storing nodes in a
+/// column family.
+pub struct RocksDB {
+	db: rocksdb::DB,
+	column: Option<String>,
+}
+
+impl RocksDB {
+	/// Create a new database from an opened RocksDB instance. Returns
+	/// `None` if the given column family does not exist.
+	pub fn new(db: rocksdb::DB, column: Option<String>) -> Option<Self> {
+		if let Some(column) = &column {
+			db.cf_handle(column)?;
+		}
+
+		Some(Self { db, column })
+	}
+
+	/// Get a reference to the underlying RocksDB instance.
+	pub fn db(&self) -> &rocksdb::DB {
+		&self.db
+	}
+
+	fn column(&self) -> Option<&rocksdb::ColumnFamily> {
+		self.column.as_ref().map(|column| {
+			self.db.cf_handle(column).expect("column family is checked on creation; qed")
+		})
+	}
+}
+
+impl KeyValueDB for RocksDB {
+	type Error = rocksdb::Error;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		match self.column() {
+			Some(column) => self.db.get_cf(column, key),
+			None => self.db.get(key),
+		}
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let column = self.column();
+		let mut batch = rocksdb::WriteBatch::default();
+		for op in ops {
+			match (op, column) {
+				(KeyValueOp::Put(key, value), Some(column)) => batch.put_cf(column, key, value),
+				(KeyValueOp::Put(key, value), None) => batch.put(key, value),
+				(KeyValueOp::Delete(key), Some(column)) => batch.delete_cf(column, key),
+				(KeyValueOp::Delete(key), None) => batch.delete(key),
+			}
+		}
+		self.db.write(batch)
+	}
+}
+
+impl IterableKeyValueDB for RocksDB {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, mut f: F) -> Result<(), Self::Error> {
+		let iter = match self.column() {
+			Some(column) => self.db.iterator_cf(column, rocksdb::IteratorMode::Start),
+			None => self.db.iterator(rocksdb::IteratorMode::Start),
+		};
+		for (key, value) in iter {
+			f(&key, &value);
+		}
+		Ok(())
+	}
+}
+
+/// Reference-counted merkle database persisted in RocksDB.
+pub type RocksBackend<C> = RefCountBackend<C, KeyValueStore<RocksDB>>;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, Vector, Owned, Dangling, Tree, Leak, WriteBackend, Error};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_rocks_backend() {
+		let path = std::env::temp_dir().join(format!("bm-rocks-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+
+		let mut options = rocksdb::Options::default();
+		options.create_if_missing(true);
+		options.create_missing_column_families(true);
+		let rocks = rocksdb::DB::open_cf(&options, &path, &["merkle"]).unwrap();
+
+		let mut db = RocksBackend::<Construct>::new(KeyValueStore::new(RocksDB::new(rocks, Some("merkle".into())).unwrap()));
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		db.begin_batch().unwrap();
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		db.commit_batch().unwrap();
+		let metadata = vector.metadata();
+		vector.leak();
+		drop(db);
+
+		let rocks = rocksdb::DB::open_cf(&options, &path, &["merkle"]).unwrap();
+		let mut db = RocksBackend::<Construct>::new(KeyValueStore::new(RocksDB::new(rocks, Some("merkle".into())).unwrap()));
+		let mut vector = Vector::<Owned, Construct>::from_leaked(metadata);
+		for i in 0..9 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+		let mut entries = 0;
+		db.store().db().for_each_entry(|_, _| entries += 1).unwrap();
+		assert!(entries > 0);
+
+		vector.set(&mut db, 0, value(100)).unwrap();
+		assert_eq!(vector.get(&mut db, 0).unwrap(), value(100));
+		let stale = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		vector.drop(&mut db).unwrap();
+		assert!(matches!(stale.get(&mut db, 1), Err(Error::CorruptedDatabase(_))));
+
+		let deep = Vector::<Owned, Construct>::create(&mut db, 1 << 40, None).unwrap();
+		assert_eq!(deep.get(&mut db, (1 << 40) - 1).unwrap(), value(0));
+		deep.drop(&mut db).unwrap();
+		let mut entries = 0;
+		db.store().db().for_each_entry(|_, _| entries += 1).unwrap();
+		assert_eq!(entries, 0);
+
+		drop(db);
+		std::fs::remove_dir_all(&path).unwrap();
+	}
+}
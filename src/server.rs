@@ -0,0 +1,206 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::{Construct, ReadBackend, Error, Index, DanglingRaw, ProvingBackend, Proofs, CompactValue,
+			LimitedBackend, LimitedBackendError, SharedBackend, BackendFuture};
+
+/// Request for a multiproof of the nodes at `indices` under `root`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProofRequest<V> {
+	/// Identifier chosen by the client, echoed in the response.
+	pub id: u64,
+	/// Root of the tree to prove against.
+	pub root: V,
+	/// Generalized indices of the nodes to prove.
+	pub indices: Vec<Index>,
+}
+
+/// Reason a proof request was rejected.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ProofRequestError {
+	/// The request asks for more indices than allowed.
+	TooManyIndices,
+	/// Proving the request reads more nodes than allowed.
+	TooManyNodes,
+	/// A node on the path to an index is not in the backend, such as
+	/// when the root is unknown, or the backend failed.
+	Unavailable,
+}
+
+/// Response to a proof request, with the compact multiproof on
+/// success.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProofResponse<V> {
+	/// Identifier of the request.
+	pub id: u64,
+	/// Multiproof, to be checked with `CompactValue::verify_nested`.
+	pub result: Result<CompactValue<V>, ProofRequestError>,
+}
+
+/// Transport carrying requests to a `ProofServer` and responses back,
+/// such as a network connection with its framing and encoding.
+pub trait ProofTransport<V> {
+	/// Transport error, which stops the server.
+	type Error;
+
+	/// Receive the next request, or `None` once the transport is closed.
+	fn recv(&mut self) -> BackendFuture<'_, Result<Option<ProofRequest<V>>, Self::Error>>;
+	/// Send a response.
+	fn send(&mut self, response: ProofResponse<V>) -> BackendFuture<'_, Result<(), Self::Error>>;
+}
+
+/// Server answering proof requests with multiproofs read from a shared
+/// backend, for light clients. Each request is bounded in the number
+/// of indices and of nodes read, so one request cannot hold the backend
+/// lock for long. A transport is served one request at a time, and the
+/// next request is only received once the response was sent, so a slow
+/// client or transport pushes back on the requests it can make. Proofs
+/// themselves are computed synchronously under the lock.
+pub struct ProofServer<DB> {
+	db: SharedBackend<DB>,
+	max_indices: usize,
+	max_nodes: u64,
+}
+
+impl<DB> Clone for ProofServer<DB> {
+	fn clone(&self) -> Self {
+		Self { db: self.db.clone(), max_indices: self.max_indices, max_nodes: self.max_nodes }
+	}
+}
+
+impl<DB: ReadBackend> ProofServer<DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	/// Create a new server over `db`, accepting at most 256 indices and
+	/// 65536 node reads per request.
+	pub fn new(db: SharedBackend<DB>) -> Self {
+		Self { db, max_indices: 256, max_nodes: 1 << 16 }
+	}
+
+	/// Set the maximum number of indices per request.
+	pub fn with_max_indices(mut self, max_indices: usize) -> Self {
+		self.max_indices = max_indices;
+		self
+	}
+
+	/// Set the maximum number of nodes read per request.
+	pub fn with_max_nodes(mut self, max_nodes: u64) -> Self {
+		self.max_nodes = max_nodes;
+		self
+	}
+
+	/// Prove the nodes of a request. The lock of the shared backend is
+	/// held for the whole proof, so other users of the backend, and
+	/// other transports served by clones of the server, wait until it is
+	/// done. `max_nodes` bounds how long that can be.
+	pub fn prove(
+		&self,
+		request: &ProofRequest<<DB::Construct as Construct>::Value>,
+	) -> Result<CompactValue<<DB::Construct as Construct>::Value>, ProofRequestError> {
+		if request.indices.len() > self.max_indices {
+			return Err(ProofRequestError::TooManyIndices)
+		}
+
+		let mut db = self.db.lock();
+		let mut limited = LimitedBackend::new(&mut *db, self.max_nodes);
+		let mut proving = ProvingBackend::new(&mut limited);
+		let raw = DanglingRaw::<DB::Construct>::new(request.root.clone());
+		for index in &request.indices {
+			raw.get_existing(&mut proving, *index).map_err(|err| match err {
+				Error::Backend(LimitedBackendError::TooManyNodes) => ProofRequestError::TooManyNodes,
+				_ => ProofRequestError::Unavailable,
+			})?;
+		}
+
+		let proofs: Proofs<_> = proving.into();
+		Ok(proofs.into_compact(request.root.clone()))
+	}
+
+	/// Serve requests from `transport` until it is closed, returning the
+	/// number of requests served. Rejected requests are answered with
+	/// their error, while transport errors stop the server.
+	pub async fn serve<T: ProofTransport<<DB::Construct as Construct>::Value>>(
+		&self,
+		transport: &mut T,
+	) -> Result<u64, T::Error> {
+		let mut served = 0;
+		while let Some(request) = transport.recv().await? {
+			#[cfg(feature = "tracing")]
+			let _span = tracing::debug_span!(target: "bm", "prove_request", id = request.id, indices = request.indices.len()).entered();
+			let result = self.prove(&request);
+			transport.send(ProofResponse { id: request.id, result }).await?;
+			served += 1;
+		}
+
+		Ok(served)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::{block_on, value};
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+	use alloc::boxed::Box;
+	use alloc::collections::VecDeque;
+
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type Value = GenericArray<u8, typenum::U32>;
+
+	struct Channel {
+		requests: VecDeque<ProofRequest<Value>>,
+		responses: Vec<ProofResponse<Value>>,
+	}
+
+	impl ProofTransport<Value> for Channel {
+		type Error = ();
+
+		fn recv(&mut self) -> BackendFuture<'_, Result<Option<ProofRequest<Value>>, ()>> {
+			let request = self.requests.pop_front();
+			Box::pin(async move { Ok(request) })
+		}
+
+		fn send(&mut self, response: ProofResponse<Value>) -> BackendFuture<'_, Result<(), ()>> {
+			self.responses.push(response);
+			Box::pin(async { Ok(()) })
+		}
+	}
+
+	#[test]
+	fn test_proof_server() {
+		let db = SharedBackend::new(InMemoryBackend::<Construct>::default());
+		let mut vector = Vector::<Owned, Construct>::create(&mut *db.lock(), 0, Some(16)).unwrap();
+		for i in 0..16 {
+			vector.push(&mut *db.lock(), value(i)).unwrap();
+		}
+		let root = vector.root();
+
+		let server = ProofServer::new(db.clone()).with_max_indices(3).with_max_nodes(8);
+		let request = |id, indices: &[u64]| ProofRequest {
+			id, root, indices: indices.iter().map(|i| Index::from_depth(*i, 4)).collect(),
+		};
+		let mut channel = Channel {
+			requests: alloc::vec![
+				request(0, &[3, 12]),
+				request(1, &[0, 1, 2, 3]),
+				request(2, &[0, 5, 15]),
+				ProofRequest { id: 3, root: value(1), indices: alloc::vec![Index::root().left()] },
+			].into(),
+			responses: Vec::new(),
+		};
+		assert_eq!(block_on(server.serve(&mut channel)), Ok(4));
+
+		let proof = channel.responses[0].result.clone().unwrap();
+		assert_eq!(proof.clone().verify_nested::<Construct>(&root, &[Index::from_depth(3, 4)]), Some(value(3)));
+		assert_eq!(proof.verify_nested::<Construct>(&root, &[Index::from_depth(12, 4)]), Some(value(12)));
+		assert_eq!(channel.responses[1].result, Err(ProofRequestError::TooManyIndices));
+		assert_eq!(channel.responses[2].result, Err(ProofRequestError::TooManyNodes));
+		assert_eq!(channel.responses[3].result, Err(ProofRequestError::Unavailable));
+		assert_eq!(channel.responses.iter().map(|response| response.id).collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+		vector.drop(&mut *db.lock()).unwrap();
+	}
+}
@@ -0,0 +1,238 @@
+//! SHA-256 of 64-byte inputs, batched over several lanes with the x86
+//! SHA extensions when the CPU has them.
+
+use generic_array::GenericArray;
+use sha2::Digest;
+
+use crate::{BatchHasher, BatchConstruct};
+
+const IV: [u32; 8] = [
+	0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+	0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const K: [u32; 64] = [
+	0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+	0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+	0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+	0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+	0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+	0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+	0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+	0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Message schedule of the padding block of a 64-byte input, with the
+/// round constants added, so that the second compression of each hash
+/// skips its schedule.
+#[cfg_attr(not(any(target_arch = "x86", target_arch = "x86_64")), allow(dead_code))]
+const PADDING_WK: [u32; 64] = {
+	let mut w = [0u32; 64];
+	w[0] = 0x8000_0000;
+	w[15] = 512;
+	let mut i = 16;
+	while i < 64 {
+		let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+		let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+		w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+		i += 1;
+	}
+	let mut i = 0;
+	while i < 64 {
+		w[i] = w[i].wrapping_add(K[i]);
+		i += 1;
+	}
+	w
+};
+
+/// SHA-256 batch hasher. With the x86 SHA extensions, detected at
+/// runtime with `std` or enabled at compile time otherwise, inputs are
+/// hashed four at a time with interleaved instructions. Other CPUs
+/// fall back to the `sha2` crate.
+pub struct Sha256Batch;
+
+impl BatchHasher for Sha256Batch {
+	fn hash_batch(inputs: &[[u8; 64]], out: &mut [[u8; 32]]) {
+		#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+		{
+			if shani::detected() {
+				unsafe { shani::hash_batch(inputs, out) };
+				return
+			}
+		}
+
+		let mut digest = sha2::Sha256::new();
+		for (input, out) in inputs.iter().zip(out.iter_mut()) {
+			digest.input(&input[..]);
+			out.copy_from_slice(&digest.result_reset());
+		}
+	}
+}
+
+/// SHA-256 construct hashing through `Sha256Batch`, with inherited
+/// empty. Its roots equal those of `InheritedDigestConstruct<Sha256>`,
+/// which does not use the SHA extensions, so constructs opt in to them
+/// by using this one.
+pub type Sha256Construct<V=GenericArray<u8, typenum::U32>> = BatchConstruct<Sha256Batch, V>;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod shani {
+	#[cfg(target_arch = "x86")]
+	use core::arch::x86::*;
+	#[cfg(target_arch = "x86_64")]
+	use core::arch::x86_64::*;
+
+	use super::{IV, K, PADDING_WK};
+
+	/// Number of inputs hashed together.
+	const LANES: usize = 4;
+
+	/// Whether the SHA extensions can be used.
+	#[cfg(feature = "std")]
+	pub fn detected() -> bool {
+		std::is_x86_feature_detected!("sha") &&
+			std::is_x86_feature_detected!("sse2") &&
+			std::is_x86_feature_detected!("ssse3") &&
+			std::is_x86_feature_detected!("sse4.1")
+	}
+
+	/// Whether the SHA extensions can be used.
+	#[cfg(not(feature = "std"))]
+	pub fn detected() -> bool {
+		cfg!(all(target_feature = "sha", target_feature = "sse2",
+				 target_feature = "ssse3", target_feature = "sse4.1"))
+	}
+
+	/// Hash each 64-byte input into `out`.
+	#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+	pub unsafe fn hash_batch(inputs: &[[u8; 64]], out: &mut [[u8; 32]]) {
+		let mut inputs = inputs.chunks_exact(LANES);
+		let mut outs = out.chunks_exact_mut(LANES);
+		for (inputs, outs) in (&mut inputs).zip(&mut outs) {
+			hash_lanes::<LANES>(inputs, outs);
+		}
+		for (input, out) in inputs.remainder().iter().zip(outs.into_remainder()) {
+			hash_lanes::<1>(core::slice::from_ref(input), core::slice::from_mut(out));
+		}
+	}
+
+	/// Hash `N` inputs, interleaving the rounds of each so that the
+	/// latency of the SHA instructions is hidden.
+	#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+	unsafe fn hash_lanes<const N: usize>(inputs: &[[u8; 64]], out: &mut [[u8; 32]]) {
+		let mask = _mm_set_epi64x(0x0c0d_0e0f_0809_0a0b, 0x0405_0607_0001_0203);
+
+		let dcba = _mm_loadu_si128(IV.as_ptr() as *const __m128i);
+		let efgh = _mm_loadu_si128(IV.as_ptr().add(4) as *const __m128i);
+		let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+		let efgh = _mm_shuffle_epi32(efgh, 0x1b);
+		let iv_abef = _mm_alignr_epi8(cdab, efgh, 8);
+		let iv_cdgh = _mm_blend_epi16(efgh, cdab, 0xf0);
+
+		let mut abef = [iv_abef; N];
+		let mut cdgh = [iv_cdgh; N];
+		let mut w = [[_mm_setzero_si128(); 4]; N];
+		for lane in 0..N {
+			for (i, w) in w[lane].iter_mut().enumerate() {
+				let block = inputs[lane].as_ptr().add(i * 16) as *const __m128i;
+				*w = _mm_shuffle_epi8(_mm_loadu_si128(block), mask);
+			}
+		}
+
+		for i in 0..16 {
+			let k = _mm_loadu_si128(K.as_ptr().add(i * 4) as *const __m128i);
+			for lane in 0..N {
+				if i >= 4 {
+					let t1 = _mm_sha256msg1_epu32(w[lane][i % 4], w[lane][(i + 1) % 4]);
+					let t2 = _mm_alignr_epi8(w[lane][(i + 3) % 4], w[lane][(i + 2) % 4], 4);
+					w[lane][i % 4] = _mm_sha256msg2_epu32(_mm_add_epi32(t1, t2), w[lane][(i + 3) % 4]);
+				}
+				let wk = _mm_add_epi32(w[lane][i % 4], k);
+				cdgh[lane] = _mm_sha256rnds2_epu32(cdgh[lane], abef[lane], wk);
+				abef[lane] = _mm_sha256rnds2_epu32(abef[lane], cdgh[lane], _mm_shuffle_epi32(wk, 0x0e));
+			}
+		}
+
+		for lane in 0..N {
+			abef[lane] = _mm_add_epi32(abef[lane], iv_abef);
+			cdgh[lane] = _mm_add_epi32(cdgh[lane], iv_cdgh);
+		}
+		let block_abef = abef;
+		let block_cdgh = cdgh;
+
+		for i in 0..16 {
+			let wk = _mm_loadu_si128(PADDING_WK.as_ptr().add(i * 4) as *const __m128i);
+			for lane in 0..N {
+				cdgh[lane] = _mm_sha256rnds2_epu32(cdgh[lane], abef[lane], wk);
+				abef[lane] = _mm_sha256rnds2_epu32(abef[lane], cdgh[lane], _mm_shuffle_epi32(wk, 0x0e));
+			}
+		}
+
+		for lane in 0..N {
+			let abef = _mm_add_epi32(abef[lane], block_abef[lane]);
+			let cdgh = _mm_add_epi32(cdgh[lane], block_cdgh[lane]);
+			let feba = _mm_shuffle_epi32(abef, 0x1b);
+			let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+			let dcba = _mm_blend_epi16(feba, dchg, 0xf0);
+			let hgef = _mm_alignr_epi8(dchg, feba, 8);
+
+			let out = out[lane].as_mut_ptr() as *mut __m128i;
+			_mm_storeu_si128(out, _mm_shuffle_epi8(dcba, mask));
+			_mm_storeu_si128(out.add(1), _mm_shuffle_epi8(hgef, mask));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use alloc::vec::Vec;
+	use crate::{Construct, InheritedDigestConstruct};
+	use crate::utils::vector_root;
+
+	#[test]
+	fn test_hash_batch() {
+		let inputs = (0..37u32).map(|i| {
+			let mut input = [0u8; 64];
+			for (j, byte) in input.iter_mut().enumerate() {
+				*byte = (i as u8).wrapping_mul(31).wrapping_add(j as u8);
+			}
+			input
+		}).collect::<Vec<_>>();
+
+		let mut out = alloc::vec![[0u8; 32]; inputs.len()];
+		Sha256Batch::hash_batch(&inputs, &mut out);
+		for (input, out) in inputs.iter().zip(&out) {
+			assert_eq!(&out[..], &sha2::Sha256::digest(&input[..])[..]);
+		}
+	}
+
+	#[test]
+	fn test_sha256_construct() {
+		type Digest256 = InheritedDigestConstruct<sha2::Sha256>;
+
+		let values = (0..10).map(crate::test_utils::value).collect::<Vec<_>>();
+		let mut out = Vec::new();
+		Sha256Construct::intermediates_of(&values, &mut out);
+		assert_eq!(out.len(), 5);
+		for (pair, out) in values.chunks(2).zip(&out) {
+			assert_eq!(out, &Digest256::intermediate_of(&pair[0], &pair[1]));
+			assert_eq!(&Sha256Construct::intermediate_of(&pair[0], &pair[1]), out);
+		}
+
+		for len in [0, 1, 7, 300] {
+			let values = (0..len).map(|i| crate::test_utils::value(i as u8)).collect::<Vec<_>>();
+			assert_eq!(
+				vector_root::<Sha256Construct, _>(values.clone(), None).unwrap(),
+				vector_root::<Digest256, _>(values, None).unwrap(),
+			);
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "even number of values")]
+	fn test_odd_intermediates_of() {
+		let values = (0..3).map(crate::test_utils::value).collect::<Vec<_>>();
+		Sha256Construct::intermediates_of(&values, &mut Vec::new());
+	}
+}
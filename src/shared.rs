@@ -0,0 +1,145 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{Backend, ReadBackend, RefReadBackend, WriteBackend, Construct, NodePair, DecodeLimits};
+
+/// Thread-safe merkle database. Cloning the backend gives another
+/// handle to the same underlying database, which can be sent to other
+/// threads. Each operation locks the underlying database. The empty
+/// values of the underlying database stay behind the lock, so empty
+/// subtrees created through this backend are written out.
+pub struct SharedBackend<DB>(Arc<Mutex<DB>>);
+
+impl<DB> SharedBackend<DB> {
+	/// Create a new shared database.
+	pub fn new(db: DB) -> Self {
+		Self(Arc::new(Mutex::new(db)))
+	}
+
+	/// Lock the underlying database.
+	pub fn lock(&self) -> MutexGuard<'_, DB> {
+		self.0.lock().unwrap_or_else(|err| err.into_inner())
+	}
+}
+
+impl<DB> Clone for SharedBackend<DB> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<DB: Default> Default for SharedBackend<DB> {
+	fn default() -> Self {
+		Self::new(DB::default())
+	}
+}
+
+impl<DB: Backend> Backend for SharedBackend<DB> {
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+}
+
+impl<DB: ReadBackend> ReadBackend for SharedBackend<DB> {
+	fn get(
+		&mut self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<Option<(<Self::Construct as Construct>::Value, <Self::Construct as Construct>::Value)>, Self::Error> {
+		self.lock().get(key)
+	}
+
+	fn prefetch(&mut self, keys: &[<Self::Construct as Construct>::Value]) {
+		self.lock().prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.lock().decode_limits()
+	}
+}
+
+impl<DB: ReadBackend> RefReadBackend for SharedBackend<DB> {
+	fn get_ref(
+		&self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<Option<NodePair<Self>>, Self::Error> {
+		self.lock().get(key)
+	}
+}
+
+impl<DB: WriteBackend> WriteBackend for SharedBackend<DB> {
+	fn rootify(
+		&mut self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<(), Self::Error> {
+		self.lock().rootify(key)
+	}
+
+	fn unrootify(
+		&mut self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<(), Self::Error> {
+		self.lock().unrootify(key)
+	}
+
+	fn insert(
+		&mut self,
+		key: <Self::Construct as Construct>::Value,
+		value: (<Self::Construct as Construct>::Value, <Self::Construct as Construct>::Value)
+	) -> Result<(), Self::Error> {
+		self.lock().insert(key, value)
+	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		self.lock().begin_batch()
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		self.lock().commit_batch()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Raw, Vector, List, Owned, Dangling, Leak};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	fn assert_send_sync<T: Send + Sync>() { }
+
+	#[test]
+	fn test_send_sync() {
+		assert_send_sync::<SharedBackend<InMemoryBackend<Construct>>>();
+		assert_send_sync::<Raw<Owned, Construct>>();
+		assert_send_sync::<Vector<Owned, Construct>>();
+		assert_send_sync::<List<Owned, Construct>>();
+	}
+
+	#[test]
+	fn test_threads() {
+		let mut db = SharedBackend::new(InMemoryBackend::<Construct>::default());
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..16 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let handles = (0..4).map(|t| {
+			let mut db = db.clone();
+			let mut vector = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+			std::thread::spawn(move || {
+				for i in 0..4 {
+					let index = t * 4 + i;
+					assert_eq!(vector.get(&mut db, index).unwrap(), value(index as u8));
+				}
+				vector.set(&mut db, t, value(100)).unwrap();
+			})
+		}).collect::<Vec<_>>();
+		for handle in handles {
+			handle.join().unwrap();
+		}
+
+		vector.set(&mut db, 0, value(100)).unwrap();
+		assert_eq!(vector.get(&mut db, 0).unwrap(), value(100));
+	}
+}
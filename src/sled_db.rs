@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+
+use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp, KeyValueStore};
+use crate::refcount::RefCountBackend;
+
+/// Key-value database over a sled tree. All keys are prefixed by the
+/// configured prefix, so that multiple merkle databases can share one
+/// sled tree.
+pub struct SledDB {
+	tree: sled::Tree,
+	prefix: Vec<u8>,
+}
+
+impl SledDB {
+	/// Create a new database over the given sled tree, with no key
+	/// prefix.
+	pub fn new(tree: sled::Tree) -> Self {
+		Self::with_prefix(tree, Vec::new())
+	}
+
+	/// Create a new database over the given sled tree, with the given
+	/// key prefix.
+	pub fn with_prefix(tree: sled::Tree, prefix: Vec<u8>) -> Self {
+		Self { tree, prefix }
+	}
+
+	/// Get a reference to the underlying sled tree.
+	pub fn tree(&self) -> &sled::Tree {
+		&self.tree
+	}
+
+	fn key(&self, key: &[u8]) -> Vec<u8> {
+		let mut ret = self.prefix.clone();
+		ret.extend_from_slice(key);
+		ret
+	}
+}
+
+impl KeyValueDB for SledDB {
+	type Error = sled::Error;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		Ok(self.tree.get(self.key(key))?.map(|value| value.to_vec()))
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let mut batch = sled::Batch::default();
+		for op in ops {
+			match op {
+				KeyValueOp::Put(key, value) => batch.insert(self.key(&key), value),
+				KeyValueOp::Delete(key) => batch.remove(self.key(&key)),
+			}
+		}
+		self.tree.apply_batch(batch)
+	}
+}
+
+impl IterableKeyValueDB for SledDB {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, mut f: F) -> Result<(), Self::Error> {
+		for entry in self.tree.scan_prefix(&self.prefix) {
+			let (key, value) = entry?;
+			f(&key[self.prefix.len()..], &value);
+		}
+		Ok(())
+	}
+}
+
+/// Reference-counted merkle database persisted in sled.
+pub type SledBackend<C> = RefCountBackend<C, KeyValueStore<SledDB>>;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, Vector, Owned, Tree, ReadBackend, WriteBackend};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_sled_backend() {
+		let sled = sled::Config::new().temporary(true).open().unwrap();
+		let tree = sled.open_tree("merkle").unwrap();
+		let mut db = SledBackend::<Construct>::new(KeyValueStore::new(SledDB::with_prefix(tree.clone(), b"a".to_vec())));
+		let mut other = SledBackend::<Construct>::new(KeyValueStore::new(SledDB::with_prefix(tree.clone(), b"b".to_vec())));
+
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		db.begin_batch().unwrap();
+		for i in 0..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		assert!(tree.scan_prefix(b"a").next().is_none());
+		db.commit_batch().unwrap();
+
+		for i in 0..9 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+		assert_eq!(other.get(&vector.root()).unwrap(), None);
+
+		let mut leaked = Vector::<Owned, Construct>::create(&mut other, 0, None).unwrap();
+		leaked.push(&mut other, value(42)).unwrap();
+		let mut entries = 0;
+		db.db().for_each_entry(|_, _| entries += 1).unwrap();
+		assert_eq!(entries, tree.scan_prefix(b"a").count());
+		assert_eq!(crate::compact(&mut other.into_store().into_db(), &[] as &[&[u8]]).unwrap().live, 0);
+		assert_eq!(tree.scan_prefix(b"b").count(), 0);
+
+		vector.drop(&mut db).unwrap();
+		assert!(tree.is_empty());
+	}
+}
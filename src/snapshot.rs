@@ -0,0 +1,101 @@
+use core::mem;
+
+use crate::traits::{WriteBackend, Construct, RootStatus, Leak, Error, Tree};
+
+/// Snapshot of a merkle tree. The snapshotted root is rootified in the
+/// backend, so that all its nodes are kept alive until the snapshot
+/// is released.
+pub struct Snapshot<T: Tree + Leak> {
+	root: <T::Construct as Construct>::Value,
+	metadata: T::Metadata,
+}
+
+impl<T: Tree + Leak> Snapshot<T> where
+	T::Metadata: Clone,
+{
+	/// Take a snapshot of the current state of the tree.
+	pub fn take<DB: WriteBackend<Construct=T::Construct> + ?Sized>(
+		tree: &T,
+		db: &mut DB
+	) -> Result<Self, Error<DB::Error>> {
+		let root = tree.root();
+		db.rootify(&root)?;
+
+		Ok(Self { root, metadata: tree.metadata() })
+	}
+
+	/// Root of the snapshot.
+	pub fn root(&self) -> <T::Construct as Construct>::Value {
+		self.root.clone()
+	}
+
+	/// Revert the tree to the snapshotted state. The snapshot is kept,
+	/// so the tree can be reverted to it again.
+	pub fn revert<DB: WriteBackend<Construct=T::Construct> + ?Sized>(
+		&self,
+		tree: &mut T,
+		db: &mut DB
+	) -> Result<(), Error<DB::Error>> {
+		if T::RootStatus::is_owned() {
+			db.rootify(&self.root)?;
+		}
+
+		let old = mem::replace(tree, T::from_leaked(self.metadata.clone()));
+		old.drop(db)
+	}
+
+	/// Release the snapshot, allowing its nodes to be garbage
+	/// collected.
+	pub fn release<DB: WriteBackend<Construct=T::Construct> + ?Sized>(
+		self,
+		db: &mut DB
+	) -> Result<(), Error<DB::Error>> {
+		db.unrootify(&self.root)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InMemoryBackend, InheritedDigestConstruct, Vector, Owned};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type InMemory = InMemoryBackend<Construct>;
+
+	#[test]
+	fn test_snapshot_revert() {
+		let mut db = InMemory::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let root = vector.root();
+		let snapshot = Snapshot::take(&vector, &mut db).unwrap();
+
+		for i in 5..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		vector.set(&mut db, 0, value(100)).unwrap();
+		assert!(vector.root() != root);
+
+		snapshot.revert(&mut vector, &mut db).unwrap();
+		assert_eq!(vector.root(), root);
+		assert_eq!(vector.len(), 5);
+		for i in 0..5 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+
+		vector.push(&mut db, value(5)).unwrap();
+		snapshot.revert(&mut vector, &mut db).unwrap();
+		snapshot.release(&mut db).unwrap();
+		assert_eq!(vector.root(), root);
+		assert_eq!(vector.get(&mut db, 4).unwrap(), value(4));
+
+		vector.drop(&mut db).unwrap();
+		assert_eq!(db.as_ref().len(), 1);
+	}
+}
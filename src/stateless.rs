@@ -0,0 +1,88 @@
+use crate::{Construct, CompactValue, Index, IndexSelection};
+
+/// Replace the node at `index` of the partial tree `proof`. Fails if
+/// the path to `index` leaves the proof, through a node whose children
+/// are not in it.
+fn update<V>(proof: &mut CompactValue<V>, index: Index, value: V) -> Option<()> {
+	let mut current = proof;
+	for selection in index.path() {
+		current = match current {
+			CompactValue::Combined(boxed) => match selection {
+				IndexSelection::Left => &mut boxed.0,
+				IndexSelection::Right => &mut boxed.1,
+			},
+			CompactValue::Single(_) => return None,
+		};
+	}
+
+	*current = CompactValue::Single(value);
+	Some(())
+}
+
+/// Statelessly apply `updates` to the partial tree of a compact
+/// multiproof, such as one used to execute a state transition, and get
+/// the new root with the updated proof. Each update replaces the node
+/// at its generalized index, in order, so a whole subtree can be
+/// replaced by its new root. Returns `None` if an update is not covered
+/// by the proof. The proof itself is not verified, so compare its root
+/// to a trusted one first.
+pub fn apply_updates<C: Construct>(
+	mut proof: CompactValue<C::Value>,
+	updates: &[(Index, C::Value)],
+) -> Option<(C::Value, CompactValue<C::Value>)> where
+	C::Value: Default,
+{
+	for (index, value) in updates {
+		update(&mut proof, *index, value.clone())?;
+	}
+
+	let root = proof.clone().root::<C>();
+	Some((root, proof))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, ProvingBackend, Proofs, Vector, Owned, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_apply_updates() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, Some(16)).unwrap();
+		for i in 0..11 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let mut proving = ProvingBackend::new(&mut db);
+		vector.get(&mut proving, 3).unwrap();
+		vector.get(&mut proving, 9).unwrap();
+		let proofs: Proofs<_> = proving.into();
+		let proof = proofs.into_compact(vector.root());
+
+		let updates = [(Index::from_depth(3, 4), value(42)), (Index::from_depth(9, 4), value(43))];
+		let (root, updated) = apply_updates::<Construct>(proof.clone(), &updates).unwrap();
+		vector.set(&mut db, 3, value(42)).unwrap();
+		vector.set(&mut db, 9, value(43)).unwrap();
+		assert_eq!(root, vector.root());
+		assert_eq!(updated.len(), proof.len());
+		assert_eq!(updated.clone().root::<Construct>(), root);
+
+		let (root, _) = apply_updates::<Construct>(updated.clone(), &[(Index::from_depth(3, 4), value(3))]).unwrap();
+		vector.set(&mut db, 3, value(3)).unwrap();
+		assert_eq!(root, vector.root());
+
+		let (root, replaced) = apply_updates::<Construct>(updated.clone(), &[(Index::root().left(), value(7))]).unwrap();
+		let (left, right) = match updated {
+			CompactValue::Combined(boxed) => *boxed,
+			CompactValue::Single(_) => panic!("proof covers leaves; qed"),
+		};
+		assert_eq!(replaced.len(), proof.len() - left.len() + 1);
+		assert_eq!(root, <Construct as crate::Construct>::intermediate_of(&value(7), &right.root::<Construct>()));
+		assert_eq!(apply_updates::<Construct>(proof, &[(Index::from_depth(5, 4), value(1))]), None);
+	}
+}
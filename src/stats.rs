@@ -0,0 +1,123 @@
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+use core::hash::Hash;
+
+use crate::{Construct, ReadBackend, Error};
+
+/// Statistics of a tree.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TreeStats {
+	/// Number of distinct intermediate nodes.
+	pub nodes: usize,
+	/// Number of references to leaves from distinct intermediate nodes,
+	/// or one if the root is a leaf.
+	pub leaves: usize,
+	/// Depth of the deepest leaf.
+	pub depth: usize,
+	/// Number of distinct intermediate nodes referenced more than once.
+	pub shared: usize,
+	/// Number of distinct intermediate nodes whose leaves are all empty.
+	pub empty: usize,
+	/// Estimated storage of the intermediate nodes in bytes, storing
+	/// the two children keyed by the node.
+	pub bytes: usize,
+}
+
+/// Calculate statistics of the tree under `root`, visiting each
+/// distinct intermediate node once.
+pub fn stats<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+) -> Result<TreeStats, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	let mut ret = TreeStats::default();
+	// Height and emptiness of each expanded node, and its references.
+	let mut info = Map::new();
+	let mut references = Map::<_, usize>::new();
+
+	let mut stack = Vec::new();
+	stack.push((root.clone(), None));
+	while let Some((key, children)) = stack.pop() {
+		match children {
+			Some((left, right)) => {
+				let child = |value: &<DB::Construct as Construct>::Value| {
+					info.get(value).cloned()
+						.unwrap_or_else(|| (0, *value == Default::default()))
+				};
+				let (left_height, left_empty) = child(&left);
+				let (right_height, right_empty) = child(&right);
+				let empty = left_empty && right_empty;
+				if empty {
+					ret.empty += 1;
+				}
+				info.insert(key, (core::cmp::max(left_height, right_height) + 1, empty));
+			},
+			None => {
+				if let Some(count) = references.get_mut(&key) {
+					*count += 1;
+					continue
+				}
+
+				match db.get(&key)? {
+					Some((left, right)) => {
+						references.insert(key.clone(), 1);
+						ret.nodes += 1;
+						stack.push((key, Some((left.clone(), right.clone()))));
+						stack.push((right, None));
+						stack.push((left, None));
+					},
+					None => {
+						ret.leaves += 1;
+					},
+				}
+			},
+		}
+	}
+
+	ret.depth = info.get(root).map(|(height, _)| *height).unwrap_or(0);
+	ret.shared = references.values().filter(|count| **count > 1).count();
+	ret.bytes = ret.nodes * 3 * core::mem::size_of::<<DB::Construct as Construct>::Value>();
+
+	Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Raw, Owned, Index, Tree};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_stats() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		// Vectors keep untouched halves as virtual empty subtrees, so the
+		// empty leaves are written explicitly.
+		let mut raw = Raw::<Owned, Construct>::default();
+		for i in 0..8 {
+			raw.set(&mut db, Index::from_one(8 + i as usize).unwrap(),
+					if i < 4 { value(i % 2 + 1) } else { Default::default() }).unwrap();
+		}
+
+		assert_eq!(stats(&mut db, &raw.root()).unwrap(), TreeStats {
+			nodes: 5,
+			leaves: 4,
+			depth: 3,
+			shared: 2,
+			empty: 2,
+			bytes: 5 * 3 * 32,
+		});
+
+		assert_eq!(stats(&mut db, &value(1)).unwrap(), TreeStats {
+			leaves: 1,
+			..Default::default()
+		});
+	}
+}
@@ -0,0 +1,306 @@
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+use crate::{Construct, ReadBackend, WriteBackend, Error, Tree, Owned, RootStatus, Raw,
+			Vector, CompactValue, AnnotatedValue};
+
+/// Amount summed by a merkle sum tree.
+pub trait Amount: Clone + Default + Eq + Debug {
+	/// Add two amounts, or `None` on overflow.
+	fn checked_add(&self, other: &Self) -> Option<Self>;
+	/// Subtract two amounts, or `None` on underflow.
+	fn checked_sub(&self, other: &Self) -> Option<Self>;
+	/// Add two amounts, saturating at the maximum.
+	fn saturating_add(&self, other: &Self) -> Self;
+	/// Write the little-endian bytes of the amount to the start of `out`.
+	fn write_le(&self, out: &mut [u8]);
+}
+
+macro_rules! impl_amount {
+	( $( $t:ty ),* ) => { $(
+		impl Amount for $t {
+			fn checked_add(&self, other: &Self) -> Option<Self> {
+				<$t>::checked_add(*self, *other)
+			}
+
+			fn checked_sub(&self, other: &Self) -> Option<Self> {
+				<$t>::checked_sub(*self, *other)
+			}
+
+			fn saturating_add(&self, other: &Self) -> Self {
+				<$t>::saturating_add(*self, *other)
+			}
+
+			fn write_le(&self, out: &mut [u8]) {
+				let bytes = self.to_le_bytes();
+				out[..bytes.len()].copy_from_slice(&bytes);
+			}
+		}
+	)* }
+}
+
+impl_amount!(u64, u128);
+
+#[cfg(feature = "primitive-types")]
+impl Amount for primitive_types::U256 {
+	fn checked_add(&self, other: &Self) -> Option<Self> {
+		primitive_types::U256::checked_add(*self, *other)
+	}
+
+	fn checked_sub(&self, other: &Self) -> Option<Self> {
+		primitive_types::U256::checked_sub(*self, *other)
+	}
+
+	fn saturating_add(&self, other: &Self) -> Self {
+		primitive_types::U256::saturating_add(*self, *other)
+	}
+
+	fn write_le(&self, out: &mut [u8]) {
+		self.to_little_endian(&mut out[..32]);
+	}
+}
+
+/// Construct of a merkle sum tree over the hashes of `C`. Each node is
+/// a hash with the sum of the amounts below it, and the hash of an
+/// intermediate node is the hash of its children's hashes together with
+/// the hash of their sums. Values of `C` must be large enough to hold
+/// the bytes of an amount.
+pub struct SumConstruct<C, S=u64>(PhantomData<(C, S)>);
+
+impl<C: Construct, S: Amount> SumConstruct<C, S> where
+	C::Value: AsMut<[u8]>,
+{
+	fn commit(amount: &S) -> C::Value {
+		let mut value = C::Value::default();
+		amount.write_le(value.as_mut());
+		value
+	}
+}
+
+impl<C: Construct, S: Amount> Construct for SumConstruct<C, S> where
+	C::Value: AsMut<[u8]>,
+{
+	type Value = AnnotatedValue<C::Value, S>;
+
+	fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value {
+		let hash = C::intermediate_of(&left.hash, &right.hash);
+		let sums = C::intermediate_of(&Self::commit(&left.data), &Self::commit(&right.data));
+		AnnotatedValue {
+			hash: C::intermediate_of(&hash, &sums),
+			data: left.data.saturating_add(&right.data),
+		}
+	}
+
+	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
+		db: &mut DB,
+		depth_to_bottom: usize
+	) -> Result<Self::Value, DB::Error> {
+		let mut current = Self::Value::default();
+		for _ in 0..depth_to_bottom {
+			let value = (current.clone(), current);
+			let key = Self::intermediate_of(&value.0, &value.1);
+			db.insert(key.clone(), value)?;
+			current = key;
+		}
+		Ok(current)
+	}
+
+	fn fmt_value(value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		C::fmt_value(&value.hash, f)
+	}
+}
+
+/// Node of a merkle sum tree over the hashes of `C`.
+pub type SumValue<C, S=u64> = AnnotatedValue<<C as Construct>::Value, S>;
+
+/// Merkle sum tree, a vector of leaf hashes with amounts whose root
+/// commits to the total, for balance commitments and proofs of
+/// reserves. Sums are maintained on every `set` or `push`, which fail
+/// with `InvalidParameter` if the total would overflow.
+pub struct SumTree<R: RootStatus, C: Construct, S: Amount=u64> where
+	C::Value: AsMut<[u8]>,
+{
+	vector: Vector<R, SumConstruct<C, S>>,
+}
+
+impl<R: RootStatus, C: Construct, S: Amount> SumTree<R, C, S> where
+	C::Value: AsMut<[u8]>,
+{
+	/// Get the sum tree of an existing vector.
+	pub fn from_vector(vector: Vector<R, SumConstruct<C, S>>) -> Self {
+		Self { vector }
+	}
+
+	/// Underlying vector of the sum tree.
+	pub fn vector(&self) -> &Vector<R, SumConstruct<C, S>> {
+		&self.vector
+	}
+
+	/// Number of leaves.
+	pub fn len(&self) -> u64 {
+		self.vector.len()
+	}
+
+	/// Whether there are no leaves.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Depth of the leaves, for verifying proofs.
+	pub fn depth(&self) -> usize {
+		self.vector.depth()
+	}
+
+	/// Sum of all amounts.
+	pub fn sum(&self) -> S {
+		self.vector.root().data
+	}
+
+	/// Get the hash and amount of the leaf at `index`.
+	pub fn get<DB: ReadBackend<Construct=SumConstruct<C, S>> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: u64,
+	) -> Result<SumValue<C, S>, Error<DB::Error>> {
+		self.vector.get(db, index)
+	}
+
+	/// Set the hash and amount of the leaf at `index`.
+	pub fn set<DB: WriteBackend<Construct=SumConstruct<C, S>> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		hash: C::Value,
+		amount: S,
+	) -> Result<(), Error<DB::Error>> {
+		let old = self.vector.get(db, index)?;
+		self.sum().checked_sub(&old.data)
+			.and_then(|rest| rest.checked_add(&amount))
+			.ok_or(Error::InvalidParameter)?;
+		self.vector.set(db, index, AnnotatedValue { hash, data: amount })
+	}
+
+	/// Push a new leaf with `hash` and `amount`.
+	pub fn push<DB: WriteBackend<Construct=SumConstruct<C, S>> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		hash: C::Value,
+		amount: S,
+	) -> Result<(), Error<DB::Error>> {
+		self.sum().checked_add(&amount).ok_or(Error::InvalidParameter)?;
+		self.vector.push(db, AnnotatedValue { hash, data: amount })
+	}
+
+	/// Prove the leaf at `index`. Each sibling in the proof exposes the
+	/// sum of its subtree, to be checked by `verify_sum_proof`.
+	pub fn prove<DB: ReadBackend<Construct=SumConstruct<C, S>> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: u64,
+	) -> Result<CompactValue<SumValue<C, S>>, Error<DB::Error>> {
+		self.vector.prove_range(db, index..(index + 1))
+	}
+}
+
+impl<C: Construct, S: Amount> SumTree<Owned, C, S> where
+	C::Value: AsMut<[u8]>,
+{
+	/// Create a new empty sum tree.
+	pub fn create<DB: WriteBackend<Construct=SumConstruct<C, S>> + ?Sized>(
+		db: &mut DB,
+		max_len: Option<u64>,
+	) -> Result<Self, Error<DB::Error>> {
+		Ok(Self { vector: Vector::create(db, 0, max_len)? })
+	}
+}
+
+impl<R: RootStatus, C: Construct, S: Amount> Tree for SumTree<R, C, S> where
+	C::Value: AsMut<[u8]>,
+{
+	type RootStatus = R;
+	type Construct = SumConstruct<C, S>;
+
+	fn root(&self) -> SumValue<C, S> {
+		self.vector.root()
+	}
+
+	fn drop<DB: WriteBackend<Construct=Self::Construct> + ?Sized>(
+		self,
+		db: &mut DB
+	) -> Result<(), Error<DB::Error>> {
+		self.vector.drop(db)
+	}
+
+	fn into_raw(self) -> Raw<R, SumConstruct<C, S>> {
+		self.vector.into_raw()
+	}
+}
+
+/// Verify a proof of `SumTree::prove` for the leaf at `index` against
+/// `root`, whose leaves are at `depth`, and get the leaf. The sum of the
+/// root is the total committed to, so a verified leaf is known to be
+/// counted in it.
+pub fn verify_sum_proof<C: Construct, S: Amount>(
+	proof: CompactValue<SumValue<C, S>>,
+	root: &SumValue<C, S>,
+	depth: usize,
+	index: u64,
+) -> Option<SumValue<C, S>> where
+	C::Value: AsMut<[u8]> + PartialEq,
+{
+	crate::range::verify_range::<SumConstruct<C, S>>(proof, root, depth, index..(index + 1))?.pop()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend};
+
+	#[cfg(feature = "primitive-types")]
+	use primitive_types::U256;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_sum_tree() {
+		let mut db = InMemoryBackend::<SumConstruct<Construct>>::default();
+		let mut tree = SumTree::<Owned, Construct>::create(&mut db, None).unwrap();
+		for i in 1..6 {
+			tree.push(&mut db, value(i), i as u64 * 10).unwrap();
+		}
+		assert_eq!(tree.sum(), 150);
+
+		let before = tree.root();
+		tree.set(&mut db, 2, value(3), 5).unwrap();
+		assert_eq!(tree.sum(), 125);
+		assert_ne!(tree.root().hash, before.hash);
+		assert_eq!(tree.get(&mut db, 2).unwrap(), AnnotatedValue { hash: value(3), data: 5 });
+
+		assert_eq!(tree.push(&mut db, value(6), u64::MAX), Err(Error::InvalidParameter));
+		assert_eq!(tree.set(&mut db, 0, value(1), u64::MAX - 114), Err(Error::InvalidParameter));
+		tree.set(&mut db, 0, value(1), u64::MAX - 115).unwrap();
+		assert_eq!(tree.sum(), u64::MAX);
+		tree.set(&mut db, 0, value(1), 10).unwrap();
+
+		let proof = tree.prove(&mut db, 3).unwrap();
+		let leaf = verify_sum_proof::<Construct, _>(proof.clone(), &tree.root(), tree.depth(), 3).unwrap();
+		assert_eq!(leaf, AnnotatedValue { hash: value(4), data: 40 });
+		assert_eq!(verify_sum_proof::<Construct, _>(proof.clone(), &tree.root(), tree.depth(), 0), None);
+		let mut forged = tree.root();
+		forged.data -= 1;
+		assert_eq!(verify_sum_proof::<Construct, _>(proof, &forged, tree.depth(), 3), None);
+	}
+
+	#[cfg(feature = "primitive-types")]
+	#[test]
+	fn test_sum_tree_u256() {
+		let mut db = InMemoryBackend::<SumConstruct<Construct, U256>>::default();
+		let mut tree = SumTree::<Owned, Construct, U256>::create(&mut db, Some(4)).unwrap();
+		tree.push(&mut db, value(1), U256::from(u128::MAX)).unwrap();
+		tree.push(&mut db, value(2), U256::from(u128::MAX)).unwrap();
+		assert_eq!(tree.sum(), U256::from(u128::MAX) * 2);
+		assert_eq!(tree.push(&mut db, value(3), U256::MAX), Err(Error::InvalidParameter));
+	}
+}
@@ -0,0 +1,32 @@
+//! Helpers shared by the unit tests.
+
+/// Run a future to completion by busy polling it with a no-op waker,
+/// enough for the in-memory async backends used by tests.
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+	use core::pin::Pin;
+	use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+	fn noop_raw_waker() -> RawWaker {
+		fn clone(_: *const ()) -> RawWaker { noop_raw_waker() }
+		fn noop(_: *const ()) { }
+		static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+		RawWaker::new(core::ptr::null(), &VTABLE)
+	}
+
+	let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+	let mut context = Context::from_waker(&waker);
+	let mut future = future;
+	let mut future = unsafe { Pin::new_unchecked(&mut future) };
+	loop {
+		if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+			return output
+		}
+	}
+}
+
+/// Leaf value whose first byte is `i`, and all other bytes zero.
+pub fn value(i: u8) -> generic_array::GenericArray<u8, typenum::U32> {
+	let mut value = generic_array::GenericArray::default();
+	value[0] = i;
+	value
+}
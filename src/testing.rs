@@ -0,0 +1,248 @@
+//! Model-based testing of vectors against backends.
+//!
+//! The same sequence of operations is applied to a `Vector` in the
+//! backend under test and to a plain `Vec` model, comparing results,
+//! lengths and roots after each operation.
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::convert::TryFrom;
+
+use crate::{Construct, WriteBackend, Error, Vector, Owned, RootStatus, Tree, NoopBackend};
+use crate::utils::vector_tree;
+
+/// Operation on a vector.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Operation<V> {
+	/// Push a value.
+	Push(V),
+	/// Pop the last value.
+	Pop,
+	/// Set the value at an index.
+	Set(u64, V),
+	/// Get the value at an index.
+	Get(u64),
+}
+
+/// Result of an operation. `None` for operations without a result,
+/// and `Overflowed` for accesses out of bound.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Outcome<V> {
+	/// Operation succeeded with an optional value.
+	Value(Option<V>),
+	/// Access was out of bound.
+	Overflowed,
+}
+
+/// Error of a model-based test.
+#[derive(Debug)]
+pub enum ModelError<V, E> {
+	/// The backend returned an error other than an overflowed access.
+	Backend(usize, Error<E>),
+	/// Result of an operation differs from the model.
+	Outcome {
+		/// Index of the operation.
+		step: usize,
+		/// Result of the model.
+		expected: Outcome<V>,
+		/// Result of the vector.
+		actual: Outcome<V>,
+	},
+	/// Length after an operation differs from the model.
+	Len {
+		/// Index of the operation.
+		step: usize,
+		/// Length of the model.
+		expected: u64,
+		/// Length of the vector.
+		actual: u64,
+	},
+	/// Root after an operation differs from the model.
+	Root {
+		/// Index of the operation.
+		step: usize,
+		/// Root of the model.
+		expected: V,
+		/// Root of the vector.
+		actual: V,
+	},
+}
+
+/// Reference model of a vector, backed by a plain `Vec`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VectorModel<V> {
+	values: Vec<V>,
+	max_len: Option<u64>,
+}
+
+impl<V: Clone> VectorModel<V> {
+	/// Create a new model with `len` default values.
+	pub fn new(len: u64, max_len: Option<u64>) -> Self where
+		V: Default,
+	{
+		Self { values: alloc::vec![V::default(); len as usize], max_len }
+	}
+
+	/// Values of the model.
+	pub fn values(&self) -> &[V] {
+		&self.values
+	}
+
+	/// Apply an operation to the model.
+	pub fn apply(&mut self, operation: &Operation<V>) -> Outcome<V> {
+		match operation {
+			Operation::Push(value) => {
+				if self.max_len.map(|max_len| self.values.len() as u64 >= max_len).unwrap_or(false) {
+					return Outcome::Overflowed
+				}
+				self.values.push(value.clone());
+				Outcome::Value(None)
+			},
+			Operation::Pop => Outcome::Value(self.values.pop()),
+			Operation::Set(index, value) => match usize::try_from(*index).ok().and_then(|index| self.values.get_mut(index)) {
+				Some(current) => {
+					*current = value.clone();
+					Outcome::Value(None)
+				},
+				None => Outcome::Overflowed,
+			},
+			Operation::Get(index) => match usize::try_from(*index).ok().and_then(|index| self.values.get(index)) {
+				Some(value) => Outcome::Value(Some(value.clone())),
+				None => Outcome::Overflowed,
+			},
+		}
+	}
+
+	/// Root of the model, calculated without a backend.
+	pub fn root<C: Construct<Value=V>>(&self) -> V where
+		V: Eq + Hash + Ord,
+	{
+		vector_tree(&self.values, &mut NoopBackend::<C>::default(), self.max_len)
+			.expect("Noop backend never fails in set; qed")
+	}
+}
+
+/// Apply an operation to a vector.
+pub fn apply<R: RootStatus, C: Construct, DB: WriteBackend<Construct=C> + ?Sized>(
+	vector: &mut Vector<R, C>,
+	db: &mut DB,
+	operation: &Operation<C::Value>,
+) -> Result<Outcome<C::Value>, Error<DB::Error>> {
+	let result = match operation {
+		Operation::Push(value) => vector.push(db, value.clone()).map(|()| None),
+		Operation::Pop => vector.pop(db),
+		Operation::Set(index, value) => vector.set(db, *index, value.clone()).map(|()| None),
+		Operation::Get(index) => vector.get(db, *index).map(Some),
+	};
+
+	match result {
+		Ok(value) => Ok(Outcome::Value(value)),
+		Err(Error::AccessOverflowed(_)) => Ok(Outcome::Overflowed),
+		Err(err) => Err(err),
+	}
+}
+
+/// Deterministic xorshift generator, so that failing sequences can be
+/// reproduced from their seed.
+fn next(state: &mut u64) -> u64 {
+	*state ^= *state << 13;
+	*state ^= *state >> 7;
+	*state ^= *state << 17;
+	*state
+}
+
+/// Generate `count` random operations from `seed`, with values created
+/// by `value` from random numbers. Indexes are drawn mostly below
+/// `max_index`, with occasional out-of-bound accesses.
+pub fn random_operations<V, F: FnMut(u64) -> V>(
+	seed: u64,
+	count: usize,
+	max_index: u64,
+	mut value: F,
+) -> Vec<Operation<V>> {
+	let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+	let bound = max_index + 2;
+
+	(0..count).map(|_| {
+		let kind = next(&mut state) % 8;
+		let index = next(&mut state) % bound;
+		match kind {
+			0..=2 => Operation::Push(value(next(&mut state))),
+			3 => Operation::Pop,
+			4 | 5 => Operation::Set(index, value(next(&mut state))),
+			_ => Operation::Get(index),
+		}
+	}).collect()
+}
+
+/// Apply `operations` to a new vector of `len` default values in `db`
+/// and to its model, comparing results, lengths and roots after each
+/// operation. The vector is dropped from `db` afterwards, including on
+/// mismatch.
+pub fn check_vector<C: Construct, DB: WriteBackend<Construct=C> + ?Sized>(
+	db: &mut DB,
+	len: u64,
+	max_len: Option<u64>,
+	operations: &[Operation<C::Value>],
+) -> Result<(), ModelError<C::Value, DB::Error>> where
+	C::Value: Eq + Hash + Ord,
+{
+	let mut vector = Vector::<Owned, C>::create(db, len, max_len)
+		.map_err(|err| ModelError::Backend(0, err))?;
+	let mut model = VectorModel::<C::Value>::new(len, max_len);
+
+	let result = (|| {
+		for (step, operation) in operations.iter().enumerate() {
+			let expected = model.apply(operation);
+			let actual = apply(&mut vector, db, operation)
+				.map_err(|err| ModelError::Backend(step, err))?;
+			if expected != actual {
+				return Err(ModelError::Outcome { step, expected, actual })
+			}
+			if model.values().len() as u64 != vector.len() {
+				return Err(ModelError::Len { step, expected: model.values().len() as u64, actual: vector.len() })
+			}
+			let (expected, actual) = (model.root::<C>(), vector.root());
+			if expected != actual {
+				return Err(ModelError::Root { step, expected, actual })
+			}
+		}
+		Ok(())
+	})();
+
+	let dropped = vector.drop(db).map_err(|err| ModelError::Backend(operations.len(), err));
+	result.and(dropped)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{InheritedDigestConstruct, InMemoryBackend};
+
+	use generic_array::GenericArray;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	fn value(i: u64) -> GenericArray<u8, typenum::U32> {
+		let mut value = GenericArray::default();
+		value[..8].copy_from_slice(&i.to_le_bytes());
+		value
+	}
+
+	#[test]
+	fn test_check_vector() {
+		for seed in 0..8 {
+			let operations = random_operations(seed, 200, 16, value);
+			let mut db = InMemoryBackend::<Construct>::default();
+			check_vector(&mut db, 0, None, &operations).unwrap();
+			assert_eq!(db.as_ref().len(), 1);
+
+			let mut db = InMemoryBackend::<Construct>::default();
+			check_vector(&mut db, 4, Some(4), &operations).unwrap();
+		}
+
+		assert_eq!(random_operations(1, 10, 4, value), random_operations(1, 10, 4, value));
+		assert_ne!(random_operations(1, 10, 4, value), random_operations(2, 10, 4, value));
+	}
+}
@@ -1,3 +1,16 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::string::String;
+use alloc::format;
+
+use crate::index::Index;
+use crate::leak::DisplayValue;
+use crate::limited::DecodeLimits;
+use crate::empty::EmptyValues;
+
 /// Construct for a merkle tree.
 pub trait Construct: Sized {
 	/// Value stored in this merkle database.
@@ -5,12 +18,42 @@ pub trait Construct: Sized {
 
 	/// Get the intermediate value of given left and right child.
 	fn intermediate_of(left: &Self::Value, right: &Self::Value) -> Self::Value;
+	/// Get intermediate values of consecutive pairs of `values`, whose
+	/// length must be even, appending them to `out`. Constructs can
+	/// override this to hash many pairs at once.
+	fn intermediates_of(values: &[Self::Value], out: &mut Vec<Self::Value>) {
+		assert!(values.len() % 2 == 0, "intermediates_of takes an even number of values");
+		out.extend(values.chunks_exact(2).map(|pair| Self::intermediate_of(&pair[0], &pair[1])));
+	}
 	/// Get or create the empty value given a backend. `empty_at(0)`
 	/// should always equal to `Value::End(Default::default())`.
 	fn empty_at<DB: WriteBackend<Construct=Self> + ?Sized>(
 		db: &mut DB,
 		depth_to_bottom: usize
 	) -> Result<Self::Value, DB::Error>;
+	/// Get the empty value without writing its nodes to a backend, if
+	/// the construct knows it from the empty values kept by `db`.
+	/// Untouched regions of such empty subtrees are then read through
+	/// `empty_children`.
+	fn lazy_empty_at<DB: Backend<Construct=Self> + ?Sized>(
+		_db: &DB,
+		_depth_to_bottom: usize
+	) -> Option<Self::Value> {
+		None
+	}
+	/// Get the children of `value` if it is a known empty value above
+	/// the bottom, so that a missing node in the backend can be read as
+	/// a virtual empty subtree.
+	fn empty_children<DB: Backend<Construct=Self> + ?Sized>(
+		_db: &DB,
+		_value: &Self::Value
+	) -> Option<(Self::Value, Self::Value)> {
+		None
+	}
+	/// Format a value for diagnostics, such as reporting a leaked root.
+	fn fmt_value(_value: &Self::Value, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str("<value>")
+	}
 }
 
 /// Represents a basic merkle tree with a known root.
@@ -34,7 +77,7 @@ pub trait Tree {
 /// A merkle tree that is similar to a vector.
 pub trait Sequence: Tree {
 	/// The length of the tree.
-	fn len(&self) -> usize;
+	fn len(&self) -> u64;
 }
 
 /// Root status of a merkle tree.
@@ -59,31 +102,157 @@ impl RootStatus for Owned {
 	fn is_dangling() -> bool { false }
 }
 
+/// Location in the tree where an error happened, for diagnostics.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct ErrorContext {
+	/// Generalized index being accessed.
+	pub index: Option<Index>,
+	/// Depth of the missing node, or of the vector being accessed.
+	pub depth: Option<usize>,
+	/// Hash of the missing node, formatted with `Construct::fmt_value`.
+	pub node: Option<String>,
+}
+
 /// Set error.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Error<DBError> {
 	/// The database is corrupted.
-	CorruptedDatabase,
+	CorruptedDatabase(ErrorContext),
 	/// Value trying to access overflowed the list or vector.
-	AccessOverflowed,
+	AccessOverflowed(ErrorContext),
 	/// Parameters are invalid.
 	InvalidParameter,
+	/// Unused padding bits of a value are set, so that its root is not
+	/// canonical.
+	NonCanonical,
+	/// Decoded length of a list exceeds its maximum length.
+	MaxLenExceeded {
+		/// Decoded length.
+		len: u64,
+		/// Maximum length.
+		max_len: u64,
+	},
 	/// Backend database error.
 	Backend(DBError),
 }
 
+impl ErrorContext {
+	/// Context of a node missing from the database.
+	pub fn missing<C: Construct>(node: &C::Value) -> Self {
+		Self {
+			node: Some(format!("{}", DisplayValue::<C>(node))),
+			..Default::default()
+		}
+	}
+}
+
+impl<DBError> Error<DBError> {
+	/// Corrupted database error without context.
+	pub fn corrupted() -> Self {
+		Error::CorruptedDatabase(Default::default())
+	}
+
+	/// Access overflowed error without context.
+	pub fn overflowed() -> Self {
+		Error::AccessOverflowed(Default::default())
+	}
+
+	/// Context of the error, if any.
+	pub fn context(&self) -> Option<&ErrorContext> {
+		match self {
+			Error::CorruptedDatabase(context) | Error::AccessOverflowed(context) => Some(context),
+			_ => None,
+		}
+	}
+
+	/// Set the index of the error, if its context does not have one.
+	pub fn at(mut self, index: Index) -> Self {
+		if let Error::CorruptedDatabase(context) | Error::AccessOverflowed(context) = &mut self {
+			context.index.get_or_insert(index);
+		}
+		self
+	}
+}
+
 impl<DBError> From<DBError> for Error<DBError> {
 	fn from(err: DBError) -> Self {
 		Error::Backend(err)
 	}
 }
 
+impl fmt::Display for ErrorContext {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut separator = " at";
+		if let Some(index) = &self.index {
+			write!(f, "{} index {}", separator, index)?;
+			separator = ",";
+		}
+		if let Some(depth) = self.depth {
+			write!(f, "{} depth {}", separator, depth)?;
+			separator = ",";
+		}
+		if let Some(node) = &self.node {
+			write!(f, "{} node {}", separator, node)?;
+		}
+		Ok(())
+	}
+}
+
+impl<DBError> Error<DBError> {
+	fn fmt_with<F>(&self, f: &mut fmt::Formatter, backend: F) -> fmt::Result where
+		F: FnOnce(&DBError, &mut fmt::Formatter) -> fmt::Result,
+	{
+		match self {
+			Error::CorruptedDatabase(context) => write!(f, "corrupted database{}", context),
+			Error::AccessOverflowed(context) => write!(f, "access overflowed{}", context),
+			Error::InvalidParameter => f.write_str("invalid parameter"),
+			Error::NonCanonical => f.write_str("unused padding bits are set"),
+			Error::MaxLenExceeded { len, max_len } =>
+				write!(f, "length {} exceeds maximum length {}", len, max_len),
+			Error::Backend(err) => backend(err, f),
+		}
+	}
+}
+
+/// With `std`, the backend error is left to `source`.
+#[cfg(feature = "std")]
+impl<DBError> fmt::Display for Error<DBError> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.fmt_with(f, |_, f| f.write_str("backend database error"))
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl<DBError: fmt::Display> fmt::Display for Error<DBError> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.fmt_with(f, |err, f| write!(f, "backend database error: {}", err))
+	}
+}
+
+#[cfg(feature = "std")]
+impl<DBError: std::error::Error + 'static> std::error::Error for Error<DBError> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Backend(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
 /// Traits for a merkle database.
 pub trait Backend {
 	/// Construct of the backend.
 	type Construct: Construct;
 	/// Error type for DB access.
 	type Error;
+
+	/// Empty values of the construct kept by this backend, so that
+	/// empty subtrees can be read without writing their nodes. The
+	/// default keeps none, and wrappers return the ones of the backend
+	/// they wrap.
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		None
+	}
 }
 
 /// Read backend.
@@ -93,6 +262,16 @@ pub trait ReadBackend: Backend {
 		&mut self,
 		key: &<Self::Construct as Construct>::Value,
 	) -> Result<Option<(<Self::Construct as Construct>::Value, <Self::Construct as Construct>::Value)>, Self::Error>;
+	/// Hint that the nodes of `keys` are about to be read, so that disk
+	/// or network backends can fetch them in one batch. The default
+	/// does nothing.
+	fn prefetch(&mut self, _keys: &[<Self::Construct as Construct>::Value]) { }
+	/// Limits that decoders reading through this backend check the
+	/// lengths and depths they read against. The default is unlimited,
+	/// and `LimitedBackend` returns the limits it was created with.
+	fn decode_limits(&self) -> DecodeLimits {
+		DecodeLimits::unlimited()
+	}
 }
 
 /// Write backend.
@@ -114,12 +293,80 @@ pub trait WriteBackend: ReadBackend {
 		key: <Self::Construct as Construct>::Value,
 		value: (<Self::Construct as Construct>::Value, <Self::Construct as Construct>::Value)
 	) -> Result<(), Self::Error>;
+	/// Begin a batch of writes. Backends persisting to disk should
+	/// apply all writes until `commit_batch` atomically.
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+	/// Commit the current batch of writes.
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+}
+
+/// Read backend whose reads only need a shared reference, so that
+/// several readers can use it at once. A shared reference `&DB` is
+/// itself a `ReadBackend`, which can be passed to APIs taking
+/// `&mut DB`.
+pub trait RefReadBackend: Backend {
+	/// Get an internal item by key.
+	fn get_ref(
+		&self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<Option<NodePair<Self>>, Self::Error>;
+}
+
+impl<DB: RefReadBackend + ?Sized> Backend for &DB {
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		(**self).empty_values()
+	}
+}
+
+impl<DB: RefReadBackend + ?Sized> ReadBackend for &DB {
+	fn get(
+		&mut self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<Option<NodePair<Self>>, Self::Error> {
+		(**self).get_ref(key)
+	}
+}
+
+/// Backends whose reads mutate internal state, such as caches, can be
+/// read through a shared reference by wrapping them in a `RefCell`.
+impl<DB: Backend> Backend for core::cell::RefCell<DB> {
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+}
+
+impl<DB: ReadBackend> RefReadBackend for core::cell::RefCell<DB> {
+	fn get_ref(
+		&self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<Option<NodePair<Self>>, Self::Error> {
+		self.borrow_mut().get(key)
+	}
 }
 
 /// Dynamic backend, where error is stripped.
 #[derive(Default, Clone, Debug)]
 pub struct DynBackend<Ba: Backend>(pub Ba);
 
+/// Backend trait object over construct `C`, so that the backend can be
+/// chosen at runtime without monomorphizing the tree API for each.
+pub type BoxedBackend<'a, C> = Box<dyn WriteBackend<Construct=C, Error=()> + 'a>;
+
+impl<Ba: WriteBackend> DynBackend<Ba> {
+	/// Strip the error of a backend and box it as a trait object.
+	pub fn boxed<'a>(backend: Ba) -> BoxedBackend<'a, Ba::Construct> where
+		Ba: 'a,
+	{
+		Box::new(Self(backend))
+	}
+}
+
 impl<Ba: Backend> core::ops::Deref for DynBackend<Ba> {
 	type Target = Ba;
 
@@ -133,6 +380,10 @@ impl<Ba: Backend> core::ops::DerefMut for DynBackend<Ba> {
 impl<Ba: Backend> Backend for DynBackend<Ba> {
 	type Construct = Ba::Construct;
 	type Error = ();
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.0.empty_values()
+	}
 }
 
 impl<Ba: ReadBackend> ReadBackend for DynBackend<Ba> {
@@ -142,6 +393,14 @@ impl<Ba: ReadBackend> ReadBackend for DynBackend<Ba> {
 	) -> Result<Option<(<Self::Construct as Construct>::Value, <Self::Construct as Construct>::Value)>, Self::Error> {
 		self.0.get(key).map_err(|_| ())
 	}
+
+	fn prefetch(&mut self, keys: &[<Self::Construct as Construct>::Value]) {
+		self.0.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.0.decode_limits()
+	}
 }
 
 impl<Ba: WriteBackend> WriteBackend for DynBackend<Ba> {
@@ -166,6 +425,142 @@ impl<Ba: WriteBackend> WriteBackend for DynBackend<Ba> {
 	) -> Result<(), Self::Error> {
 		self.0.insert(key, value).map_err(|_| ())
 	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		self.0.begin_batch().map_err(|_| ())
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		self.0.commit_batch().map_err(|_| ())
+	}
+}
+
+impl<DB: Backend + ?Sized> Backend for Box<DB> {
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		(**self).empty_values()
+	}
+}
+
+impl<DB: ReadBackend + ?Sized> ReadBackend for Box<DB> {
+	fn get(
+		&mut self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<Option<NodePair<Self>>, Self::Error> {
+		(**self).get(key)
+	}
+
+	fn prefetch(&mut self, keys: &[<Self::Construct as Construct>::Value]) {
+		(**self).prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		(**self).decode_limits()
+	}
+}
+
+impl<DB: WriteBackend + ?Sized> WriteBackend for Box<DB> {
+	fn rootify(
+		&mut self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<(), Self::Error> {
+		(**self).rootify(key)
+	}
+
+	fn unrootify(
+		&mut self,
+		key: &<Self::Construct as Construct>::Value,
+	) -> Result<(), Self::Error> {
+		(**self).unrootify(key)
+	}
+
+	fn insert(
+		&mut self,
+		key: <Self::Construct as Construct>::Value,
+		value: NodePair<Self>,
+	) -> Result<(), Self::Error> {
+		(**self).insert(key, value)
+	}
+
+	fn begin_batch(&mut self) -> Result<(), Self::Error> {
+		(**self).begin_batch()
+	}
+
+	fn commit_batch(&mut self) -> Result<(), Self::Error> {
+		(**self).commit_batch()
+	}
+}
+
+/// Boxed future returned by async backends.
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output=T> + 'a>>;
+
+/// Pair of child values of an intermediate node in the backend.
+pub type NodePair<DB> = (<<DB as Backend>::Construct as Construct>::Value, <<DB as Backend>::Construct as Construct>::Value);
+
+/// Async read backend, for networked or async storage. All read
+/// backends are also async read backends.
+pub trait AsyncReadBackend: Backend {
+	/// Get an internal item by key.
+	fn get_async<'a>(
+		&'a mut self,
+		key: &'a <Self::Construct as Construct>::Value,
+	) -> BackendFuture<'a, Result<Option<NodePair<Self>>, Self::Error>>;
+}
+
+/// Async write backend. All write backends are also async write
+/// backends.
+pub trait AsyncWriteBackend: AsyncReadBackend {
+	/// Rootify a key.
+	fn rootify_async<'a>(
+		&'a mut self,
+		key: &'a <Self::Construct as Construct>::Value,
+	) -> BackendFuture<'a, Result<(), Self::Error>>;
+	/// Unrootify a key.
+	fn unrootify_async<'a>(
+		&'a mut self,
+		key: &'a <Self::Construct as Construct>::Value,
+	) -> BackendFuture<'a, Result<(), Self::Error>>;
+	/// Insert a new internal item.
+	fn insert_async<'a>(
+		&'a mut self,
+		key: <Self::Construct as Construct>::Value,
+		value: (<Self::Construct as Construct>::Value, <Self::Construct as Construct>::Value)
+	) -> BackendFuture<'a, Result<(), Self::Error>>;
+}
+
+impl<DB: ReadBackend + ?Sized> AsyncReadBackend for DB {
+	fn get_async<'a>(
+		&'a mut self,
+		key: &'a <Self::Construct as Construct>::Value,
+	) -> BackendFuture<'a, Result<Option<NodePair<Self>>, Self::Error>> {
+		Box::pin(core::future::ready(self.get(key)))
+	}
+}
+
+impl<DB: WriteBackend + ?Sized> AsyncWriteBackend for DB {
+	fn rootify_async<'a>(
+		&'a mut self,
+		key: &'a <Self::Construct as Construct>::Value,
+	) -> BackendFuture<'a, Result<(), Self::Error>> {
+		Box::pin(core::future::ready(self.rootify(key)))
+	}
+
+	fn unrootify_async<'a>(
+		&'a mut self,
+		key: &'a <Self::Construct as Construct>::Value,
+	) -> BackendFuture<'a, Result<(), Self::Error>> {
+		Box::pin(core::future::ready(self.unrootify(key)))
+	}
+
+	fn insert_async<'a>(
+		&'a mut self,
+		key: <Self::Construct as Construct>::Value,
+		value: (<Self::Construct as Construct>::Value, <Self::Construct as Construct>::Value)
+	) -> BackendFuture<'a, Result<(), Self::Error>> {
+		Box::pin(core::future::ready(self.insert(key, value)))
+	}
 }
 
 /// Leakable value, whose default behavior of drop is to leak.
@@ -177,4 +572,73 @@ pub trait Leak {
 	fn from_leaked(metadata: Self::Metadata) -> Self;
 	/// Metadata of the value.
 	fn metadata(&self) -> Self::Metadata;
+	/// Leak the value intentionally, returning its metadata, so that
+	/// an owned root is not reported as leaked.
+	fn leak(self) -> Self::Metadata where
+		Self: Sized,
+	{
+		let metadata = self.metadata();
+		core::mem::forget(self);
+		metadata
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, ArcBackend, CachedBackend, InstrumentedBackend, Vector, Owned};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	fn backend(kind: usize) -> BoxedBackend<'static, Construct> {
+		match kind {
+			0 => DynBackend::boxed(InMemoryBackend::<Construct>::default()),
+			1 => DynBackend::boxed(ArcBackend::<Construct>::default()),
+			_ => DynBackend::boxed(InstrumentedBackend::new(InMemoryBackend::<Construct>::default())),
+		}
+	}
+
+	#[test]
+	fn test_boxed_backend() {
+		let mut roots = Vec::new();
+		for kind in 0..3 {
+			let mut db = backend(kind);
+			let mut vector = Vector::<Owned, Construct>::create(&mut *db, 0, None).unwrap();
+			for i in 0..5 {
+				vector.push(&mut db, value(i)).unwrap();
+			}
+			assert_eq!(vector.get(&mut *db, 4).unwrap(), value(4));
+			roots.push(vector.root());
+
+			let mut cached = CachedBackend::new(db, 2);
+			assert_eq!(vector.get(&mut cached, 2).unwrap(), value(2));
+		}
+		assert!(roots.iter().all(|root| *root == roots[0]));
+	}
+
+	#[test]
+	fn test_error_display() {
+		use crate::{InMemoryBackendError, CopyError};
+
+		let context = ErrorContext { index: Some(Index::root().left()), depth: Some(1), node: None };
+		assert_eq!(Error::<InMemoryBackendError>::CorruptedDatabase(context).to_string(),
+				   "corrupted database at index 0b10, depth 1");
+		assert_eq!(Error::<InMemoryBackendError>::overflowed().to_string(), "access overflowed");
+		assert_eq!(Error::<InMemoryBackendError>::MaxLenExceeded { len: 5, max_len: 4 }.to_string(),
+				   "length 5 exceeds maximum length 4");
+
+		let err = Error::Backend(InMemoryBackendError::FetchingKeyNotExist);
+		assert_eq!(err.to_string(), "backend database error");
+		let source = std::error::Error::source(&err).unwrap();
+		assert_eq!(source.to_string(), "FetchingKeyNotExist");
+
+		let err = CopyError::<InMemoryBackendError, InMemoryBackendError>::Destination(err);
+		assert_eq!(err.to_string(), "writing destination failed: backend database error");
+		assert!(std::error::Error::source(&err).is_some());
+		let boxed: Box<dyn std::error::Error> = Box::new(Error::<InMemoryBackendError>::InvalidParameter);
+		assert_eq!(boxed.to_string(), "invalid parameter");
+	}
 }
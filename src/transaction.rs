@@ -0,0 +1,177 @@
+use crate::{Backend, ReadBackend, WriteBackend, Construct, DecodeLimits, EmptyValues};
+use core::hash::Hash;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+
+/// Write operation recorded by a transactional backend.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum TransactionOp<V> {
+	/// Rootify a key.
+	Rootify(V),
+	/// Unrootify a key.
+	Unrootify(V),
+	/// Insert a new internal item.
+	Insert(V, (V, V)),
+}
+
+/// Transactional merkle database. Writes are recorded in a log and only
+/// applied to the underlying database on `commit`. Dropping the backend
+/// or calling `rollback` discards them, leaving the underlying database
+/// untouched.
+pub struct TransactionalBackend<'a, DB: Backend + ?Sized> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord
+{
+	db: &'a mut DB,
+	log: Vec<TransactionOp<<DB::Construct as Construct>::Value>>,
+	inserts: Map<<DB::Construct as Construct>::Value, usize>,
+}
+
+impl<'a, DB: Backend + ?Sized> TransactionalBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	/// Create a new transactional database.
+	pub fn new(db: &'a mut DB) -> Self {
+		Self {
+			db,
+			log: Default::default(),
+			inserts: Default::default(),
+		}
+	}
+
+	/// Write operations recorded so far.
+	pub fn log(&self) -> &[TransactionOp<<DB::Construct as Construct>::Value>] {
+		&self.log
+	}
+
+	/// Discard all recorded write operations.
+	pub fn rollback(self) { }
+}
+
+impl<'a, DB: WriteBackend + ?Sized> TransactionalBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	/// Apply all recorded write operations to the underlying database,
+	/// in a single batch.
+	pub fn commit(self) -> Result<(), DB::Error> {
+		apply_ops(self.db, self.log)
+	}
+}
+
+/// Apply write operations to `db` in a single batch.
+pub(crate) fn apply_ops<DB: WriteBackend + ?Sized>(
+	db: &mut DB,
+	ops: Vec<TransactionOp<<DB::Construct as Construct>::Value>>,
+) -> Result<(), DB::Error> {
+	db.begin_batch()?;
+	for op in ops {
+		match op {
+			TransactionOp::Rootify(key) => db.rootify(&key)?,
+			TransactionOp::Unrootify(key) => db.unrootify(&key)?,
+			TransactionOp::Insert(key, value) => db.insert(key, value)?,
+		}
+	}
+	db.commit_batch()
+}
+
+impl<'a, DB: Backend + ?Sized> Backend for TransactionalBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	type Construct = DB::Construct;
+	type Error = DB::Error;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.db.empty_values()
+	}
+}
+
+impl<'a, DB: ReadBackend + ?Sized> ReadBackend for TransactionalBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	fn get(
+		&mut self,
+		key: &<DB::Construct as Construct>::Value
+	) -> Result<Option<(<DB::Construct as Construct>::Value, <DB::Construct as Construct>::Value)>, Self::Error> {
+		match self.inserts.get(key).map(|index| &self.log[*index]) {
+			Some(TransactionOp::Insert(_, value)) => Ok(Some(value.clone())),
+			_ => self.db.get(key),
+		}
+	}
+
+	fn prefetch(&mut self, keys: &[<DB::Construct as Construct>::Value]) {
+		self.db.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.db.decode_limits()
+	}
+}
+
+impl<'a, DB: WriteBackend + ?Sized> WriteBackend for TransactionalBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: Eq + Hash + Ord,
+{
+	fn rootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.log.push(TransactionOp::Rootify(key.clone()));
+		Ok(())
+	}
+
+	fn unrootify(&mut self, key: &<DB::Construct as Construct>::Value) -> Result<(), Self::Error> {
+		self.log.push(TransactionOp::Unrootify(key.clone()));
+		Ok(())
+	}
+
+	fn insert(
+		&mut self,
+		key: <DB::Construct as Construct>::Value,
+		value: (<DB::Construct as Construct>::Value, <DB::Construct as Construct>::Value)
+	) -> Result<(), Self::Error> {
+		self.inserts.insert(key.clone(), self.log.len());
+		self.log.push(TransactionOp::Insert(key, value));
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InMemoryBackend, InheritedDigestConstruct, Vector, Owned, Tree, Leak};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type InMemory = InMemoryBackend<Construct>;
+
+	#[test]
+	fn test_commit_rollback() {
+		let mut db = InMemory::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let metadata = vector.metadata();
+		let snapshot = db.as_ref().clone();
+
+		{
+			let mut tx = TransactionalBackend::new(&mut db);
+			vector.set(&mut tx, 2, value(42)).unwrap();
+			vector.push(&mut tx, value(5)).unwrap();
+			assert_eq!(vector.get(&mut tx, 2).unwrap(), value(42));
+			tx.rollback();
+		}
+		assert_eq!(db.as_ref(), &snapshot);
+
+		let mut vector = Vector::<Owned, Construct>::from_leaked(metadata);
+		let mut expected_db = db.clone();
+		let mut expected = Vector::<Owned, Construct>::from_leaked(vector.metadata());
+		expected.set(&mut expected_db, 2, value(42)).unwrap();
+
+		let mut tx = TransactionalBackend::new(&mut db);
+		vector.set(&mut tx, 2, value(42)).unwrap();
+		tx.commit().unwrap();
+		assert_eq!(vector.root(), expected.root());
+		assert_eq!(db.as_ref(), expected_db.as_ref());
+	}
+}
@@ -1,39 +1,261 @@
 //! Utilities
 
-use crate::{Construct, WriteBackend, Error};
-use alloc::collections::VecDeque;
+use crate::{Construct, Backend, ReadBackend, WriteBackend, Error, EmptyValues};
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::marker::PhantomData;
 use generic_array::ArrayLength;
 
-/// Required depth of given length.
+/// Format bytes as lower-case hex with a `0x` prefix.
+pub fn fmt_hex(bytes: &[u8], f: &mut core::fmt::Formatter) -> core::fmt::Result {
+	f.write_str("0x")?;
+	for byte in bytes {
+		write!(f, "{:02x}", byte)?;
+	}
+	Ok(())
+}
+
+/// Required depth of given length, at most 64.
 pub fn required_depth(len: u64) -> usize {
-	let mut max_len = 1;
-	let mut total_depth = 0;
-	while max_len < len {
-		max_len *= 2;
-		total_depth += 1;
+	match len {
+		0 | 1 => 0,
+		len => (64 - (len - 1).leading_zeros()) as usize,
 	}
-	total_depth
 }
 
 /// Serialize a vector at given depth.
-pub fn vector_tree<DB: WriteBackend>(values: &[<DB::Construct as Construct>::Value], db: &mut DB, max_len: Option<u64>) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> {
+pub fn vector_tree<DB: WriteBackend + ?Sized>(values: &[<DB::Construct as Construct>::Value], db: &mut DB, max_len: Option<u64>) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> {
+	if max_len.map(|max_len| values.len() as u64 > max_len).unwrap_or(false) {
+		return Err(Error::InvalidParameter)
+	}
+
 	let total_depth = required_depth(max_len.unwrap_or(values.len() as u64));
 
-	let mut current = values.iter().cloned().collect::<VecDeque<_>>();
-	let mut next = VecDeque::new();
+	let mut current = values.to_vec();
+	let mut next = Vec::new();
 	for depth in (1..(total_depth + 1)).rev() {
 		let depth_to_bottom = total_depth - depth;
-		while !current.is_empty() {
-			let left = current.pop_front().unwrap_or(<DB::Construct as Construct>::empty_at(db, depth_to_bottom)?);
-			let right = current.pop_front().unwrap_or(<DB::Construct as Construct>::empty_at(db, depth_to_bottom)?);
+		if current.is_empty() {
+			break
+		}
+		if current.len() % 2 == 1 {
+			current.push(<DB::Construct as Construct>::empty_at(db, depth_to_bottom)?);
+		}
 
-			let key = <DB::Construct as Construct>::intermediate_of(&left, &right);
+		<DB::Construct as Construct>::intermediates_of(&current, &mut next);
+		for (key, pair) in next.iter().zip(current.chunks(2)) {
+			db.insert(key.clone(), (pair[0].clone(), pair[1].clone()))?;
+		}
+		current.clear();
+		core::mem::swap(&mut current, &mut next);
+	}
+
+	if current.is_empty() {
+		Ok(<DB::Construct as Construct>::empty_at(db, total_depth)?)
+	} else {
+		Ok(current[0].clone())
+	}
+}
+
+/// Streaming builder of a vector tree, hashing intermediate nodes as
+/// values are pushed. It keeps at most one pending node per level, so
+/// the values do not need to be collected in memory beforehand. The
+/// resulting root equals the one of `vector_tree`.
+#[derive(Debug, Clone)]
+pub struct VectorTreeBuilder<V> {
+	pending: Vec<Option<V>>,
+	len: u64,
+}
+
+impl<V> Default for VectorTreeBuilder<V> {
+	fn default() -> Self {
+		Self { pending: Vec::new(), len: 0 }
+	}
+}
+
+impl<V: Clone> VectorTreeBuilder<V> {
+	/// Create a new empty builder.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create a builder continuing after `len` values, given the roots
+	/// of the complete left subtrees at each level, where `pending[l]`
+	/// covers `2^l` values and is set when bit `l` of `len` is set.
+	pub(crate) fn with_pending(pending: Vec<Option<V>>, len: u64) -> Self {
+		Self { pending, len }
+	}
+
+	/// Number of values pushed.
+	pub fn len(&self) -> u64 {
+		self.len
+	}
 
+	/// Whether no values were pushed.
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Push a value, inserting the intermediate nodes it completes.
+	pub fn push<DB: WriteBackend + ?Sized>(&mut self, db: &mut DB, value: V) -> Result<(), Error<DB::Error>> where
+		DB::Construct: Construct<Value=V>,
+	{
+		let mut current = value;
+		let mut level = 0;
+		while let Some(left) = self.pending.get_mut(level).and_then(|pending| pending.take()) {
+			let key = DB::Construct::intermediate_of(&left, &current);
+			db.insert(key.clone(), (left, current))?;
+			current = key;
+			level += 1;
+		}
+
+		if level == self.pending.len() {
+			self.pending.push(None);
+		}
+		self.pending[level] = Some(current);
+		self.len += 1;
+		Ok(())
+	}
+
+	/// Finish the tree, padding it with empty nodes up to `max_len`, or
+	/// up to the next power of two of the length if not given.
+	pub fn finish<DB: WriteBackend + ?Sized>(self, db: &mut DB, max_len: Option<u64>) -> Result<V, Error<DB::Error>> where
+		DB::Construct: Construct<Value=V>,
+	{
+		if max_len.map(|max_len| self.len > max_len).unwrap_or(false) {
+			return Err(Error::InvalidParameter)
+		}
+
+		let total_depth = required_depth(max_len.unwrap_or(self.len));
+		let mut current: Option<V> = None;
+		for level in 0..total_depth {
+			let pending = self.pending.get(level).cloned().unwrap_or(None);
+			let (left, right) = match (pending, current.take()) {
+				(Some(left), Some(right)) => (left, right),
+				(Some(left), None) => (left, DB::Construct::empty_at(db, level)?),
+				(None, Some(left)) => (left, DB::Construct::empty_at(db, level)?),
+				(None, None) => continue,
+			};
+			let key = DB::Construct::intermediate_of(&left, &right);
 			db.insert(key.clone(), (left, right))?;
-			next.push_back(key);
+			current = Some(key);
+		}
+
+		match current.or_else(|| self.pending.get(total_depth).cloned().unwrap_or(None)) {
+			Some(root) => Ok(root),
+			None => Ok(DB::Construct::empty_at(db, total_depth)?),
 		}
-		current = next;
-		next = VecDeque::new();
+	}
+}
+
+/// Backend for computing roots only. Every node written is discarded,
+/// so building a tree over it stores nothing, and it never fails.
+/// Unlike `NoopBackend`, reads succeed: known empty subtrees are read
+/// through `Construct::empty_children`, and other nodes are missing.
+pub struct HashOnlyBackend<C: Construct>(PhantomData<C>, EmptyValues<C::Value>);
+
+impl<C: Construct> HashOnlyBackend<C> {
+	/// Create a new hash-only backend.
+	pub fn new() -> Self {
+		Self(PhantomData, EmptyValues::new())
+	}
+}
+
+impl<C: Construct> Default for HashOnlyBackend<C> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<C: Construct> Backend for HashOnlyBackend<C> {
+	type Construct = C;
+	type Error = Infallible;
+
+	fn empty_values(&self) -> Option<&EmptyValues<C::Value>> {
+		Some(&self.1)
+	}
+}
+
+impl<C: Construct> ReadBackend for HashOnlyBackend<C> {
+	fn get(&mut self, key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Infallible> {
+		Ok(C::empty_children(self, key))
+	}
+}
+
+impl<C: Construct> WriteBackend for HashOnlyBackend<C> {
+	fn rootify(&mut self, _key: &C::Value) -> Result<(), Infallible> { Ok(()) }
+	fn unrootify(&mut self, _key: &C::Value) -> Result<(), Infallible> { Ok(()) }
+	fn insert(&mut self, _key: C::Value, _value: (C::Value, C::Value)) -> Result<(), Infallible> { Ok(()) }
+}
+
+/// Root of a vector of `values` over a `HashOnlyBackend`, keeping one
+/// pending node per level. Fails only if more than `max_len` values
+/// are produced.
+pub fn vector_root<C: Construct, I: IntoIterator<Item=C::Value>>(
+	values: I,
+	max_len: Option<u64>,
+) -> Result<C::Value, Error<Infallible>> {
+	vector_tree_from_iter(values, &mut HashOnlyBackend::<C>::new(), max_len)
+}
+
+/// Serialize a vector from an iterator of values, without collecting
+/// them, through a `VectorTreeBuilder`. With a `NoopBackend`, only one
+/// pending node per level is kept, so the root of arbitrarily many
+/// values is computed in memory proportional to the depth. Fails as
+/// soon as more than `max_len` values are produced.
+pub fn vector_tree_from_iter<DB: WriteBackend + ?Sized, I: IntoIterator<Item=<DB::Construct as Construct>::Value>>(
+	values: I,
+	db: &mut DB,
+	max_len: Option<u64>,
+) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> {
+	let mut builder = VectorTreeBuilder::new();
+	for value in values {
+		if max_len.map(|max_len| builder.len() >= max_len).unwrap_or(false) {
+			return Err(Error::InvalidParameter)
+		}
+		builder.push(db, value)?;
+	}
+	builder.finish(db, max_len)
+}
+
+/// Number of values hashed in one batch by each parallel job.
+#[cfg(feature = "parallel")]
+pub(crate) const PARALLEL_BATCH_LEN: usize = 256;
+
+/// Serialize a vector at given depth, hashing each level of
+/// intermediate nodes in parallel.
+#[cfg(feature = "parallel")]
+pub fn vector_tree_parallel<DB: WriteBackend + ?Sized>(values: &[<DB::Construct as Construct>::Value], db: &mut DB, max_len: Option<u64>) -> Result<<DB::Construct as Construct>::Value, Error<DB::Error>> where
+	<DB::Construct as Construct>::Value: Send + Sync,
+{
+	use rayon::prelude::*;
+
+	if max_len.map(|max_len| values.len() as u64 > max_len).unwrap_or(false) {
+		return Err(Error::InvalidParameter)
+	}
+
+	let total_depth = required_depth(max_len.unwrap_or(values.len() as u64));
+
+	let mut current = values.to_vec();
+	for depth in (1..(total_depth + 1)).rev() {
+		let depth_to_bottom = total_depth - depth;
+		if current.is_empty() {
+			break
+		}
+		if current.len() % 2 == 1 {
+			current.push(<DB::Construct as Construct>::empty_at(db, depth_to_bottom)?);
+		}
+
+		let intermediates = current.par_chunks(PARALLEL_BATCH_LEN).flat_map_iter(|batch| {
+			let mut out = Vec::with_capacity(batch.len() / 2);
+			<DB::Construct as Construct>::intermediates_of(batch, &mut out);
+			out
+		}).collect::<Vec<_>>();
+
+		for (key, pair) in intermediates.iter().zip(current.chunks(2)) {
+			db.insert(key.clone(), (pair[0].clone(), pair[1].clone()))?;
+		}
+		current = intermediates;
 	}
 
 	if current.is_empty() {
@@ -57,6 +279,122 @@ pub fn host_max_len<Host: ArrayLength<u8>, Value: ArrayLength<u8>>(value_len: u6
 }
 
 /// Get the host len of a packed vector.
-pub fn host_len<Host: ArrayLength<u8>, Value: ArrayLength<u8>>(value_len: usize) -> usize {
-	host_max_len::<Host, Value>(value_len as u64) as usize
+pub fn host_len<Host: ArrayLength<u8>, Value: ArrayLength<u8>>(value_len: u64) -> u64 {
+	host_max_len::<Host, Value>(value_len)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{InMemoryBackend, InheritedDigestConstruct, Vector, DanglingRaw, Owned, Index, Leak, Tree};
+
+	use sha2::Sha256;
+	use generic_array::GenericArray;
+
+	type InMemory = InMemoryBackend<InheritedDigestConstruct<Sha256>>;
+
+	fn value(i: u64) -> GenericArray<u8, typenum::U32> {
+		let mut value = GenericArray::default();
+		value[..8].copy_from_slice(&i.to_le_bytes());
+		value
+	}
+
+	#[test]
+	fn test_required_depth() {
+		assert_eq!(required_depth(0), 0);
+		assert_eq!(required_depth(1), 0);
+		assert_eq!(required_depth(2), 1);
+		assert_eq!(required_depth(5), 3);
+		assert_eq!(required_depth(1 << 40), 40);
+		assert_eq!(required_depth((1 << 40) + 1), 41);
+		assert_eq!(required_depth(u64::MAX), 64);
+	}
+
+	#[test]
+	fn test_vector_tree_builder() {
+		for len in (0..40).chain(Some(1 << 12)) {
+			let values = (0..len).map(value).collect::<Vec<_>>();
+
+			for max_len in &[None, Some(64), Some(1 << 12)] {
+				if max_len.map(|max_len| len > max_len).unwrap_or(false) {
+					continue
+				}
+
+				let mut db = InMemory::default();
+				let mut builder_db = InMemory::default();
+				let root = vector_tree(&values, &mut db, *max_len).unwrap();
+				let mut builder = VectorTreeBuilder::new();
+				for value in &values {
+					builder.push(&mut builder_db, *value).unwrap();
+				}
+				assert_eq!(builder.len(), len);
+				assert_eq!(builder.finish(&mut builder_db, *max_len).unwrap(), root);
+				assert_eq!(db.as_ref(), builder_db.as_ref());
+			}
+		}
+
+		let mut db = InMemory::default();
+		let mut builder = VectorTreeBuilder::new();
+		for i in 0..3 {
+			builder.push(&mut db, value(i)).unwrap();
+		}
+		assert!(matches!(builder.finish(&mut db, Some(2)), Err(Error::InvalidParameter)));
+	}
+
+	#[test]
+	fn test_vector_tree_from_iter() {
+		let values = (0..37).map(value).collect::<Vec<_>>();
+		for max_len in [None, Some(37), Some(64)] {
+			let root = vector_tree(&values, &mut InMemory::default(), max_len).unwrap();
+			let mut db = crate::NoopBackend::<InheritedDigestConstruct<Sha256>>::default();
+			assert_eq!(vector_tree_from_iter(values.iter().cloned(), &mut db, max_len).unwrap(), root);
+		}
+
+		let mut db = crate::NoopBackend::<InheritedDigestConstruct<Sha256>>::default();
+		assert!(matches!(vector_tree_from_iter((0..).map(value), &mut db, Some(8)),
+						 Err(Error::InvalidParameter)));
+	}
+
+	#[test]
+	fn test_hash_only() {
+		type Construct = InheritedDigestConstruct<Sha256>;
+
+		let values = (0..37).map(value).collect::<Vec<_>>();
+		for max_len in [None, Some(37), Some(1 << 40)] {
+			let root = vector_tree(&values, &mut InMemory::default(), max_len).unwrap();
+			assert_eq!(vector_root::<Construct, _>(values.iter().cloned(), max_len).unwrap(), root);
+		}
+		assert!(matches!(vector_root::<Construct, _>((0..).map(value), Some(8)), Err(Error::InvalidParameter)));
+
+		let mut db = HashOnlyBackend::<Construct>::new();
+		let vector = Vector::<Owned, Construct>::create(&mut db, 1 << 20, None).unwrap();
+		assert_eq!(vector.get(&mut db, 12345).unwrap(), value(0));
+		vector.drop(&mut db).unwrap();
+		let root = vector_root::<Construct, _>(values.iter().cloned(), None).unwrap();
+		assert!(matches!(DanglingRaw::<Construct>::from_leaked(root).get_existing(&mut db, Index::from_one(2).unwrap()),
+						 Err(Error::CorruptedDatabase(_))));
+	}
+
+	#[test]
+	#[cfg(feature = "parallel")]
+	fn test_vector_tree_parallel() {
+		use crate::ReadBackend;
+
+		for len in 0..20u8 {
+			let values = (0..len).map(|i| {
+				let mut value = GenericArray::default();
+				value[0] = i;
+				value
+			}).collect::<Vec<_>>();
+
+			for max_len in &[None, Some(32)] {
+				let mut db = InMemory::default();
+				let mut par_db = InMemory::default();
+				let root = vector_tree(&values, &mut db, *max_len).unwrap();
+				let par_root = vector_tree_parallel(&values, &mut par_db, *max_len).unwrap();
+				assert_eq!(root, par_root);
+				assert_eq!(db.get(&root).unwrap(), par_db.get(&par_root).unwrap());
+			}
+		}
+	}
 }
@@ -1,10 +1,16 @@
-use crate::traits::{ReadBackend, WriteBackend, Construct, RootStatus, Owned, Dangling, Leak, Error, Tree, Sequence};
-use crate::raw::Raw;
+use alloc::vec::Vec;
+
+use core::convert::Infallible;
+use core::ops::Range;
+
+use crate::traits::{Backend, ReadBackend, WriteBackend, AsyncReadBackend, AsyncWriteBackend, NodePair, Construct, RootStatus, Owned, Dangling, Leak, Error, ErrorContext, Tree, Sequence};
+use crate::raw::{Raw, children};
 use crate::index::Index;
+use crate::proving::CompactValue;
+use crate::utils::{vector_tree, required_depth, VectorTreeBuilder};
 
 const ROOT_INDEX: Index = Index::root();
 const EXTEND_INDEX: Index = Index::root().left();
-const EMPTY_INDEX: Index = Index::root().right();
 
 /// `Vector` with owned root.
 pub type OwnedVector<C> = Vector<Owned, C>;
@@ -16,28 +22,43 @@ pub type DanglingVector<C> = Vector<Dangling, C>;
 pub struct Vector<R: RootStatus, C: Construct> {
 	raw: Raw<R, C>,
 	max_len: Option<u64>,
-	len: usize,
+	len: u64,
 }
 
 impl<R: RootStatus, C: Construct> Vector<R, C> {
-	fn raw_index(&self, i: usize) -> Index {
+	fn raw_index(&self, i: u64) -> Index {
 		Index::from_depth(i, self.depth())
 	}
 
+	pub(crate) fn overflowed<E>(&self, i: u64) -> Error<E> {
+		let depth = self.depth();
+		let in_tree = 1u64.checked_shl(depth as u32).map(|width| i < width).unwrap_or(false);
+		Error::AccessOverflowed(ErrorContext {
+			index: if in_tree { Some(self.raw_index(i)) } else { None },
+			depth: Some(depth),
+			node: None,
+		})
+	}
+
+	/// Double the tree, placing the current root on the left and an
+	/// empty subtree on the right. Only the new root is hashed, and the
+	/// empty subtree is not written if the construct knows it lazily.
 	fn extend<DB: WriteBackend<Construct=C> + ?Sized>(
 		&mut self,
 		db: &mut DB
 	) -> Result<(), Error<DB::Error>> {
 		let root = self.root();
-		let mut new_raw = Raw::default();
-		let empty = C::empty_at(db, self.depth())?;
-		new_raw.set(db, EXTEND_INDEX, root)?;
-		new_raw.set(db, EMPTY_INDEX, empty)?;
-		self.raw.set(db, ROOT_INDEX, Default::default())?;
-		self.raw = new_raw;
+		let empty = match C::lazy_empty_at(&*db, self.depth()) {
+			Some(empty) => empty,
+			None => C::empty_at(db, self.depth())?,
+		};
+		let extended = C::intermediate_of(&root, &empty);
+		db.insert(extended.clone(), (root, empty))?;
+		self.raw.set(db, ROOT_INDEX, extended)?;
 		Ok(())
 	}
 
+	/// Halve the tree, keeping its left subtree as the new root.
 	fn shrink<DB: WriteBackend<Construct=C> + ?Sized>(
 		&mut self,
 		db: &mut DB
@@ -49,11 +70,252 @@ impl<R: RootStatus, C: Construct> Vector<R, C> {
 		Ok(())
 	}
 
+	fn rebuild_node<DB: WriteBackend<Construct=C> + ?Sized>(
+		db: &mut DB,
+		node: C::Value,
+		depth_to_bottom: usize,
+		offset: u64,
+		start: u64,
+		values: &[C::Value],
+	) -> Result<C::Value, Error<DB::Error>> {
+		let width = 1 << depth_to_bottom;
+		if offset + width <= start || offset >= start + values.len() as u64 {
+			return Ok(node)
+		}
+
+		if depth_to_bottom == 0 {
+			return Ok(values[(offset - start) as usize].clone())
+		}
+
+		let (left, right) = children(db, &node)?
+			.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<C>(&node)))?;
+		let left = Self::rebuild_node(db, left, depth_to_bottom - 1, offset, start, values)?;
+		let right = Self::rebuild_node(db, right, depth_to_bottom - 1, offset + width / 2, start, values)?;
+
+		let key = C::intermediate_of(&left, &right);
+		db.insert(key.clone(), (left, right))?;
+		Ok(key)
+	}
+
+	/// Read the children of every node above the bottom that covers an
+	/// update, collecting them per depth to the bottom in ascending
+	/// order of their position in that level.
+	fn affected_nodes<DB: ReadBackend<Construct=C> + ?Sized>(
+		db: &mut DB,
+		node: &C::Value,
+		depth_to_bottom: usize,
+		position: u64,
+		updates: &[(u64, C::Value)],
+		levels: &mut Vec<Vec<(u64, NodePair<DB>)>>,
+	) -> Result<(), Error<DB::Error>> {
+		if updates.is_empty() || depth_to_bottom == 0 {
+			return Ok(())
+		}
+
+		let mid = (2 * position + 1) << (depth_to_bottom - 1);
+		let split = updates.iter().position(|(index, _)| *index >= mid).unwrap_or(updates.len());
+
+		let (left, right) = children(db, node)?
+			.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<C>(node)))?;
+		Self::affected_nodes(db, &left, depth_to_bottom - 1, 2 * position, &updates[..split], levels)?;
+		Self::affected_nodes(db, &right, depth_to_bottom - 1, 2 * position + 1, &updates[split..], levels)?;
+		levels[depth_to_bottom].push((position, (left, right)));
+		Ok(())
+	}
+
+	fn clear_node<DB: WriteBackend<Construct=C> + ?Sized>(
+		db: &mut DB,
+		node: C::Value,
+		depth_to_bottom: usize,
+		offset: u64,
+		start: u64,
+	) -> Result<C::Value, Error<DB::Error>> {
+		let width = 1 << depth_to_bottom;
+		if offset + width <= start {
+			return Ok(node)
+		}
+
+		if offset >= start {
+			return Ok(C::empty_at(db, depth_to_bottom)?)
+		}
+
+		let (left, right) = children(db, &node)?
+			.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<C>(&node)))?;
+		let left = Self::clear_node(db, left, depth_to_bottom - 1, offset, start)?;
+		let right = Self::clear_node(db, right, depth_to_bottom - 1, offset + width / 2, start)?;
+
+		let key = C::intermediate_of(&left, &right);
+		db.insert(key.clone(), (left, right))?;
+		Ok(key)
+	}
+
+	/// Read the leaves at the sorted `indexes` under `root`, walking the
+	/// tree one level at a time so that each level is prefetched at once.
+	fn read_nodes<DB: ReadBackend<Construct=C> + ?Sized>(
+		db: &mut DB,
+		root: C::Value,
+		depth: usize,
+		indexes: &[(u64, usize)],
+		out: &mut [C::Value],
+	) -> Result<(), Error<DB::Error>> {
+		if indexes.is_empty() {
+			return Ok(())
+		}
+
+		let mut current = alloc::vec![(root, 0, indexes)];
+		let mut next = Vec::new();
+		for depth_to_bottom in (1..=depth).rev() {
+			db.prefetch(&current.iter().map(|(node, _, _)| node.clone()).collect::<Vec<_>>());
+
+			for (node, offset, indexes) in current.drain(..) {
+				let mid = offset + (1 << (depth_to_bottom - 1));
+				let split = indexes.iter().position(|(index, _)| *index >= mid).unwrap_or(indexes.len());
+
+				let (left, right) = children(db, &node)?
+					.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<C>(&node)))?;
+				if split > 0 {
+					next.push((left, offset, &indexes[..split]));
+				}
+				if split < indexes.len() {
+					next.push((right, mid, &indexes[split..]));
+				}
+			}
+			core::mem::swap(&mut current, &mut next);
+		}
+
+		for (node, _, indexes) in current {
+			for (_, position) in indexes {
+				out[*position] = node.clone();
+			}
+		}
+		Ok(())
+	}
+
+	/// Grow the vector to `new_len`, extending the depth as needed. New
+	/// values are left empty.
+	fn grow<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		new_len: u64
+	) -> Result<(), Error<DB::Error>> {
+		if let Some(max_len) = self.max_len {
+			if new_len > max_len {
+				return Err(self.overflowed(new_len - 1))
+			}
+		}
+
+		let old_depth = self.depth();
+		let new_depth = self.depth_at(new_len);
+
+		let mut root = self.root();
+		for depth in old_depth..new_depth {
+			let empty = C::empty_at(db, depth)?;
+			let key = C::intermediate_of(&root, &empty);
+			db.insert(key.clone(), (root, empty))?;
+			root = key;
+		}
+		self.raw.set(db, ROOT_INDEX, root)?;
+		self.len = new_len;
+		Ok(())
+	}
+
+	/// Set a contiguous range of values starting at `start`,
+	/// recomputing each affected intermediate node only once.
+	pub fn set_range<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		start: u64,
+		values: &[C::Value]
+	) -> Result<(), Error<DB::Error>> {
+		if values.is_empty() {
+			return Ok(())
+		}
+
+		let end = start + values.len() as u64;
+		if end > self.len() {
+			return Err(self.overflowed(end - 1))
+		}
+
+		let root = Self::rebuild_node(db, self.root(), self.depth(), 0, start, values)?;
+		self.raw.set(db, ROOT_INDEX, root)?;
+		Ok(())
+	}
+
+	/// Set values at the given indexes, in any order. All updates are
+	/// applied before recomputing each affected intermediate node once,
+	/// bottom-up. If an index is repeated, its last value is kept.
+	pub fn set_many<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		updates: &[(u64, C::Value)]
+	) -> Result<(), Error<DB::Error>> {
+		let mut sorted = updates.to_vec();
+		sorted.sort_by_key(|(index, _)| *index);
+		self.set_sorted(db, &sorted)
+	}
+
+	/// Set values at the given indexes, which must be sorted in
+	/// ascending order. Each affected intermediate node is only
+	/// recomputed once.
+	pub(crate) fn set_sorted<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		updates: &[(u64, C::Value)]
+	) -> Result<(), Error<DB::Error>> {
+		match updates.last() {
+			None => return Ok(()),
+			Some((index, _)) if *index >= self.len() => return Err(self.overflowed(*index)),
+			Some(_) => (),
+		}
+
+		let depth = self.depth();
+		let mut levels = (0..(depth + 1)).map(|_| Vec::new()).collect::<Vec<_>>();
+		Self::affected_nodes(db, &self.root(), depth, 0, updates, &mut levels)?;
+
+		// Values of the current level that changed, the last update of
+		// an index taking precedence.
+		let mut current = Vec::<(u64, C::Value)>::with_capacity(updates.len());
+		for (index, value) in updates {
+			match current.last_mut() {
+				Some(last) if last.0 == *index => last.1 = value.clone(),
+				_ => current.push((*index, value.clone())),
+			}
+		}
+
+		// Hash each level at once, so that constructs can batch it.
+		let mut pairs = Vec::new();
+		let mut keys = Vec::new();
+		for nodes in levels.into_iter().skip(1) {
+			let mut changed = current.into_iter().peekable();
+			for (position, (left, right)) in &nodes {
+				let left = changed.next_if(|(index, _)| *index == 2 * position)
+					.map(|(_, value)| value).unwrap_or_else(|| left.clone());
+				let right = changed.next_if(|(index, _)| *index == 2 * position + 1)
+					.map(|(_, value)| value).unwrap_or_else(|| right.clone());
+				pairs.push(left);
+				pairs.push(right);
+			}
+
+			keys.clear();
+			C::intermediates_of(&pairs, &mut keys);
+			current = Vec::with_capacity(nodes.len());
+			for ((position, _), (key, pair)) in nodes.iter().zip(keys.drain(..).zip(pairs.chunks(2))) {
+				db.insert(key.clone(), (pair[0].clone(), pair[1].clone()))?;
+				current.push((*position, key));
+			}
+			pairs.clear();
+		}
+
+		let root = current.pop().expect("updates are not empty, so the root is changed; qed").1;
+		self.raw.set(db, ROOT_INDEX, root)?;
+		Ok(())
+	}
+
 	/// Current maximum length of the vector.
 	pub fn current_max_len(&self) -> u64 {
 		self.max_len.unwrap_or({
 			let mut max_len = 1;
-			while max_len < self.len as u64 {
+			while max_len < self.len {
 				max_len *= 2;
 			}
 			max_len
@@ -67,38 +329,88 @@ impl<R: RootStatus, C: Construct> Vector<R, C> {
 
 	/// Depth of the vector.
 	pub fn depth(&self) -> usize {
-		let mut max_len = 1;
-		let mut depth = 0;
-		while max_len < self.current_max_len() {
-			max_len *= 2;
-			depth += 1;
-		}
-		depth
+		self.depth_at(self.len)
+	}
+
+	/// Depth of the tree once the vector has `len` values.
+	fn depth_at(&self, len: u64) -> usize {
+		required_depth(self.max_len.unwrap_or(len))
 	}
 
 	/// Get value at index.
 	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(
 		&self,
 		db: &mut DB,
-		index: usize
+		index: u64
 	) -> Result<C::Value, Error<DB::Error>> {
 		if index >= self.len() {
-			return Err(Error::AccessOverflowed)
+			return Err(self.overflowed(index))
 		}
 
 		let raw_index = self.raw_index(index);
-		self.raw.get(db, raw_index)?.ok_or(Error::CorruptedDatabase)
+		self.raw.get_existing(db, raw_index)
+	}
+
+	/// Get values at the given indexes, in the same order. Indexes are
+	/// sorted so that the tree is walked once, and each intermediate
+	/// node shared by several paths is only read once. The nodes of
+	/// each level are prefetched together.
+	pub fn get_many<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		indexes: &[u64]
+	) -> Result<Vec<C::Value>, Error<DB::Error>> {
+		let mut sorted = indexes.iter().cloned().zip(0..).collect::<Vec<_>>();
+		sorted.sort_unstable();
+		if let Some((index, _)) = sorted.last() {
+			if *index >= self.len() {
+				return Err(self.overflowed(*index))
+			}
+		}
+
+		let mut out = alloc::vec![C::Value::default(); indexes.len()];
+		Self::read_nodes(db, self.root(), self.depth(), &sorted, &mut out)?;
+		Ok(out)
+	}
+
+	/// Prove the values in `range` with a compact range proof, to be
+	/// checked by `verify_range` against the root at the vector's depth.
+	pub fn prove_range<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		range: Range<u64>,
+	) -> Result<CompactValue<C::Value>, Error<DB::Error>> {
+		if range.end > self.len() {
+			return Err(self.overflowed(range.end - 1))
+		}
+
+		crate::range::prove_range(db, &self.root(), self.depth(), range)
+	}
+
+	/// Prove that the vector extends its append-only history at
+	/// `old_len`, to be checked by `verify_consistency` against the old
+	/// root and depth.
+	pub fn prove_consistency<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		old_len: u64,
+	) -> Result<CompactValue<C::Value>, Error<DB::Error>> {
+		if old_len > self.len() {
+			return Err(self.overflowed(old_len - 1))
+		}
+
+		crate::consistency::prove_consistency(db, &self.root(), self.depth(), old_len)
 	}
 
 	/// Set value at index.
 	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(
 		&mut self,
 		db: &mut DB,
-		index: usize,
+		index: u64,
 		value: C::Value
 	) -> Result<(), Error<DB::Error>> {
 		if index >= self.len() {
-			return Err(Error::AccessOverflowed)
+			return Err(self.overflowed(index))
 		}
 
 		let raw_index = self.raw_index(index);
@@ -113,9 +425,9 @@ impl<R: RootStatus, C: Construct> Vector<R, C> {
 		value: C::Value
 	) -> Result<(), Error<DB::Error>> {
 		let old_len = self.len();
-		if (old_len as u64) == self.current_max_len() {
+		if old_len == self.current_max_len() {
 			if self.max_len.is_some() {
-				return Err(Error::AccessOverflowed)
+				return Err(self.overflowed(old_len))
 			} else {
 				self.extend(db)?;
 			}
@@ -142,7 +454,7 @@ impl<R: RootStatus, C: Construct> Vector<R, C> {
 		let len = old_len - 1;
 		let index = old_len - 1;
 		let raw_index = self.raw_index(index);
-		let value = self.raw.get(db, raw_index)?.ok_or(Error::CorruptedDatabase)?;
+		let value = self.raw.get_existing(db, raw_index)?;
 
 		let mut empty_depth_to_bottom = 0;
 		let mut replace_index = raw_index;
@@ -161,7 +473,7 @@ impl<R: RootStatus, C: Construct> Vector<R, C> {
 		let empty = C::empty_at(db, empty_depth_to_bottom)?;
 		self.raw.set(db, replace_index, empty)?;
 
-		if (len as u64) <= self.current_max_len() / 2 {
+		if len <= self.current_max_len() / 2 {
 			if self.max_len.is_none() {
 				self.shrink(db)?;
 			}
@@ -170,17 +482,391 @@ impl<R: RootStatus, C: Construct> Vector<R, C> {
 		Ok(Some(value))
 	}
 
+	/// Insert a value at index, shifting all values after it to the
+	/// right.
+	pub fn insert<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		value: C::Value
+	) -> Result<(), Error<DB::Error>> {
+		let old_len = self.len();
+		if index > old_len {
+			return Err(self.overflowed(index))
+		}
+
+		let mut values = Vec::with_capacity((old_len - index + 1) as usize);
+		values.push(value);
+		for i in index..old_len {
+			values.push(self.get(db, i)?);
+		}
+
+		self.push(db, Default::default())?;
+		self.set_range(db, index, &values)
+	}
+
+	/// Remove the value at index, shifting all values after it to the
+	/// left.
+	pub fn remove<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64
+	) -> Result<C::Value, Error<DB::Error>> {
+		let old_len = self.len();
+		if index >= old_len {
+			return Err(self.overflowed(index))
+		}
+
+		let value = self.get(db, index)?;
+		let mut values = Vec::with_capacity((old_len - index - 1) as usize);
+		for i in (index + 1)..old_len {
+			values.push(self.get(db, i)?);
+		}
+
+		self.set_range(db, index, &values)?;
+		self.pop(db)?;
+		Ok(value)
+	}
+
+	/// Remove the value at index, replacing it with the last value of
+	/// the vector. This does not preserve ordering, but is O(log n).
+	pub fn swap_remove<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64
+	) -> Result<C::Value, Error<DB::Error>> {
+		if index >= self.len() {
+			return Err(self.overflowed(index))
+		}
+
+		let last = self.pop(db)?.ok_or_else(Error::corrupted)?;
+		if index == self.len() {
+			return Ok(last)
+		}
+
+		let value = self.get(db, index)?;
+		self.set(db, index, last)?;
+		Ok(value)
+	}
+
+	/// Shorten the vector, keeping the first `new_len` values. Has no
+	/// effect if `new_len` is greater or equal to the current length.
+	pub fn truncate<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		new_len: u64
+	) -> Result<(), Error<DB::Error>> {
+		if new_len >= self.len() {
+			return Ok(())
+		}
+
+		let old_depth = self.depth();
+		let new_depth = self.depth_at(new_len);
+
+		let mut root = self.root();
+		for _ in new_depth..old_depth {
+			root = children(db, &root)?
+				.ok_or_else(|| Error::CorruptedDatabase(ErrorContext::missing::<C>(&root)))?.0;
+		}
+		let root = Self::clear_node(db, root, new_depth, 0, new_len)?;
+
+		self.raw.set(db, ROOT_INDEX, root)?;
+		self.len = new_len;
+		Ok(())
+	}
+
+	/// Resize the vector to `new_len`. Extra values are filled with
+	/// `value` if the vector grows, and truncated if it shrinks.
+	pub fn resize<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		new_len: u64,
+		value: C::Value
+	) -> Result<(), Error<DB::Error>> {
+		let old_len = self.len();
+		if new_len <= old_len {
+			return self.truncate(db, new_len)
+		}
+
+		self.grow(db, new_len)?;
+
+		let mut values = Vec::new();
+		values.resize((new_len - old_len) as usize, value);
+		self.set_range(db, old_len, &values)
+	}
+
+	/// Append all values in the slice to the vector. New subtrees are
+	/// built bottom-up, so each intermediate node is only hashed once.
+	pub fn extend_from_slice<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		values: &[C::Value]
+	) -> Result<(), Error<DB::Error>> {
+		let old_len = self.len();
+		self.grow(db, old_len + values.len() as u64)?;
+		self.set_range(db, old_len, values)
+	}
+
+	/// Iterate over values of the vector. The tree is walked
+	/// depth-first, so each intermediate node is only read once.
+	pub fn iter<'a, DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &'a mut DB
+	) -> VectorIter<'a, C, DB> {
+		let mut stack = Vec::new();
+		if self.len() > 0 {
+			stack.push((self.root(), self.depth(), 0));
+		}
+
+		VectorIter { db, stack, len: self.len() }
+	}
+
+	/// Start appending values through a cursor, which caches the
+	/// rightmost path of the tree read once here.
+	pub fn push_cursor<DB: ReadBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB
+	) -> Result<PushCursor<'_, R, C>, Error<DB::Error>> {
+		let depth = self.depth();
+		let len = self.len();
+		let mut pending = Vec::new();
+		for level in 0..=depth {
+			if (len >> level) & 1 == 0 {
+				pending.push(None);
+				continue
+			}
+
+			let index = Index::from_depth((len >> level) - 1, depth - level);
+			let node = self.raw.get(db, index)?.ok_or_else(|| Error::CorruptedDatabase(ErrorContext {
+				index: Some(index),
+				depth: Some(depth),
+				node: None,
+			}))?;
+			pending.push(Some(node));
+		}
+
+		Ok(PushCursor {
+			builder: VectorTreeBuilder::with_pending(pending, len),
+			vector: self,
+		})
+	}
+
 	/// Get the length of the tuple.
-	pub fn len(&self) -> usize {
+	pub fn len(&self) -> u64 {
 		self.len
 	}
 
 	/// Create a tuple from raw merkle tree.
-	pub fn from_raw(raw: Raw<R, C>, len: usize, max_len: Option<u64>) -> Self {
+	pub fn from_raw(raw: Raw<R, C>, len: u64, max_len: Option<u64>) -> Self {
 		Self { raw, len, max_len }
 	}
 }
 
+/// Cursor for sequential appends to a vector. The rightmost path of
+/// the tree is read once when the cursor is created, and each push
+/// then only hashes and writes the subtrees it completes, so appends
+/// take amortized constant hashes and no further reads. The root and
+/// length of the vector are only updated on `finish`. Dropping the
+/// cursor without finishing leaves the vector unchanged, and orphans
+/// the nodes its pushes already wrote, which no root references.
+#[must_use = "the vector is only updated on `finish`"]
+pub struct PushCursor<'a, R: RootStatus, C: Construct> {
+	vector: &'a mut Vector<R, C>,
+	builder: VectorTreeBuilder<C::Value>,
+}
+
+impl<'a, R: RootStatus, C: Construct> PushCursor<'a, R, C> {
+	/// Length of the vector including the values pushed so far.
+	pub fn len(&self) -> u64 {
+		self.builder.len()
+	}
+
+	/// Whether the vector is empty including the values pushed so far.
+	pub fn is_empty(&self) -> bool {
+		self.builder.is_empty()
+	}
+
+	/// Push a new value.
+	pub fn push<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		value: C::Value
+	) -> Result<(), Error<DB::Error>> {
+		if self.vector.max_len.map(|max_len| self.builder.len() >= max_len).unwrap_or(false) {
+			return Err(self.vector.overflowed(self.len()))
+		}
+
+		self.builder.push(db, value)
+	}
+
+	/// Hash the remaining path and set the new root of the vector.
+	pub fn finish<DB: WriteBackend<Construct=C> + ?Sized>(
+		self,
+		db: &mut DB
+	) -> Result<(), Error<DB::Error>> {
+		let len = self.len();
+		let max_len = self.vector.max_len.unwrap_or_else(|| {
+			let mut max_len = 1;
+			while max_len < len {
+				max_len *= 2;
+			}
+			max_len
+		});
+
+		let root = self.builder.finish(db, Some(max_len))?;
+		self.vector.raw.set(db, ROOT_INDEX, root)?;
+		self.vector.len = len;
+		Ok(())
+	}
+}
+
+/// Iterator over values of a vector.
+pub struct VectorIter<'a, C: Construct, DB: ?Sized> {
+	db: &'a mut DB,
+	stack: Vec<(C::Value, usize, u64)>,
+	len: u64,
+}
+
+impl<'a, C: Construct, DB: ReadBackend<Construct=C> + ?Sized> Iterator for VectorIter<'a, C, DB> {
+	type Item = Result<C::Value, Error<DB::Error>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while let Some((node, depth_to_bottom, offset)) = self.stack.pop() {
+			if offset >= self.len {
+				continue
+			}
+
+			if depth_to_bottom == 0 {
+				return Some(Ok(node))
+			}
+
+			match children(self.db, &node) {
+				Ok(Some((left, right))) => {
+					if depth_to_bottom > 1 {
+						self.db.prefetch(&[left.clone(), right.clone()]);
+					}
+					self.stack.push((right, depth_to_bottom - 1, offset + (1 << (depth_to_bottom - 1))));
+					self.stack.push((left, depth_to_bottom - 1, offset));
+				},
+				Ok(None) => {
+					self.stack.clear();
+					return Some(Err(Error::CorruptedDatabase(ErrorContext::missing::<C>(&node))))
+				},
+				Err(err) => {
+					self.stack.clear();
+					return Some(Err(Error::Backend(err)))
+				},
+			}
+		}
+
+		None
+	}
+}
+
+/// Backend recording all inserts, used to compute empty subtrees for
+/// async backends.
+struct RecordingBackend<C: Construct>(Vec<(C::Value, NodePair<Self>)>);
+
+impl<C: Construct> Backend for RecordingBackend<C> {
+	type Construct = C;
+	type Error = Infallible;
+}
+
+impl<C: Construct> ReadBackend for RecordingBackend<C> {
+	fn get(&mut self, _key: &C::Value) -> Result<Option<(C::Value, C::Value)>, Infallible> {
+		Ok(None)
+	}
+}
+
+impl<C: Construct> WriteBackend for RecordingBackend<C> {
+	fn rootify(&mut self, _key: &C::Value) -> Result<(), Infallible> { Ok(()) }
+	fn unrootify(&mut self, _key: &C::Value) -> Result<(), Infallible> { Ok(()) }
+	fn insert(&mut self, key: C::Value, value: (C::Value, C::Value)) -> Result<(), Infallible> {
+		self.0.push((key, value));
+		Ok(())
+	}
+}
+
+impl<R: RootStatus, C: Construct> Vector<R, C> {
+	async fn extend_async<DB: AsyncWriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB
+	) -> Result<(), Error<DB::Error>> {
+		let root = self.root();
+		let empty = match C::lazy_empty_at(&*db, self.depth()) {
+			Some(empty) => empty,
+			None => {
+				let mut recording = RecordingBackend::<C>(Vec::new());
+				let empty = match C::empty_at(&mut recording, self.depth()) {
+					Ok(empty) => empty,
+					Err(never) => match never {},
+				};
+				for (key, value) in recording.0 {
+					db.insert_async(key, value).await?;
+				}
+				empty
+			},
+		};
+		let extended = C::intermediate_of(&root, &empty);
+		db.insert_async(extended.clone(), (root, empty)).await?;
+		self.raw.set_async(db, ROOT_INDEX, extended).await?;
+		Ok(())
+	}
+
+	/// Get value at index, using an async backend.
+	pub async fn get_async<DB: AsyncReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: u64
+	) -> Result<C::Value, Error<DB::Error>> {
+		if index >= self.len() {
+			return Err(self.overflowed(index))
+		}
+
+		let raw_index = self.raw_index(index);
+		self.raw.get_async(db, raw_index).await?.ok_or_else(|| Error::corrupted().at(raw_index))
+	}
+
+	/// Set value at index, using an async backend.
+	pub async fn set_async<DB: AsyncWriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		value: C::Value
+	) -> Result<(), Error<DB::Error>> {
+		if index >= self.len() {
+			return Err(self.overflowed(index))
+		}
+
+		let raw_index = self.raw_index(index);
+		self.raw.set_async(db, raw_index, value).await?;
+		Ok(())
+	}
+
+	/// Push a new value to the vector, using an async backend.
+	pub async fn push_async<DB: AsyncWriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		value: C::Value
+	) -> Result<(), Error<DB::Error>> {
+		let old_len = self.len();
+		if old_len == self.current_max_len() {
+			if self.max_len.is_some() {
+				return Err(self.overflowed(old_len))
+			} else {
+				self.extend_async(db).await?;
+			}
+		}
+		let len = old_len + 1;
+		let index = old_len;
+		self.len = len;
+
+		let raw_index = self.raw_index(index);
+		self.raw.set_async(db, raw_index, value).await?;
+		Ok(())
+	}
+}
+
 impl<R: RootStatus, C: Construct> Tree for Vector<R, C> {
 	type RootStatus = R;
 	type Construct = C;
@@ -203,13 +889,13 @@ impl<R: RootStatus, C: Construct> Tree for Vector<R, C> {
 }
 
 impl<R: RootStatus, C: Construct> Sequence for Vector<R, C> {
-	fn len(&self) -> usize {
+	fn len(&self) -> u64 {
 		self.len
 	}
 }
 
 impl<R: RootStatus, C: Construct> Leak for Vector<R, C> {
-	type Metadata = (C::Value, usize, Option<u64>);
+	type Metadata = (C::Value, u64, Option<u64>);
 
 	fn metadata(&self) -> Self::Metadata {
 		let len = self.len();
@@ -227,29 +913,27 @@ impl<R: RootStatus, C: Construct> Leak for Vector<R, C> {
 }
 
 impl<C: Construct> Vector<Owned, C> {
-	/// Create a new tuple.
+	/// Create a new tuple of `len` empty values. With `max_len`, the
+	/// tree is sized for `max_len` values, and `len` must not exceed it
+	/// or `max_len` be zero, otherwise `InvalidParameter` is returned.
 	pub fn create<DB: WriteBackend<Construct=C> + ?Sized>(
 		db: &mut DB,
-		len: usize,
+		len: u64,
 		max_len: Option<u64>
 	) -> Result<Self, Error<DB::Error>> {
 		if let Some(max_len) = max_len {
-			if (len as u64) < max_len || max_len == 0 {
+			if len > max_len || max_len == 0 {
 				return Err(Error::InvalidParameter)
 			}
 		}
 
 		let mut raw = Raw::<Owned, C>::default();
 
-		let target_len = max_len.unwrap_or(len as u64);
-		let mut current_max_len = 1;
-		let mut depth = 0;
-		while current_max_len < target_len {
-			current_max_len *= 2;
-			depth += 1;
-		}
-
-		let empty = C::empty_at(db, depth)?;
+		let depth = required_depth(max_len.unwrap_or(len));
+		let empty = match C::lazy_empty_at(&*db, depth) {
+			Some(empty) => empty,
+			None => C::empty_at(db, depth)?,
+		};
 		raw.set(db, ROOT_INDEX, empty)?;
 
 		Ok(Self {
@@ -258,11 +942,502 @@ impl<C: Construct> Vector<Owned, C> {
 			max_len,
 		})
 	}
+
+	/// Create a tuple from the given values. The tree is built
+	/// bottom-up, hashing each intermediate node only once.
+	pub fn create_from<DB: WriteBackend<Construct=C> + ?Sized>(
+		db: &mut DB,
+		values: &[C::Value],
+		max_len: Option<u64>
+	) -> Result<Self, Error<DB::Error>> {
+		if let Some(max_len) = max_len {
+			if (values.len() as u64) > max_len || max_len == 0 {
+				return Err(Error::InvalidParameter)
+			}
+		}
+
+		let mut raw = Raw::<Owned, C>::default();
+		let root = vector_tree(values, db, max_len)?;
+		raw.set(db, ROOT_INDEX, root)?;
+
+		Ok(Self {
+			raw,
+			len: values.len() as u64,
+			max_len,
+		})
+	}
+
+	/// Clone into an independent owned vector sharing all nodes with
+	/// the current one.
+	pub fn clone_in<DB: WriteBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB
+	) -> Result<Self, Error<DB::Error>> {
+		Ok(Self {
+			raw: self.raw.clone_in(db)?,
+			len: self.len,
+			max_len: self.max_len,
+		})
+	}
 }
 
 impl<R: RootStatus, C: Construct> Raw<R, C> {
 	/// Convert the current value to a vector.
-	pub fn into_vector(self, len: usize, max_len: Option<u64>) -> Vector<R, C> {
+	pub fn into_vector(self, len: u64, max_len: Option<u64>) -> Vector<R, C> {
 		Vector::from_raw(self, len, max_len)
 	}
 }
+
+/// Serialized metadata of a vector.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct VectorMetadata<V> {
+	pub(crate) root: V,
+	pub(crate) len: u64,
+	pub(crate) max_len: Option<u64>,
+}
+
+/// Serialize a vector handle as its root, length and maximum length.
+#[cfg(feature = "serde")]
+impl<R: RootStatus, C: Construct> serde::Serialize for Vector<R, C> where
+	C::Value: serde::Serialize,
+{
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+		S: serde::Serializer,
+	{
+		VectorMetadata { root: self.root(), len: self.len, max_len: self.max_len }.serialize(serializer)
+	}
+}
+
+/// Deserialize a vector handle from its root, length and maximum
+/// length. Only dangling handles can be deserialized, as the root is
+/// not rootified.
+#[cfg(feature = "serde")]
+impl<'de, C: Construct> serde::Deserialize<'de> for Vector<Dangling, C> where
+	C::Value: serde::Deserialize<'de>,
+{
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+		D: serde::Deserializer<'de>,
+	{
+		let metadata = VectorMetadata::<C::Value>::deserialize(deserializer)?;
+		if metadata.max_len.map(|max_len| metadata.len > max_len).unwrap_or(false) {
+			return Err(<D::Error as serde::de::Error>::custom("invalid length"))
+		}
+
+		Ok(Self::from_leaked((metadata.root, metadata.len, metadata.max_len)))
+	}
+}
+
+/// Encode a vector handle as its root, length and maximum length.
+#[cfg(feature = "parity-codec")]
+impl<R: RootStatus, C: Construct> parity_codec::Encode for Vector<R, C> where
+	C::Value: parity_codec::Encode,
+{
+	fn encode_to<W: parity_codec::Output>(&self, dest: &mut W) {
+		(self.root(), self.len, self.max_len).encode_to(dest)
+	}
+}
+
+#[cfg(feature = "parity-codec")]
+impl<C: Construct> parity_codec::Decode for Vector<Dangling, C> where
+	C::Value: parity_codec::Decode,
+{
+	fn decode<I: parity_codec::Input>(input: &mut I) -> Option<Self> {
+		let (root, len, max_len) = <(C::Value, u64, Option<u64>)>::decode(input)?;
+		if max_len.map(|max_len| len > max_len).unwrap_or(false) {
+			return None
+		}
+
+		Some(Self::from_leaked((root, len, max_len)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::test_utils::block_on;
+	use sha2::Sha256;
+
+	type Construct = crate::InheritedDigestConstruct<Sha256>;
+	type InMemory = crate::memory::InMemoryBackend<Construct>;
+
+	fn vector_of(db: &mut InMemory, values: &[u8]) -> OwnedVector<Construct> {
+		let mut vector = OwnedVector::create(db, 0, None).unwrap();
+		for v in values {
+			vector.push(db, value(*v)).unwrap();
+		}
+		vector
+	}
+
+	#[test]
+	fn test_insert_remove() {
+		let mut db = InMemory::default();
+		let mut vector = vector_of(&mut db, &[1, 2, 3, 5, 6, 7, 8]);
+
+		vector.insert(&mut db, 3, value(4)).unwrap();
+		assert_eq!(vector.len(), 8);
+		assert_eq!(vector.root(), vector_of(&mut db, &[1, 2, 3, 4, 5, 6, 7, 8]).root());
+
+		vector.insert(&mut db, 8, value(9)).unwrap();
+		vector.insert(&mut db, 0, value(0)).unwrap();
+		assert_eq!(vector.root(), vector_of(&mut db, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).root());
+
+		assert_eq!(vector.remove(&mut db, 0).unwrap(), value(0));
+		assert_eq!(vector.remove(&mut db, 4).unwrap(), value(5));
+		assert_eq!(vector.remove(&mut db, 7).unwrap(), value(9));
+		assert_eq!(vector.len(), 7);
+		assert_eq!(vector.root(), vector_of(&mut db, &[1, 2, 3, 4, 6, 7, 8]).root());
+
+		assert!(matches!(vector.insert(&mut db, 8, value(0)), Err(Error::AccessOverflowed(_))));
+		assert!(matches!(vector.remove(&mut db, 7), Err(Error::AccessOverflowed(_))));
+	}
+
+	#[test]
+	fn test_set_range() {
+		let mut db = InMemory::default();
+		let mut vector = vector_of(&mut db, &(0..20).collect::<Vec<_>>());
+
+		vector.set_range(&mut db, 5, &(105..115).map(value).collect::<Vec<_>>()).unwrap();
+		let expected = (0..5).chain(105..115).chain(15..20).collect::<Vec<_>>();
+		assert_eq!(vector.root(), vector_of(&mut db, &expected).root());
+
+		assert!(matches!(vector.set_range(&mut db, 15, &[value(0); 6]), Err(Error::AccessOverflowed(_))));
+	}
+
+	#[test]
+	fn test_missing_node() {
+		let mut db = InMemory::default();
+		let mut vector = vector_of(&mut db, &(0..20).collect::<Vec<_>>());
+		let root = vector.root();
+
+		let mut empty = InMemory::default();
+		assert!(matches!(vector.set_range(&mut empty, 5, &[value(1); 3]), Err(Error::CorruptedDatabase(_))));
+		assert!(matches!(vector.set_many(&mut empty, &[(1, value(1))]), Err(Error::CorruptedDatabase(_))));
+		assert_eq!(vector.root(), root);
+
+		vector.set_range(&mut db, 5, &[value(1); 3]).unwrap();
+		vector.drop(&mut db).unwrap();
+
+		// Unit empty subtrees are never written, and read as empty.
+		type Unit = crate::UnitDigestConstruct<Sha256>;
+		let mut db = crate::memory::InMemoryBackend::<Unit>::default();
+		let mut vector = OwnedVector::<Unit>::create(&mut db, 8, None).unwrap();
+		vector.set_range(&mut db, 2, &[value(1); 3]).unwrap();
+		vector.set_many(&mut db, &[(7, value(2))]).unwrap();
+		assert_eq!(vector.get(&mut db, 7).unwrap(), value(2));
+		vector.drop(&mut db).unwrap();
+	}
+
+	#[test]
+	fn test_swap_remove() {
+		let mut db = InMemory::default();
+		let mut vector = vector_of(&mut db, &[1, 2, 3, 4, 5]);
+
+		assert_eq!(vector.swap_remove(&mut db, 1).unwrap(), value(2));
+		assert_eq!(vector.root(), vector_of(&mut db, &[1, 5, 3, 4]).root());
+		assert_eq!(vector.swap_remove(&mut db, 3).unwrap(), value(4));
+		assert_eq!(vector.root(), vector_of(&mut db, &[1, 5, 3]).root());
+		assert!(matches!(vector.swap_remove(&mut db, 3), Err(Error::AccessOverflowed(_))));
+	}
+
+	#[test]
+	fn test_error_context() {
+		let mut db = InMemory::default();
+		let vector = vector_of(&mut db, &[1, 2, 3, 4, 5]);
+		let depth = vector.depth();
+
+		let err = vector.get(&mut db, 6).unwrap_err();
+		assert_eq!(err, Error::AccessOverflowed(ErrorContext {
+			index: Some(Index::from_depth(6, depth)),
+			depth: Some(depth),
+			node: None,
+		}));
+
+		let root = vector.root();
+		let hex = root.iter().map(|byte| alloc::format!("{:02x}", byte)).collect::<alloc::string::String>();
+		let mut empty = InMemory::default();
+		let err = vector.get(&mut empty, 3).unwrap_err();
+		assert_eq!(err, Error::CorruptedDatabase(ErrorContext {
+			index: Some(Index::from_depth(3, depth)),
+			depth: Some(0),
+			node: Some(alloc::format!("0x{}", hex)),
+		}));
+	}
+
+	#[test]
+	fn test_lazy_empty() {
+		let mut db = InMemory::default();
+		let len = 1u64 << 40;
+		let mut vector = OwnedVector::<Construct>::create(&mut db, len, Some(len)).unwrap();
+		assert!(db.as_ref().values().all(|(children, _)| children.is_none()));
+		assert_eq!(vector.root(), <Construct as crate::Construct>::empty_at(&mut InMemory::default(), 40).unwrap());
+		assert_eq!(vector.get(&mut db, len - 1).unwrap(), Default::default());
+
+		vector.set(&mut db, 12345, value(1)).unwrap();
+		assert_eq!(db.as_ref().values().filter(|(children, _)| children.is_some()).count(), 40);
+		assert_eq!(vector.get(&mut db, 12345).unwrap(), value(1));
+		assert_eq!(vector.get(&mut db, 12344).unwrap(), Default::default());
+		assert_eq!(vector.get(&mut db, len - 1).unwrap(), Default::default());
+
+		vector.set(&mut db, 12345, Default::default()).unwrap();
+		assert_eq!(vector.root(), <Construct as crate::Construct>::empty_at(&mut InMemory::default(), 40).unwrap());
+		vector.drop(&mut db).unwrap();
+	}
+
+	#[test]
+	fn test_push_cursor() {
+		let mut db = crate::InstrumentedBackend::new(InMemory::default());
+		for (start, end) in [(0, 1), (0, 16), (3, 13), (4, 5), (5, 32)] {
+			let mut vector = OwnedVector::<Construct>::create(&mut db, 0, None).unwrap();
+			for i in 0..start {
+				vector.push(&mut db, value(i)).unwrap();
+			}
+
+			let mut cursor = vector.push_cursor(&mut db).unwrap();
+			let before = db.stats();
+			for i in start..end {
+				cursor.push(&mut db, value(i)).unwrap();
+			}
+			let stats = db.stats();
+			assert_eq!(stats.reads, before.reads);
+			assert!(stats.hashes - before.hashes <= 2 * (end - start) as usize);
+			assert_eq!(cursor.len(), end as u64);
+			cursor.finish(&mut db).unwrap();
+
+			let mut expected_db = InMemory::default();
+			let expected = vector_of(&mut expected_db, &(0..end).collect::<Vec<_>>());
+			assert_eq!(vector.len(), end as u64);
+			assert_eq!(vector.root(), expected.root());
+			assert_eq!(vector.get(&mut db, end as u64 - 1).unwrap(), value(end - 1));
+		}
+
+		let mut bounded = OwnedVector::<Construct>::create(&mut db, 3, Some(4)).unwrap();
+		let mut cursor = bounded.push_cursor(&mut db).unwrap();
+		cursor.push(&mut db, value(3)).unwrap();
+		assert!(matches!(cursor.push(&mut db, value(4)), Err(Error::AccessOverflowed(_))));
+		cursor.finish(&mut db).unwrap();
+		assert_eq!(bounded.len(), 4);
+		assert_eq!(bounded.get(&mut db, 3).unwrap(), value(3));
+
+		let mut db = InMemory::default();
+		let mut vector = vector_of(&mut db, &[1, 2, 3]);
+		let root = vector.root();
+		let mut cursor = vector.push_cursor(&mut db).unwrap();
+		for i in 4..8 {
+			cursor.push(&mut db, value(i)).unwrap();
+		}
+		drop(cursor);
+		assert_eq!((vector.len(), vector.root()), (3, root));
+		assert!(!db.unreachable(&[root]).is_empty());
+		vector.drop(&mut db).unwrap();
+	}
+
+	#[test]
+	fn test_extend_shrink_cost() {
+		let mut db = crate::InstrumentedBackend::new(InMemory::default());
+		let mut vector = OwnedVector::<Construct>::create(&mut db, 0, None).unwrap();
+		for i in 1..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let before = db.stats();
+		vector.push(&mut db, value(5)).unwrap();
+		// One hash for the extended root, and three for the pushed path.
+		assert_eq!(db.stats().hashes - before.hashes, 4);
+
+		let mut expected_db = InMemory::default();
+		let values = (1..6).map(value).collect::<Vec<_>>();
+		let expected = OwnedVector::<Construct>::create_from(&mut expected_db, &values, None).unwrap();
+		assert_eq!(vector.root(), expected.root());
+
+		assert_eq!(vector.pop(&mut db).unwrap(), Some(value(5)));
+		assert_eq!(vector.root(), vector_of(&mut expected_db, &[1, 2, 3, 4]).root());
+		for i in 0..4 {
+			vector.pop(&mut db).unwrap();
+			assert_eq!(vector.root(), vector_of(&mut expected_db, &(1..(4 - i)).collect::<Vec<_>>()).root());
+		}
+	}
+
+	#[test]
+	fn test_truncate_resize() {
+		let mut db = InMemory::default();
+		let mut vector = vector_of(&mut db, &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+		vector.truncate(&mut db, 10).unwrap();
+		assert_eq!(vector.len(), 9);
+		vector.truncate(&mut db, 3).unwrap();
+		assert_eq!(vector.len(), 3);
+		assert_eq!(vector.root(), vector_of(&mut db, &[1, 2, 3]).root());
+
+		vector.resize(&mut db, 11, value(7)).unwrap();
+		assert_eq!(vector.len(), 11);
+		assert_eq!(vector.root(), vector_of(&mut db, &[1, 2, 3, 7, 7, 7, 7, 7, 7, 7, 7]).root());
+
+		vector.resize(&mut db, 0, value(7)).unwrap();
+		assert_eq!(vector.len(), 0);
+		assert_eq!(vector.root(), vector_of(&mut db, &[]).root());
+
+		let mut vector = vector_of(&mut db, &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+		let root = vector.root();
+		let mut empty = InMemory::default();
+		assert!(matches!(vector.truncate(&mut empty, 2), Err(Error::CorruptedDatabase(_))));
+		assert!(matches!(vector.truncate(&mut empty, 7), Err(Error::CorruptedDatabase(_))));
+		assert_eq!((vector.len(), vector.root()), (9, root));
+
+		let mut bounded = OwnedVector::<Construct>::create(&mut db, 3, Some(4)).unwrap();
+		assert!(matches!(bounded.resize(&mut db, 5, value(7)), Err(Error::AccessOverflowed(_))));
+		assert_eq!(bounded.len(), 3);
+		bounded.drop(&mut db).unwrap();
+		vector.drop(&mut db).unwrap();
+	}
+
+	#[test]
+	fn test_extend_from_slice() {
+		let mut db = InMemory::default();
+		let mut vector = vector_of(&mut db, &[1, 2, 3]);
+
+		vector.extend_from_slice(&mut db, &[]).unwrap();
+		assert_eq!(vector.root(), vector_of(&mut db, &[1, 2, 3]).root());
+		vector.extend_from_slice(&mut db, &(4..20).map(value).collect::<Vec<_>>()).unwrap();
+		assert_eq!(vector.len(), 19);
+		assert_eq!(vector.root(), vector_of(&mut db, &(1..20).collect::<Vec<_>>()).root());
+
+		let mut bounded = OwnedVector::create(&mut db, 4, Some(4)).unwrap();
+		bounded.truncate(&mut db, 2).unwrap();
+		assert!(matches!(bounded.extend_from_slice(&mut db, &[value(1), value(2), value(3)]), Err(Error::AccessOverflowed(_))));
+	}
+
+	#[test]
+	fn test_get_many() {
+		let mut db = crate::InstrumentedBackend::new(InMemory::default());
+		let mut vector = OwnedVector::<Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..8 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+
+		let before = db.stats();
+		assert_eq!(vector.get_many(&mut db, &[1, 0, 3, 1]).unwrap(),
+				   vec![value(1), value(0), value(3), value(1)]);
+		// Root, its left child, and the two nodes below it.
+		assert_eq!(db.stats().reads - before.reads, 4);
+		// Each level is prefetched before it is read.
+		assert_eq!(db.stats().prefetched - before.prefetched, 4);
+
+		assert_eq!(vector.get_many(&mut db, &[]).unwrap(), Vec::new());
+		assert!(matches!(vector.get_many(&mut db, &[2, 8]), Err(Error::AccessOverflowed(_))));
+	}
+
+	#[test]
+	fn test_set_many() {
+		let mut db = crate::InstrumentedBackend::new(InMemory::default());
+		let mut vector = OwnedVector::<Construct>::create(&mut db, 8, None).unwrap();
+
+		let before = db.stats();
+		vector.set_many(&mut db, &[(1, value(1)), (0, value(9)), (0, value(0))]).unwrap();
+		// One hash for each node on the shared path.
+		assert_eq!(db.stats().hashes - before.hashes, 3);
+
+		vector.set_many(&mut db, &[(7, value(7)), (4, value(4))]).unwrap();
+		let mut expected_db = InMemory::default();
+		let mut expected = OwnedVector::<Construct>::create(&mut expected_db, 8, None).unwrap();
+		for i in [0, 1, 4, 7] {
+			expected.set(&mut expected_db, i, value(i as u8)).unwrap();
+		}
+		assert_eq!(vector.root(), expected.root());
+
+		assert!(matches!(vector.set_many(&mut db, &[(8, value(8))]), Err(Error::AccessOverflowed(_))));
+		assert_eq!(vector.root(), expected.root());
+	}
+
+	#[test]
+	fn test_create_bounded() {
+		let mut db = InMemory::default();
+		let mut vector = OwnedVector::<Construct>::create(&mut db, 2, Some(8)).unwrap();
+		let expected = OwnedVector::<Construct>::create_from(&mut db, &[Default::default(), Default::default()], Some(8)).unwrap();
+		assert_eq!(vector.root(), expected.root());
+		assert_eq!(vector.get(&mut db, 1).unwrap(), Default::default());
+
+		for i in 2..8 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		assert!(matches!(vector.push(&mut db, value(8)), Err(Error::AccessOverflowed(_))));
+
+		assert!(matches!(OwnedVector::<Construct>::create(&mut db, 9, Some(8)), Err(Error::InvalidParameter)));
+		assert!(matches!(OwnedVector::<Construct>::create(&mut db, 0, Some(0)), Err(Error::InvalidParameter)));
+	}
+
+	#[test]
+	fn test_create_from() {
+		let mut db = InMemory::default();
+		let values = (0..13).map(value).collect::<Vec<_>>();
+
+		let vector = OwnedVector::create_from(&mut db, &values, None).unwrap();
+		assert_eq!(vector.len(), 13);
+		assert_eq!(vector.root(), vector_of(&mut db, &(0..13).collect::<Vec<_>>()).root());
+		for i in 0..13 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8));
+		}
+
+		let mut bounded = OwnedVector::create_from(&mut db, &values, Some(32)).unwrap();
+		assert_eq!(bounded.depth(), 5);
+		bounded.push(&mut db, value(13)).unwrap();
+		assert_eq!(bounded.get(&mut db, 13).unwrap(), value(13));
+
+		assert!(OwnedVector::create_from(&mut db, &values, Some(8)).is_err());
+	}
+
+	#[test]
+	fn test_iter() {
+		let mut db = InMemory::default();
+		let vector = vector_of(&mut db, &(0..21).collect::<Vec<_>>());
+
+		let values = vector.iter(&mut db).collect::<Result<Vec<_>, _>>().unwrap();
+		assert_eq!(values, (0..21).map(value).collect::<Vec<_>>());
+
+		let empty = vector_of(&mut db, &[]);
+		assert_eq!(empty.iter(&mut db).count(), 0);
+	}
+
+	#[test]
+	fn test_async() {
+		let mut db = InMemory::default();
+		let sync = vector_of(&mut db, &[1, 2, 3, 4, 5]);
+
+		let mut async_db = InMemory::default();
+		let mut vector = OwnedVector::create(&mut async_db, 0, None).unwrap();
+		for v in &[1, 2, 3, 4, 5] {
+			block_on(vector.push_async(&mut async_db, value(*v))).unwrap();
+		}
+		assert_eq!(vector.root(), sync.root());
+		assert_eq!(block_on(vector.get_async(&mut async_db, 3)).unwrap(), value(4));
+
+		block_on(vector.set_async(&mut async_db, 3, value(9))).unwrap();
+		let mut sync = sync;
+		sync.set(&mut db, 3, value(9)).unwrap();
+		assert_eq!(vector.root(), sync.root());
+		assert_eq!(vector.get(&mut async_db, 3).unwrap(), value(9));
+	}
+
+	#[test]
+	fn test_clone_in() {
+		let mut db = InMemory::default();
+		let mut other = InMemory::default();
+		let vector = vector_of(&mut db, &[1, 2, 3, 4, 5]);
+		let mut branch = vector.clone_in(&mut db).unwrap();
+		assert_eq!(branch.root(), vector.root());
+
+		branch.set(&mut db, 0, value(10)).unwrap();
+		branch.push(&mut db, value(6)).unwrap();
+		assert_eq!(branch.root(), vector_of(&mut other, &[10, 2, 3, 4, 5, 6]).root());
+
+		branch.drop(&mut db).unwrap();
+		assert_eq!(vector.root(), vector_of(&mut other, &[1, 2, 3, 4, 5]).root());
+		for i in 0..5 {
+			assert_eq!(vector.get(&mut db, i).unwrap(), value(i as u8 + 1));
+		}
+
+		vector.drop(&mut db).unwrap();
+		assert_eq!(db.as_ref().len(), 1);
+	}
+}
@@ -0,0 +1,95 @@
+use crate::{Backend, ReadBackend, Construct, NodePair, DecodeLimits, EmptyValues};
+
+/// Error of `VerifiedBackend`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum VerifiedBackendError<E> {
+	/// The children read for a node do not hash to its key.
+	Mismatch,
+	/// Backend database error.
+	Backend(E),
+}
+
+/// Read backend recomputing each parent hash from the children read
+/// for it, and failing with `VerifiedBackendError::Mismatch` if they
+/// differ. Decoding through it catches corrupted or modified databases
+/// instead of returning wrong values.
+pub struct VerifiedBackend<'a, DB: ?Sized> {
+	db: &'a mut DB,
+	verified: u64,
+}
+
+impl<'a, DB: ?Sized> VerifiedBackend<'a, DB> {
+	/// Create a new verified backend reading from `db`.
+	pub fn new(db: &'a mut DB) -> Self {
+		Self { db, verified: 0 }
+	}
+
+	/// Number of node hashes recomputed so far.
+	pub fn verified(&self) -> u64 {
+		self.verified
+	}
+}
+
+impl<'a, DB: Backend + ?Sized> Backend for VerifiedBackend<'a, DB> {
+	type Construct = DB::Construct;
+	type Error = VerifiedBackendError<DB::Error>;
+
+	fn empty_values(&self) -> Option<&EmptyValues<<Self::Construct as Construct>::Value>> {
+		self.db.empty_values()
+	}
+}
+
+impl<'a, DB: ReadBackend + ?Sized> ReadBackend for VerifiedBackend<'a, DB> where
+	<DB::Construct as Construct>::Value: PartialEq,
+{
+	fn get(
+		&mut self,
+		key: &<DB::Construct as Construct>::Value
+	) -> Result<Option<NodePair<DB>>, Self::Error> {
+		let value = self.db.get(key).map_err(VerifiedBackendError::Backend)?;
+		if let Some((left, right)) = &value {
+			self.verified += 1;
+			if DB::Construct::intermediate_of(left, right) != *key {
+				return Err(VerifiedBackendError::Mismatch)
+			}
+		}
+		Ok(value)
+	}
+
+	fn prefetch(&mut self, keys: &[<DB::Construct as Construct>::Value]) {
+		self.db.prefetch(keys)
+	}
+
+	fn decode_limits(&self) -> DecodeLimits {
+		self.db.decode_limits()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree, Error};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_verified() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..4 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		assert_eq!(vector.get(&mut VerifiedBackend::new(&mut db), 2).unwrap(), value(2));
+
+		let (left, _) = db.get(&vector.root()).unwrap().unwrap();
+		let (_, right) = db.get(&left).unwrap().unwrap();
+		db.populate(core::iter::once((left, (value(42), right))).collect());
+		assert_eq!(vector.get(&mut db, 0).unwrap(), value(42));
+		assert_eq!(vector.get(&mut VerifiedBackend::new(&mut db), 0).err(),
+				   Some(Error::Backend(VerifiedBackendError::Mismatch)));
+		assert_eq!(vector.get(&mut VerifiedBackend::new(&mut db), 2).unwrap(), value(2));
+	}
+}
@@ -0,0 +1,115 @@
+use core::ops::{Deref, DerefMut};
+use alloc::vec::Vec;
+
+use crate::traits::{WriteBackend, Construct, Leak, Error, Tree};
+use crate::vector::{OwnedVector, DanglingVector};
+
+/// Vector recording its root after each mutation batch. Every committed
+/// root is rootified in the backend, so that historical versions share
+/// their unchanged nodes with the current vector and stay readable
+/// until the vector is dropped.
+pub struct VersionedVector<C: Construct> {
+	vector: OwnedVector<C>,
+	versions: Vec<(C::Value, u64, Option<u64>)>,
+}
+
+impl<C: Construct> VersionedVector<C> {
+	/// Create a new versioned vector from the current vector, with no
+	/// committed versions.
+	pub fn new(vector: OwnedVector<C>) -> Self {
+		Self { vector, versions: Vec::new() }
+	}
+
+	/// Commit the current state as a new version, returning its number.
+	pub fn commit<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB
+	) -> Result<usize, Error<DB::Error>> {
+		let metadata = self.vector.metadata();
+		db.rootify(&metadata.0)?;
+		self.versions.push(metadata);
+
+		Ok(self.versions.len() - 1)
+	}
+
+	/// Number of committed versions.
+	pub fn versions(&self) -> usize {
+		self.versions.len()
+	}
+
+	/// Root of a committed version.
+	pub fn root_at(&self, version: usize) -> Option<C::Value> {
+		self.versions.get(version).map(|metadata| metadata.0.clone())
+	}
+
+	/// Reopen a committed version as a read-only vector.
+	pub fn at(&self, version: usize) -> Option<DanglingVector<C>> {
+		self.versions.get(version).cloned().map(DanglingVector::from_leaked)
+	}
+
+	/// Drop the current vector and all committed versions.
+	pub fn drop<DB: WriteBackend<Construct=C> + ?Sized>(
+		self,
+		db: &mut DB
+	) -> Result<(), Error<DB::Error>> {
+		for (root, _, _) in self.versions {
+			db.unrootify(&root)?;
+		}
+		self.vector.drop(db)
+	}
+}
+
+impl<C: Construct> Deref for VersionedVector<C> {
+	type Target = OwnedVector<C>;
+
+	fn deref(&self) -> &OwnedVector<C> {
+		&self.vector
+	}
+}
+
+impl<C: Construct> DerefMut for VersionedVector<C> {
+	fn deref_mut(&mut self) -> &mut OwnedVector<C> {
+		&mut self.vector
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InMemoryBackend, InheritedDigestConstruct, Vector, Owned};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type InMemory = InMemoryBackend<Construct>;
+
+	#[test]
+	fn test_versions() {
+		let mut db = InMemory::default();
+		let mut vector = VersionedVector::new(Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap());
+		for i in 0..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		assert_eq!(vector.commit(&mut db).unwrap(), 0);
+
+		vector.set(&mut db, 0, value(100)).unwrap();
+		vector.push(&mut db, value(5)).unwrap();
+		assert_eq!(vector.commit(&mut db).unwrap(), 1);
+		vector.pop(&mut db).unwrap();
+		assert_eq!(vector.versions(), 2);
+
+		let first = vector.at(0).unwrap();
+		assert_eq!(first.len(), 5);
+		assert_eq!(first.get(&mut db, 0).unwrap(), value(0));
+		let second = vector.at(1).unwrap();
+		assert_eq!(second.len(), 6);
+		assert_eq!(second.get(&mut db, 0).unwrap(), value(100));
+		assert_eq!(second.get(&mut db, 5).unwrap(), value(5));
+		assert_eq!(vector.root_at(1), Some(second.root()));
+		assert!(vector.at(2).is_none());
+
+		vector.drop(&mut db).unwrap();
+		assert_eq!(db.as_ref().len(), 1);
+	}
+}
@@ -0,0 +1,130 @@
+use crate::{Construct, ReadBackend, WriteBackend, RootStatus, Error, Index, Raw, OwnedRaw, Tree};
+
+/// View of the subtree of a `Raw` pinned at an anchor index, whose
+/// leaves are at a fixed depth below the anchor, such as a nested
+/// structure inside a larger tree. Leaves are accessed with local
+/// indices, and `set` only rehashes up to the anchor. The new subtree
+/// is attached to the tree once on `finish`.
+pub struct RawView<'a, R: RootStatus, C: Construct> {
+	raw: &'a mut Raw<R, C>,
+	anchor: Index,
+	depth: usize,
+	subtree: OwnedRaw<C>,
+}
+
+impl<R: RootStatus, C: Construct> Raw<R, C> {
+	/// Pin a view of the subtree at `anchor`, whose leaves are at
+	/// `depth` below it.
+	pub fn view<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		anchor: Index,
+		depth: usize,
+	) -> Result<RawView<'_, R, C>, Error<DB::Error>> {
+		let subtree = self.owned_subtree(db, anchor)?;
+		Ok(RawView { raw: self, anchor, depth, subtree })
+	}
+}
+
+impl<'a, R: RootStatus, C: Construct> RawView<'a, R, C> {
+	/// Index of the anchor in the tree.
+	pub fn anchor(&self) -> Index {
+		self.anchor
+	}
+
+	/// Depth of the leaves below the anchor.
+	pub fn depth(&self) -> usize {
+		self.depth
+	}
+
+	/// Current root of the subtree, not yet attached to the tree.
+	pub fn root(&self) -> C::Value {
+		self.subtree.root()
+	}
+
+	/// Index in the tree of the leaf at local `index`.
+	pub fn index_of(&self, index: u64) -> Index {
+		self.anchor.sub(Index::from_depth(index, self.depth))
+	}
+
+	fn local<E>(&self, index: u64) -> Result<Index, Error<E>> {
+		if self.depth < 64 && index >> self.depth != 0 {
+			return Err(Error::overflowed())
+		}
+		Ok(Index::from_depth(index, self.depth))
+	}
+
+	/// Get the leaf at local `index`.
+	pub fn get<DB: ReadBackend<Construct=C> + ?Sized>(
+		&self,
+		db: &mut DB,
+		index: u64,
+	) -> Result<C::Value, Error<DB::Error>> {
+		let local = self.local(index)?;
+		self.subtree.get_existing(db, local)
+	}
+
+	/// Set the leaf at local `index`, rehashing up to the anchor.
+	pub fn set<DB: WriteBackend<Construct=C> + ?Sized>(
+		&mut self,
+		db: &mut DB,
+		index: u64,
+		value: C::Value,
+	) -> Result<(), Error<DB::Error>> {
+		let local = self.local(index)?;
+		self.subtree.set(db, local, value)
+	}
+
+	/// Attach the subtree at the anchor, rehashing from the anchor to
+	/// the root of the tree.
+	pub fn finish<DB: WriteBackend<Construct=C> + ?Sized>(
+		self,
+		db: &mut DB,
+	) -> Result<(), Error<DB::Error>> {
+		self.raw.set(db, self.anchor, self.subtree.root())?;
+		self.subtree.drop(db)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend};
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_raw_view() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut raw = OwnedRaw::<Construct>::default();
+		let mut expected = OwnedRaw::<Construct>::default();
+		for i in 0..16 {
+			raw.set(&mut db, Index::from_depth(i, 4), value(i as u8)).unwrap();
+			expected.set(&mut db, Index::from_depth(i, 4), value(i as u8)).unwrap();
+		}
+
+		let anchor = Index::from_depth(1, 2);
+		let mut view = raw.view(&mut db, anchor, 2).unwrap();
+		assert_eq!(view.index_of(3), Index::from_depth(7, 4));
+		assert_eq!(view.get(&mut db, 1).unwrap(), value(5));
+		assert_eq!(view.get(&mut db, 4), Err(Error::overflowed()));
+		assert_eq!(view.set(&mut db, 4, value(42)), Err(Error::overflowed()));
+
+		view.set(&mut db, 0, value(40)).unwrap();
+		view.set(&mut db, 3, value(43)).unwrap();
+		assert_eq!(view.get(&mut db, 3).unwrap(), value(43));
+		let subroot = view.root();
+		view.finish(&mut db).unwrap();
+
+		expected.set(&mut db, Index::from_depth(4, 4), value(40)).unwrap();
+		expected.set(&mut db, Index::from_depth(7, 4), value(43)).unwrap();
+		assert_eq!(raw.root(), expected.root());
+		assert_eq!(raw.get(&mut db, anchor).unwrap(), Some(subroot));
+
+		raw.drop(&mut db).unwrap();
+		expected.drop(&mut db).unwrap();
+	}
+}
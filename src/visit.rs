@@ -0,0 +1,149 @@
+use alloc::collections::VecDeque;
+
+use crate::{Construct, ReadBackend, Error, ErrorContext, Index, MAX_INDEX_DEPTH};
+
+/// Node reached during a traversal.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VisitNode<V> {
+	/// Generalized index of the node, relative to the traversal root.
+	pub index: Index,
+	/// Depth of the node, where the traversal root is depth 0.
+	pub depth: usize,
+	/// Value of the node.
+	pub value: V,
+	/// Children of the node, or `None` if it is a leaf.
+	pub children: Option<(V, V)>,
+}
+
+/// What to do after visiting a node.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VisitControl {
+	/// Continue the traversal, including children of the node.
+	Continue,
+	/// Continue the traversal, but skip children of the node.
+	SkipChildren,
+	/// Stop the traversal.
+	Stop,
+}
+
+fn fetch<DB: ReadBackend + ?Sized>(
+	db: &mut DB,
+	value: <DB::Construct as Construct>::Value,
+	index: Index,
+	depth: usize,
+) -> Result<VisitNode<<DB::Construct as Construct>::Value>, Error<DB::Error>> {
+	let children = db.get(&value)?;
+	if children.is_some() && depth >= MAX_INDEX_DEPTH {
+		return Err(Error::CorruptedDatabase(ErrorContext {
+			index: Some(index),
+			depth: Some(depth),
+			..ErrorContext::missing::<DB::Construct>(&value)
+		}))
+	}
+
+	Ok(VisitNode { index, depth, value, children })
+}
+
+/// Visit nodes reachable from `root` depth-first, parents before their
+/// children and left before right. Subtrees shared by several parents
+/// are visited once for each position. Returns whether the traversal
+/// completed without being stopped by the visitor.
+pub fn visit<DB: ReadBackend + ?Sized, F>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	mut visitor: F,
+) -> Result<bool, Error<DB::Error>> where
+	F: FnMut(&VisitNode<<DB::Construct as Construct>::Value>) -> VisitControl,
+{
+	let mut stack = alloc::vec![(root.clone(), Index::root(), 0)];
+	while let Some((value, index, depth)) = stack.pop() {
+		let node = fetch(db, value, index, depth)?;
+		match visitor(&node) {
+			VisitControl::Stop => return Ok(false),
+			VisitControl::SkipChildren => (),
+			VisitControl::Continue => if let Some((left, right)) = node.children {
+				stack.push((right, index.right(), depth + 1));
+				stack.push((left, index.left(), depth + 1));
+			},
+		}
+	}
+
+	Ok(true)
+}
+
+/// Visit nodes reachable from `root` breadth-first, level by level and
+/// left to right. Returns whether the traversal completed without being
+/// stopped by the visitor.
+pub fn visit_breadth_first<DB: ReadBackend + ?Sized, F>(
+	db: &mut DB,
+	root: &<DB::Construct as Construct>::Value,
+	mut visitor: F,
+) -> Result<bool, Error<DB::Error>> where
+	F: FnMut(&VisitNode<<DB::Construct as Construct>::Value>) -> VisitControl,
+{
+	let mut queue = VecDeque::new();
+	queue.push_back((root.clone(), Index::root(), 0));
+	while let Some((value, index, depth)) = queue.pop_front() {
+		let node = fetch(db, value, index, depth)?;
+		match visitor(&node) {
+			VisitControl::Stop => return Ok(false),
+			VisitControl::SkipChildren => (),
+			VisitControl::Continue => if let Some((left, right)) = node.children {
+				queue.push_back((left, index.left(), depth + 1));
+				queue.push_back((right, index.right(), depth + 1));
+			},
+		}
+	}
+
+	Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, InMemoryBackend, Vector, Owned, Tree};
+
+	use alloc::vec::Vec;
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+
+	#[test]
+	fn test_visit() {
+		let mut db = InMemoryBackend::<Construct>::default();
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 1..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		let root = vector.root();
+
+		let mut visited = Vec::new();
+		assert!(visit(&mut db, &root, |node| {
+			visited.push((node.index.to_one().unwrap(), node.depth, node.children.is_some()));
+			VisitControl::Continue
+		}).unwrap());
+		assert_eq!(visited, vec![(1, 0, true), (2, 1, true), (4, 2, false), (5, 2, false),
+								 (3, 1, true), (6, 2, false), (7, 2, false)]);
+
+		let mut visited = Vec::new();
+		assert!(visit_breadth_first(&mut db, &root, |node| {
+			visited.push(node.index.to_one().unwrap());
+			if node.index == Index::root().left() {
+				VisitControl::SkipChildren
+			} else {
+				VisitControl::Continue
+			}
+		}).unwrap());
+		assert_eq!(visited, vec![1, 2, 3, 6, 7]);
+
+		let mut leaves = Vec::new();
+		assert!(!visit(&mut db, &root, |node| {
+			if node.children.is_none() {
+				leaves.push(node.value);
+			}
+			if leaves.len() == 3 { VisitControl::Stop } else { VisitControl::Continue }
+		}).unwrap());
+		assert_eq!(leaves, vec![value(1), value(2), value(3)]);
+	}
+}
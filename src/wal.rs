@@ -0,0 +1,400 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Construct;
+use crate::kv::{KeyValueDB, IterableKeyValueDB, KeyValueOp, KeyValueStore, KeyValueStoreError};
+use crate::refcount::RefCountBackend;
+
+const MAGIC: &[u8; 8] = b"bmwal001";
+const PUT: u8 = 0;
+const DELETE: u8 = 1;
+
+/// Error of a write-ahead logged database.
+#[derive(Debug)]
+pub enum WalDBError<E> {
+	/// The underlying database failed.
+	Backend(E),
+	/// The log could not be read or written, or is corrupted.
+	Io(io::Error),
+}
+
+impl<E> From<io::Error> for WalDBError<E> {
+	fn from(err: io::Error) -> Self {
+		WalDBError::Io(err)
+	}
+}
+
+/// FNV-1a checksum of a log record.
+fn checksum(bytes: &[u8]) -> u64 {
+	bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+		(hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+	})
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+	out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+	out.extend_from_slice(bytes);
+}
+
+fn encode(root: Option<&[u8]>, ops: &[KeyValueOp]) -> Vec<u8> {
+	let mut body = Vec::new();
+	match root {
+		Some(root) => { body.push(1); put_bytes(&mut body, root); },
+		None => body.push(0),
+	}
+	for op in ops {
+		match op {
+			KeyValueOp::Put(key, value) => {
+				body.push(PUT);
+				put_bytes(&mut body, key);
+				put_bytes(&mut body, value);
+			},
+			KeyValueOp::Delete(key) => {
+				body.push(DELETE);
+				put_bytes(&mut body, key);
+			},
+		}
+	}
+
+	let mut ret = MAGIC.to_vec();
+	ret.extend_from_slice(&(body.len() as u64).to_le_bytes());
+	ret.extend_from_slice(&checksum(&body).to_le_bytes());
+	ret.extend_from_slice(&body);
+	ret
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+		if self.0.len() < len {
+			return None
+		}
+		let (ret, rest) = self.0.split_at(len);
+		self.0 = rest;
+		Some(ret)
+	}
+
+	fn byte(&mut self) -> Option<u8> {
+		self.take(1).map(|bytes| bytes[0])
+	}
+
+	fn bytes(&mut self) -> Option<Vec<u8>> {
+		let mut len = [0u8; 4];
+		len.copy_from_slice(self.take(4)?);
+		self.take(u32::from_le_bytes(len) as usize).map(|bytes| bytes.to_vec())
+	}
+}
+
+fn decode(raw: &[u8]) -> Option<(Option<Vec<u8>>, Vec<KeyValueOp>)> {
+	let mut reader = Reader(raw);
+	if reader.take(8)? != MAGIC {
+		return None
+	}
+	let mut len = [0u8; 8];
+	len.copy_from_slice(reader.take(8)?);
+	let mut sum = [0u8; 8];
+	sum.copy_from_slice(reader.take(8)?);
+	if reader.0.len() as u64 != u64::from_le_bytes(len) || checksum(reader.0) != u64::from_le_bytes(sum) {
+		return None
+	}
+
+	let root = match reader.byte()? {
+		0 => None,
+		1 => Some(reader.bytes()?),
+		_ => return None,
+	};
+	let mut ops = Vec::new();
+	while !reader.0.is_empty() {
+		ops.push(match reader.byte()? {
+			PUT => KeyValueOp::Put(reader.bytes()?, reader.bytes()?),
+			DELETE => KeyValueOp::Delete(reader.bytes()?),
+			_ => return None,
+		});
+	}
+	Some((root, ops))
+}
+
+/// Key-value database logging every write, together with the root it
+/// results in, to a write-ahead log file before applying it. Wrap the
+/// database of a persistent backend, such as `SledDB` or `LmdbDB`, so
+/// that a process crash in the middle of a batch never leaves a tree
+/// whose root references missing nodes: on `open`, the last logged
+/// batch is replayed, and its root is the one to reopen the tree from.
+///
+/// The log holds only the last batch, and is replaced atomically by
+/// renaming on every write, after which the directory is synced so the
+/// rename survives a power failure. As puts and deletes store whole
+/// values, replaying an already applied batch has no effect.
+///
+/// Every write creates, syncs and renames the log, so callers must
+/// group writes with `begin_batch` and `commit_root`: outside a batch,
+/// each node write is logged on its own. Inside a batch, nothing is
+/// logged until the outermost batch is committed, which logs and syncs
+/// all of its writes at once.
+pub struct WalDB<D> {
+	db: D,
+	path: PathBuf,
+	root: Option<Vec<u8>>,
+	staged: Option<Vec<u8>>,
+}
+
+impl<D: KeyValueDB> WalDB<D> {
+	/// Open `db` with the write-ahead log at `path`, replaying the last
+	/// logged batch into `db` if the log exists.
+	pub fn open<P: AsRef<Path>>(mut db: D, path: P) -> Result<Self, WalDBError<D::Error>> {
+		let path = path.as_ref().to_path_buf();
+		let root = match fs::read(&path) {
+			Ok(raw) => {
+				let (root, ops) = decode(&raw).ok_or_else(|| io::Error::new(
+					io::ErrorKind::InvalidData, "corrupted write-ahead log"
+				))?;
+				#[cfg(feature = "tracing")]
+				tracing::debug!(target: "bm", ops = ops.len(), "replaying write-ahead log");
+				db.write(ops).map_err(WalDBError::Backend)?;
+				root
+			},
+			Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+			Err(err) => return Err(err.into()),
+		};
+
+		Ok(Self { db, path, root, staged: None })
+	}
+}
+
+impl<D> WalDB<D> {
+	/// Root recorded with the last write, to reopen the tree from.
+	pub fn root(&self) -> Option<&[u8]> {
+		self.root.as_deref()
+	}
+
+	/// Record `root` with the next write, as the root the write results in.
+	pub fn set_root(&mut self, root: &[u8]) {
+		self.staged = Some(root.to_vec());
+	}
+
+	/// Path of the write-ahead log.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Get a reference to the underlying database.
+	pub fn db(&self) -> &D {
+		&self.db
+	}
+
+	/// Convert into the underlying database.
+	pub fn into_db(self) -> D {
+		self.db
+	}
+
+	fn log(&self, record: &[u8]) -> io::Result<()> {
+		let mut tmp = self.path.clone().into_os_string();
+		tmp.push(".tmp");
+		let mut file = fs::File::create(&tmp)?;
+		file.write_all(record)?;
+		file.sync_all()?;
+		fs::rename(&tmp, &self.path)?;
+		self.sync_dir()
+	}
+
+	#[cfg(unix)]
+	fn sync_dir(&self) -> io::Result<()> {
+		let dir = match self.path.parent() {
+			Some(dir) if !dir.as_os_str().is_empty() => dir,
+			_ => Path::new("."),
+		};
+		fs::File::open(dir)?.sync_all()
+	}
+
+	/// Directories cannot be opened for syncing on other platforms,
+	/// where the rename is relied on to be durable.
+	#[cfg(not(unix))]
+	fn sync_dir(&self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl<D: KeyValueDB> KeyValueDB for WalDB<D> {
+	type Error = WalDBError<D::Error>;
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.db.get(key).map_err(WalDBError::Backend)
+	}
+
+	fn get_with<R, F: FnOnce(&[u8]) -> R>(&self, key: &[u8], f: F) -> Result<Option<R>, Self::Error> {
+		self.db.get_with(key, f).map_err(WalDBError::Backend)
+	}
+
+	fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), Self::Error> {
+		let root = self.staged.take().or_else(|| self.root.clone());
+		self.log(&encode(root.as_deref(), &ops))?;
+		self.root = root;
+		self.db.write(ops).map_err(WalDBError::Backend)
+	}
+}
+
+impl<D: IterableKeyValueDB> IterableKeyValueDB for WalDB<D> {
+	fn for_each_entry<F: FnMut(&[u8], &[u8])>(&self, f: F) -> Result<(), Self::Error> {
+		self.db.for_each_entry(f).map_err(WalDBError::Backend)
+	}
+}
+
+/// Reference-counted merkle database whose writes are recorded in a
+/// write-ahead log.
+pub type WalBackend<C, D> = RefCountBackend<C, KeyValueStore<WalDB<D>>>;
+
+impl<C: Construct, D: KeyValueDB> RefCountBackend<C, KeyValueStore<WalDB<D>>> where
+	C::Value: AsRef<[u8]> + AsMut<[u8]> + Default,
+{
+	/// Commit the current batch, or an empty one if none was begun,
+	/// recording `root` in the log as the root it results in. Inside a
+	/// nested batch, `root` is recorded once the outermost batch is
+	/// committed.
+	pub fn commit_root(&mut self, root: &C::Value) -> Result<(), KeyValueStoreError<WalDBError<D::Error>>> {
+		use crate::WriteBackend;
+
+		self.store_mut().db_mut().set_root(root.as_ref());
+		if self.store().batch_depth() == 0 {
+			self.begin_batch()?;
+		}
+		self.commit_batch()
+	}
+
+	/// Root recorded with the last committed batch, or replayed from
+	/// the log on open. `None` if no root was recorded, or if it does not
+	/// fit a value of the construct.
+	pub fn committed_root(&self) -> Option<C::Value> {
+		let raw = self.db().root()?;
+		let mut root = C::Value::default();
+		if root.as_ref().len() != raw.len() {
+			return None
+		}
+		root.as_mut().copy_from_slice(raw);
+		Some(root)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::value;
+	use crate::{InheritedDigestConstruct, Vector, Owned, Dangling, Tree, Leak, WriteBackend, Error};
+	use std::cell::RefCell;
+	use std::collections::HashMap;
+	use std::rc::Rc;
+
+	use sha2::Sha256;
+
+	type Construct = InheritedDigestConstruct<Sha256>;
+	type Shared = Rc<RefCell<HashMap<Vec<u8>, Vec<u8>>>>;
+
+	/// Database whose writes are lost once crashed, as if the process
+	/// died after logging a batch but before applying it.
+	struct Crashing {
+		db: Shared,
+		crashed: bool,
+	}
+
+	impl KeyValueDB for Crashing {
+		type Error = ();
+
+		fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ()> {
+			Ok(self.db.borrow().get(key).cloned())
+		}
+
+		fn write(&mut self, ops: Vec<KeyValueOp>) -> Result<(), ()> {
+			if self.crashed {
+				return Err(())
+			}
+			self.db.write(ops).map_err(|_| ())
+		}
+	}
+
+	#[test]
+	fn test_wal_replay() {
+		let path = std::env::temp_dir().join(format!("bm-wal-{}", std::process::id()));
+		let _ = fs::remove_file(&path);
+		let shared = Shared::default();
+
+		let mut db = WalBackend::<Construct, _>::from_db(
+			WalDB::open(Crashing { db: shared.clone(), crashed: false }, &path).unwrap()
+		);
+		assert_eq!(db.committed_root(), None);
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		for i in 0..5 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		db.commit_root(&vector.root()).unwrap();
+		assert_eq!(db.committed_root(), Some(vector.root()));
+		let committed = vector.root();
+		let logged = fs::read(&path).unwrap();
+
+		db.begin_batch().unwrap();
+		for i in 5..9 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		db.begin_batch().unwrap();
+		vector.set(&mut db, 0, value(42)).unwrap();
+		db.commit_root(&vector.root()).unwrap();
+		assert_eq!(db.committed_root(), Some(committed));
+		assert_eq!(fs::read(&path).unwrap(), logged);
+		db.store_mut().db_mut().db.crashed = true;
+		assert!(matches!(db.commit_root(&vector.root()), Err(KeyValueStoreError::Backend(WalDBError::Backend(())))));
+		drop(db);
+
+		let mut reopened = WalBackend::<Construct, _>::from_db(
+			WalDB::open(Crashing { db: shared, crashed: false }, &path).unwrap()
+		);
+		assert_eq!(reopened.committed_root(), Some(vector.root()));
+
+		let leaked = Vector::<Dangling, Construct>::from_leaked(vector.metadata());
+		assert_eq!(leaked.get(&mut reopened, 0).unwrap(), value(42));
+		for i in 1..9 {
+			assert_eq!(leaked.get(&mut reopened, i).unwrap(), value(i as u8));
+		}
+
+		fs::write(&path, b"bmwal001 torn").unwrap();
+		assert!(matches!(WalDB::open(HashMap::<Vec<u8>, Vec<u8>>::new(), &path), Err(WalDBError::Io(_))));
+		fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn test_wal_edge_cases() {
+		let path = std::env::temp_dir().join(format!("bm-wal-edge-{}", std::process::id()));
+		let _ = fs::remove_file(&path);
+
+		let mut db = WalBackend::<Construct, _>::from_db(WalDB::open(HashMap::new(), &path).unwrap());
+		db.commit_batch().unwrap();
+		assert!(!path.exists());
+
+		let deep = Vector::<Owned, Construct>::create(&mut db, 1 << 40, None).unwrap();
+		assert_eq!(deep.get(&mut db, (1 << 40) - 1).unwrap(), value(0));
+		db.commit_root(&deep.root()).unwrap();
+		assert_eq!(db.committed_root(), Some(deep.root()));
+
+		let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+		db.begin_batch().unwrap();
+		for i in 0..4 {
+			vector.push(&mut db, value(i)).unwrap();
+		}
+		db.commit_root(&vector.root()).unwrap();
+		let mut missing = WalBackend::<Construct, _>::from_db(WalDB::open(HashMap::new(), path.with_extension("missing")).unwrap());
+		assert!(matches!(vector.get(&mut missing, 0), Err(Error::CorruptedDatabase(_))));
+		assert!(!missing.db().path().exists());
+
+		let mut empty = WalBackend::<Construct, _>::from_db(WalDB::open(HashMap::new(), &path).unwrap());
+		assert_eq!(empty.committed_root(), Some(vector.root()));
+		empty.store_mut().db_mut().set_root(b"short");
+		empty.commit_batch().unwrap();
+		empty.begin_batch().unwrap();
+		empty.commit_batch().unwrap();
+		assert_eq!(empty.committed_root(), None);
+		assert_eq!(empty.db().root(), Some(&b"short"[..]));
+
+		deep.drop(&mut db).unwrap();
+		vector.drop(&mut db).unwrap();
+		fs::remove_file(&path).unwrap();
+	}
+}
@@ -0,0 +1,101 @@
+use bm::{InMemoryBackend, InheritedDigestConstruct, Vector, List, Owned, Tree, Leak,
+		 Sequence, LeakPolicy, set_leak_policy, leak_policy, Error};
+use sha2::Sha256;
+use generic_array::GenericArray;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+type Construct = InheritedDigestConstruct<Sha256>;
+
+fn value(i: u8) -> GenericArray<u8, typenum::U32> {
+	let mut value = GenericArray::default();
+	value[0] = i;
+	value
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, Ord, PartialOrd, Hash)]
+struct ListValue([u8; 32]);
+
+impl AsRef<[u8]> for ListValue {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl From<GenericArray<u8, typenum::U32>> for ListValue {
+	fn from(array: GenericArray<u8, typenum::U32>) -> Self {
+		ListValue(array.into())
+	}
+}
+
+impl From<u64> for ListValue {
+	fn from(value: u64) -> Self {
+		let mut bytes = [0u8; 32];
+		bytes[0..8].copy_from_slice(&value.to_le_bytes());
+		ListValue(bytes)
+	}
+}
+
+impl From<ListValue> for u64 {
+	fn from(value: ListValue) -> u64 {
+		let mut raw = [0u8; 8];
+		raw.copy_from_slice(&value.0[0..8]);
+		u64::from_le_bytes(raw)
+	}
+}
+
+type ListConstruct = InheritedDigestConstruct<Sha256, ListValue>;
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_leak_policy() {
+	assert_eq!(leak_policy(), LeakPolicy::Ignore);
+	set_leak_policy(LeakPolicy::Panic);
+
+	let mut db = InMemoryBackend::<Construct>::default();
+	let mut vector = Vector::<Owned, Construct>::create(&mut db, 0, None).unwrap();
+	for i in 0..5 {
+		vector.push(&mut db, value(i)).unwrap();
+	}
+	let root = vector.root();
+
+	let leaked = catch_unwind(AssertUnwindSafe(|| {
+		let _ = Vector::<Owned, Construct>::from_leaked(vector.metadata());
+	}));
+	let message = leaked.unwrap_err().downcast::<String>().unwrap();
+	assert!(message.contains(&format!("{:02x}{:02x}", root[0], root[1])));
+
+	let metadata = vector.leak();
+	let vector = Vector::<Owned, Construct>::from_leaked(metadata);
+	vector.drop(&mut db).unwrap();
+
+	// Failing to reconstruct does not report the root as leaked.
+	let mut db = InMemoryBackend::<ListConstruct>::default();
+	let mut list = List::<Owned, ListConstruct>::create(&mut db, None).unwrap();
+	list.push(&mut db, 7.into()).unwrap();
+	let root = list.deconstruct(&mut db).unwrap();
+	let mut empty = InMemoryBackend::<ListConstruct>::default();
+	assert!(matches!(
+		List::<Owned, ListConstruct>::reconstruct(root.clone(), &mut empty, None),
+		Err(Error::CorruptedDatabase(_)),
+	));
+	assert!(matches!(
+		List::<Owned, ListConstruct>::reconstruct(root.clone(), &mut db, Some(0)),
+		Err(Error::MaxLenExceeded { len: 1, max_len: 0 }),
+	));
+	let list = List::<Owned, ListConstruct>::reconstruct(root, &mut db, None).unwrap();
+	assert_eq!(list.len(), 1);
+	list.drop(&mut db).unwrap();
+
+	// Empty trees, and trees at the maximum depth, are dropped without
+	// being reported.
+	let empty = List::<Owned, ListConstruct>::create(&mut db, Some(4)).unwrap();
+	let root = empty.deconstruct(&mut db).unwrap();
+	let empty = List::<Owned, ListConstruct>::reconstruct(root, &mut db, Some(4)).unwrap();
+	assert_eq!(empty.len(), 0);
+	empty.drop(&mut db).unwrap();
+	let deep = Vector::<Owned, ListConstruct>::create(&mut db, u64::MAX, None).unwrap();
+	assert_eq!(deep.get(&mut db, u64::MAX - 1).unwrap(), ListValue::default());
+	deep.drop(&mut db).unwrap();
+
+	set_leak_policy(LeakPolicy::Ignore);
+}
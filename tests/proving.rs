@@ -1,4 +1,4 @@
-use bm::{OwnedList, ProvingBackend, Sequence, Proofs};
+use bm::{OwnedList, ProvingBackend, Sequence, Proofs, CompactValue, verify_proofs, Leak, Tree};
 use sha2::Sha256;
 use generic_array::GenericArray;
 
@@ -11,19 +11,19 @@ impl AsRef<[u8]> for VecValue {
 	}
 }
 
-impl From<usize> for VecValue {
-	fn from(value: usize) -> Self {
+impl From<u64> for VecValue {
+	fn from(value: u64) -> Self {
 		let mut bytes = [0u8; 32];
-		(&mut bytes[0..8]).copy_from_slice(&(value as u64).to_le_bytes()[..]);
+		(&mut bytes[0..8]).copy_from_slice(&value.to_le_bytes()[..]);
 		VecValue(bytes)
 	}
 }
 
-impl Into<usize> for VecValue {
-	fn into(self) -> usize {
+impl Into<u64> for VecValue {
+	fn into(self) -> u64 {
 		let mut raw = [0u8; 8];
 		(&mut raw[..]).copy_from_slice(&self.0[0..8]);
-		u64::from_le_bytes(raw) as usize
+		u64::from_le_bytes(raw)
 	}
 }
 
@@ -59,8 +59,8 @@ fn basic_proving_vec() {
 	drop(proving);
 
 	let mut proving = ProvingBackend::new(&mut db);
-	vec.get(&mut proving, 5usize.into()).unwrap();
-	vec.get(&mut proving, 7usize.into()).unwrap();
+	vec.get(&mut proving, 5u64.into()).unwrap();
+	vec.get(&mut proving, 7u64.into()).unwrap();
 	let vec_hash = vec.deconstruct(&mut proving).unwrap();
 	let proofs: Proofs<VecValue> = proving.into();
 	let compact_proofs = proofs.into_compact(vec_hash.clone());
@@ -72,6 +72,106 @@ fn basic_proving_vec() {
 	let mut proved = InMemory::default();
 	proved.populate(proofs.into());
 	let proved_vec = OwnedList::reconstruct(vec_hash, &mut proved, None).unwrap();
-	assert_eq!(proved_vec.get(&mut proved, 5usize.into()).unwrap(), 5usize.into());
-	assert_eq!(proved_vec.get(&mut proved, 7usize.into()).unwrap(), 7usize.into());
+	assert_eq!(proved_vec.get(&mut proved, 5u64.into()).unwrap(), 5u64.into());
+	assert_eq!(proved_vec.get(&mut proved, 7u64.into()).unwrap(), 7u64.into());
+}
+
+#[test]
+fn forest_proving() {
+	type Construct = bm::InheritedDigestConstruct<Sha256, VecValue>;
+
+	let mut inner_db = InMemory::default();
+	let mut inner = bm::OwnedVector::<Construct>::create(&mut inner_db, 0, Some(8)).unwrap();
+	for i in 0..8 {
+		inner.push(&mut inner_db, i.into()).unwrap();
+	}
+	let inner_root = bm::Tree::root(&inner);
+
+	let mut outer_db = InMemory::default();
+	let mut outer = bm::OwnedVector::<Construct>::create(&mut outer_db, 0, Some(4)).unwrap();
+	for i in 0..4 {
+		outer.push(&mut outer_db, if i == 2 { inner_root.clone() } else { (100 + i).into() }).unwrap();
+	}
+	let outer_root = bm::Tree::root(&outer);
+
+	let mut proving = ProvingBackend::new(&mut inner_db);
+	inner.get(&mut proving, 5).unwrap();
+	let inner_proofs: Proofs<VecValue> = proving.into();
+
+	let mut proving = ProvingBackend::new(&mut outer_db);
+	outer.get(&mut proving, 2).unwrap();
+	let mut proofs: Proofs<VecValue> = proving.into();
+	proofs.merge(inner_proofs);
+
+	let indices = [bm::Index::from_depth(2, 2), bm::Index::from_depth(5, 3)];
+	assert_eq!(proofs.get_nested(&outer_root, &indices), Some(5u64.into()));
+
+	let compact = proofs.into_compact(outer_root.clone());
+	assert_eq!(compact.len(), 6);
+	assert_eq!(compact.clone().verify_nested::<Construct>(&outer_root, &indices), Some(5u64.into()));
+	assert_eq!(compact.clone().verify_nested::<Construct>(&inner_root, &indices), None);
+	assert_eq!(compact.verify_nested::<Construct>(&outer_root, &[bm::Index::from_depth(1, 2)]), None);
+}
+
+#[test]
+fn batch_verify_proofs() {
+	type Construct = bm::InheritedDigestConstruct<Sha256, VecValue>;
+
+	let mut db = InMemory::default();
+	let mut vector = bm::OwnedVector::<Construct>::create(&mut db, 0, Some(16)).unwrap();
+	for i in 0..16 {
+		vector.push(&mut db, i.into()).unwrap();
+	}
+	let root = bm::Tree::root(&vector);
+
+	let mut proofs = Vec::new();
+	for index in [3, 4, 9, 3] {
+		let mut proving = ProvingBackend::new(&mut db);
+		vector.get(&mut proving, index).unwrap();
+		let index_proofs: Proofs<VecValue> = proving.into();
+		proofs.push(index_proofs.into_compact(root.clone()));
+	}
+	proofs.push(CompactValue::Single(root.clone()));
+
+	assert!(verify_proofs::<Construct>(&root, &proofs));
+	assert!(verify_proofs::<Construct>(&root, &[]));
+	assert!(!verify_proofs::<Construct>(&0u64.into(), &proofs));
+
+	let mut tampered = proofs.clone();
+	if let CompactValue::Combined(boxed) = &mut tampered[2] {
+		boxed.1 = CompactValue::Single(1u64.into());
+	}
+	assert!(!verify_proofs::<Construct>(&root, &tampered));
+}
+
+#[test]
+fn proving_empty_and_missing() {
+	type Construct = bm::InheritedDigestConstruct<Sha256, VecValue>;
+
+	let mut db = InMemory::default();
+	let vector = bm::OwnedVector::<Construct>::create(&mut db, 1 << 40, None).unwrap();
+	let root = vector.root();
+
+	let mut proving = ProvingBackend::new(&mut db);
+	assert!(bm::Backend::empty_values(&proving).is_some());
+	assert_eq!(vector.get(&mut proving, (1 << 40) - 1).unwrap(), 0u64.into());
+	let proofs: Proofs<VecValue> = proving.into();
+
+	let mut proved = InMemory::default();
+	proved.populate(proofs.into());
+	let proved_vector = bm::DanglingVector::<Construct>::from_leaked((root, 1 << 40, None));
+	assert_eq!(proved_vector.get(&mut proved, (1 << 40) - 1).unwrap(), 0u64.into());
+	assert!(matches!(proved_vector.get(&mut proved, 1 << 40), Err(bm::Error::AccessOverflowed(_))));
+
+	let mut list = OwnedList::create(&mut db, None).unwrap();
+	list.push(&mut db, 7u64.into()).unwrap();
+	list.push(&mut db, 8u64.into()).unwrap();
+	let mut empty = InMemory::default();
+	let mut proving = ProvingBackend::new(&mut empty);
+	let missing = bm::DanglingList::<Construct>::from_leaked(list.metadata());
+	assert!(matches!(missing.get(&mut proving, 0), Err(bm::Error::CorruptedDatabase(_))));
+	assert!(proving.into_state().proofs.is_empty());
+
+	vector.drop(&mut db).unwrap();
+	list.drop(&mut db).unwrap();
 }